@@ -10,7 +10,7 @@ use vfs::{file_set::FileSet, VfsPath};
 
 use crate::{
     input::CrateName, Change, CrateGraph, CrateId, Edition, Env, FileId, FilePosition, FileRange,
-    SourceDatabaseExt, SourceRoot, SourceRootId,
+    ProcMacro, SourceDatabaseExt, SourceRoot, SourceRootId,
 };
 
 pub const WORKSPACE: SourceRootId = SourceRootId(0);
@@ -79,6 +79,13 @@ pub struct ChangeFixture {
 
 impl ChangeFixture {
     pub fn parse(ra_fixture: &str) -> ChangeFixture {
+        ChangeFixture::parse_with_proc_macros(ra_fixture, Vec::new())
+    }
+
+    /// Like [`ChangeFixture::parse`], but every crate of the fixture is given
+    /// `proc_macros` as its loaded proc macros. Only the crates declaring them
+    /// with `#[proc_macro_derive]` and friends export them.
+    pub fn parse_with_proc_macros(ra_fixture: &str, proc_macros: Vec<ProcMacro>) -> ChangeFixture {
         let fixture = Fixture::parse(ra_fixture);
         let mut change = Change::new();
 
@@ -125,7 +132,7 @@ impl ChangeFixture {
                     Some(crate_name.clone().into()),
                     meta.cfg,
                     meta.env,
-                    Default::default(),
+                    Ok(proc_macros.clone()),
                 );
                 let prev = crates.insert(crate_name.clone(), crate_id);
                 assert!(prev.is_none());
@@ -154,7 +161,7 @@ impl ChangeFixture {
                 Some(CrateName::new("test").unwrap().into()),
                 default_cfg,
                 Env::default(),
-                Default::default(),
+                Ok(proc_macros),
             );
         } else {
             for (from, to) in crate_deps {
@@ -173,6 +173,27 @@ impl PartialEq for ProcMacro {
     }
 }
 
+/// The proc macros of a crate, or the reason why they could not be loaded.
+pub type ProcMacroLoadResult = Result<Vec<ProcMacro>, String>;
+
+/// How name resolution treats uses of an attribute or derive proc macro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProcMacroBehavior {
+    /// Expand the macro as usual.
+    Expand,
+    /// Treat the macro as if it wasn't there: attributes leave the item
+    /// unchanged, derives produce nothing.
+    Ignore,
+    /// Drop the item the attribute is applied to. Derives produce nothing.
+    Disable,
+}
+
+impl Default for ProcMacroBehavior {
+    fn default() -> ProcMacroBehavior {
+        ProcMacroBehavior::Expand
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CrateData {
     pub root_file_id: FileId,
@@ -187,7 +208,10 @@ pub struct CrateData {
     pub cfg_options: CfgOptions,
     pub env: Env,
     pub dependencies: Vec<Dependency>,
-    pub proc_macro: Vec<ProcMacro>,
+    pub proc_macro: ProcMacroLoadResult,
+    /// User overrides for the proc macros exported by this crate, by macro
+    /// name. Macros not listed here are expanded.
+    pub proc_macro_behavior: FxHashMap<SmolStr, ProcMacroBehavior>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -216,7 +240,7 @@ impl CrateGraph {
         display_name: Option<CrateDisplayName>,
         cfg_options: CfgOptions,
         env: Env,
-        proc_macro: Vec<ProcMacro>,
+        proc_macro: ProcMacroLoadResult,
     ) -> CrateId {
         let data = CrateData {
             root_file_id: file_id,
@@ -225,6 +249,7 @@ impl CrateGraph {
             cfg_options,
             env,
             proc_macro,
+            proc_macro_behavior: FxHashMap::default(),
             dependencies: Vec::new(),
         };
         let crate_id = CrateId(self.arena.len() as u32);
@@ -250,6 +275,15 @@ impl CrateGraph {
         Ok(())
     }
 
+    pub fn set_proc_macro_behavior(
+        &mut self,
+        krate: CrateId,
+        macro_name: &str,
+        behavior: ProcMacroBehavior,
+    ) {
+        self.arena.get_mut(&krate).unwrap().proc_macro_behavior.insert(macro_name.into(), behavior);
+    }
+
     pub fn is_empty(&self) -> bool {
         self.arena.is_empty()
     }
@@ -501,7 +535,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         let crate2 = graph.add_crate_root(
             FileId(2u32),
@@ -509,7 +543,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         let crate3 = graph.add_crate_root(
             FileId(3u32),
@@ -517,7 +551,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         assert!(graph.add_dep(crate1, CrateName::new("crate2").unwrap(), crate2).is_ok());
         assert!(graph.add_dep(crate2, CrateName::new("crate3").unwrap(), crate3).is_ok());
@@ -533,7 +567,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         let crate2 = graph.add_crate_root(
             FileId(2u32),
@@ -541,7 +575,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         assert!(graph.add_dep(crate1, CrateName::new("crate2").unwrap(), crate2).is_ok());
         assert!(graph.add_dep(crate2, CrateName::new("crate2").unwrap(), crate2).is_err());
@@ -556,7 +590,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         let crate2 = graph.add_crate_root(
             FileId(2u32),
@@ -564,7 +598,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         let crate3 = graph.add_crate_root(
             FileId(3u32),
@@ -572,7 +606,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         assert!(graph.add_dep(crate1, CrateName::new("crate2").unwrap(), crate2).is_ok());
         assert!(graph.add_dep(crate2, CrateName::new("crate3").unwrap(), crate3).is_ok());
@@ -587,7 +621,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         let crate2 = graph.add_crate_root(
             FileId(2u32),
@@ -595,7 +629,7 @@ mod tests {
             None,
             CfgOptions::default(),
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         assert!(graph
             .add_dep(crate1, CrateName::normalize_dashes("crate-name-with-dashes"), crate2)
@@ -14,7 +14,8 @@ pub use crate::{
     change::Change,
     input::{
         CrateData, CrateDisplayName, CrateGraph, CrateId, CrateName, Dependency, Edition, Env,
-        ProcMacro, ProcMacroExpander, ProcMacroId, ProcMacroKind, SourceRoot, SourceRootId,
+        ProcMacro, ProcMacroBehavior, ProcMacroExpander, ProcMacroId, ProcMacroKind,
+        ProcMacroLoadResult, SourceRoot, SourceRootId,
     },
 };
 pub use salsa;
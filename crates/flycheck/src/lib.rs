@@ -3,9 +3,10 @@
 //! LSP diagnostics based on the output of the command.
 
 use std::{
+    collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
     fmt,
     io::{self, BufRead, BufReader},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{self, Command, Stdio},
     time::Duration,
 };
@@ -34,6 +35,42 @@ pub enum FlycheckConfig {
         command: String,
         args: Vec<String>,
     },
+    /// Checks only the crate owning the saved file, using a build-system
+    /// specific command template (e.g. from `rust-project.json`).
+    CrateCommands {
+        crates: Vec<CrateCheckCommand>,
+    },
+}
+
+/// A command template for checking a single crate.
+///
+/// The `{label}` and `{saved_file}` placeholders in `args` are substituted
+/// before the command is run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrateCheckCommand {
+    pub label: String,
+    /// Directories containing the source files of the crate.
+    pub include_dirs: Vec<PathBuf>,
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl CrateCheckCommand {
+    fn owns(&self, file: &Path) -> Option<usize> {
+        self.include_dirs
+            .iter()
+            .filter(|dir| file.starts_with(dir))
+            .map(|dir| dir.components().count())
+            .max()
+    }
+
+    fn substitute(&self, saved_file: &Path) -> Vec<String> {
+        let saved_file = saved_file.to_string_lossy();
+        self.args
+            .iter()
+            .map(|arg| arg.replace("{label}", &self.label).replace("{saved_file}", &saved_file))
+            .collect()
+    }
 }
 
 impl fmt::Display for FlycheckConfig {
@@ -43,6 +80,9 @@ impl fmt::Display for FlycheckConfig {
             FlycheckConfig::CustomCommand { command, args } => {
                 write!(f, "{} {}", command, args.join(" "))
             }
+            FlycheckConfig::CrateCommands { crates } => {
+                write!(f, "per-crate check ({} crates)", crates.len())
+            }
         }
     }
 }
@@ -53,6 +93,7 @@ impl fmt::Display for FlycheckConfig {
 /// The spawned thread is shut down when this struct is dropped.
 #[derive(Debug)]
 pub struct FlycheckHandle {
+    id: usize,
     // XXX: drop order is significant
     sender: Sender<Restart>,
     thread: jod_thread::JoinHandle,
@@ -68,18 +109,34 @@ impl FlycheckHandle {
         let actor = FlycheckActor::new(id, sender, config, workspace_root);
         let (sender, receiver) = unbounded::<Restart>();
         let thread = jod_thread::spawn(move || actor.run(receiver));
-        FlycheckHandle { sender, thread }
+        FlycheckHandle { id, sender, thread }
+    }
+
+    /// Returns the id of this flycheck instance.
+    pub fn id(&self) -> usize {
+        self.id
     }
 
     /// Schedule a re-start of the cargo check worker.
     pub fn update(&self) {
-        self.sender.send(Restart).unwrap();
+        self.sender.send(Restart { saved_file: None }).unwrap();
+    }
+
+    /// Schedule a re-start of the cargo check worker after `saved_file` was
+    /// saved. Per-crate configurations only check the crate owning the file.
+    pub fn update_for_file(&self, saved_file: PathBuf) {
+        self.sender.send(Restart { saved_file: Some(saved_file) }).unwrap();
     }
 }
 
 pub enum Message {
     /// Request adding a diagnostic with fixes included to a file
-    AddDiagnostic { workspace_root: PathBuf, diagnostic: Diagnostic },
+    AddDiagnostic {
+        /// Flycheck instance ID
+        id: usize,
+        workspace_root: PathBuf,
+        diagnostic: Diagnostic,
+    },
 
     /// Request check progress notification to client
     Progress {
@@ -92,8 +149,9 @@ pub enum Message {
 impl fmt::Debug for Message {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Message::AddDiagnostic { workspace_root, diagnostic } => f
+            Message::AddDiagnostic { id, workspace_root, diagnostic } => f
                 .debug_struct("AddDiagnostic")
+                .field("id", id)
                 .field("workspace_root", workspace_root)
                 .field("diagnostic_code", &diagnostic.code.as_ref().map(|it| &it.code))
                 .finish(),
@@ -107,12 +165,19 @@ impl fmt::Debug for Message {
 #[derive(Debug)]
 pub enum Progress {
     DidStart,
-    DidCheckCrate(String),
+    /// All the compiler artifacts of a package were produced.
+    DidCheckPackage {
+        package: String,
+        /// Number of distinct packages checked since the check started.
+        n_checked: usize,
+    },
     DidFinish(io::Result<()>),
     DidCancel,
 }
 
-struct Restart;
+struct Restart {
+    saved_file: Option<PathBuf>,
+}
 
 struct FlycheckActor {
     id: usize,
@@ -125,6 +190,13 @@ struct FlycheckActor {
     /// have to wrap sub-processes output handling in a thread and pass messages
     /// back over a channel.
     cargo_handle: Option<CargoHandle>,
+    /// The last command spawned, kept for error reporting.
+    last_command: String,
+    /// Commands still to run for the current check, e.g. when files owned by
+    /// several crates were saved at once.
+    queued_commands: VecDeque<Command>,
+    /// Ids of the packages checked by the current process.
+    checked_packages: HashSet<String>,
 }
 
 enum Event {
@@ -139,7 +211,16 @@ impl FlycheckActor {
         config: FlycheckConfig,
         workspace_root: PathBuf,
     ) -> FlycheckActor {
-        FlycheckActor { id, sender, config, workspace_root, cargo_handle: None }
+        FlycheckActor {
+            id,
+            sender,
+            config,
+            workspace_root,
+            cargo_handle: None,
+            last_command: String::new(),
+            queued_commands: VecDeque::new(),
+            checked_packages: HashSet::new(),
+        }
     }
     fn progress(&self, progress: Progress) {
         self.send(Message::Progress { id: self.id, progress });
@@ -154,16 +235,21 @@ impl FlycheckActor {
     fn run(mut self, inbox: Receiver<Restart>) {
         while let Some(event) = self.next_event(&inbox) {
             match event {
-                Event::Restart(Restart) => {
-                    while let Ok(Restart) = inbox.recv_timeout(Duration::from_millis(50)) {}
+                Event::Restart(Restart { saved_file }) => {
+                    let mut saved_files: BTreeSet<PathBuf> = saved_file.into_iter().collect();
+                    while let Ok(restart) = inbox.recv_timeout(Duration::from_millis(50)) {
+                        saved_files.extend(restart.saved_file);
+                    }
 
                     self.cancel_check_process();
 
-                    let mut command = self.check_command();
-                    log::info!("restart flycheck {:?}", command);
-                    command.stdout(Stdio::piped()).stderr(Stdio::null()).stdin(Stdio::null());
-                    if let Ok(child) = command.spawn().map(JodChild) {
-                        self.cargo_handle = Some(CargoHandle::spawn(child));
+                    self.queued_commands = self.check_commands(&saved_files).into();
+                    if self.queued_commands.is_empty() {
+                        log::info!("no flycheck command for {:?}", saved_files);
+                        continue;
+                    }
+                    self.checked_packages.clear();
+                    if self.spawn_next_command() {
                         self.progress(Progress::DidStart);
                     }
                 }
@@ -174,19 +260,23 @@ impl FlycheckActor {
                     let res = cargo_handle.join();
                     if res.is_err() {
                         log::error!(
-                            "Flycheck failed to run the following command: {:?}",
-                            self.check_command()
+                            "Flycheck failed to run the following command: {}",
+                            self.last_command
                         )
                     }
-                    self.progress(Progress::DidFinish(res));
+                    if res.is_err() || !self.spawn_next_command() {
+                        self.queued_commands.clear();
+                        self.progress(Progress::DidFinish(res));
+                    }
                 }
                 Event::CheckEvent(Some(message)) => match message {
                     CargoMessage::CompilerArtifact(msg) => {
-                        self.progress(Progress::DidCheckCrate(msg.target.name));
+                        self.did_check_artifact(msg.package_id.repr, &msg.target.name);
                     }
 
                     CargoMessage::Diagnostic(msg) => {
                         self.send(Message::AddDiagnostic {
+                            id: self.id,
                             workspace_root: self.workspace_root.clone(),
                             diagnostic: msg,
                         });
@@ -197,12 +287,39 @@ impl FlycheckActor {
         // If we rerun the thread, we need to discard the previous check results first
         self.cancel_check_process();
     }
+    fn did_check_artifact(&mut self, package_id: String, target_name: &str) {
+        // A package may produce several artifacts (lib, bins, tests, ...),
+        // only report it the first time we see it.
+        if !self.checked_packages.contains(&package_id) {
+            let package = package_name(&package_id).unwrap_or(target_name).to_string();
+            self.checked_packages.insert(package_id);
+            self.progress(Progress::DidCheckPackage {
+                package,
+                n_checked: self.checked_packages.len(),
+            });
+        }
+    }
+    /// Spawns the next queued command, returning `false` if there is none left
+    /// or none of the remaining ones could be started.
+    fn spawn_next_command(&mut self) -> bool {
+        while let Some(mut command) = self.queued_commands.pop_front() {
+            log::info!("restart flycheck {:?}", command);
+            self.last_command = format!("{:?}", command);
+            command.stdout(Stdio::piped()).stderr(Stdio::null()).stdin(Stdio::null());
+            if let Ok(child) = command.spawn().map(JodChild) {
+                self.cargo_handle = Some(CargoHandle::spawn(child));
+                return true;
+            }
+        }
+        false
+    }
     fn cancel_check_process(&mut self) {
+        self.queued_commands.clear();
         if self.cargo_handle.take().is_some() {
             self.progress(Progress::DidCancel);
         }
     }
-    fn check_command(&self) -> Command {
+    fn check_commands(&self, saved_files: &BTreeSet<PathBuf>) -> Vec<Command> {
         let mut cmd = match &self.config {
             FlycheckConfig::CargoCommand {
                 command,
@@ -244,9 +361,20 @@ impl FlycheckActor {
                 cmd.args(args);
                 cmd
             }
+            FlycheckConfig::CrateCommands { crates } => {
+                return crate_commands(crates, saved_files)
+                    .into_iter()
+                    .map(|(krate, saved_file)| {
+                        let mut cmd = Command::new(&krate.command);
+                        cmd.args(krate.substitute(saved_file));
+                        cmd.current_dir(&self.workspace_root);
+                        cmd
+                    })
+                    .collect();
+            }
         };
         cmd.current_dir(&self.workspace_root);
-        cmd
+        vec![cmd]
     }
 
     fn send(&self, check_task: Message) {
@@ -254,6 +382,43 @@ impl FlycheckActor {
     }
 }
 
+/// Picks the crate owning each saved file, checking every crate only once with
+/// the first of its saved files.
+fn crate_commands<'a>(
+    crates: &'a [CrateCheckCommand],
+    saved_files: &'a BTreeSet<PathBuf>,
+) -> Vec<(&'a CrateCheckCommand, &'a Path)> {
+    let mut by_crate = BTreeMap::new();
+    for saved_file in saved_files {
+        let owner = crates
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, krate)| Some((krate.owns(saved_file)?, idx)))
+            .max_by_key(|(depth, _)| *depth);
+        if let Some((_, idx)) = owner {
+            by_crate.entry(idx).or_insert(saved_file.as_path());
+        }
+    }
+    by_crate.into_iter().map(|(idx, saved_file)| (&crates[idx], saved_file)).collect()
+}
+
+/// Extracts the package name from a cargo package id. Newer cargo versions
+/// use `source#name@version`, or `source#version` when the name is the last
+/// segment of the source path; older ones use `name version (source)`.
+fn package_name(package_id: &str) -> Option<&str> {
+    let (source, fragment) = match package_id.rfind('#') {
+        Some(idx) => (&package_id[..idx], &package_id[idx + 1..]),
+        None => return package_id.split_whitespace().next(),
+    };
+    match fragment.find('@') {
+        Some(idx) => Some(&fragment[..idx]),
+        None if fragment.starts_with(|c: char| c.is_ascii_digit()) => {
+            source.rsplit('/').next().filter(|it| !it.is_empty())
+        }
+        None => Some(fragment),
+    }
+}
+
 struct CargoHandle {
     child: JodChild,
     #[allow(unused)]
@@ -361,3 +526,105 @@ enum JsonMessage {
     Cargo(cargo_metadata::Message),
     Rustc(Diagnostic),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn krate(label: &str, dirs: &[&str]) -> CrateCheckCommand {
+        CrateCheckCommand {
+            label: label.to_string(),
+            include_dirs: dirs.iter().map(PathBuf::from).collect(),
+            command: "build-tool".to_string(),
+            args: vec![
+                "check".to_string(),
+                "{label}".to_string(),
+                "--file={saved_file}".to_string(),
+            ],
+        }
+    }
+
+    #[test]
+    fn reports_each_checked_package_once() {
+        let (sender, receiver) = unbounded();
+        let mut actor = FlycheckActor::new(
+            3,
+            Box::new(move |msg| sender.send(msg).unwrap()),
+            FlycheckConfig::CustomCommand { command: "check".to_string(), args: Vec::new() },
+            PathBuf::from("/ws"),
+        );
+        actor.did_check_artifact("foo 0.1.0 (path+file:///ws/foo)".to_string(), "foo");
+        actor.did_check_artifact("foo 0.1.0 (path+file:///ws/foo)".to_string(), "foo-bin");
+        actor.did_check_artifact("bar 0.2.0 (path+file:///ws/bar)".to_string(), "bar");
+        drop(actor);
+
+        let reports: Vec<_> = receiver
+            .iter()
+            .map(|msg| match msg {
+                Message::Progress {
+                    id: 3,
+                    progress: Progress::DidCheckPackage { package, n_checked },
+                } => (package, n_checked),
+                msg => panic!("unexpected message: {:?}", msg),
+            })
+            .collect();
+        assert_eq!(reports, vec![("foo".to_string(), 1), ("bar".to_string(), 2)]);
+    }
+
+    #[test]
+    fn parses_package_names() {
+        assert_eq!(package_name("foo 0.1.0 (path+file:///ws/foo)"), Some("foo"));
+        assert_eq!(
+            package_name("serde 1.0.130 (registry+https://github.com/rust-lang/crates.io-index)"),
+            Some("serde")
+        );
+        assert_eq!(package_name("path+file:///ws/foo#bar@0.1.0"), Some("bar"));
+        assert_eq!(package_name("path+file:///ws/foo#0.1.0"), Some("foo"));
+        assert_eq!(
+            package_name("registry+https://github.com/rust-lang/crates.io-index#serde@1.0.130"),
+            Some("serde")
+        );
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let krate = krate("//foo:bar", &["/src/foo"]);
+        assert_eq!(
+            krate.substitute(Path::new("/src/foo/lib.rs")),
+            vec!["check", "//foo:bar", "--file=/src/foo/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn innermost_crate_owns_file() {
+        let outer = krate("//foo", &["/src/foo"]);
+        let inner = krate("//foo/bar", &["/src/other", "/src/foo/bar"]);
+        assert_eq!(outer.owns(Path::new("/src/foo/bar/lib.rs")), Some(3));
+        assert_eq!(inner.owns(Path::new("/src/foo/bar/lib.rs")), Some(4));
+        assert_eq!(inner.owns(Path::new("/src/baz/lib.rs")), None);
+
+        let crates = vec![outer, inner];
+        let saved_files = vec![PathBuf::from("/src/foo/bar/lib.rs")].into_iter().collect();
+        let commands = crate_commands(&crates, &saved_files);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].0.label, "//foo/bar");
+    }
+
+    #[test]
+    fn checks_every_crate_with_saved_files() {
+        let crates = vec![krate("//a", &["/src/a"]), krate("//b", &["/src/b"])];
+        let saved_files: BTreeSet<PathBuf> =
+            vec!["/src/b/lib.rs", "/src/a/x.rs", "/src/a/y.rs", "/src/c/lib.rs"]
+                .into_iter()
+                .map(PathBuf::from)
+                .collect();
+        let commands: Vec<_> = crate_commands(&crates, &saved_files)
+            .into_iter()
+            .map(|(krate, saved_file)| (krate.label.as_str(), saved_file.to_path_buf()))
+            .collect();
+        assert_eq!(
+            commands,
+            vec![("//a", PathBuf::from("/src/a/x.rs")), ("//b", PathBuf::from("/src/b/lib.rs"))]
+        );
+    }
+}
@@ -117,6 +117,11 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
     pub fn expand(&self, macro_call: &ast::MacroCall) -> Option<SyntaxNode> {
         self.imp.expand(macro_call)
     }
+    /// Expands the derive macro `derive`, listed in the `#[derive]` attribute `attr`.
+    pub fn expand_derive_macro(&self, attr: &ast::Attr, derive: &ast::Path) -> Option<SyntaxNode> {
+        self.imp.expand_derive_macro(attr, derive)
+    }
+
     pub fn speculative_expand(
         &self,
         actual_macro_call: &ast::MacroCall,
@@ -239,6 +244,11 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.imp.resolve_macro_call(macro_call)
     }
 
+    /// Resolves the derive macro `derive`, listed in the `#[derive]` attribute `attr`.
+    pub fn resolve_derive_macro(&self, attr: &ast::Attr, derive: &ast::Path) -> Option<MacroDef> {
+        self.imp.resolve_derive_macro(attr, derive)
+    }
+
     pub fn resolve_path(&self, path: &ast::Path) -> Option<PathResolution> {
         self.imp.resolve_path(path)
     }
@@ -332,6 +342,15 @@ impl<'db> SemanticsImpl<'db> {
         Some(node)
     }
 
+    fn expand_derive_macro(&self, attr: &ast::Attr, derive: &ast::Path) -> Option<SyntaxNode> {
+        let item = attr.syntax().parent().and_then(ast::Item::cast)?;
+        let sa = self.analyze(item.syntax());
+        let file_id = sa.expand_derive(self.db, InFile::new(sa.file_id, &item), attr, derive)?;
+        let node = self.db.parse_or_expand(file_id)?;
+        self.cache(node.clone(), file_id);
+        Some(node)
+    }
+
     fn speculative_expand(
         &self,
         actual_macro_call: &ast::MacroCall,
@@ -533,6 +552,12 @@ impl<'db> SemanticsImpl<'db> {
         sa.resolve_macro_call(self.db, macro_call)
     }
 
+    fn resolve_derive_macro(&self, attr: &ast::Attr, derive: &ast::Path) -> Option<MacroDef> {
+        let item = attr.syntax().parent().and_then(ast::Item::cast)?;
+        let sa = self.analyze(item.syntax());
+        sa.resolve_derive_macro(self.db, InFile::new(sa.file_id, derive)).map(Into::into)
+    }
+
     fn resolve_path(&self, path: &ast::Path) -> Option<PathResolution> {
         self.analyze(path.syntax()).resolve_path(self.db, path)
     }
@@ -18,7 +18,9 @@ use hir_def::{
     resolver::{resolver_for_scope, Resolver, TypeNs, ValueNs},
     AsMacroCall, DefWithBodyId, FieldId, FunctionId, LocalFieldId, VariantId,
 };
-use hir_expand::{hygiene::Hygiene, name::AsName, HirFileId, InFile};
+use hir_expand::{
+    hygiene::Hygiene, name::AsName, AstId, HirFileId, InFile, MacroCallKind, MacroDefId,
+};
 use hir_ty::{
     diagnostics::{record_literal_missing_fields, record_pattern_missing_fields},
     InferenceResult, Interner, Substitution, TyExt, TyLoweringContext,
@@ -367,6 +369,40 @@ impl SourceAnalyzer {
         Some(macro_call_id.as_file()).filter(|it| it.expansion_level(db.upcast()) < 64)
     }
 
+    pub(crate) fn resolve_derive_macro(
+        &self,
+        db: &dyn HirDatabase,
+        derive: InFile<&ast::Path>,
+    ) -> Option<MacroDefId> {
+        let ctx = body::LowerCtx::new(db.upcast(), derive.file_id);
+        let path = Path::from_src(derive.value.clone(), &ctx)?;
+        self.resolver.resolve_path_as_macro(db.upcast(), path.mod_path())
+    }
+
+    pub(crate) fn expand_derive(
+        &self,
+        db: &dyn HirDatabase,
+        item: InFile<&ast::Item>,
+        attr: &ast::Attr,
+        derive: &ast::Path,
+    ) -> Option<HirFileId> {
+        let krate = self.resolver.krate()?;
+        let def = self.resolve_derive_macro(db, item.with_value(derive))?;
+        let derive_name = derive.segment()?.name_ref()?.text().to_string();
+        // Matches the numbering of `AttrId`s, which only counts outer attributes for items.
+        let derive_attr_index = ast::AttrsOwner::attrs(item.value)
+            .filter(|it| it.kind().is_outer())
+            .position(|it| it == *attr)?;
+        let ast_id = AstId::new(item.file_id, db.ast_id_map(item.file_id).ast_id(item.value));
+        let kind = MacroCallKind::Derive {
+            ast_id,
+            derive_name,
+            derive_attr_index: derive_attr_index as u32,
+        };
+        let macro_call_id = def.as_lazy_macro(db.upcast(), krate, kind);
+        Some(macro_call_id.as_file()).filter(|it| it.expansion_level(db.upcast()) < 64)
+    }
+
     pub(crate) fn resolve_variant(
         &self,
         db: &dyn HirDatabase,
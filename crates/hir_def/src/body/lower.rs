@@ -605,13 +605,14 @@ impl ExprCollector<'_> {
         };
 
         match &res.err {
-            Some(ExpandError::UnresolvedProcMacro) => {
+            Some(ExpandError::UnresolvedProcMacro(reason)) => {
                 self.source_map.diagnostics.push(BodyDiagnostic::UnresolvedProcMacro(
                     UnresolvedProcMacro {
                         file: outer_file,
                         node: syntax_ptr.into(),
                         precise_location: None,
                         macro_name: None,
+                        reason: reason.clone(),
                     },
                 ));
             }
@@ -174,6 +174,8 @@ pub struct UnresolvedProcMacro {
     /// to use instead.
     pub precise_location: Option<TextRange>,
     pub macro_name: Option<String>,
+    /// Why the proc macros of the defining crate could not be loaded, if known.
+    pub reason: Option<String>,
 }
 
 impl Diagnostic for UnresolvedProcMacro {
@@ -182,10 +184,14 @@ impl Diagnostic for UnresolvedProcMacro {
     }
 
     fn message(&self) -> String {
-        match &self.macro_name {
+        let mut message = match &self.macro_name {
             Some(name) => format!("proc macro `{}` not expanded", name),
             None => "proc macro not expanded".to_string(),
+        };
+        if let Some(reason) = &self.reason {
+            format_to!(message, ": {}", reason);
         }
+        message
     }
 
     fn display_source(&self) -> InFile<SyntaxNodePtr> {
@@ -493,7 +493,7 @@ mod diagnostics {
 
         UnconfiguredCode { ast: AstId<ast::Item>, cfg: CfgExpr, opts: CfgOptions },
 
-        UnresolvedProcMacro { ast: MacroCallKind },
+        UnresolvedProcMacro { ast: MacroCallKind, reason: Option<String> },
 
         UnresolvedMacroCall { ast: AstId<ast::MacroCall>, path: ModPath },
 
@@ -545,8 +545,12 @@ mod diagnostics {
             Self { in_module: container, kind: DiagnosticKind::UnconfiguredCode { ast, cfg, opts } }
         }
 
-        pub(super) fn unresolved_proc_macro(container: LocalModuleId, ast: MacroCallKind) -> Self {
-            Self { in_module: container, kind: DiagnosticKind::UnresolvedProcMacro { ast } }
+        pub(super) fn unresolved_proc_macro(
+            container: LocalModuleId,
+            ast: MacroCallKind,
+            reason: Option<String>,
+        ) -> Self {
+            Self { in_module: container, kind: DiagnosticKind::UnresolvedProcMacro { ast, reason } }
         }
 
         pub(super) fn macro_error(
@@ -626,7 +630,7 @@ mod diagnostics {
                     });
                 }
 
-                DiagnosticKind::UnresolvedProcMacro { ast } => {
+                DiagnosticKind::UnresolvedProcMacro { ast, reason } => {
                     let mut precise_location = None;
                     let (file, ast, name) = match ast {
                         MacroCallKind::FnLike { ast_id, .. } => {
@@ -675,6 +679,7 @@ mod diagnostics {
                         node: ast,
                         precise_location,
                         macro_name: name,
+                        reason: reason.clone(),
                     });
                 }
 
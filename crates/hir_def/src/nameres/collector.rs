@@ -5,7 +5,7 @@
 
 use std::iter;
 
-use base_db::{CrateId, FileId, ProcMacroId};
+use base_db::{CrateId, FileId, ProcMacroBehavior, ProcMacroId};
 use cfg::{CfgExpr, CfgOptions};
 use hir_expand::{
     ast_id_map::FileAstId,
@@ -80,6 +80,7 @@ pub(super) fn collect_defs(
     let proc_macros = &crate_graph[def_map.krate].proc_macro;
     let proc_macros = proc_macros
         .iter()
+        .flatten()
         .enumerate()
         .map(|(idx, it)| {
             // FIXME: a hacky way to create a Name from string.
@@ -417,20 +418,12 @@ impl DefCollector<'_> {
         let mut unresolved_macros = std::mem::replace(&mut self.unresolved_macros, Vec::new());
         let pos = unresolved_macros.iter().position(|directive| {
             if let MacroDirectiveKind::Attr { ast_id, mod_item, attr } = &directive.kind {
-                self.skip_attrs.insert(ast_id.ast_id.with_value(*mod_item), *attr);
-
-                let file_id = ast_id.ast_id.file_id;
-                let item_tree = self.db.file_item_tree(file_id);
-                let mod_dir = self.mod_dirs[&directive.module_id].clone();
-                ModCollector {
-                    def_collector: &mut *self,
-                    macro_depth: directive.depth,
-                    module_id: directive.module_id,
-                    file_id,
-                    item_tree: &item_tree,
-                    mod_dir,
-                }
-                .collect(&[*mod_item]);
+                self.collect_item_after_attr(
+                    directive.module_id,
+                    directive.depth,
+                    ast_id.ast_id.with_value(*mod_item),
+                    *attr,
+                );
                 true
             } else {
                 false
@@ -950,6 +943,12 @@ impl DefCollector<'_> {
                     }
                 }
                 MacroDirectiveKind::Derive { ast_id, derive_attr } => {
+                    if let Some(def) = resolver(ast_id.path.clone()) {
+                        if self.proc_macro_behavior(&def) != ProcMacroBehavior::Expand {
+                            cov_mark::hit!(ignored_proc_macro_derive);
+                            return false;
+                        }
+                    }
                     match derive_macro_as_call_id(
                         ast_id,
                         *derive_attr,
@@ -973,19 +972,12 @@ impl DefCollector<'_> {
 
                                 // Resolved to derive helper. Collect the item's attributes again,
                                 // starting after the derive helper.
-                                let file_id = ast_id.ast_id.file_id;
-                                let item_tree = self.db.file_item_tree(file_id);
-                                let mod_dir = self.mod_dirs[&directive.module_id].clone();
-                                self.skip_attrs.insert(InFile::new(file_id, *mod_item), *attr);
-                                ModCollector {
-                                    def_collector: &mut *self,
-                                    macro_depth: directive.depth,
-                                    module_id: directive.module_id,
-                                    file_id,
-                                    item_tree: &item_tree,
-                                    mod_dir,
-                                }
-                                .collect(&[*mod_item]);
+                                self.collect_item_after_attr(
+                                    directive.module_id,
+                                    directive.depth,
+                                    ast_id.ast_id.with_value(*mod_item),
+                                    *attr,
+                                );
 
                                 // Remove the original directive since we resolved it.
                                 return false;
@@ -994,7 +986,28 @@ impl DefCollector<'_> {
                     }
 
                     // Not resolved to a derive helper, so try to resolve as a macro.
-                    // FIXME: not yet :)
+                    // FIXME: expansion isn't supported yet, we only apply the behavior configured
+                    // by the user.
+                    if let Some(def) = resolver(ast_id.path.clone()) {
+                        match self.proc_macro_behavior(&def) {
+                            ProcMacroBehavior::Expand => {}
+                            ProcMacroBehavior::Ignore => {
+                                cov_mark::hit!(ignored_proc_macro_attribute);
+                                self.collect_item_after_attr(
+                                    directive.module_id,
+                                    directive.depth,
+                                    ast_id.ast_id.with_value(*mod_item),
+                                    *attr,
+                                );
+                                return false;
+                            }
+                            ProcMacroBehavior::Disable => {
+                                // Drop the item entirely.
+                                cov_mark::hit!(disabled_proc_macro_attribute);
+                                return false;
+                            }
+                        }
+                    }
                 }
             }
 
@@ -1010,6 +1023,51 @@ impl DefCollector<'_> {
         res
     }
 
+    /// Collects `mod_item` again, treating it as if it had no attributes up to and including
+    /// `attr`.
+    fn collect_item_after_attr(
+        &mut self,
+        module_id: LocalModuleId,
+        depth: usize,
+        mod_item: InFile<ModItem>,
+        attr: AttrId,
+    ) {
+        self.skip_attrs.insert(mod_item, attr);
+
+        let file_id = mod_item.file_id;
+        let item_tree = self.db.file_item_tree(file_id);
+        let mod_dir = self.mod_dirs[&module_id].clone();
+        ModCollector {
+            def_collector: &mut *self,
+            macro_depth: depth,
+            module_id,
+            file_id,
+            item_tree: &item_tree,
+            mod_dir,
+        }
+        .collect(&[mod_item.value]);
+    }
+
+    /// Returns how the user wants uses of the macro `def` to be treated. Only proc macros can be
+    /// configured, everything else is always expanded.
+    fn proc_macro_behavior(&self, def: &MacroDefId) -> ProcMacroBehavior {
+        if !matches!(def.kind, MacroDefKind::ProcMacro(..)) || def.krate == self.def_map.krate {
+            return ProcMacroBehavior::Expand;
+        }
+        let crate_graph = self.db.crate_graph();
+        let behaviors = &crate_graph[def.krate].proc_macro_behavior;
+        if behaviors.is_empty() {
+            return ProcMacroBehavior::Expand;
+        }
+        let def_map = self.db.crate_def_map(def.krate);
+        match def_map.exported_proc_macros.get(def) {
+            Some(proc_macro) => {
+                behaviors.get(proc_macro.name.to_string().as_str()).copied().unwrap_or_default()
+            }
+            None => ProcMacroBehavior::Expand,
+        }
+    }
+
     fn collect_macro_expansion(
         &mut self,
         module_id: LocalModuleId,
@@ -1030,9 +1088,9 @@ impl DefCollector<'_> {
         let err = self.db.macro_expand_error(macro_call_id);
         if let Some(err) = err {
             let diag = match err {
-                hir_expand::ExpandError::UnresolvedProcMacro => {
+                hir_expand::ExpandError::UnresolvedProcMacro(reason) => {
                     // Missing proc macros are non-fatal, so they are handled specially.
-                    DefDiagnostic::unresolved_proc_macro(module_id, loc.kind.clone())
+                    DefDiagnostic::unresolved_proc_macro(module_id, loc.kind.clone(), reason)
                 }
                 _ => DefDiagnostic::macro_error(module_id, loc.kind.clone(), err.to_string()),
             };
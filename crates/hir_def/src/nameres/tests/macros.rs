@@ -1,3 +1,5 @@
+use base_db::ProcMacroBehavior;
+
 use super::*;
 use crate::nameres::proc_macro::{ProcMacroDef, ProcMacroKind};
 
@@ -937,6 +939,121 @@ fn proc_macro_censoring() {
     );
 }
 
+fn check_with_proc_macro_behavior(
+    ra_fixture: &str,
+    macro_name: &str,
+    behavior: ProcMacroBehavior,
+    expect: Expect,
+) {
+    let mut db = TestDB::with_files(ra_fixture);
+    let mut crate_graph = (*db.crate_graph()).clone();
+    let main = crate_graph.iter().next().unwrap();
+    let macros = crate_graph
+        .iter()
+        .find(|&krate| crate_graph[krate].display_name.as_deref() == Some("macros"))
+        .unwrap();
+    crate_graph.set_proc_macro_behavior(macros, macro_name, behavior);
+    db.set_crate_graph(Arc::new(crate_graph));
+    expect.assert_eq(&db.crate_def_map(main).dump(&db));
+}
+
+const PROC_MACRO_ATTR_FIXTURE: &str = r"
+//- /main.rs crate:main deps:macros
+use macros::attribute_macro;
+
+#[attribute_macro]
+struct Foo;
+
+struct Bar;
+
+//- /macros.rs crate:macros
+pub struct TokenStream;
+
+#[proc_macro_attribute]
+pub fn attribute_macro(_args: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+";
+
+#[test]
+fn ignored_proc_macro_attribute() {
+    cov_mark::check!(ignored_proc_macro_attribute);
+    check_with_proc_macro_behavior(
+        PROC_MACRO_ATTR_FIXTURE,
+        "attribute_macro",
+        ProcMacroBehavior::Ignore,
+        expect![[r#"
+            crate
+            Bar: t v
+            Foo: t v
+            attribute_macro: v m
+        "#]],
+    );
+}
+
+#[test]
+fn disabled_proc_macro_attribute() {
+    cov_mark::check!(disabled_proc_macro_attribute);
+    check_with_proc_macro_behavior(
+        PROC_MACRO_ATTR_FIXTURE,
+        "attribute_macro",
+        ProcMacroBehavior::Disable,
+        expect![[r#"
+            crate
+            Bar: t v
+            attribute_macro: v m
+        "#]],
+    );
+}
+
+#[test]
+fn proc_macro_behavior_is_per_macro() {
+    check_with_proc_macro_behavior(
+        PROC_MACRO_ATTR_FIXTURE,
+        "other_macro",
+        ProcMacroBehavior::Disable,
+        expect![[r#"
+            crate
+            Bar: t v
+            Foo: t v
+            attribute_macro: v m
+        "#]],
+    );
+}
+
+#[test]
+fn ignored_proc_macro_derive() {
+    cov_mark::check!(ignored_proc_macro_derive);
+    let mut db = TestDB::with_files(
+        r"
+//- /main.rs crate:main deps:macros
+use macros::DummyTrait;
+
+#[derive(DummyTrait)]
+struct Foo;
+
+//- /macros.rs crate:macros
+pub struct TokenStream;
+
+#[proc_macro_derive(DummyTrait)]
+pub fn derive_macro(_item: TokenStream) -> TokenStream {
+    TokenStream
+}
+",
+    );
+    let mut crate_graph = (*db.crate_graph()).clone();
+    let main = crate_graph.iter().next().unwrap();
+    assert!(!db.crate_def_map(main).diagnostics.is_empty());
+
+    let macros = crate_graph
+        .iter()
+        .find(|&krate| crate_graph[krate].display_name.as_deref() == Some("macros"))
+        .unwrap();
+    crate_graph.set_proc_macro_behavior(macros, "DummyTrait", ProcMacroBehavior::Ignore);
+    db.set_crate_graph(Arc::new(crate_graph));
+    assert!(db.crate_def_map(main).diagnostics.is_empty());
+}
+
 #[test]
 fn collects_derive_helpers() {
     let def_map = compute_crate_def_map(
@@ -34,11 +34,14 @@ impl ProcMacroExpander {
         calling_crate: CrateId,
         tt: &tt::Subtree,
     ) -> Result<tt::Subtree, mbe::ExpandError> {
+        let krate_graph = db.crate_graph();
+        let proc_macros = match &krate_graph[self.krate].proc_macro {
+            Ok(it) => it,
+            Err(reason) => return Err(mbe::ExpandError::UnresolvedProcMacro(Some(reason.clone()))),
+        };
         match self.proc_macro_id {
             Some(id) => {
-                let krate_graph = db.crate_graph();
-                let proc_macro = krate_graph[self.krate]
-                    .proc_macro
+                let proc_macro = proc_macros
                     .get(id.0 as usize)
                     .clone()
                     .ok_or_else(|| err!("No derive macro found."))?;
@@ -48,7 +51,7 @@ impl ProcMacroExpander {
 
                 proc_macro.expander.expand(&tt, None, &env).map_err(mbe::ExpandError::from)
             }
-            None => Err(mbe::ExpandError::UnresolvedProcMacro),
+            None => Err(mbe::ExpandError::UnresolvedProcMacro(None)),
         }
     }
 }
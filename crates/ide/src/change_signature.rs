@@ -0,0 +1,658 @@
+//! Rewrites the parameter list of a function and all of its call sites.
+
+use std::fmt;
+
+use hir::{AsAssocItem, HasSource, Semantics};
+use ide_db::{
+    base_db::{FileId, SourceDatabaseExt},
+    defs::{Definition, NameRefClass},
+    traits::resolve_target_trait,
+    RootDatabase,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::{
+    algo::find_node_at_range,
+    ast::{self, ArgListOwner, NameOwner},
+    lex_single_syntax_kind, AstNode, SyntaxKind, TextRange, T,
+};
+use text_edit::TextEdit;
+
+use crate::{references::rename::source_edit_from_references, FilePosition, SourceChange};
+
+/// A parameter of the changed signature, in its new position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureParam {
+    /// A parameter the function already has, identified by its current name (or pattern).
+    Existing { old_name: String, new_name: String },
+    /// A new parameter. Callers pass `default_value` for it.
+    New { name: String, ty: String, default_value: String },
+}
+
+#[derive(Debug)]
+pub struct ChangeSignatureError(String);
+
+impl fmt::Display for ChangeSignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+macro_rules! bail {
+    ($($arg:tt)*) => {return Err(ChangeSignatureError(format!($($arg)*)))}
+}
+
+type ChangeSignatureResult<T> = Result<T, ChangeSignatureError>;
+
+// Feature: Change Signature
+//
+// Reorders, adds, removes and renames the parameters of the function under the cursor.
+// The definition, the matching declaration and implementations of trait methods, and all
+// call sites are updated. Callers pass the given default value for added parameters.
+//
+// |===
+// | Editor  | Action Name
+//
+// | VS Code | **Rust Analyzer: Change signature...**
+// |===
+pub(crate) fn change_signature(
+    db: &RootDatabase,
+    position: FilePosition,
+    params: &[SignatureParam],
+) -> ChangeSignatureResult<SourceChange> {
+    let sema = Semantics::new(db);
+    let func = match find_function(&sema, position) {
+        Some(it) => it,
+        None => bail!("No function found at position"),
+    };
+    let name = func.name(db);
+
+    let mut functions = Vec::new();
+    for it in related_functions(&sema, func)? {
+        let source = fn_source(&sema, it)?;
+        if db.source_root(db.file_source_root(source.file_id)).is_library {
+            bail!("Cannot change the signature of `{}`: it is defined in a library", name);
+        }
+        functions.push(source);
+    }
+    let primary = match functions.iter().find(|it| it.def == func) {
+        Some(it) => it,
+        None => bail!("No function found at position"),
+    };
+
+    let old_params: Vec<ast::Param> = primary.param_list.params().collect();
+    if functions.iter().any(|it| it.param_list.params().count() != old_params.len()) {
+        bail!(
+            "Cannot change the signature of `{}`: its declarations have different parameters",
+            name
+        );
+    }
+    let has_self = primary.param_list.self_param().is_some();
+    let planned = plan_params(&old_params, params)?;
+
+    let mut edits: FxHashMap<FileId, FileEdits> = FxHashMap::default();
+
+    for source in &functions {
+        let params: Vec<ast::Param> = source.param_list.params().collect();
+        let mut new_params = Vec::new();
+        if let Some(self_param) = source.param_list.self_param() {
+            new_params.push(self_param.syntax().text().to_string());
+        }
+        for planned in &planned {
+            match planned {
+                PlannedParam::Existing { index, rename } => {
+                    let param = &params[*index];
+                    let (new_name, ident_pat) = match (rename, param.pat()) {
+                        (Some(new_name), Some(ast::Pat::IdentPat(it))) => (new_name.as_str(), it),
+                        _ => {
+                            new_params.push(param.syntax().text().to_string());
+                            continue;
+                        }
+                    };
+                    new_params.push(rename_in_param(param, &ident_pat, new_name));
+
+                    let local = match sema.to_def(&ident_pat) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    let def = Definition::Local(local);
+                    for (file_id, references) in def.usages(&sema).all() {
+                        let file_edits = edits.entry(file_id).or_default();
+                        for indel in source_edit_from_references(&references, def, new_name) {
+                            file_edits.edits.push((indel.delete, indel.insert));
+                        }
+                    }
+                }
+                PlannedParam::New { param, .. } => new_params.push(param.clone()),
+            }
+        }
+        edits.entry(source.file_id).or_default().edits.push((
+            source.param_list.syntax().text_range(),
+            format!("({})", new_params.join(", ")),
+        ));
+    }
+
+    let mut seen = FxHashSet::default();
+    for source in &functions {
+        let def = Definition::ModuleDef(source.def.into());
+        for (file_id, references) in def.usages(&sema).all() {
+            let file = sema.parse(file_id);
+            for reference in references {
+                if !seen.insert((file_id, reference.range)) {
+                    continue;
+                }
+                let name_ref = match reference.name {
+                    ast::NameLike::NameRef(it) => it,
+                    _ => continue,
+                };
+                // references inside macro calls can't be rewritten
+                if name_ref.syntax().ancestors().last().as_ref() != Some(file.syntax()) {
+                    continue;
+                }
+                if let Some(call) = rewrite_call(&name_ref, has_self, old_params.len(), &planned) {
+                    edits.entry(file_id).or_default().calls.push(call);
+                }
+            }
+        }
+    }
+
+    let edits = edits
+        .into_iter()
+        .map(|(file_id, file_edits)| (file_id, file_edits.finish(&db.file_text(file_id))))
+        .collect::<FxHashMap<_, _>>();
+    Ok(SourceChange::from(edits))
+}
+
+struct FnSource {
+    def: hir::Function,
+    file_id: FileId,
+    fn_: ast::Fn,
+    param_list: ast::ParamList,
+}
+
+enum PlannedParam {
+    Existing { index: usize, rename: Option<String> },
+    New { param: String, default_value: String },
+}
+
+/// A rewritten argument list: arguments are either copied from the call site or inserted.
+enum ArgPiece {
+    Source(TextRange),
+    Text(String),
+}
+
+#[derive(Default)]
+struct FileEdits {
+    edits: Vec<(TextRange, String)>,
+    calls: Vec<(TextRange, Vec<ArgPiece>)>,
+}
+
+impl FileEdits {
+    fn finish(self, text: &str) -> TextEdit {
+        let FileEdits { mut edits, mut calls } = self;
+        // Arguments may themselves contain edited calls or renamed locals, so rewrite the
+        // innermost argument lists first and splice their edits into the outer ones.
+        calls.sort_by_key(|(range, _)| range.len());
+        for (range, pieces) in calls {
+            let mut args = Vec::new();
+            for piece in pieces {
+                match piece {
+                    ArgPiece::Text(it) => args.push(it),
+                    ArgPiece::Source(arg_range) => {
+                        let (inner, rest): (Vec<_>, Vec<_>) =
+                            edits.into_iter().partition(|(it, _)| arg_range.contains_range(*it));
+                        edits = rest;
+                        let mut builder = TextEdit::builder();
+                        for (it, insert) in inner {
+                            builder.replace(it - arg_range.start(), insert);
+                        }
+                        let mut arg = text[arg_range].to_string();
+                        builder.finish().apply(&mut arg);
+                        args.push(arg);
+                    }
+                }
+            }
+            // edits inside removed arguments are dropped together with them
+            edits.retain(|(it, _)| !range.contains_range(*it));
+            edits.push((range, format!("({})", args.join(", "))));
+        }
+
+        edits.sort_by_key(|(range, _)| range.start());
+        let mut builder = TextEdit::builder();
+        for (range, insert) in edits {
+            builder.replace(range, insert);
+        }
+        builder.finish()
+    }
+}
+
+fn find_function(sema: &Semantics<RootDatabase>, position: FilePosition) -> Option<hir::Function> {
+    let file = sema.parse(position.file_id);
+    let syntax = file.syntax();
+    if let Some(name_ref) =
+        sema.find_node_at_offset_with_descend::<ast::NameRef>(syntax, position.offset)
+    {
+        if let Some(NameRefClass::Definition(Definition::ModuleDef(hir::ModuleDef::Function(it)))) =
+            NameRefClass::classify(sema, &name_ref)
+        {
+            return Some(it);
+        }
+    }
+    let fn_ = sema.find_node_at_offset_with_descend::<ast::Fn>(syntax, position.offset)?;
+    sema.to_def(&fn_)
+}
+
+/// Returns the function together with the trait declaration and all implementations
+/// that have to keep the same signature.
+fn related_functions(
+    sema: &Semantics<RootDatabase>,
+    func: hir::Function,
+) -> ChangeSignatureResult<Vec<hir::Function>> {
+    let db = sema.db;
+    let trait_ = match func.as_assoc_item(db).map(|it| it.container(db)) {
+        Some(hir::AssocItemContainer::Trait(it)) => Some(it),
+        Some(hir::AssocItemContainer::Impl(_)) => {
+            let source = fn_source(sema, func)?;
+            source
+                .fn_
+                .syntax()
+                .ancestors()
+                .find_map(ast::Impl::cast)
+                .and_then(|impl_| resolve_target_trait(sema, &impl_))
+        }
+        None => None,
+    };
+    let trait_ = match trait_ {
+        Some(it) => it,
+        None => return Ok(vec![func]),
+    };
+
+    let name = func.name(db);
+    let same_name = |items: Vec<hir::AssocItem>| {
+        items.into_iter().filter_map(|it| match it {
+            hir::AssocItem::Function(it) if it.name(db) == name => Some(it),
+            _ => None,
+        })
+    };
+    let mut res: Vec<hir::Function> = same_name(trait_.items(db)).collect();
+    for impl_ in hir::Impl::all_for_trait(db, trait_) {
+        res.extend(same_name(impl_.items(db)));
+    }
+    if !res.contains(&func) {
+        res.push(func);
+    }
+    Ok(res)
+}
+
+fn fn_source(
+    sema: &Semantics<RootDatabase>,
+    func: hir::Function,
+) -> ChangeSignatureResult<FnSource> {
+    let db = sema.db;
+    let name = func.name(db);
+    let source = match func.source(db) {
+        Some(it) => it,
+        None => bail!("No source found for `{}`", name),
+    };
+    let file_id = source.file_id.original_file(db);
+    if source.file_id != file_id.into() {
+        bail!("Cannot change the signature of `{}`: it is defined by a macro", name);
+    }
+    // re-find the node in the tree known to `sema` so that it can be resolved
+    let file = sema.parse(file_id);
+    let fn_ = find_node_at_range::<ast::Fn>(file.syntax(), source.value.syntax().text_range());
+    match fn_.and_then(|fn_| Some((fn_.param_list()?, fn_))) {
+        Some((param_list, fn_)) => Ok(FnSource { def: func, file_id, fn_, param_list }),
+        None => bail!("No parameter list found for `{}`", name),
+    }
+}
+
+fn plan_params(
+    old_params: &[ast::Param],
+    params: &[SignatureParam],
+) -> ChangeSignatureResult<Vec<PlannedParam>> {
+    let old_names: Vec<String> = old_params
+        .iter()
+        .map(|param| match param.pat() {
+            Some(ast::Pat::IdentPat(it)) => it.name().map(|it| it.to_string()).unwrap_or_default(),
+            Some(it) => it.to_string(),
+            None => String::new(),
+        })
+        .collect();
+
+    let mut used = FxHashSet::default();
+    let mut names = FxHashSet::default();
+    let mut res = Vec::new();
+    for param in params {
+        match param {
+            SignatureParam::Existing { old_name, new_name } => {
+                let index = match old_names.iter().position(|it| it == old_name) {
+                    Some(it) => it,
+                    None => bail!("No parameter named `{}`", old_name),
+                };
+                if !used.insert(index) {
+                    bail!("Parameter `{}` is used more than once", old_name);
+                }
+                let rename = if new_name != old_name {
+                    if !matches!(old_params[index].pat(), Some(ast::Pat::IdentPat(_))) {
+                        bail!("Parameter `{}` cannot be renamed", old_name);
+                    }
+                    check_name(new_name)?;
+                    Some(new_name.clone())
+                } else {
+                    None
+                };
+                if new_name != "_" && !names.insert(new_name.clone()) {
+                    bail!("Duplicate parameter name `{}`", new_name);
+                }
+                res.push(PlannedParam::Existing { index, rename });
+            }
+            SignatureParam::New { name, ty, default_value } => {
+                check_name(name)?;
+                if name != "_" && !names.insert(name.clone()) {
+                    bail!("Duplicate parameter name `{}`", name);
+                }
+                let ty = match ast::Type::parse(ty.trim()) {
+                    Ok(it) => it,
+                    Err(()) => bail!("Invalid type `{}`", ty),
+                };
+                let default_value = match ast::Expr::parse(default_value.trim()) {
+                    Ok(it) => it,
+                    Err(()) => bail!("Invalid default value `{}`", default_value),
+                };
+                res.push(PlannedParam::New {
+                    param: format!("{}: {}", name, ty),
+                    default_value: default_value.to_string(),
+                });
+            }
+        }
+    }
+    Ok(res)
+}
+
+fn check_name(name: &str) -> ChangeSignatureResult<()> {
+    match lex_single_syntax_kind(name) {
+        Some((SyntaxKind::IDENT, None)) | Some((T![_], None)) => Ok(()),
+        _ => bail!("Invalid parameter name `{}`", name),
+    }
+}
+
+fn rename_in_param(param: &ast::Param, ident_pat: &ast::IdentPat, new_name: &str) -> String {
+    let mut text = param.syntax().text().to_string();
+    if let Some(name) = ident_pat.name() {
+        let range = name.syntax().text_range() - param.syntax().text_range().start();
+        text.replace_range(std::ops::Range::<usize>::from(range), new_name);
+    }
+    text
+}
+
+/// Computes the new argument list for the call `name_ref` refers to, if it is the callee of
+/// a call with the expected number of arguments.
+fn rewrite_call(
+    name_ref: &ast::NameRef,
+    has_self: bool,
+    param_count: usize,
+    planned: &[PlannedParam],
+) -> Option<(TextRange, Vec<ArgPiece>)> {
+    let (arg_list, receivers) = match name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast)
+    {
+        Some(call) => (call.arg_list()?, 0),
+        None => {
+            let segment = name_ref.syntax().parent().and_then(ast::PathSegment::cast)?;
+            let path = segment.parent_path();
+            let path_expr = path.syntax().parent().and_then(ast::PathExpr::cast)?;
+            let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast)?;
+            if call.expr()?.syntax() != path_expr.syntax() {
+                return None;
+            }
+            (call.arg_list()?, if has_self { 1 } else { 0 })
+        }
+    };
+    let args: Vec<ast::Expr> = arg_list.args().collect();
+    if args.len() != receivers + param_count {
+        cov_mark::hit!(change_signature_skip_wrong_arg_count);
+        return None;
+    }
+    let mut pieces: Vec<ArgPiece> =
+        args[..receivers].iter().map(|it| ArgPiece::Source(it.syntax().text_range())).collect();
+    pieces.extend(planned.iter().map(|it| match it {
+        PlannedParam::Existing { index, .. } => {
+            ArgPiece::Source(args[receivers + index].syntax().text_range())
+        }
+        PlannedParam::New { default_value, .. } => ArgPiece::Text(default_value.clone()),
+    }));
+    Some((arg_list.syntax().text_range(), pieces))
+}
+
+#[cfg(test)]
+mod tests {
+    use stdx::trim_indent;
+    use test_utils::assert_eq_text;
+
+    use crate::fixture;
+
+    use super::SignatureParam;
+
+    fn existing(old_name: &str, new_name: &str) -> SignatureParam {
+        SignatureParam::Existing { old_name: old_name.to_string(), new_name: new_name.to_string() }
+    }
+
+    fn new(name: &str, ty: &str, default_value: &str) -> SignatureParam {
+        SignatureParam::New {
+            name: name.to_string(),
+            ty: ty.to_string(),
+            default_value: default_value.to_string(),
+        }
+    }
+
+    fn check(params: &[SignatureParam], ra_fixture_before: &str, ra_fixture_after: &str) {
+        let ra_fixture_after = &trim_indent(ra_fixture_after);
+        let (analysis, position) = fixture::position(ra_fixture_before);
+        let result = analysis.change_signature(position, params).unwrap();
+        if let Some(error) = ra_fixture_after.strip_prefix("error:") {
+            match result {
+                Ok(_) => panic!("expected an error"),
+                Err(err) => assert_eq!(error.trim(), err.to_string()),
+            }
+            return;
+        }
+        let source_change = result.unwrap();
+        assert_eq!(source_change.source_file_edits.len(), 1);
+        let (file_id, edit) = source_change.source_file_edits.into_iter().next().unwrap();
+        let mut text = analysis.file_text(file_id).unwrap().to_string();
+        edit.apply(&mut text);
+        assert_eq_text!(ra_fixture_after, &text);
+    }
+
+    #[test]
+    fn reorder_params() {
+        check(
+            &[existing("b", "b"), existing("a", "a")],
+            r#"
+fn foo$0(a: i32, b: u8) -> i32 { a + b as i32 }
+
+fn main() {
+    foo(1, 2);
+    let f = foo;
+}
+"#,
+            r#"
+fn foo(b: u8, a: i32) -> i32 { a + b as i32 }
+
+fn main() {
+    foo(2, 1);
+    let f = foo;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_and_remove_params() {
+        check(
+            &[existing("a", "a"), new("c", "bool", "true")],
+            r#"
+fn foo(a: i32, b: u8) {}
+
+fn main() {
+    foo$0(1 + 1, 2);
+    self::foo(3, 4);
+}
+"#,
+            r#"
+fn foo(a: i32, c: bool) {}
+
+fn main() {
+    foo(1 + 1, true);
+    self::foo(3, true);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn rename_param() {
+        check(
+            &[existing("a", "x"), existing("b", "b")],
+            r#"
+struct S { a: i32 }
+fn foo$0(mut a: i32, b: i32) -> S {
+    a += b;
+    S { a }
+}
+"#,
+            r#"
+struct S { a: i32 }
+fn foo(mut x: i32, b: i32) -> S {
+    x += b;
+    S { a: x }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn method_call_and_ufcs() {
+        check(
+            &[new("y", "u32", "0"), existing("x", "x")],
+            r#"
+struct S;
+impl S {
+    fn frobnicate$0(&self, x: u32) {}
+}
+fn main() {
+    S.frobnicate(1);
+    S::frobnicate(&S, 2);
+}
+"#,
+            r#"
+struct S;
+impl S {
+    fn frobnicate(&self, y: u32, x: u32) {}
+}
+fn main() {
+    S.frobnicate(0, 1);
+    S::frobnicate(&S, 0, 2);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn trait_method_and_impls() {
+        check(
+            &[existing("b", "b"), existing("a", "a")],
+            r#"
+trait Tr {
+    fn f(&self, a: u8, b: u16);
+}
+struct A;
+impl Tr for A {
+    fn f$0(&self, a: u8, b: u16) {}
+}
+struct B;
+impl Tr for B {
+    fn f(&self, x: u8, _: u16) {}
+}
+fn g<T: Tr>(t: T) {
+    t.f(1, 2);
+    A.f(3, 4);
+    B.f(5, 6);
+}
+"#,
+            r#"
+trait Tr {
+    fn f(&self, b: u16, a: u8);
+}
+struct A;
+impl Tr for A {
+    fn f(&self, b: u16, a: u8) {}
+}
+struct B;
+impl Tr for B {
+    fn f(&self, _: u16, x: u8) {}
+}
+fn g<T: Tr>(t: T) {
+    t.f(2, 1);
+    A.f(4, 3);
+    B.f(6, 5);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn nested_calls() {
+        check(
+            &[existing("b", "y"), existing("a", "a")],
+            r#"
+fn foo$0(a: i32, b: i32) -> i32 {
+    foo(foo(a, b), b)
+}
+"#,
+            r#"
+fn foo(y: i32, a: i32) -> i32 {
+    foo(y, foo(y, a))
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn skips_calls_with_wrong_arg_count() {
+        cov_mark::check!(change_signature_skip_wrong_arg_count);
+        check(
+            &[],
+            r#"
+fn foo$0(a: i32) {}
+fn main() {
+    foo();
+    foo(1);
+}
+"#,
+            r#"
+fn foo() {}
+fn main() {
+    foo();
+    foo();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn errors() {
+        check(&[existing("c", "c")], "fn foo$0(a: i32) {}", "error: No parameter named `c`");
+        check(
+            &[existing("a", "a"), existing("a", "b")],
+            "fn foo$0(a: i32) {}",
+            "error: Parameter `a` is used more than once",
+        );
+        check(&[existing("a", "1x")], "fn foo$0(a: i32) {}", "error: Invalid parameter name `1x`");
+        check(
+            &[existing("a", "a"), new("a", "i32", "0")],
+            "fn foo$0(a: i32) {}",
+            "error: Duplicate parameter name `a`",
+        );
+        check(&[new("b", "i32", "1 +")], "fn foo$0() {}", "error: Invalid default value `1 +`");
+    }
+}
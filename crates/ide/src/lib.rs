@@ -24,6 +24,7 @@ mod display;
 
 mod annotations;
 mod call_hierarchy;
+mod change_signature;
 mod diagnostics;
 mod expand_macro;
 mod extend_selection;
@@ -71,6 +72,7 @@ use crate::display::ToNav;
 pub use crate::{
     annotations::{Annotation, AnnotationConfig, AnnotationKind},
     call_hierarchy::CallItem,
+    change_signature::{ChangeSignatureError, SignatureParam},
     diagnostics::{Diagnostic, DiagnosticsConfig, Severity},
     display::navigation_target::NavigationTarget,
     expand_macro::ExpandedMacro,
@@ -218,7 +220,7 @@ impl Analysis {
             None,
             cfg_options,
             Env::default(),
-            Default::default(),
+            Ok(Vec::new()),
         );
         change.change_file(file_id, Some(Arc::new(text)));
         change.set_crate_graph(crate_graph);
@@ -610,6 +612,16 @@ impl Analysis {
         self.with_db(|db| references::rename::will_rename_file(db, file_id, new_name_stem))
     }
 
+    /// Returns the edit required to change the parameters of the function at
+    /// the position to `params`, updating all of its call sites.
+    pub fn change_signature(
+        &self,
+        position: FilePosition,
+        params: &[SignatureParam],
+    ) -> Cancelable<Result<SourceChange, ChangeSignatureError>> {
+        self.with_db(|db| change_signature::change_signature(db, position, params))
+    }
+
     pub fn structural_search_replace(
         &self,
         query: &str,
@@ -647,6 +659,20 @@ impl Analysis {
         self.with_db(|db| move_item::move_item(db, range, direction))
     }
 
+    /// Moves the item whose name is at `position` into the module the
+    /// `destination` path resolves to, updating all references to it.
+    pub fn move_item_to_module(
+        &self,
+        config: &AssistConfig,
+        position: FilePosition,
+        destination: &str,
+        reexport: bool,
+    ) -> Cancelable<Result<SourceChange, String>> {
+        self.with_db(|db| {
+            ide_assists::move_item_to_module(db, config, position, destination, reexport)
+        })
+    }
+
     /// Performs an operation on that may be Canceled.
     fn with_db<F, T>(&self, f: F) -> Cancelable<T>
     where
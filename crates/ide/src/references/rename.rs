@@ -400,7 +400,7 @@ fn text_edit_from_self_param(self_param: &ast::SelfParam, new_name: &str) -> Opt
     Some(TextEdit::replace(self_param.syntax().text_range(), replacement_text))
 }
 
-fn source_edit_from_references(
+pub(crate) fn source_edit_from_references(
     references: &[FileReference],
     def: Definition,
     new_name: &str,
@@ -296,7 +296,7 @@ impl AssistBuilder {
         self.source_change.push_file_system_edit(file_system_edit);
     }
 
-    fn finish(mut self) -> SourceChange {
+    pub(crate) fn finish(mut self) -> SourceChange {
         self.commit();
         mem::take(&mut self.source_change)
     }
@@ -0,0 +1,584 @@
+use ide_db::{defs::Definition, helpers::mod_path_to_ast};
+use syntax::{
+    ast::{
+        self, edit::IndentLevel, ArgListOwner, AstNode, BinOp, LiteralKind, PrefixOp,
+        VisibilityOwner,
+    },
+    SyntaxKind, SyntaxNode,
+};
+
+use crate::{assist_context::AssistBuilder, AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: convert_bool_to_enum
+//
+// Replaces a `bool` local, parameter or field with a dedicated two-variant enum, rewriting the
+// values assigned to it and the places where it is used as a condition.
+//
+// ```
+// fn open(path: &str, $0create: bool) {
+//     if create {
+//         make_file(path);
+//     }
+// }
+//
+// fn main() {
+//     open("a.txt", true);
+// }
+// ```
+// ->
+// ```
+// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+// enum Create {
+//     Yes,
+//     No,
+// }
+//
+// fn open(path: &str, create: Create) {
+//     if create == Create::Yes {
+//         make_file(path);
+//     }
+// }
+//
+// fn main() {
+//     open("a.txt", Create::Yes);
+// }
+// ```
+pub(crate) fn convert_bool_to_enum(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let name = ctx.find_node_at_offset::<ast::Name>()?;
+    let target = BoolTarget::find(ctx, &name)?;
+
+    let enum_name = to_camel_case(&name.text());
+    if enum_name.is_empty() {
+        return None;
+    }
+    let anchor = name.syntax().ancestors().filter_map(ast::Item::cast).find(|it| {
+        matches!(
+            it.syntax().parent().map(|p| p.kind()),
+            Some(SyntaxKind::SOURCE_FILE) | Some(SyntaxKind::ITEM_LIST)
+        )
+    })?;
+    let new_enum =
+        NewEnum { name: enum_name.clone(), module: ctx.sema.scope(anchor.syntax()).module()? };
+
+    acc.add(
+        AssistId("convert_bool_to_enum", AssistKind::RefactorRewrite),
+        format!("Convert `{}` to enum `{}`", name, enum_name),
+        name.syntax().text_range(),
+        |edit| {
+            for (file_id, refs) in target.def.usages(&ctx.sema).all() {
+                edit.edit_file(file_id);
+                for r in refs {
+                    if let Some(name_ref) = r.name.as_name_ref() {
+                        let enum_path = new_enum.path_from(ctx, name_ref.syntax());
+                        edit_usage(target.def, &enum_path, edit, name_ref);
+                    }
+                }
+            }
+            if let Some(fn_def) = &target.param_of {
+                edit_call_sites(ctx, edit, fn_def, &new_enum, &name);
+            }
+
+            edit.edit_file(ctx.frange.file_id);
+            if let Some(ty) = &target.ty {
+                edit.replace(ty.syntax().text_range(), enum_name.clone());
+            }
+            if let Some(init) = &target.init {
+                convert_value(edit, &enum_name, init);
+            }
+
+            let indent = IndentLevel::from_node(anchor.syntax());
+            let vis = target.visibility.as_ref().map_or(String::new(), |it| format!("{} ", it));
+            let enum_def = format!(
+                "#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n{indent}{vis}enum {name} {{\n{variants_indent}Yes,\n{variants_indent}No,\n{indent}}}\n\n{indent}",
+                indent = indent,
+                variants_indent = indent + 1,
+                vis = vis,
+                name = enum_name,
+            );
+            edit.insert(anchor.syntax().text_range().start(), enum_def);
+        },
+    )
+}
+
+/// The enum replacing the `bool`.
+struct NewEnum {
+    name: String,
+    module: hir::Module,
+}
+
+impl NewEnum {
+    /// The path to the enum from the module containing `node`.
+    fn path_from(&self, ctx: &AssistContext, node: &SyntaxNode) -> String {
+        let module = match ctx.sema.scope(node).module() {
+            Some(it) if it != self.module => it,
+            _ => return self.name.clone(),
+        };
+        match module.find_use_path(ctx.db(), hir::ModuleDef::Module(self.module)) {
+            Some(path) => format!("{}::{}", mod_path_to_ast(&path), self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// The `bool` declaration being converted.
+struct BoolTarget {
+    def: Definition,
+    /// The `bool` type annotation, if any.
+    ty: Option<ast::Type>,
+    /// The initializer of a `let` statement.
+    init: Option<ast::Expr>,
+    /// The function whose call sites need to be updated for parameters.
+    param_of: Option<ast::Fn>,
+    visibility: Option<ast::Visibility>,
+}
+
+impl BoolTarget {
+    fn find(ctx: &AssistContext, name: &ast::Name) -> Option<BoolTarget> {
+        let parent = name.syntax().parent()?;
+        if let Some(field) = ast::RecordField::cast(parent.clone()) {
+            let def = ctx.sema.to_def(&field)?;
+            if !def.ty(ctx.db()).is_bool() {
+                return None;
+            }
+            return Some(BoolTarget {
+                def: Definition::Field(def),
+                ty: field.ty(),
+                init: None,
+                param_of: None,
+                visibility: field.visibility(),
+            });
+        }
+
+        let ident_pat = ast::IdentPat::cast(parent)?;
+        if ident_pat.ref_token().is_some() || ident_pat.at_token().is_some() {
+            return None;
+        }
+        if !ctx.sema.type_of_pat(&ast::Pat::IdentPat(ident_pat.clone()))?.is_bool() {
+            return None;
+        }
+        let def = Definition::Local(ctx.sema.to_def(&ident_pat)?);
+
+        let container = ident_pat.syntax().parent()?;
+        if let Some(let_stmt) = ast::LetStmt::cast(container.clone()) {
+            return Some(BoolTarget {
+                def,
+                ty: let_stmt.ty(),
+                init: let_stmt.initializer(),
+                param_of: None,
+                visibility: None,
+            });
+        }
+
+        let param = ast::Param::cast(container)?;
+        let fn_def = param.syntax().ancestors().nth(2).and_then(ast::Fn::cast)?;
+        // The signature of trait methods and their implementations can't be changed on its own.
+        if fn_def.syntax().ancestors().nth(2).filter(is_trait_or_trait_impl).is_some() {
+            return None;
+        }
+        Some(BoolTarget {
+            def,
+            ty: param.ty(),
+            init: None,
+            visibility: fn_def.visibility(),
+            param_of: Some(fn_def),
+        })
+    }
+}
+
+fn is_trait_or_trait_impl(node: &SyntaxNode) -> bool {
+    match ast::Impl::cast(node.clone()) {
+        Some(impl_) => impl_.trait_().is_some(),
+        None => ast::Trait::can_cast(node.kind()),
+    }
+}
+
+/// Rewrites a single reference to the converted local or field.
+fn edit_usage(def: Definition, enum_path: &str, edit: &mut AssistBuilder, name_ref: &ast::NameRef) {
+    // `Foo { flag: value }` or `Foo { flag }`
+    if let Some(field) = ast::RecordExprField::for_field_name(name_ref) {
+        match (def, field.name_ref(), field.expr()) {
+            (Definition::Field(_), Some(_), Some(expr)) => convert_value(edit, enum_path, &expr),
+            (Definition::Field(_), None, _) => edit.replace(
+                field.syntax().text_range(),
+                format!("{0}: if {0} {{ {1}::Yes }} else {{ {1}::No }}", name_ref, enum_path),
+            ),
+            (Definition::Local(_), None, _) => edit.replace(
+                field.syntax().text_range(),
+                format!("{0}: {0} == {1}::Yes", name_ref, enum_path),
+            ),
+            _ => (),
+        }
+        return;
+    }
+
+    let expr = match def {
+        Definition::Field(_) => {
+            name_ref.syntax().parent().and_then(ast::FieldExpr::cast).map(ast::Expr::FieldExpr)
+        }
+        _ => name_ref
+            .syntax()
+            .ancestors()
+            .nth(3)
+            .and_then(ast::PathExpr::cast)
+            .map(ast::Expr::PathExpr),
+    };
+    let expr = match expr {
+        Some(it) => it,
+        None => return,
+    };
+
+    let parent = expr.syntax().parent();
+    if let Some(bin_expr) = parent.clone().and_then(ast::BinExpr::cast) {
+        if bin_expr.op_kind() == Some(BinOp::Assignment) && bin_expr.lhs().as_ref() == Some(&expr) {
+            if let Some(rhs) = bin_expr.rhs() {
+                convert_value(edit, enum_path, &rhs);
+            }
+            return;
+        }
+    }
+
+    // `!flag` is better expressed as a comparison with the other variant.
+    let (expr, variant) = match parent.and_then(ast::PrefixExpr::cast) {
+        Some(prefix) if prefix.op_kind() == Some(PrefixOp::Not) => {
+            (ast::Expr::PrefixExpr(prefix), "No")
+        }
+        _ => (expr, "Yes"),
+    };
+    let operand = match &expr {
+        ast::Expr::PrefixExpr(prefix) => match prefix.expr() {
+            Some(it) => it.syntax().text().to_string(),
+            None => return,
+        },
+        _ => expr.syntax().text().to_string(),
+    };
+    let comparison = format!("{} == {}::{}", operand, enum_path, variant);
+    let comparison = if needs_parens(&expr) { format!("({})", comparison) } else { comparison };
+    edit.replace(expr.syntax().text_range(), comparison);
+}
+
+/// Updates the arguments passed for the converted parameter.
+fn edit_call_sites(
+    ctx: &AssistContext,
+    edit: &mut AssistBuilder,
+    fn_def: &ast::Fn,
+    new_enum: &NewEnum,
+    name: &ast::Name,
+) -> Option<()> {
+    let param_list = fn_def.param_list()?;
+    let position = param_list
+        .params()
+        .position(|p| p.syntax().text_range().contains_range(name.syntax().text_range()))?;
+    let has_self = param_list.self_param().is_some();
+    let function = ctx.sema.to_def(fn_def)?;
+
+    let usages = Definition::ModuleDef(hir::ModuleDef::Function(function)).usages(&ctx.sema).all();
+    for (file_id, refs) in usages {
+        edit.edit_file(file_id);
+        for r in refs {
+            let name_ref = match r.name.as_name_ref() {
+                Some(it) => it,
+                None => continue,
+            };
+            let arg = if let Some(call) =
+                name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast)
+            {
+                call.arg_list().and_then(|it| it.args().nth(position))
+            } else {
+                let path_expr = name_ref.syntax().ancestors().nth(3).and_then(ast::PathExpr::cast);
+                let call =
+                    path_expr.and_then(|it| it.syntax().parent()).and_then(ast::CallExpr::cast);
+                let idx = if has_self { position + 1 } else { position };
+                call.and_then(|it| it.arg_list()).and_then(|it| it.args().nth(idx))
+            };
+            if let Some(arg) = arg {
+                convert_value(edit, &new_enum.path_from(ctx, arg.syntax()), &arg);
+            }
+        }
+    }
+    Some(())
+}
+
+/// Turns a `bool` valued expression into the corresponding enum variant.
+fn convert_value(edit: &mut AssistBuilder, enum_path: &str, expr: &ast::Expr) {
+    let range = expr.syntax().text_range();
+    match expr {
+        ast::Expr::Literal(lit) if lit.kind() == LiteralKind::Bool(true) => {
+            edit.replace(range, format!("{}::Yes", enum_path))
+        }
+        ast::Expr::Literal(lit) if lit.kind() == LiteralKind::Bool(false) => {
+            edit.replace(range, format!("{}::No", enum_path))
+        }
+        // Inserting around the expression keeps nested rewrites of the same binding disjoint.
+        _ => {
+            edit.insert(range.start(), "if ");
+            edit.insert(range.end(), format!(" {{ {0}::Yes }} else {{ {0}::No }}", enum_path));
+        }
+    }
+}
+
+/// Whether a `==` comparison in place of `expr` has to be parenthesized.
+fn needs_parens(expr: &ast::Expr) -> bool {
+    let parent = match expr.syntax().parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    if let Some(bin_expr) = ast::BinExpr::cast(parent.clone()) {
+        return !matches!(
+            bin_expr.op_kind(),
+            Some(BinOp::BooleanAnd) | Some(BinOp::BooleanOr) | Some(BinOp::Assignment)
+        );
+    }
+    !matches!(
+        parent.kind(),
+        SyntaxKind::CONDITION
+            | SyntaxKind::ARG_LIST
+            | SyntaxKind::LET_STMT
+            | SyntaxKind::EXPR_STMT
+            | SyntaxKind::BLOCK_EXPR
+            | SyntaxKind::RETURN_EXPR
+            | SyntaxKind::PAREN_EXPR
+            | SyntaxKind::RECORD_EXPR_FIELD
+            | SyntaxKind::MATCH_GUARD
+            | SyntaxKind::MATCH_EXPR
+    )
+}
+
+fn to_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|it| !it.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn not_applicable_on_non_bool() {
+        check_assist_not_applicable(convert_bool_to_enum, r#"fn f($0count: u32) {}"#);
+        check_assist_not_applicable(convert_bool_to_enum, r#"struct S { $0count: u32 }"#);
+        check_assist_not_applicable(
+            convert_bool_to_enum,
+            r#"
+trait T {
+    fn f(&self, $0flag: bool);
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_local() {
+        check_assist(
+            convert_bool_to_enum,
+            r#"
+fn main() {
+    let mut $0is_done = false;
+    let n = 3;
+    if !is_done && n > 2 {
+        is_done = n > 5;
+    }
+    let done: bool = is_done;
+}"#,
+            r#"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IsDone {
+    Yes,
+    No,
+}
+
+fn main() {
+    let mut is_done = IsDone::No;
+    let n = 3;
+    if is_done == IsDone::No && n > 2 {
+        is_done = if n > 5 { IsDone::Yes } else { IsDone::No };
+    }
+    let done: bool = is_done == IsDone::Yes;
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_local_with_type_and_self_assignment() {
+        check_assist(
+            convert_bool_to_enum,
+            r#"
+fn main() {
+    let mut $0flag: bool = true;
+    flag = !flag;
+}"#,
+            r#"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flag {
+    Yes,
+    No,
+}
+
+fn main() {
+    let mut flag: Flag = Flag::Yes;
+    flag = if flag == Flag::No { Flag::Yes } else { Flag::No };
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_param() {
+        check_assist(
+            convert_bool_to_enum,
+            r#"
+mod fs {
+    pub fn open(path: &str, $0create: bool, truncate: bool) -> bool {
+        create || truncate
+    }
+}
+
+fn main() {
+    fs::open("a.txt", true, false);
+    let x = 1;
+    fs::open("b.txt", x == 1, true);
+}"#,
+            r#"
+mod fs {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Create {
+        Yes,
+        No,
+    }
+
+    pub fn open(path: &str, create: Create, truncate: bool) -> bool {
+        create == Create::Yes || truncate
+    }
+}
+
+fn main() {
+    fs::open("a.txt", fs::Create::Yes, false);
+    let x = 1;
+    fs::open("b.txt", if x == 1 { fs::Create::Yes } else { fs::Create::No }, true);
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_method_param() {
+        check_assist(
+            convert_bool_to_enum,
+            r#"
+struct File;
+
+impl File {
+    fn set_hidden(&mut self, $0hidden: bool) -> u32 {
+        match hidden {
+            true => 1,
+            false => 0,
+        }
+    }
+}
+
+fn main() {
+    let mut f = File;
+    f.set_hidden(false);
+    File::set_hidden(&mut f, true);
+}"#,
+            r#"
+struct File;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Hidden {
+    Yes,
+    No,
+}
+
+impl File {
+    fn set_hidden(&mut self, hidden: Hidden) -> u32 {
+        match hidden == Hidden::Yes {
+            true => 1,
+            false => 0,
+        }
+    }
+}
+
+fn main() {
+    let mut f = File;
+    f.set_hidden(Hidden::No);
+    File::set_hidden(&mut f, Hidden::Yes);
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_field() {
+        check_assist(
+            convert_bool_to_enum,
+            r#"
+//- /main.rs
+mod opts;
+
+fn main() {
+    let verbose = true;
+    let opts =
+        opts::Options { verbose };
+    if opts.verbose {
+        return;
+    }
+}
+
+//- /opts.rs
+pub struct Options {
+    pub $0verbose: bool,
+}
+
+impl Options {
+    pub fn quiet() -> Options {
+        Options { verbose: false }
+    }
+
+    pub fn set_quiet(&mut self) {
+        self.verbose = false;
+    }
+}
+"#,
+            r#"
+//- /main.rs
+mod opts;
+
+fn main() {
+    let verbose = true;
+    let opts =
+        opts::Options { verbose: if verbose { opts::Verbose::Yes } else { opts::Verbose::No } };
+    if opts.verbose == opts::Verbose::Yes {
+        return;
+    }
+}
+
+//- /opts.rs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbose {
+    Yes,
+    No,
+}
+
+pub struct Options {
+    pub verbose: Verbose,
+}
+
+impl Options {
+    pub fn quiet() -> Options {
+        Options { verbose: Verbose::No }
+    }
+
+    pub fn set_quiet(&mut self) {
+        self.verbose = Verbose::No;
+    }
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,458 @@
+use hir::{AsAssocItem, HasSource, HirDisplay};
+use ide_db::defs::Definition;
+use itertools::Itertools;
+use rustc_hash::FxHashSet;
+use syntax::{
+    algo::find_node_at_range,
+    ast::{
+        self,
+        edit::{AstNodeEdit, IndentLevel},
+        make, ArgListOwner, AstNode, GenericParamsOwner, NameOwner, VisibilityOwner,
+    },
+    TextSize,
+};
+
+use crate::{
+    assist_context::AssistBuilder,
+    handlers::{
+        extract_function::{captured_params, fix_param_usages},
+        generate_function::{next_space_for_fn_after_call_site, GeneratedFunctionTarget},
+    },
+    utils::{extract_trivial_expression, range_with_whitespace},
+    AssistContext, AssistId, AssistKind, Assists,
+};
+
+// Assist: convert_closure_to_fn
+//
+// Converts a closure bound to a local variable into a function, passing the
+// captured variables as additional arguments.
+//
+// ```
+// struct Config { step: i32 }
+// fn main() {
+//     let config = Config { step: 2 };
+//     let next = |$0x: i32| x + config.step;
+//     next(1);
+// }
+// ```
+// ->
+// ```
+// struct Config { step: i32 }
+// fn main() {
+//     let config = Config { step: 2 };
+//     fn next(x: i32, config: &Config) -> i32 {
+//         x + config.step
+//     }
+//     next(1, &config);
+// }
+// ```
+pub(crate) fn convert_closure_to_fn(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let closure = ctx.find_node_at_offset::<ast::ClosureExpr>()?;
+    let body = closure.body()?;
+    if body.syntax().text_range().contains_inclusive(ctx.offset())
+        || closure.async_token().is_some()
+    {
+        return None;
+    }
+    let let_stmt = closure.syntax().parent().and_then(ast::LetStmt::cast)?;
+    let ident_pat = match let_stmt.pat()? {
+        ast::Pat::IdentPat(it) if it.ref_token().is_none() && it.pat().is_none() => it,
+        _ => return None,
+    };
+    let name = ident_pat.name()?;
+    let local = ctx.sema.to_def(&ident_pat)?;
+    let module = ctx.sema.scope(closure.syntax()).module()?;
+
+    let captures = captured_params(
+        ctx,
+        &ast::Expr::ClosureExpr(closure.clone()),
+        closure.move_token().is_none(),
+    )?;
+
+    let render_ty = |ty: hir::Type| {
+        if ty.is_unknown() || ty.is_closure() {
+            return None;
+        }
+        ty.display_source_code(ctx.db(), module.into()).ok()
+    };
+    let mut params = Vec::new();
+    for param in closure.param_list()?.params() {
+        let pat = param.pat()?;
+        let ty = match param.ty() {
+            Some(ty) => ty.to_string(),
+            None => render_ty(ctx.sema.type_of_pat(&pat)?)?,
+        };
+        params.push((pat.to_string(), ty));
+    }
+    for capture in &captures {
+        let param = capture.to_param(ctx, module);
+        params.push((param.pat()?.to_string(), param.ty()?.to_string()));
+    }
+    let ret_ty = match closure.ret_type() {
+        Some(ret_type) => Some(ret_type.ty()?.to_string()),
+        None => {
+            let ty = ctx.sema.type_of_expr(&body)?;
+            if ty.is_unit() {
+                None
+            } else {
+                Some(render_ty(ty)?)
+            }
+        }
+    };
+
+    // Nested and module-level fns can't refer to the generic parameters in scope.
+    let generic_params: FxHashSet<String> = closure
+        .syntax()
+        .ancestors()
+        .filter_map(|it| it.children().find_map(ast::GenericParamList::cast))
+        .flat_map(|it| it.type_params())
+        .filter_map(|it| it.name())
+        .map(|it| it.to_string())
+        .collect();
+    let mentions_generic_param = |ty: &str| {
+        make::ty(ty)
+            .syntax()
+            .descendants()
+            .filter_map(ast::NameRef::cast)
+            .any(|it| generic_params.contains(&it.to_string()))
+    };
+    let mut types = params.iter().map(|(_, ty)| ty.as_str()).chain(ret_ty.as_deref());
+    if types.any(mentions_generic_param) {
+        return None;
+    }
+
+    // With captures, every usage has to be a call we can pass them to.
+    let file = ctx.sema.parse(ctx.frange.file_id);
+    let mut calls = Vec::new();
+    for (file_id, references) in Definition::Local(local).usages(&ctx.sema).all() {
+        if file_id != ctx.frange.file_id {
+            return None;
+        }
+        for reference in references {
+            let name_ref = find_node_at_range::<ast::NameRef>(file.syntax(), reference.range)?;
+            let path_expr = name_ref.syntax().ancestors().find_map(ast::PathExpr::cast)?;
+            let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast);
+            match call {
+                Some(call) if call.expr()?.syntax() == path_expr.syntax() => {
+                    calls.push(call.arg_list()?)
+                }
+                _ if captures.is_empty() => {}
+                _ => {
+                    cov_mark::hit!(convert_closure_to_fn_used_as_value);
+                    return None;
+                }
+            }
+        }
+    }
+    let args = captures.iter().map(|it| it.to_arg(ctx)).join(", ");
+
+    let indent = IndentLevel::from_node(let_stmt.syntax());
+    let body = fix_param_usages(ctx, &captures, body.syntax());
+    let body = match ast::BlockExpr::cast(body.clone()) {
+        Some(block) => block.reset_indent().to_string(),
+        None => format!("{{\n{}{}\n}}", IndentLevel(1), body),
+    };
+    let ret = ret_ty.map(|it| format!(" -> {}", it)).unwrap_or_default();
+    let params = params.iter().map(|(pat, ty)| format!("{}: {}", pat, ty)).join(", ");
+    let fn_text = format!("fn {}({}){} {}", name, params, ret, body);
+    let edit_calls = |builder: &mut AssistBuilder| {
+        if args.is_empty() {
+            return;
+        }
+        for arg_list in &calls {
+            let sep = if arg_list.args().next().is_some() { ", " } else { "" };
+            let offset = arg_list.syntax().text_range().end() - TextSize::of(')');
+            builder.insert(offset, format!("{}{}", sep, args));
+        }
+    };
+
+    let target = closure.syntax().text_range();
+    acc.add(
+        AssistId("convert_closure_to_fn", AssistKind::RefactorRewrite),
+        "Convert closure to nested function",
+        target,
+        |builder| {
+            builder.replace(let_stmt.syntax().text_range(), indented(&fn_text, indent));
+            edit_calls(builder);
+        },
+    )?;
+
+    // A module-level fn can't see the items and variables of the enclosing function.
+    let item = match next_space_for_fn_after_call_site(let_stmt.syntax())? {
+        GeneratedFunctionTarget::BehindItem(it) => it,
+        GeneratedFunctionTarget::InEmptyItemList(_) => return None,
+    };
+    acc.add(
+        AssistId("convert_closure_to_fn", AssistKind::RefactorRewrite),
+        "Convert closure to module-level function",
+        target,
+        |builder| {
+            builder.delete(range_with_whitespace(let_stmt.syntax()));
+            edit_calls(builder);
+            let indent = IndentLevel::from_node(&item);
+            let fn_text = indented(&fn_text, indent);
+            builder.insert(item.text_range().end(), format!("\n\n{}{}", indent, fn_text));
+        },
+    )
+}
+
+// Assist: convert_fn_to_closure
+//
+// Replaces a function used once as a value with an equivalent closure.
+//
+// ```
+// fn apply(f: impl Fn(i32) -> i32) {}
+// fn main() {
+//     fn double(x: i32) -> i32 {
+//         x * 2
+//     }
+//     apply(double$0);
+// }
+// ```
+// ->
+// ```
+// fn apply(f: impl Fn(i32) -> i32) {}
+// fn main() {
+//     apply(|x| x * 2);
+// }
+// ```
+pub(crate) fn convert_fn_to_closure(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let path_expr = ctx.find_node_at_offset::<ast::PathExpr>()?;
+    if let Some(call) = path_expr.syntax().parent().and_then(ast::CallExpr::cast) {
+        if call.expr()?.syntax() == path_expr.syntax() {
+            return None;
+        }
+    }
+    let db = ctx.db();
+    let func = match ctx.sema.resolve_path(&path_expr.path()?)? {
+        hir::PathResolution::Def(hir::ModuleDef::Function(it)) => it,
+        _ => return None,
+    };
+    if func.as_assoc_item(db).is_some()
+        || Some(func.module(db)) != ctx.sema.scope(path_expr.syntax()).module()
+    {
+        return None;
+    }
+    let source = func.source(db)?;
+    if source.file_id.call_node(db).is_some()
+        || source.file_id.original_file(db) != ctx.frange.file_id
+    {
+        return None;
+    }
+    let fn_ = source.value;
+    if fn_.generic_param_list().is_some()
+        || fn_.visibility().is_some()
+        || fn_.async_token().is_some()
+        || fn_.const_token().is_some()
+        || fn_.unsafe_token().is_some()
+        || fn_.abi().is_some()
+    {
+        return None;
+    }
+
+    let usages = Definition::ModuleDef(func.into()).usages(&ctx.sema).all();
+    if usages.iter().map(|(_, references)| references.len()).sum::<usize>() != 1 {
+        cov_mark::hit!(convert_fn_to_closure_used_more_than_once);
+        return None;
+    }
+
+    let params = fn_.param_list()?.params().map(|it| it.pat()).collect::<Option<Vec<_>>>()?;
+    let body = fn_.body()?;
+    let indent = IndentLevel::from_node(path_expr.syntax());
+    let body = match extract_trivial_expression(&body) {
+        Some(expr) if body.statements().next().is_none() => expr.dedent(IndentLevel(1)),
+        _ => ast::Expr::BlockExpr(body),
+    };
+    let body = body.dedent(IndentLevel::from_node(fn_.syntax())).indent(indent);
+    let closure = format!("|{}| {}", params.iter().format(", "), body);
+
+    acc.add(
+        AssistId("convert_fn_to_closure", AssistKind::RefactorRewrite),
+        format!("Convert `{}` to a closure", fn_.name()?),
+        path_expr.syntax().text_range(),
+        |builder| {
+            builder.replace(path_expr.syntax().text_range(), closure);
+            builder.delete(range_with_whitespace(fn_.syntax()));
+        },
+    )
+}
+
+fn indented(text: &str, indent: IndentLevel) -> String {
+    text.replace('\n', &format!("\n{}", indent))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_by_label, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn closure_without_captures() {
+        check_assist_by_label(
+            convert_closure_to_fn,
+            r#"
+fn apply(f: impl Fn(u32) -> u32) {}
+fn main() {
+    let double = $0|x: u32| x * 2;
+    apply(double);
+}
+"#,
+            r#"
+fn apply(f: impl Fn(u32) -> u32) {}
+fn main() {
+    fn double(x: u32) -> u32 {
+        x * 2
+    }
+    apply(double);
+}
+"#,
+            "Convert closure to nested function",
+        );
+    }
+
+    #[test]
+    fn closure_with_mutable_and_shared_captures() {
+        check_assist_by_label(
+            convert_closure_to_fn,
+            r#"
+struct Name;
+impl Name {
+    fn len(&self) -> usize { 0 }
+}
+fn main() {
+    let mut total = 0;
+    let name = Name;
+    let add = |$0n| {
+        total += n;
+        name.len()
+    };
+    add(1u8);
+}
+"#,
+            r#"
+struct Name;
+impl Name {
+    fn len(&self) -> usize { 0 }
+}
+fn main() {
+    let mut total = 0;
+    let name = Name;
+    fn add(n: u8, total: &mut u8, name: &Name) -> usize {
+        *total += n;
+        name.len()
+    }
+    add(1u8, &mut total, &name);
+}
+"#,
+            "Convert closure to nested function",
+        );
+    }
+
+    #[test]
+    fn closure_to_module_level_fn() {
+        check_assist_by_label(
+            convert_closure_to_fn,
+            r#"
+struct Config { step: i32 }
+fn main() {
+    let config = Config { step: 2 };
+    let next = |$0x: i32| x + config.step;
+    next(1);
+}
+"#,
+            r#"
+struct Config { step: i32 }
+fn main() {
+    let config = Config { step: 2 };
+    next(1, &config);
+}
+
+fn next(x: i32, config: &Config) -> i32 {
+    x + config.step
+}
+"#,
+            "Convert closure to module-level function",
+        );
+    }
+
+    #[test]
+    fn closure_not_applicable_when_used_as_value() {
+        cov_mark::check!(convert_closure_to_fn_used_as_value);
+        check_assist_not_applicable(
+            convert_closure_to_fn,
+            r#"
+fn call(f: impl Fn(i32) -> i32) {}
+fn main() {
+    let base = 10;
+    let add = |$0x: i32| x + base;
+    call(add);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn closure_not_applicable_with_generic_types() {
+        check_assist_not_applicable(
+            convert_closure_to_fn,
+            r#"
+fn main<T: Copy>(t: T) {
+    let f = |$0| t;
+    f();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn fn_to_closure() {
+        check_assist(
+            convert_fn_to_closure,
+            r#"
+fn apply(f: impl Fn(i32, i32) -> i32) {}
+fn main() {
+    fn add(a: i32, b: i32) -> i32 {
+        let sum = a + b;
+        sum
+    }
+    apply(add$0);
+}
+"#,
+            r#"
+fn apply(f: impl Fn(i32, i32) -> i32) {}
+fn main() {
+    apply(|a, b| {
+        let sum = a + b;
+        sum
+    });
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn fn_to_closure_not_applicable_to_calls_or_repeated_uses() {
+        check_assist_not_applicable(
+            convert_fn_to_closure,
+            r#"
+fn add(a: i32, b: i32) -> i32 { a + b }
+fn main() {
+    add$0(1, 2);
+}
+"#,
+        );
+        cov_mark::check!(convert_fn_to_closure_used_more_than_once);
+        check_assist_not_applicable(
+            convert_fn_to_closure,
+            r#"
+fn apply(f: fn(i32) -> i32) {}
+fn inc(a: i32) -> i32 { a + 1 }
+fn main() {
+    apply(inc$0);
+    apply(inc);
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,992 @@
+use std::iter::successors;
+
+use hir::HirDisplay;
+use ide_db::{defs::Definition, search::SearchScope};
+use itertools::Itertools;
+use stdx::format_to;
+use syntax::{
+    algo::find_node_at_range,
+    ast::{self, edit::IndentLevel, ArgListOwner, AstNode, LoopBodyOwner, NameOwner},
+    SyntaxKind::{AWAIT_EXPR, BREAK_EXPR, CONTINUE_EXPR, RETURN_EXPR, TRY_EXPR},
+    SyntaxNode, TextRange, TextSize,
+};
+
+use crate::{
+    handlers::{
+        extract_function::expr_require_exclusive_access,
+        replace_for_loop_with_for_each::iterator_of,
+    },
+    utils::invert_boolean_expression,
+    AssistContext, AssistId, AssistKind, Assists,
+};
+
+// Assist: convert_for_loop_to_iterator_chain
+//
+// Rewrites a `for` loop that collects, accumulates or searches into an iterator chain.
+//
+// ```
+// fn main() {
+//     let xs = vec![1, 2, 3];
+//     let mut doubled = Vec::new();
+//     for$0 x in xs {
+//         if x > 1 {
+//             doubled.push(x * 2);
+//         }
+//     }
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let xs = vec![1, 2, 3];
+//     let doubled: Vec<_> = xs.into_iter().filter(|x| *x > 1).map(|x| x * 2).collect();
+// }
+// ```
+pub(crate) fn convert_for_loop_to_iterator_chain(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    let for_loop = ctx.find_node_at_offset::<ast::ForExpr>()?;
+    let body = for_loop.loop_body()?;
+    if body.syntax().text_range().start() < ctx.offset() || for_loop.label().is_some() {
+        return None;
+    }
+    let pat = for_loop.pat()?;
+    let iterable = for_loop.iterable()?;
+    let loop_node = match for_loop.syntax().parent().and_then(ast::ExprStmt::cast) {
+        Some(stmt) => stmt.syntax().clone(),
+        None => for_loop.syntax().clone(),
+    };
+    let (conds, action) = loop_parts(&body)?;
+    if conds.iter().any(has_control_flow) {
+        return None;
+    }
+    let indent = IndentLevel::from_node(&loop_node);
+    let iter = chain_start(ctx, &iterable);
+
+    // Filters receive a reference to the item.
+    let filter = |cond: &ast::Expr| {
+        let ident_pat = match &pat {
+            ast::Pat::IdentPat(it) => it,
+            _ => return None,
+        };
+        let local = ctx.sema.to_def(ident_pat)?;
+        let name = ident_pat.name()?.to_string();
+        let text = rewrite_usages(ctx, cond, &[(local, &name, Some(Adjust::ToRef))])?;
+        Some(reindent(&text, IndentLevel::from_node(cond.syntax()), indent))
+    };
+    let filters = conds.iter().map(&filter).collect::<Option<Vec<_>>>();
+
+    let (range, replacement) = match &action {
+        ast::Expr::ReturnExpr(ret) => {
+            // `for x in xs { if f(x) { return true; } } false`
+            let tail = loop_node.next_sibling().and_then(ast::Expr::cast)?;
+            let block = ast::BlockExpr::cast(loop_node.parent()?)?;
+            if block.tail_expr()? != tail || !ast::Fn::can_cast(block.syntax().parent()?.kind()) {
+                return None;
+            }
+            let (last, conds) = conds.split_last()?;
+            let pred = reindent(&last.to_string(), IndentLevel::from_node(last.syntax()), indent);
+            let filters = filters?[..conds.len()]
+                .iter()
+                .map(|it| format!(".filter(|{}| {})", pat, it))
+                .join("");
+            let value = ret.expr()?;
+            if has_control_flow(&value) {
+                return None;
+            }
+            let search = match (value.to_string().as_str(), tail.to_string().as_str()) {
+                ("true", "false") => format!("{}{}.any(|{}| {})", iter, filters, pat, pred),
+                ("false", "true") => {
+                    let pred = invert_boolean_expression(&ctx.sema, last.clone());
+                    format!("{}{}.all(|{}| {})", iter, filters, pat, pred)
+                }
+                (_, "None") => {
+                    let value = match value {
+                        ast::Expr::CallExpr(call) if call.expr()?.to_string() == "Some" => {
+                            call.arg_list()?.args().exactly_one().ok()?
+                        }
+                        _ => return None,
+                    };
+                    match &pat {
+                        ast::Pat::IdentPat(_) if value.to_string() == pat.to_string() => {
+                            let pred = filter(last)?;
+                            format!("{}{}.find(|{}| {})", iter, filters, pat, pred)
+                        }
+                        // `for (i, x) in xs.enumerate() { if f(x) { return Some(i); } } None`
+                        ast::Pat::TuplePat(tuple) if conds.is_empty() => {
+                            let (index, item) = tuple.fields().collect_tuple()?;
+                            let enumerated = match &iterable {
+                                ast::Expr::MethodCallExpr(call)
+                                    if call.name_ref()?.text() == "enumerate" =>
+                                {
+                                    call.receiver()?
+                                }
+                                _ => return None,
+                            };
+                            if value.to_string() != index.to_string()
+                                || mentions(last.syntax(), &index.to_string())
+                            {
+                                return None;
+                            }
+                            format!("{}.position(|{}| {})", enumerated, item, pred)
+                        }
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            };
+            (
+                TextRange::new(loop_node.text_range().start(), tail.syntax().text_range().end()),
+                search,
+            )
+        }
+        _ => {
+            if has_control_flow(&action) {
+                return None;
+            }
+            let filters = filters?.iter().map(|it| format!(".filter(|{}| {})", pat, it)).join("");
+            let let_stmt = loop_node.prev_sibling().and_then(ast::LetStmt::cast)?;
+            let ident_pat = match let_stmt.pat()? {
+                ast::Pat::IdentPat(it) if it.pat().is_none() && it.ref_token().is_none() => it,
+                _ => return None,
+            };
+            let name = ident_pat.name()?.to_string();
+            if conds.iter().any(|it| mentions(it.syntax(), &name)) {
+                return None;
+            }
+            let init = let_stmt.initializer()?;
+            let mut_ =
+                if needs_mut(ctx, &ident_pat, loop_node.text_range().end()) { "mut " } else { "" };
+            let value_indent = IndentLevel::from_node(action.syntax());
+            let map = |value: &ast::Expr| {
+                if value.to_string() == pat.to_string() {
+                    String::new()
+                } else {
+                    format!(
+                        ".map(|{}| {})",
+                        pat,
+                        reindent(&value.to_string(), value_indent, indent)
+                    )
+                }
+            };
+
+            let chain = match &action {
+                // `let mut v = Vec::new(); for x in xs { v.push(f(x)); }`
+                ast::Expr::MethodCallExpr(call) if call.name_ref()?.text() == "push" => {
+                    if call.receiver()?.to_string() != name || !is_new_vec(&init) {
+                        return None;
+                    }
+                    let item = call.arg_list()?.args().exactly_one().ok()?;
+                    if mentions(item.syntax(), &name) {
+                        return None;
+                    }
+                    let ty =
+                        let_stmt.ty().map_or_else(|| "Vec<_>".to_string(), |it| it.to_string());
+                    format!(
+                        "let {}{}: {} = {}{}{}.collect();",
+                        mut_,
+                        name,
+                        ty,
+                        iter,
+                        filters,
+                        map(&item)
+                    )
+                }
+                // `let mut sum = 0; for x in xs { sum += f(x); }`
+                ast::Expr::BinExpr(bin) if bin.op_kind()?.is_assignment() => {
+                    if bin.lhs()?.to_string() != name {
+                        return None;
+                    }
+                    let value = bin.rhs()?;
+                    let op = bin.op_kind()?;
+                    let ty = match let_stmt.ty() {
+                        Some(ty) => Some(ty.to_string()),
+                        None => {
+                            let module = ctx.sema.scope(let_stmt.syntax()).module()?;
+                            let ty =
+                                ctx.sema.type_of_pat(&ast::Pat::IdentPat(ident_pat.clone()))?;
+                            ty.display_source_code(ctx.db(), module.into()).ok()
+                        }
+                    };
+                    let reduce = match op {
+                        ast::BinOp::AddAssign if is_literal(&init, 0.0) => Some("sum"),
+                        ast::BinOp::MulAssign if is_literal(&init, 1.0) => Some("product"),
+                        _ => None,
+                    };
+                    match (reduce, ty) {
+                        (Some(reduce), Some(ty)) if !mentions(value.syntax(), &name) => format!(
+                            "let {}{}: {} = {}{}{}.{}();",
+                            mut_,
+                            name,
+                            ty,
+                            iter,
+                            filters,
+                            map(&value),
+                            reduce
+                        ),
+                        _ => {
+                            let value = reindent(&value.to_string(), value_indent, indent);
+                            let folded = match op {
+                                ast::BinOp::Assignment => value,
+                                _ => {
+                                    let op =
+                                        bin.op_token()?.text().trim_end_matches('=').to_string();
+                                    match bin.rhs()? {
+                                        ast::Expr::BinExpr(_) => {
+                                            format!("{} {} ({})", name, op, value)
+                                        }
+                                        _ => format!("{} {} {}", name, op, value),
+                                    }
+                                }
+                            };
+                            let ty =
+                                let_stmt.ty().map(|it| format!(": {}", it)).unwrap_or_default();
+                            format!(
+                                "let {}{}{} = {}{}.fold({}, |{}, {}| {});",
+                                mut_, name, ty, iter, filters, init, name, pat, folded
+                            )
+                        }
+                    }
+                }
+                _ => return None,
+            };
+            (
+                TextRange::new(
+                    let_stmt.syntax().text_range().start(),
+                    loop_node.text_range().end(),
+                ),
+                chain,
+            )
+        }
+    };
+
+    acc.add(
+        AssistId("convert_for_loop_to_iterator_chain", AssistKind::RefactorRewrite),
+        "Convert loop to iterator chain",
+        for_loop.syntax().text_range(),
+        |builder| builder.replace(range, replacement),
+    )
+}
+
+// Assist: convert_iterator_chain_to_for_loop
+//
+// Expands an iterator chain that is bound to a variable into an equivalent `for` loop.
+//
+// ```
+// fn main() {
+//     let xs = vec![1, 2, 3];
+//     let total: i32 = xs.into_iter().map(|x| x * 2).sum$0();
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let xs = vec![1, 2, 3];
+//     let mut total: i32 = 0;
+//     for x in xs.into_iter() {
+//         let x = x * 2;
+//         total += x;
+//     }
+// }
+// ```
+pub(crate) fn convert_iterator_chain_to_for_loop(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    let name_ref = ctx.find_node_at_offset::<ast::NameRef>()?;
+    let call = name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast)?;
+    let terminal = successors(Some(call), |call| {
+        let parent = call.syntax().parent().and_then(ast::MethodCallExpr::cast)?;
+        if parent.receiver()?.syntax() == call.syntax() {
+            Some(parent)
+        } else {
+            None
+        }
+    })
+    .last()?;
+    let let_stmt = terminal.syntax().parent().and_then(ast::LetStmt::cast)?;
+    let ident_pat = match let_stmt.pat()? {
+        ast::Pat::IdentPat(it) if it.pat().is_none() && it.ref_token().is_none() => it,
+        _ => return None,
+    };
+    let var = ident_pat.name()?.to_string();
+
+    let mut stages = Vec::new();
+    let mut receiver = terminal.receiver()?;
+    while let ast::Expr::MethodCallExpr(call) = receiver.clone() {
+        let method = call.name_ref()?.text().to_string();
+        if !["map", "filter"].contains(&method.as_str()) {
+            break;
+        }
+        match call.arg_list()?.args().exactly_one().ok()? {
+            ast::Expr::ClosureExpr(closure) => stages.push((method, closure)),
+            _ => return None,
+        }
+        receiver = call.receiver()?;
+    }
+    stages.reverse();
+
+    let args: Vec<ast::Expr> = terminal.arg_list()?.args().collect();
+    let terminal_closure = match args.last() {
+        Some(ast::Expr::ClosureExpr(closure)) => Some(closure.clone()),
+        _ => None,
+    };
+    // Also returns how many `&` patterns the binding is nested in, as in `|&x|`.
+    let closure_param = |closure: &ast::ClosureExpr, idx: usize| {
+        let mut pat = closure.param_list()?.params().nth(idx)?.pat()?;
+        let mut derefs = 0;
+        while let ast::Pat::RefPat(ref_pat) = pat {
+            pat = ref_pat.pat()?;
+            derefs += 1;
+        }
+        match pat {
+            ast::Pat::IdentPat(it) if it.pat().is_none() && it.ref_token().is_none() => {
+                Some((ctx.sema.to_def(&it)?, it.name()?.to_string(), derefs))
+            }
+            _ => None,
+        }
+    };
+    let terminal_name = terminal.name_ref()?.text().to_string();
+    let item_idx = if terminal_name == "fold" { 1 } else { 0 };
+    let param_names = stages
+        .iter()
+        .map(|(_, closure)| closure_param(closure, 0).map(|(_, name, _)| name))
+        .chain(
+            terminal_closure
+                .as_ref()
+                .map(|it| closure_param(it, item_idx).map(|(_, name, _)| name)),
+        )
+        .collect::<Option<Vec<_>>>()?;
+    let mut cur = param_names.first().cloned().unwrap_or_else(|| "item".to_string());
+    let loop_var = cur.clone();
+
+    let mut lines = Vec::new();
+    for (idx, (method, closure)) in stages.iter().enumerate() {
+        let (local, _, derefs) = closure_param(closure, 0)?;
+        let adjust = param_adjust(method == "filter", derefs)?;
+        let body = closure.body()?;
+        let from = IndentLevel::from_node(body.syntax());
+        if method == "filter" {
+            let cond = rewrite_usages(ctx, &body, &[(local, &cur, adjust)])?;
+            lines.push(format!("if !{} {{\n    continue;\n}}", parenthesized(&body, cond)));
+        } else {
+            // The mapped value is bound to the name the next closure uses for it.
+            let value = rewrite_usages(ctx, &body, &[(local, &cur, adjust)])?;
+            let next = param_names.get(idx + 1).cloned().unwrap_or_else(|| cur.clone());
+            lines.push(format!("let {} = {};", next, reindent(&value, from, IndentLevel(0))));
+            cur = next;
+        }
+    }
+
+    let predicate = |by_ref: bool| {
+        let closure = terminal_closure.as_ref()?;
+        let (local, _, derefs) = closure_param(closure, 0)?;
+        let adjust = param_adjust(by_ref, derefs)?;
+        let body = closure.body()?;
+        let cond = rewrite_usages(ctx, &body, &[(local, &cur, adjust)])?;
+        Some((body, cond))
+    };
+    let mut loop_pat = loop_var;
+    let mut iterable = receiver.to_string();
+    let (init, action) = match terminal_name.as_str() {
+        "collect" if args.is_empty() => {
+            let ty = let_stmt.ty().map(|it| it.to_string()).or_else(|| {
+                terminal
+                    .generic_arg_list()
+                    .map(|it| it.to_string().trim_start_matches("::<").to_string())
+            })?;
+            if !ty.starts_with("Vec<") {
+                return None;
+            }
+            ("Vec::new()".to_string(), format!("{}.push({});", var, cur))
+        }
+        "sum" | "product" if args.is_empty() => {
+            let ty = ctx.sema.type_of_pat(&ast::Pat::IdentPat(ident_pat.clone()))?;
+            let is_float = ["f32", "f64"].contains(&ty.display(ctx.db()).to_string().as_str());
+            let (init, op) = match (terminal_name.as_str(), is_float) {
+                ("sum", false) => ("0", "+="),
+                ("sum", true) => ("0.0", "+="),
+                (_, false) => ("1", "*="),
+                (_, true) => ("1.0", "*="),
+            };
+            (init.to_string(), format!("{} {} {};", var, op, cur))
+        }
+        "count" if args.is_empty() => ("0".to_string(), format!("{} += 1;", var)),
+        "fold" if args.len() == 2 => {
+            let closure = terminal_closure.as_ref()?;
+            let (acc_local, _, acc_derefs) = closure_param(closure, 0)?;
+            let (item_local, _, derefs) = closure_param(closure, 1)?;
+            if acc_derefs != 0 {
+                return None;
+            }
+            let adjust = param_adjust(false, derefs)?;
+            let body = closure.body()?;
+            let value =
+                rewrite_usages(ctx, &body, &[(acc_local, &var, None), (item_local, &cur, adjust)])?;
+            let value = reindent(&value, IndentLevel::from_node(body.syntax()), IndentLevel(0));
+            (args[0].to_string(), format!("{} = {};", var, value))
+        }
+        "any" if args.len() == 1 => {
+            let (_, cond) = predicate(false)?;
+            ("false".to_string(), format!("if {} {{\n    {} = true;\n    break;\n}}", cond, var))
+        }
+        "all" if args.len() == 1 => {
+            let (body, cond) = predicate(false)?;
+            let cond = parenthesized(&body, cond);
+            ("true".to_string(), format!("if !{} {{\n    {} = false;\n    break;\n}}", cond, var))
+        }
+        "find" if args.len() == 1 => {
+            let (_, cond) = predicate(true)?;
+            let action = format!("if {} {{\n    {} = Some({});\n    break;\n}}", cond, var, cur);
+            ("None".to_string(), action)
+        }
+        // The index counts the items after filtering, which `enumerate` on the source can't.
+        "position" if args.len() == 1 && stages.iter().all(|(method, _)| method == "map") => {
+            let (_, cond) = predicate(false)?;
+            loop_pat = format!("(index, {})", loop_pat);
+            iterable = format!("{}.enumerate()", receiver);
+            let action = format!("if {} {{\n    {} = Some(index);\n    break;\n}}", cond, var);
+            ("None".to_string(), action)
+        }
+        _ => return None,
+    };
+
+    let indent = IndentLevel::from_node(let_stmt.syntax());
+    let ty = let_stmt.ty().map(|it| format!(": {}", it)).unwrap_or_default();
+    let mut buf = format!("let mut {}{} = {};\n", var, ty, init);
+    format_to!(buf, "{}for {} in {} {{\n", indent, loop_pat, iterable);
+    for line in lines.iter().chain(Some(&action)) {
+        format_to!(buf, "{}{}\n", indent + 1, reindent(line, IndentLevel(0), indent + 1));
+    }
+    format_to!(buf, "{}}}", indent);
+
+    acc.add(
+        AssistId("convert_iterator_chain_to_for_loop", AssistKind::RefactorRewrite),
+        "Convert iterator chain to `for` loop",
+        terminal.syntax().text_range(),
+        |builder| builder.replace(let_stmt.syntax().text_range(), buf),
+    )
+}
+
+/// The iterator the chain starts from. `iter`, `iter_mut` and `into_iter` calls are
+/// kept as they are even if their type is unknown, as they already return iterators.
+fn chain_start(ctx: &AssistContext, iterable: &ast::Expr) -> String {
+    match iterable {
+        ast::Expr::MethodCallExpr(call)
+            if matches!(
+                call.name_ref(),
+                Some(it) if ["iter", "iter_mut", "into_iter"].contains(&it.text().as_str())
+            ) =>
+        {
+            iterable.to_string()
+        }
+        _ => iterator_of(&ctx.sema, iterable),
+    }
+}
+
+/// Splits a loop body into the conditions of nested `if`s without `else` and
+/// the single expression they guard.
+fn loop_parts(body: &ast::BlockExpr) -> Option<(Vec<ast::Expr>, ast::Expr)> {
+    let mut conds = Vec::new();
+    let mut expr = single_expr(body)?;
+    while let ast::Expr::IfExpr(if_expr) = expr.clone() {
+        let condition = if_expr.condition()?;
+        if if_expr.else_branch().is_some() || condition.pat().is_some() {
+            return None;
+        }
+        conds.push(condition.expr()?);
+        expr = single_expr(&if_expr.then_branch()?)?;
+    }
+    Some((conds, expr))
+}
+
+fn single_expr(block: &ast::BlockExpr) -> Option<ast::Expr> {
+    let mut exprs = block
+        .statements()
+        .map(|stmt| match stmt {
+            ast::Stmt::ExprStmt(it) => it.expr(),
+            _ => None,
+        })
+        .chain(block.tail_expr().map(Some));
+    let expr = exprs.next()??;
+    if exprs.next().is_some() {
+        return None;
+    }
+    Some(expr)
+}
+
+fn has_control_flow(expr: &ast::Expr) -> bool {
+    expr.syntax().descendants().any(|it| {
+        [RETURN_EXPR, BREAK_EXPR, CONTINUE_EXPR, TRY_EXPR, AWAIT_EXPR].contains(&it.kind())
+    })
+}
+
+fn mentions(node: &SyntaxNode, name: &str) -> bool {
+    node.descendants().filter_map(ast::NameRef::cast).any(|it| it.text() == name)
+}
+
+fn needs_mut(ctx: &AssistContext, ident_pat: &ast::IdentPat, after: TextSize) -> bool {
+    let local = match ctx.sema.to_def(ident_pat) {
+        Some(it) => it,
+        None => return true,
+    };
+    let file = ctx.sema.parse(ctx.frange.file_id);
+    let usages = Definition::Local(local)
+        .usages(&ctx.sema)
+        .in_scope(SearchScope::single_file(ctx.frange.file_id))
+        .all();
+    let mutated_later =
+        usages.iter().flat_map(|(_, refs)| refs).filter(|it| it.range.start() >= after).any(|it| {
+            let path_expr = find_node_at_range::<ast::PathExpr>(file.syntax(), it.range);
+            match path_expr {
+                Some(path_expr) => {
+                    expr_require_exclusive_access(ctx, &path_expr.into()).unwrap_or(false)
+                }
+                None => true,
+            }
+        });
+    mutated_later
+}
+
+fn is_new_vec(init: &ast::Expr) -> bool {
+    match init {
+        ast::Expr::CallExpr(call) => {
+            let callee = call.expr().map(|it| it.to_string()).unwrap_or_default();
+            ["Vec::new", "Vec::with_capacity"].contains(&callee.as_str())
+        }
+        ast::Expr::MacroCall(call) => call.to_string().replace(' ', "") == "vec![]",
+        _ => false,
+    }
+}
+
+/// Whether `expr` is a number literal equal to `value`, like `0`, `0.0` or `1u32`.
+fn is_literal(expr: &ast::Expr, value: f64) -> bool {
+    let literal = match expr {
+        ast::Expr::Literal(it) => it.to_string(),
+        _ => return false,
+    };
+    let number: String =
+        literal.chars().take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '_').collect();
+    matches!(number.replace('_', "").parse::<f64>(), Ok(it) if it == value)
+}
+
+fn parenthesized(expr: &ast::Expr, text: String) -> String {
+    match expr {
+        ast::Expr::PathExpr(_)
+        | ast::Expr::CallExpr(_)
+        | ast::Expr::MethodCallExpr(_)
+        | ast::Expr::FieldExpr(_)
+        | ast::Expr::ParenExpr(_)
+        | ast::Expr::Literal(_) => text,
+        _ => format!("({})", text),
+    }
+}
+
+/// How usages change when a binding switches between a value and a reference to it.
+#[derive(Clone, Copy)]
+enum Adjust {
+    /// The binding becomes a reference, `x` is now `*x`.
+    ToRef,
+    /// The binding stops being a reference, `*x` is now `x` and `x` is `&x`.
+    ToValue,
+}
+
+/// How the usages of a closure parameter bound through `derefs` `&` patterns change when
+/// it binds the loop item instead, which the closure gets by reference if `by_ref`.
+/// Returns `None` if no single adjustment does it.
+fn param_adjust(by_ref: bool, derefs: usize) -> Option<Option<Adjust>> {
+    match (by_ref, derefs) {
+        (true, 0) => Some(Some(Adjust::ToValue)),
+        (true, 1) | (false, 0) => Some(None),
+        (true, 2) | (false, 1) => Some(Some(Adjust::ToRef)),
+        _ => None,
+    }
+}
+
+/// Renders `expr` with the usages of each local replaced by the given name,
+/// adjusting them as needed. Method receivers and field accesses are left alone
+/// as they auto-deref.
+fn rewrite_usages(
+    ctx: &AssistContext,
+    expr: &ast::Expr,
+    renames: &[(hir::Local, &str, Option<Adjust>)],
+) -> Option<String> {
+    let file = ctx.sema.parse(ctx.frange.file_id);
+    let expr_range = expr.syntax().text_range();
+    let mut edits = Vec::new();
+    for &(local, name, adjust) in renames {
+        let usages = Definition::Local(local)
+            .usages(&ctx.sema)
+            .in_scope(SearchScope::single_file(ctx.frange.file_id))
+            .all();
+        for reference in usages.iter().flat_map(|(_, refs)| refs) {
+            if !expr_range.contains_range(reference.range) {
+                continue;
+            }
+            let name_ref = find_node_at_range::<ast::NameRef>(file.syntax(), reference.range)?;
+            let path_expr = name_ref.syntax().ancestors().find_map(ast::PathExpr::cast)?;
+            let range = path_expr.syntax().text_range();
+            let parent = path_expr.syntax().parent()?;
+            let auto_deref = matches!(
+                ast::MethodCallExpr::cast(parent.clone()).and_then(|it| it.receiver()),
+                Some(receiver) if receiver.syntax() == path_expr.syntax()
+            ) || ast::FieldExpr::can_cast(parent.kind());
+            let deref = ast::PrefixExpr::cast(parent)
+                .filter(|it| it.op_kind() == Some(ast::PrefixOp::Deref));
+            let edit = match (adjust, deref) {
+                (None, _) => (range, name.to_string()),
+                (Some(_), _) if auto_deref => (range, name.to_string()),
+                (Some(Adjust::ToRef), _) => (range, format!("*{}", name)),
+                (Some(Adjust::ToValue), Some(deref)) => {
+                    (deref.syntax().text_range(), name.to_string())
+                }
+                (Some(Adjust::ToValue), None) => (range, format!("&{}", name)),
+            };
+            edits.push(edit);
+        }
+    }
+
+    let mut text = expr.to_string();
+    edits.sort_by_key(|(range, _)| range.start());
+    for (range, replacement) in edits.into_iter().rev() {
+        let range = range - expr_range.start();
+        text.replace_range(usize::from(range.start())..usize::from(range.end()), &replacement);
+    }
+    Some(text)
+}
+
+/// Moves the lines after the first one of `text` from `from` indentation to `to`.
+fn reindent(text: &str, from: IndentLevel, to: IndentLevel) -> String {
+    let from = from.to_string();
+    let mut lines = text.lines();
+    let mut buf = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        format_to!(buf, "\n{}{}", to, line.strip_prefix(from.as_str()).unwrap_or(line));
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn push_into_vec() {
+        check_assist(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut ys = Vec::new();
+    for$0 x in xs {
+        ys.push(x);
+    }
+    let n = ys.len();
+}
+"#,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let ys: Vec<_> = xs.into_iter().collect();
+    let n = ys.len();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn accumulate_into_sum() {
+        check_assist(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut total = 0;
+    for$0 x in xs {
+        if x % 2 == 0 {
+            total += x * x;
+        }
+    }
+}
+"#,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let total: i32 = xs.into_iter().filter(|x| *x % 2 == 0).map(|x| x * x).sum();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn accumulate_into_fold() {
+        check_assist(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut n = 0;
+    for$0 x in xs {
+        n = n * 10 + x;
+    }
+}
+"#,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let n = xs.into_iter().fold(0, |n, x| n * 10 + x);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn early_return_into_any_and_all() {
+        check_assist(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn has_negative(xs: Vec<i32>) -> bool {
+    for$0 x in xs {
+        if x < 0 {
+            return true;
+        }
+    }
+    false
+}
+"#,
+            r#"
+fn has_negative(xs: Vec<i32>) -> bool {
+    xs.into_iter().any(|x| x < 0)
+}
+"#,
+        );
+        check_assist(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn all_positive(xs: Vec<i32>) -> bool {
+    for$0 x in xs {
+        if x <= 0 {
+            return false;
+        }
+    }
+    true
+}
+"#,
+            r#"
+fn all_positive(xs: Vec<i32>) -> bool {
+    xs.into_iter().all(|x| !(x <= 0))
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn early_return_into_find() {
+        check_assist(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn first_big(xs: Vec<u32>) -> Option<u32> {
+    for$0 x in xs {
+        if x > 10 {
+            return Some(x);
+        }
+    }
+    None
+}
+"#,
+            r#"
+fn first_big(xs: Vec<u32>) -> Option<u32> {
+    xs.into_iter().find(|x| *x > 10)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn keeps_iterator_calls() {
+        check_assist(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn has_negative(xs: &[i32]) -> bool {
+    for$0 x in xs.iter() {
+        if *x < 0 {
+            return true;
+        }
+    }
+    false
+}
+"#,
+            r#"
+fn has_negative(xs: &[i32]) -> bool {
+    xs.iter().any(|x| *x < 0)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_other_loops() {
+        check_assist_not_applicable(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn main() {
+    let mut ys = Vec::new();
+    for$0 x in [1, 2] {
+        if x > 1 {
+            break;
+        }
+        ys.push(x);
+    }
+}
+"#,
+        );
+        check_assist_not_applicable(
+            convert_for_loop_to_iterator_chain,
+            r#"
+fn main() {
+    let mut ys = Vec::new();
+    let mut zs = Vec::new();
+    for$0 x in [1, 2] {
+        ys.push(x);
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn expand_collect() {
+        check_assist(
+            convert_iterator_chain_to_for_loop,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let ys: Vec<i32> = xs.into_iter().filter(|x| *x > 1).map(|x| x * 2).collect$0();
+}
+"#,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut ys: Vec<i32> = Vec::new();
+    for x in xs.into_iter() {
+        if !(x > 1) {
+            continue;
+        }
+        let x = x * 2;
+        ys.push(x);
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn expand_ref_patterns() {
+        check_assist(
+            convert_iterator_chain_to_for_loop,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let ys: Vec<i32> = xs.iter().filter(|&&x| x > 1).map(|&x| x * 2).collect$0();
+}
+"#,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut ys: Vec<i32> = Vec::new();
+    for x in xs.iter() {
+        if !(*x > 1) {
+            continue;
+        }
+        let x = *x * 2;
+        ys.push(x);
+    }
+}
+"#,
+        );
+        check_assist(
+            convert_iterator_chain_to_for_loop,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let big = xs.into_iter().filter(|&x| x % 2 == 0).find$0(|&x| x > 1);
+}
+"#,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut big = None;
+    for x in xs.into_iter() {
+        if !(x % 2 == 0) {
+            continue;
+        }
+        if x > 1 {
+            big = Some(x);
+            break;
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn expand_fold_and_any() {
+        check_assist(
+            convert_iterator_chain_to_for_loop,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let n = xs.into_iter().fold$0(0, |acc, x| acc * 10 + x);
+}
+"#,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut n = 0;
+    for x in xs.into_iter() {
+        n = n * 10 + x;
+    }
+}
+"#,
+        );
+        check_assist(
+            convert_iterator_chain_to_for_loop,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let found = xs.into_iter().map(|x| x + 1).any$0(|y| y == 3);
+}
+"#,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut found = false;
+    for x in xs.into_iter() {
+        let y = x + 1;
+        if y == 3 {
+            found = true;
+            break;
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn expand_not_applicable_to_unknown_terminal() {
+        check_assist_not_applicable(
+            convert_iterator_chain_to_for_loop,
+            r#"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let it = xs.into_iter().map(|x| x + 1).rev$0();
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,560 @@
+use either::Either;
+use ide_db::defs::Definition;
+use syntax::{
+    ast::{self, AstNode, GenericParamsOwner, NameOwner},
+    match_ast, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize, T,
+};
+
+use crate::{assist_context::AssistBuilder, AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: convert_named_struct_to_tuple_struct
+//
+// Converts struct with named fields to tuple struct, and analogously for enum variants with named
+// fields.
+//
+// ```
+// struct Point$0 { x: f32, y: f32 }
+//
+// impl Point {
+//     pub fn new(x: f32, y: f32) -> Self {
+//         Point { x, y }
+//     }
+//
+//     pub fn x(&self) -> f32 {
+//         self.x
+//     }
+//
+//     pub fn y(&self) -> f32 {
+//         self.y
+//     }
+// }
+// ```
+// ->
+// ```
+// struct Point(f32, f32);
+//
+// impl Point {
+//     pub fn new(x: f32, y: f32) -> Self {
+//         Point(x, y)
+//     }
+//
+//     pub fn x(&self) -> f32 {
+//         self.0
+//     }
+//
+//     pub fn y(&self) -> f32 {
+//         self.1
+//     }
+// }
+// ```
+pub(crate) fn convert_named_struct_to_tuple_struct(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    let strukt = ctx
+        .find_node_at_offset::<ast::Struct>()
+        .map(Either::Left)
+        .or_else(|| ctx.find_node_at_offset::<ast::Variant>().map(Either::Right))?;
+    let field_list = strukt.as_ref().either(|s| s.field_list(), |v| v.field_list())?;
+    let record_fields = match field_list {
+        ast::FieldList::RecordFieldList(it) => it,
+        ast::FieldList::TupleFieldList(_) => return None,
+    };
+    let names = record_fields
+        .fields()
+        .map(|f| {
+            f.ty()?;
+            Some(f.name()?.text().to_string())
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let (strukt_def, label) = match &strukt {
+        Either::Left(s) => {
+            (hir::ModuleDef::Adt(hir::Adt::Struct(ctx.sema.to_def(s)?)), "Convert to tuple struct")
+        }
+        Either::Right(v) => {
+            (hir::ModuleDef::Variant(ctx.sema.to_def(v)?), "Convert to tuple variant")
+        }
+    };
+
+    let target = strukt.as_ref().either(|s| s.syntax(), |v| v.syntax()).text_range();
+    acc.add(
+        AssistId("convert_named_struct_to_tuple_struct", AssistKind::RefactorRewrite),
+        label,
+        target,
+        |edit| {
+            edit_field_references(ctx, edit, &record_fields);
+            edit_struct_references(ctx, edit, Definition::ModuleDef(strukt_def), &names);
+            edit_struct_def(ctx, edit, &strukt, &record_fields);
+        },
+    )
+}
+
+fn edit_struct_def(
+    ctx: &AssistContext,
+    edit: &mut AssistBuilder,
+    strukt: &Either<ast::Struct, ast::Variant>,
+    record_fields: &ast::RecordFieldList,
+) -> Option<()> {
+    let l_curly = record_fields.l_curly_token()?;
+    let r_curly = record_fields.r_curly_token()?;
+
+    edit.edit_file(ctx.frange.file_id);
+
+    // The where clause of a tuple struct goes after the fields, so it is moved along with `{`.
+    let where_clause = strukt.as_ref().left().and_then(|s| s.where_clause());
+    let open_start = match &where_clause {
+        Some(w) => whitespace_before(w.syntax().first_token()?),
+        None => whitespace_before(l_curly.clone()),
+    };
+    edit.replace(TextRange::new(open_start, open_end(&l_curly)), "(");
+
+    for field in record_fields.fields() {
+        if let (Some(name), Some(ty)) = (field.name(), field.ty()) {
+            edit.delete(TextRange::new(
+                name.syntax().text_range().start(),
+                ty.syntax().text_range().start(),
+            ));
+        }
+    }
+
+    let mut close = ")".to_string();
+    if let Either::Left(_) = strukt {
+        if let Some(w) = where_clause {
+            let separator = match w.syntax().prev_sibling_or_token() {
+                Some(ws)
+                    if ws.kind() == SyntaxKind::WHITESPACE && ws.to_string().contains('\n') =>
+                {
+                    "\n"
+                }
+                _ => " ",
+            };
+            close.push_str(separator);
+            close.push_str(w.syntax().text().to_string().trim_end().trim_end_matches(','));
+        }
+        close.push(';');
+    }
+    edit.replace(
+        TextRange::new(close_start(&l_curly, &r_curly), r_curly.text_range().end()),
+        close,
+    );
+    Some(())
+}
+
+fn edit_struct_references(
+    ctx: &AssistContext,
+    edit: &mut AssistBuilder,
+    strukt_def: Definition,
+    names: &[String],
+) {
+    let usages = strukt_def.usages(&ctx.sema).include_self_refs().all();
+
+    let edit_node = |edit: &mut AssistBuilder, name_ref: &ast::NameRef| -> Option<()> {
+        let path = ast::PathSegment::cast(name_ref.syntax().parent()?)?.parent_path();
+        let node = path.syntax().parent()?;
+        match_ast! {
+            match node {
+                ast::RecordExpr(record_expr) => {
+                    let field_list = record_expr.record_expr_field_list()?;
+                    let fields = field_list
+                        .fields()
+                        .map(|f| {
+                            Some(FieldUse {
+                                name: f.field_name()?.text().to_string(),
+                                value_start: f.expr()?.syntax().text_range().start(),
+                                name_ref: f.name_ref(),
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    // A functional record update can't be written with call syntax.
+                    let allow_call = field_list.dotdot_token().is_none();
+                    edit_field_uses(edit, names, field_list.syntax(), fields, allow_call)
+                },
+                ast::RecordPat(record_pat) => {
+                    let field_list = record_pat.record_pat_field_list()?;
+                    let fields = field_list
+                        .fields()
+                        .map(|f| {
+                            let name = match f.field_name()? {
+                                ast::NameOrNameRef::Name(it) => it.text().to_string(),
+                                ast::NameOrNameRef::NameRef(it) => it.text().to_string(),
+                            };
+                            Some(FieldUse {
+                                name,
+                                value_start: f.pat()?.syntax().text_range().start(),
+                                name_ref: f.name_ref(),
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    edit_field_uses(edit, names, field_list.syntax(), fields, true)
+                },
+                _ => None,
+            }
+        }
+    };
+
+    for (file_id, refs) in usages {
+        edit.edit_file(file_id);
+        for r in refs {
+            if let Some(name_ref) = r.name.as_name_ref() {
+                edit_node(edit, name_ref);
+            }
+        }
+    }
+}
+
+/// A field of a record literal or record pattern.
+struct FieldUse {
+    /// The field name, absent for shorthand fields.
+    name_ref: Option<ast::NameRef>,
+    name: String,
+    value_start: TextSize,
+}
+
+/// Rewrites the fields of a struct literal or pattern. Fields listed in declaration order become
+/// `Foo(a, b)`, anything else keeps the braces and uses indices instead: `Foo { 1: b, ..base }`.
+fn edit_field_uses(
+    edit: &mut AssistBuilder,
+    names: &[String],
+    field_list: &SyntaxNode,
+    fields: Vec<FieldUse>,
+    allow_call: bool,
+) -> Option<()> {
+    let indices = fields
+        .iter()
+        .map(|f| names.iter().position(|it| *it == f.name))
+        .collect::<Option<Vec<_>>>()?;
+
+    if allow_call && indices.iter().enumerate().all(|(i, &idx)| i == idx) {
+        let tokens = || field_list.children_with_tokens().filter_map(|it| it.into_token());
+        let l_curly = tokens().find(|it| it.kind() == T!['{'])?;
+        let r_curly = tokens().find(|it| it.kind() == T!['}'])?;
+        edit.replace(TextRange::new(whitespace_before(l_curly.clone()), open_end(&l_curly)), "(");
+        for field in fields {
+            if let Some(name_ref) = field.name_ref {
+                edit.delete(TextRange::new(
+                    name_ref.syntax().text_range().start(),
+                    field.value_start,
+                ));
+            }
+        }
+        edit.replace(
+            TextRange::new(close_start(&l_curly, &r_curly), r_curly.text_range().end()),
+            ")",
+        );
+    } else {
+        for (field, idx) in fields.into_iter().zip(indices) {
+            match field.name_ref {
+                Some(name_ref) => edit.replace(name_ref.syntax().text_range(), idx.to_string()),
+                None => edit.insert(field.value_start, format!("{}: ", idx)),
+            }
+        }
+    }
+    Some(())
+}
+
+fn edit_field_references(
+    ctx: &AssistContext,
+    edit: &mut AssistBuilder,
+    record_fields: &ast::RecordFieldList,
+) {
+    for (idx, field) in record_fields.fields().enumerate() {
+        let field = match ctx.sema.to_def(&field) {
+            Some(it) => it,
+            None => continue,
+        };
+        let def = Definition::Field(field);
+        let usages = def.usages(&ctx.sema).all();
+        for (file_id, refs) in usages {
+            edit.edit_file(file_id);
+            for r in refs {
+                // Record literals and patterns are handled together with the struct references.
+                let name_ref = match r.name.as_name_ref() {
+                    Some(it) => it,
+                    None => continue,
+                };
+                if name_ref.syntax().parent().and_then(ast::FieldExpr::cast).is_some() {
+                    edit.replace(name_ref.syntax().text_range(), idx.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Start of `token`, including the whitespace in front of it.
+fn whitespace_before(token: SyntaxToken) -> TextSize {
+    match token.prev_token() {
+        Some(ws) if ws.kind() == SyntaxKind::WHITESPACE => ws.text_range().start(),
+        _ => token.text_range().start(),
+    }
+}
+
+/// End of `l_curly`, including the whitespace that follows it on the same line.
+fn open_end(l_curly: &SyntaxToken) -> TextSize {
+    match l_curly.next_token() {
+        Some(ws) if is_inline_whitespace(&ws) => ws.text_range().end(),
+        _ => l_curly.text_range().end(),
+    }
+}
+
+/// Start of `r_curly`, including the whitespace that precedes it on the same line.
+fn close_start(l_curly: &SyntaxToken, r_curly: &SyntaxToken) -> TextSize {
+    match r_curly.prev_token() {
+        Some(ws) if is_inline_whitespace(&ws) && ws.prev_token().as_ref() != Some(l_curly) => {
+            ws.text_range().start()
+        }
+        _ => r_curly.text_range().start(),
+    }
+}
+
+fn is_inline_whitespace(token: &SyntaxToken) -> bool {
+    token.kind() == SyntaxKind::WHITESPACE && !token.text().contains('\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn not_applicable_other_than_record_struct() {
+        check_assist_not_applicable(convert_named_struct_to_tuple_struct, r#"struct Foo$0(u32)"#);
+        check_assist_not_applicable(convert_named_struct_to_tuple_struct, r#"struct Foo$0;"#);
+        check_assist_not_applicable(
+            convert_named_struct_to_tuple_struct,
+            r#"enum Foo { Bar$0(u32) }"#,
+        );
+    }
+
+    #[test]
+    fn convert_simple_struct() {
+        check_assist(
+            convert_named_struct_to_tuple_struct,
+            r#"
+struct Inner;
+struct A$0 { inner: Inner }
+
+impl A {
+    fn new(inner: Inner) -> A {
+        A { inner }
+    }
+
+    fn new_with_default() -> A {
+        A::new(Inner)
+    }
+
+    fn into_inner(self) -> Inner {
+        self.inner
+    }
+}"#,
+            r#"
+struct Inner;
+struct A(Inner);
+
+impl A {
+    fn new(inner: Inner) -> A {
+        A(inner)
+    }
+
+    fn new_with_default() -> A {
+        A::new(Inner)
+    }
+
+    fn into_inner(self) -> Inner {
+        self.0
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_struct_referenced_via_self_kw() {
+        check_assist(
+            convert_named_struct_to_tuple_struct,
+            r#"
+struct Inner;
+struct A$0 { inner: Inner }
+
+impl A {
+    fn new(inner: Inner) -> Self {
+        Self { inner }
+    }
+
+    fn into_inner(self) -> Inner {
+        let Self { inner: it } = self;
+        it
+    }
+}"#,
+            r#"
+struct Inner;
+struct A(Inner);
+
+impl A {
+    fn new(inner: Inner) -> Self {
+        Self(inner)
+    }
+
+    fn into_inner(self) -> Inner {
+        let Self(it) = self;
+        it
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_struct_with_visibility_and_multiline_fields() {
+        check_assist(
+            convert_named_struct_to_tuple_struct,
+            r#"
+struct A$0 {
+    /// The first one.
+    pub first: u32,
+    pub(crate) second: u64,
+}
+
+fn f(a: A) -> A {
+    A {
+        first: a.first,
+        second: a.second,
+    }
+}"#,
+            r#"
+struct A(
+    /// The first one.
+    pub u32,
+    pub(crate) u64,
+);
+
+fn f(a: A) -> A {
+    A(
+        a.0,
+        a.1,
+    )
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_out_of_order_and_partial_usages() {
+        check_assist(
+            convert_named_struct_to_tuple_struct,
+            r#"
+struct A$0 { x: u32, y: u32 }
+
+fn f(a: A) -> A {
+    let A { y, .. } = a;
+    let A { x, .. } = a;
+    A { y: x, x: y }
+}
+
+fn g(a: A) -> A {
+    A { y: 0, ..a }
+}"#,
+            r#"
+struct A(u32, u32);
+
+fn f(a: A) -> A {
+    let A { 1: y, .. } = a;
+    let A(x, ..) = a;
+    A { 1: x, 0: y }
+}
+
+fn g(a: A) -> A {
+    A { 1: 0, ..a }
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_struct_with_where_clause() {
+        check_assist(
+            convert_named_struct_to_tuple_struct,
+            r#"
+struct Wrap$0<T>
+where
+    T: Display,
+{ field1: T }
+"#,
+            r#"
+struct Wrap<T>(T)
+where
+    T: Display;
+"#,
+        );
+    }
+
+    #[test]
+    fn convert_struct_with_multi_file_references() {
+        check_assist(
+            convert_named_struct_to_tuple_struct,
+            r#"
+//- /main.rs
+struct Inner;
+struct A$0 { inner: Inner }
+
+mod foo;
+
+//- /foo.rs
+use crate::{A, Inner};
+fn f() {
+    let a = A { inner: Inner };
+    let _ = a.inner;
+}
+"#,
+            r#"
+//- /main.rs
+struct Inner;
+struct A(Inner);
+
+mod foo;
+
+//- /foo.rs
+use crate::{A, Inner};
+fn f() {
+    let a = A(Inner);
+    let _ = a.0;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn convert_variant() {
+        check_assist(
+            convert_named_struct_to_tuple_struct,
+            r#"
+enum Shape {
+    Circle { radius: f64 },
+    Rect$0 { width: f64, height: f64 },
+}
+
+fn area(shape: &Shape) -> f64 {
+    match shape {
+        Shape::Circle { radius } => radius * radius,
+        Shape::Rect { width, height } => width * height,
+    }
+}
+
+fn square(side: f64) -> Shape {
+    Shape::Rect { width: side, height: side }
+}"#,
+            r#"
+enum Shape {
+    Circle { radius: f64 },
+    Rect(f64, f64),
+}
+
+fn area(shape: &Shape) -> f64 {
+    match shape {
+        Shape::Circle { radius } => radius * radius,
+        Shape::Rect(width, height) => width * height,
+    }
+}
+
+fn square(side: f64) -> Shape {
+    Shape::Rect(side, side)
+}"#,
+        );
+    }
+}
@@ -1,14 +1,16 @@
+use either::Either;
 use ide_db::defs::{Definition, NameRefClass};
 use syntax::{
     ast::{self, AstNode, GenericParamsOwner, VisibilityOwner},
-    match_ast, SyntaxNode,
+    match_ast, SyntaxKind, SyntaxNode,
 };
 
 use crate::{assist_context::AssistBuilder, AssistContext, AssistId, AssistKind, Assists};
 
 // Assist: convert_tuple_struct_to_named_struct
 //
-// Converts tuple struct to struct with named fields.
+// Converts tuple struct to struct with named fields, and analogously for tuple and unit enum
+// variants.
 //
 // ```
 // struct Point$0(f32, f32);
@@ -49,23 +51,43 @@ pub(crate) fn convert_tuple_struct_to_named_struct(
     acc: &mut Assists,
     ctx: &AssistContext,
 ) -> Option<()> {
-    let strukt = ctx.find_node_at_offset::<ast::Struct>()?;
-    let tuple_fields = match strukt.field_list()? {
-        ast::FieldList::TupleFieldList(it) => it,
-        ast::FieldList::RecordFieldList(_) => return None,
+    let strukt = ctx
+        .find_node_at_offset::<ast::Struct>()
+        .map(Either::Left)
+        .or_else(|| ctx.find_node_at_offset::<ast::Variant>().map(Either::Right))?;
+    let field_list = strukt.as_ref().either(|s| s.field_list(), |v| v.field_list());
+    let tuple_fields = match (&strukt, field_list) {
+        (_, Some(ast::FieldList::TupleFieldList(it))) => Some(it),
+        // Unit variants become variants with an empty field list. Explicit discriminants
+        // are only allowed on those with `#[repr]`, so they are left alone.
+        (Either::Right(v), None) if v.expr().is_none() => None,
+        _ => return None,
+    };
+    let (strukt_def, label) = match &strukt {
+        Either::Left(s) => {
+            (hir::ModuleDef::Adt(hir::Adt::Struct(ctx.sema.to_def(s)?)), "Convert to named struct")
+        }
+        Either::Right(v) => {
+            (hir::ModuleDef::Variant(ctx.sema.to_def(v)?), "Convert to record variant")
+        }
     };
-    let strukt_def = ctx.sema.to_def(&strukt)?;
 
-    let target = strukt.syntax().text_range();
+    let target = strukt.as_ref().either(|s| s.syntax(), |v| v.syntax()).text_range();
     acc.add(
         AssistId("convert_tuple_struct_to_named_struct", AssistKind::RefactorRewrite),
-        "Convert to named struct",
+        label,
         target,
-        |edit| {
-            let names = generate_names(tuple_fields.fields());
-            edit_field_references(ctx, edit, tuple_fields.fields(), &names);
-            edit_struct_references(ctx, edit, strukt_def, &names);
-            edit_struct_def(ctx, edit, &strukt, tuple_fields, names);
+        |edit| match tuple_fields {
+            Some(tuple_fields) => {
+                let names = generate_names(tuple_fields.fields());
+                edit_field_references(ctx, edit, tuple_fields.fields(), &names);
+                edit_struct_references(ctx, edit, strukt_def, &names);
+                edit_struct_def(ctx, edit, &strukt, tuple_fields, names);
+            }
+            None => {
+                edit_unit_variant_references(ctx, edit, strukt_def);
+                edit.insert(target.end(), " {}");
+            }
         },
     )
 }
@@ -73,7 +95,7 @@ pub(crate) fn convert_tuple_struct_to_named_struct(
 fn edit_struct_def(
     ctx: &AssistContext,
     edit: &mut AssistBuilder,
-    strukt: &ast::Struct,
+    strukt: &Either<ast::Struct, ast::Variant>,
     tuple_fields: ast::TupleFieldList,
     names: Vec<ast::Name>,
 ) {
@@ -86,7 +108,7 @@ fn edit_struct_def(
 
     edit.edit_file(ctx.frange.file_id);
 
-    if let Some(w) = strukt.where_clause() {
+    if let Some(w) = strukt.as_ref().left().and_then(|s| s.where_clause()) {
         edit.delete(w.syntax().text_range());
         edit.insert(tuple_fields_text_range.start(), ast::make::tokens::single_newline().text());
         edit.insert(tuple_fields_text_range.start(), w.syntax().text());
@@ -97,16 +119,18 @@ fn edit_struct_def(
     }
 
     edit.replace(tuple_fields_text_range, record_fields.to_string());
-    strukt.semicolon_token().map(|t| edit.delete(t.text_range()));
+    if let Some(t) = strukt.as_ref().left().and_then(|s| s.semicolon_token()) {
+        edit.delete(t.text_range());
+    }
 }
 
 fn edit_struct_references(
     ctx: &AssistContext,
     edit: &mut AssistBuilder,
-    strukt: hir::Struct,
+    strukt: hir::ModuleDef,
     names: &[ast::Name],
 ) {
-    let strukt_def = Definition::ModuleDef(hir::ModuleDef::Adt(hir::Adt::Struct(strukt)));
+    let strukt_def = Definition::ModuleDef(strukt);
     let usages = strukt_def.usages(&ctx.sema).include_self_refs().all();
 
     let edit_node = |edit: &mut AssistBuilder, node: SyntaxNode| -> Option<()> {
@@ -178,6 +202,27 @@ fn edit_struct_references(
     }
 }
 
+fn edit_unit_variant_references(
+    ctx: &AssistContext,
+    edit: &mut AssistBuilder,
+    variant: hir::ModuleDef,
+) {
+    let usages = Definition::ModuleDef(variant).usages(&ctx.sema).all();
+    for (file_id, refs) in usages {
+        edit.edit_file(file_id);
+        for r in refs {
+            let path = match r.name.syntax().parent().and_then(ast::PathSegment::cast) {
+                Some(segment) => segment.parent_path(),
+                None => continue,
+            };
+            let parent = path.syntax().parent().map(|it| it.kind());
+            if matches!(parent, Some(SyntaxKind::PATH_EXPR) | Some(SyntaxKind::PATH_PAT)) {
+                edit.insert(path.syntax().text_range().end(), " {}");
+            }
+        }
+    }
+}
+
 fn edit_field_references(
     ctx: &AssistContext,
     edit: &mut AssistBuilder,
@@ -513,4 +558,98 @@ where
 "#,
         );
     }
+
+    #[test]
+    fn convert_tuple_variant() {
+        check_assist(
+            convert_tuple_struct_to_named_struct,
+            r#"
+enum Shape {
+    Circle$0(f64),
+    Empty,
+}
+
+fn area(shape: &Shape) -> f64 {
+    match shape {
+        Shape::Circle(r) => r * r,
+        Shape::Empty => 0.0,
+    }
+}
+
+fn unit() -> Shape {
+    Shape::Circle(1.0)
+}"#,
+            r#"
+enum Shape {
+    Circle { field1: f64 },
+    Empty,
+}
+
+fn area(shape: &Shape) -> f64 {
+    match shape {
+        Shape::Circle { field1: r } => r * r,
+        Shape::Empty => 0.0,
+    }
+}
+
+fn unit() -> Shape {
+    Shape::Circle { field1: 1.0 }
+}"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_unit_variant_with_discriminant() {
+        check_assist_not_applicable(
+            convert_tuple_struct_to_named_struct,
+            r#"
+enum Shape {
+    Circle = 1,
+    Empty$0 = 3,
+}"#,
+        );
+    }
+
+    #[test]
+    fn convert_unit_variant() {
+        check_assist(
+            convert_tuple_struct_to_named_struct,
+            r#"
+enum Shape {
+    Circle(f64),
+    Empty$0,
+}
+
+impl Shape {
+    fn area(&self) -> f64 {
+        match self {
+            Shape::Circle(r) => r * r,
+            Self::Empty => 0.0,
+        }
+    }
+
+    fn empty() -> Shape {
+        Shape::Empty
+    }
+}"#,
+            r#"
+enum Shape {
+    Circle(f64),
+    Empty {},
+}
+
+impl Shape {
+    fn area(&self) -> f64 {
+        match self {
+            Shape::Circle(r) => r * r,
+            Self::Empty {} => 0.0,
+        }
+    }
+
+    fn empty() -> Shape {
+        Shape::Empty {}
+    }
+}"#,
+        );
+    }
 }
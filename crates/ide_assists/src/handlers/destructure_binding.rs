@@ -0,0 +1,316 @@
+use hir::{HasVisibility, ModuleDef, StructKind};
+use ide_db::{defs::Definition, helpers::mod_path_to_ast};
+use itertools::Itertools;
+use rustc_hash::FxHashSet;
+use syntax::{
+    algo::find_node_at_range,
+    ast::{self, NameOwner},
+    AstNode,
+};
+
+use crate::{AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: destructure_binding
+//
+// Destructures a tuple or struct binding, replacing field accesses with the
+// new bindings.
+//
+// ```
+// struct Point { x: i32, y: i32 }
+// fn len($0p: Point) -> i32 {
+//     p.x * p.x + p.y * p.y
+// }
+// ```
+// ->
+// ```
+// struct Point { x: i32, y: i32 }
+// fn len(Point { x, y }: Point) -> i32 {
+//     x * x + y * y
+// }
+// ```
+pub(crate) fn destructure_binding(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let ident_pat = ctx.find_node_at_offset::<ast::IdentPat>()?;
+    if ident_pat.ref_token().is_some() || ident_pat.pat().is_some() {
+        return None;
+    }
+    let parent = ident_pat.syntax().parent()?;
+    if !ast::LetStmt::can_cast(parent.kind()) && !ast::Param::can_cast(parent.kind()) {
+        return None;
+    }
+    let name = ident_pat.name()?.to_string();
+
+    let db = ctx.db();
+    let module = ctx.sema.scope(ident_pat.syntax()).module()?;
+    let ty = ctx.sema.type_of_pat(&ast::Pat::IdentPat(ident_pat.clone()))?;
+    // Destructuring a reference changes the types of the bindings.
+    if ty.remove_ref().is_some() {
+        return None;
+    }
+    let (path, kind, fields) = match ty.as_adt() {
+        Some(hir::Adt::Struct(strukt)) => {
+            let path = module.find_use_path(db, ModuleDef::Adt(strukt.into()))?;
+            let fields: Vec<_> = strukt
+                .fields(db)
+                .into_iter()
+                .filter(|it| it.is_visible_from(db, module))
+                .map(|it| it.name(db).to_string())
+                .collect();
+            let kind = strukt.kind(db);
+            // Tuple struct patterns can't skip private fields.
+            if kind == StructKind::Unit
+                || kind == StructKind::Tuple && fields.len() != strukt.fields(db).len()
+            {
+                return None;
+            }
+            let has_rest = fields.len() != strukt.fields(db).len();
+            (Some((mod_path_to_ast(&path), has_rest)), kind, fields)
+        }
+        Some(_) => return None,
+        None => {
+            let fields = ty.tuple_fields(db);
+            if fields.is_empty() {
+                return None;
+            }
+            (None, StructKind::Tuple, (0..fields.len()).map(|it| it.to_string()).collect())
+        }
+    };
+
+    // Every usage has to be a field access, the value itself is gone afterwards.
+    let local = ctx.sema.to_def(&ident_pat)?;
+    let file = ctx.sema.parse(ctx.frange.file_id);
+    let mut field_exprs = Vec::new();
+    for (file_id, references) in Definition::Local(local).usages(&ctx.sema).all() {
+        if file_id != ctx.frange.file_id {
+            return None;
+        }
+        for reference in references {
+            let name_ref = find_node_at_range::<ast::NameRef>(file.syntax(), reference.range)?;
+            let path_expr = name_ref.syntax().ancestors().find_map(ast::PathExpr::cast)?;
+            let field_expr = path_expr.syntax().parent().and_then(ast::FieldExpr::cast);
+            let field_idx = field_expr.as_ref().and_then(|it| {
+                let field = it.name_ref()?.to_string();
+                fields.iter().position(|it| *it == field)
+            });
+            match (field_expr, field_idx) {
+                (Some(field_expr), Some(idx)) => field_exprs.push((field_expr, idx)),
+                _ => {
+                    cov_mark::hit!(destructure_binding_with_whole_value_usage);
+                    return None;
+                }
+            }
+        }
+    }
+
+    let scope = ident_pat
+        .syntax()
+        .ancestors()
+        .find(|it| ast::Fn::can_cast(it.kind()) || ast::ClosureExpr::can_cast(it.kind()))
+        .unwrap_or_else(|| file.syntax().clone());
+    let taken: FxHashSet<String> = scope
+        .descendants()
+        .filter_map(ast::IdentPat::cast)
+        .filter_map(|it| it.name())
+        .map(|it| it.to_string())
+        .collect();
+    let binding_names: Vec<String> = fields
+        .iter()
+        .map(|field| match kind {
+            StructKind::Record if !taken.contains(field) => field.clone(),
+            _ => format!("{}_{}", name, field),
+        })
+        .collect();
+
+    let mut_ = if ident_pat.mut_token().is_some() { "mut " } else { "" };
+    let pattern = match (path, kind) {
+        (Some((path, has_rest)), StructKind::Record) => {
+            let fields = fields.iter().zip(&binding_names).map(|(field, binding)| {
+                if field == binding {
+                    format!("{}{}", mut_, field)
+                } else {
+                    format!("{}: {}{}", field, mut_, binding)
+                }
+            });
+            let rest = if has_rest { ", .." } else { "" };
+            format!("{} {{ {}{} }}", path, fields.format(", "), rest)
+        }
+        (path, _) => {
+            let fields = binding_names.iter().map(|it| format!("{}{}", mut_, it));
+            let path = path.map_or(String::new(), |(it, _)| it.to_string());
+            // A 1-tuple pattern needs a trailing comma.
+            let comma = if path.is_empty() && binding_names.len() == 1 { "," } else { "" };
+            format!("{}({}{})", path, fields.format(", "), comma)
+        }
+    };
+
+    acc.add(
+        AssistId("destructure_binding", AssistKind::RefactorRewrite),
+        format!("Destructure `{}`", name),
+        ident_pat.syntax().text_range(),
+        |builder| {
+            builder.replace(ident_pat.syntax().text_range(), pattern);
+            for (field_expr, idx) in field_exprs {
+                builder.replace(field_expr.syntax().text_range(), binding_names[idx].clone());
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn destructures_tuple_let() {
+        check_assist(
+            destructure_binding,
+            r#"
+fn main() {
+    let $0pair = (1, 2);
+    let sum = pair.0 + pair.1;
+}
+"#,
+            r#"
+fn main() {
+    let (pair_0, pair_1) = (1, 2);
+    let sum = pair_0 + pair_1;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn destructures_mutable_tuple() {
+        check_assist(
+            destructure_binding,
+            r#"
+fn main() {
+    let mut $0single = (1,);
+    single.0 += 1;
+}
+"#,
+            r#"
+fn main() {
+    let (mut single_0,) = (1,);
+    single_0 += 1;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn destructures_struct_param() {
+        check_assist(
+            destructure_binding,
+            r#"
+struct Point { x: i32, y: i32 }
+fn f($0p: Point) -> i32 {
+    let y = 2;
+    p.x * p.y + y
+}
+"#,
+            r#"
+struct Point { x: i32, y: i32 }
+fn f(Point { x, y: p_y }: Point) -> i32 {
+    let y = 2;
+    x * p_y + y
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn destructures_tuple_struct() {
+        check_assist(
+            destructure_binding,
+            r#"
+mod m {
+    pub struct Pair(pub u8, pub u8);
+}
+fn f($0p: m::Pair) -> u8 {
+    p.1
+}
+"#,
+            r#"
+mod m {
+    pub struct Pair(pub u8, pub u8);
+}
+fn f(m::Pair(p_0, p_1): m::Pair) -> u8 {
+    p_1
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn skips_private_fields() {
+        check_assist(
+            destructure_binding,
+            r#"
+mod m {
+    pub struct S { pub a: u8, b: u8 }
+    pub fn s() -> S { S { a: 0, b: 0 } }
+}
+use m::S;
+fn f() -> u8 {
+    let $0s = m::s();
+    s.a
+}
+"#,
+            r#"
+mod m {
+    pub struct S { pub a: u8, b: u8 }
+    pub fn s() -> S { S { a: 0, b: 0 } }
+}
+use m::S;
+fn f() -> u8 {
+    let S { a, .. } = m::s();
+    a
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_private_tuple_fields() {
+        check_assist_not_applicable(
+            destructure_binding,
+            r#"
+mod m {
+    pub struct Pair(pub u8, u8);
+}
+fn f($0p: m::Pair) -> u8 {
+    p.0
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_references() {
+        check_assist_not_applicable(
+            destructure_binding,
+            r#"
+fn f($0pair: &(u8, u8)) -> u8 {
+    pair.0
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_whole_value_usage() {
+        cov_mark::check!(destructure_binding_with_whole_value_usage);
+        check_assist_not_applicable(
+            destructure_binding,
+            r#"
+fn g(pair: (u8, u8)) {}
+fn f($0pair: (u8, u8)) -> u8 {
+    g(pair);
+    pair.0
+}
+"#,
+        );
+    }
+}
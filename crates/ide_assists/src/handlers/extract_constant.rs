@@ -0,0 +1,704 @@
+use hir::{AssocItem, HirDisplay, ModuleDef, ModuleSource, PathResolution, StructKind};
+use ide_db::{defs::Definition, RootDatabase};
+use stdx::{format_to, to_upper_snake_case};
+use syntax::{
+    ast::{self, edit::IndentLevel, ArgListOwner, AstNode, NameOwner},
+    SyntaxKind::{IDENT, ITEM_LIST, SOURCE_FILE, WHITESPACE},
+    SyntaxNode, TextRange, TextSize,
+};
+
+use crate::{
+    assist_context::{AssistContext, Assists},
+    utils::suggest_name,
+    AssistId, AssistKind, GroupLabel,
+};
+
+// Assist: extract_constant
+//
+// Extracts a const-evaluable expression, or a local initialized with one, into a `const` item.
+// All identical occurrences in the enclosing function are replaced.
+//
+// ```
+// fn area(r: f64) -> f64 {
+//     $03.14159$0 * r * r
+// }
+// ```
+// ->
+// ```
+// fn area(r: f64) -> f64 {
+//     const $0VAR_NAME: f64 = 3.14159;
+//     VAR_NAME * r * r
+// }
+// ```
+pub(crate) fn extract_constant(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    extract_item(acc, ctx, ItemKind::Const)
+}
+
+// Assist: extract_static
+//
+// Extracts a const-evaluable expression, or a local initialized with one, into a `static` item.
+// All identical occurrences in the enclosing function are replaced.
+//
+// ```
+// fn greet() -> &'static str {
+//     let $0greeting = "Hello";
+//     greeting
+// }
+// ```
+// ->
+// ```
+// fn greet() -> &'static str {
+//     static $0GREETING: &str = "Hello";
+//     GREETING
+// }
+// ```
+pub(crate) fn extract_static(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    extract_item(acc, ctx, ItemKind::Static)
+}
+
+#[derive(Clone, Copy)]
+enum ItemKind {
+    Const,
+    Static,
+}
+
+impl ItemKind {
+    fn keyword(self) -> &'static str {
+        match self {
+            ItemKind::Const => "const",
+            ItemKind::Static => "static",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ItemKind::Const => "constant",
+            ItemKind::Static => "static",
+        }
+    }
+}
+
+enum Scope {
+    Function,
+    Impl(ast::AssocItemList),
+    /// The module level item containing the function.
+    Module(SyntaxNode),
+}
+
+impl Scope {
+    fn label(&self) -> &'static str {
+        match self {
+            Scope::Function => "function",
+            Scope::Impl(_) => "impl",
+            Scope::Module(_) => "module",
+        }
+    }
+}
+
+enum Target {
+    Expr(ast::Expr),
+    Local(ast::LetStmt, hir::Local),
+}
+
+fn extract_item(acc: &mut Assists, ctx: &AssistContext, kind: ItemKind) -> Option<()> {
+    let (target, init, name, ty) = if ctx.frange.range.is_empty() {
+        let ident_pat = ctx.find_node_at_offset::<ast::IdentPat>()?;
+        let let_stmt = ident_pat.syntax().parent().and_then(ast::LetStmt::cast)?;
+        if ident_pat.mut_token().is_some() || ident_pat.ref_token().is_some() {
+            return None;
+        }
+        let local = ctx.sema.to_def(&ident_pat)?;
+        let init = let_stmt.initializer()?;
+        let name = to_upper_snake_case(&ident_pat.name()?.text());
+        let ty = let_stmt.ty().map(|ty| ty.to_string());
+        (Target::Local(let_stmt, local), init, name, ty)
+    } else {
+        let expr = ctx.covering_element().ancestors().find_map(ast::Expr::cast)?;
+        if let ast::Expr::PathExpr(_) = expr {
+            return None;
+        }
+        let name = to_upper_snake_case(&suggest_name::for_variable(&expr, &ctx.sema));
+        (Target::Expr(expr.clone()), expr, name, None)
+    };
+    if !is_const_evaluable(&ctx.sema, &init) {
+        return None;
+    }
+    let ty = match ty {
+        Some(ty) => ty,
+        None => {
+            let ty = ctx.sema.type_of_expr(&init)?;
+            if ty.is_unknown() {
+                return None;
+            }
+            let module = ctx.sema.scope(init.syntax()).module()?;
+            ty.display_source_code(ctx.db(), module.into()).ok()?
+        }
+    };
+
+    let fn_ = init.syntax().ancestors().find_map(ast::Fn::cast)?;
+    let body = fn_.body()?;
+    let uses_self = init
+        .syntax()
+        .descendants_with_tokens()
+        .any(|it| it.kind() == IDENT && it.as_token().filter(|it| it.text() == "Self").is_some());
+
+    // Items declared in the function are only visible inside of it.
+    let local_items = uses_items_of(ctx, &fn_, &init);
+
+    let mut scopes = Vec::new();
+    if !uses_self {
+        scopes.push(Scope::Function);
+    }
+    if let (ItemKind::Const, false) = (kind, local_items) {
+        let impl_items = fn_.syntax().parent().and_then(ast::AssocItemList::cast);
+        if let Some(impl_items) = impl_items {
+            let is_inherent =
+                impl_items.syntax().parent().and_then(ast::Impl::cast)?.trait_().is_none();
+            if is_inherent {
+                scopes.push(Scope::Impl(impl_items));
+            }
+        }
+    }
+    if !uses_self && !local_items {
+        let item = fn_.syntax().ancestors().find(|it| {
+            it.parent().filter(|p| matches!(p.kind(), SOURCE_FILE | ITEM_LIST)).is_some()
+        });
+        if let Some(item) = item {
+            scopes.push(Scope::Module(item));
+        }
+    }
+
+    let usages = match &target {
+        Target::Expr(expr) => occurrences(&ctx.sema, &fn_, &body, expr),
+        Target::Local(_, local) => {
+            let usages = Definition::Local(*local).usages(&ctx.sema).all();
+            let refs = usages.references.get(&ctx.frange.file_id).cloned().unwrap_or_default();
+            refs.into_iter()
+                .map(|reference| {
+                    let shorthand = reference
+                        .name
+                        .as_name_ref()
+                        .and_then(ast::RecordExprField::for_field_name)
+                        .is_some();
+                    Usage { range: reference.range, field_shorthand: shorthand }
+                })
+                .collect()
+        }
+    };
+
+    let label = GroupLabel(format!("Extract into {}…", kind.description()));
+    let assist_id = match kind {
+        ItemKind::Const => AssistId("extract_constant", AssistKind::RefactorExtract),
+        ItemKind::Static => AssistId("extract_static", AssistKind::RefactorExtract),
+    };
+    let target_range = match &target {
+        Target::Expr(expr) => expr.syntax().text_range(),
+        Target::Local(let_stmt, _) => let_stmt.syntax().text_range(),
+    };
+    for scope in scopes {
+        acc.add_group(
+            &label,
+            assist_id,
+            format!("Extract into {} in {}", kind.description(), scope.label()),
+            target_range,
+            |builder| {
+                let decl = |name: &str| format!("{} {}: {} = {};", kind.keyword(), name, ty, init);
+                let snippet_name = match ctx.config.snippet_cap {
+                    Some(_) => format!("$0{}", name),
+                    None => name.clone(),
+                };
+                let mut insert = None;
+                match &scope {
+                    Scope::Function => match &target {
+                        Target::Local(let_stmt, _) => {
+                            let range = let_stmt.syntax().text_range();
+                            match ctx.config.snippet_cap {
+                                Some(cap) => {
+                                    builder.replace_snippet(cap, range, decl(&snippet_name))
+                                }
+                                None => builder.replace(range, decl(&name)),
+                            }
+                        }
+                        Target::Expr(_) => {
+                            let offset = body.l_curly_token();
+                            let offset = match offset {
+                                Some(it) => it.text_range().end(),
+                                None => body.syntax().text_range().start(),
+                            };
+                            let indent = IndentLevel::from_node(fn_.syntax()) + 1;
+                            insert = Some((offset, format!("\n{}{}", indent, decl(&snippet_name))));
+                        }
+                    },
+                    Scope::Impl(items) => {
+                        let offset = match items.l_curly_token() {
+                            Some(it) => it.text_range().end(),
+                            None => items.syntax().text_range().start(),
+                        };
+                        let indent = IndentLevel::from_node(items.syntax()) + 1;
+                        insert = Some((offset, format!("\n{}{}\n", indent, decl(&snippet_name))));
+                    }
+                    Scope::Module(item) => {
+                        let offset = item.text_range().start();
+                        let indent = IndentLevel::from_node(item);
+                        insert = Some((offset, format!("{}\n\n{}", decl(&snippet_name), indent)));
+                    }
+                }
+                if let (Some((offset, text)), Some(cap)) = (&insert, ctx.config.snippet_cap) {
+                    builder.insert_snippet(cap, *offset, text);
+                } else if let Some((offset, text)) = insert {
+                    builder.insert(offset, text);
+                }
+
+                if let (Target::Local(let_stmt, _), Scope::Impl(_) | Scope::Module(_)) =
+                    (&target, &scope)
+                {
+                    builder.delete(with_leading_whitespace(let_stmt.syntax()));
+                }
+
+                let reference = match scope {
+                    Scope::Impl(_) => format!("Self::{}", name),
+                    _ => name.clone(),
+                };
+                for usage in &usages {
+                    if usage.field_shorthand {
+                        builder.insert(usage.range.end(), format!(": {}", reference));
+                    } else {
+                        builder.replace(usage.range, reference.clone());
+                    }
+                }
+            },
+        );
+    }
+    Some(())
+}
+
+struct Usage {
+    range: TextRange,
+    /// Whether this is a record literal field shorthand, which needs to be expanded.
+    field_shorthand: bool,
+}
+
+/// Finds all expressions in `fn_` which are textually identical to `expr` and still evaluate
+/// to a constant, skipping nested functions.
+fn occurrences(
+    sema: &hir::Semantics<RootDatabase>,
+    fn_: &ast::Fn,
+    body: &ast::BlockExpr,
+    expr: &ast::Expr,
+) -> Vec<Usage> {
+    let text = normalized_text(expr.syntax());
+    let mut res: Vec<Usage> = Vec::new();
+    let mut last_end = TextSize::from(0);
+    for node in body.syntax().descendants() {
+        if node.text_range().start() < last_end || node.kind() != expr.syntax().kind() {
+            continue;
+        }
+        let candidate = match ast::Expr::cast(node) {
+            Some(it) => it,
+            None => continue,
+        };
+        let in_fn = candidate.syntax().ancestors().find_map(ast::Fn::cast).as_ref() == Some(fn_);
+        if !in_fn
+            || normalized_text(candidate.syntax()) != text
+            || !is_const_evaluable(sema, &candidate)
+        {
+            continue;
+        }
+        let range = candidate.syntax().text_range();
+        last_end = range.end();
+        res.push(Usage { range, field_shorthand: false });
+    }
+    res
+}
+
+fn normalized_text(node: &SyntaxNode) -> String {
+    let mut res = String::new();
+    for token in node.descendants_with_tokens().filter_map(|it| it.into_token()) {
+        if !token.kind().is_trivia() {
+            format_to!(res, "{} ", token.text());
+        }
+    }
+    res
+}
+
+/// Checks that `expr` only consists of literals, constants, constructors and `const fn` calls,
+/// which makes it usable as the initializer of a `const` or `static`.
+fn is_const_evaluable(sema: &hir::Semantics<RootDatabase>, expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Literal(_) => true,
+        ast::Expr::ParenExpr(it) => all(sema, it.expr().into_iter()),
+        ast::Expr::PrefixExpr(it) => {
+            it.op_kind() != Some(ast::PrefixOp::Deref) && all(sema, it.expr().into_iter())
+        }
+        ast::Expr::BinExpr(it) => {
+            matches!(it.op_kind(), Some(op) if !op.is_assignment())
+                && all(sema, it.lhs().into_iter().chain(it.rhs()))
+        }
+        ast::Expr::RefExpr(it) => it.mut_token().is_none() && all(sema, it.expr().into_iter()),
+        ast::Expr::CastExpr(it) => all(sema, it.expr().into_iter()),
+        ast::Expr::IndexExpr(it) => all(sema, it.base().into_iter().chain(it.index())),
+        ast::Expr::TupleExpr(it) => all(sema, it.fields()),
+        ast::Expr::ArrayExpr(it) => all(sema, it.exprs()),
+        ast::Expr::RecordExpr(it) => {
+            let fields = match it.record_expr_field_list() {
+                Some(it) => it,
+                None => return false,
+            };
+            let spread = fields.spread();
+            fields.fields().all(|field| match field.expr() {
+                Some(expr) => is_const_evaluable(sema, &expr),
+                None => false,
+            }) && all(sema, spread.into_iter())
+        }
+        ast::Expr::PathExpr(it) => match it.path() {
+            Some(path) => is_const_path(sema, &path),
+            None => false,
+        },
+        ast::Expr::CallExpr(it) => {
+            let callee_is_const = match it.expr() {
+                Some(ast::Expr::PathExpr(callee)) => {
+                    callee.path().filter(|path| is_const_path(sema, path)).is_some()
+                }
+                _ => false,
+            };
+            callee_is_const && all(sema, it.arg_list().into_iter().flat_map(|it| it.args()))
+        }
+        ast::Expr::MethodCallExpr(it) => {
+            let is_const = sema.resolve_method_call(it).filter(|it| it.is_const(sema.db)).is_some();
+            let generic = match it.generic_arg_list() {
+                Some(args) => mentions_generic_params(sema, args.syntax()),
+                None => false,
+            };
+            is_const
+                && !generic
+                && all(
+                    sema,
+                    it.receiver()
+                        .into_iter()
+                        .chain(it.arg_list().into_iter().flat_map(|it| it.args())),
+                )
+        }
+        _ => false,
+    }
+}
+
+fn all(sema: &hir::Semantics<RootDatabase>, mut exprs: impl Iterator<Item = ast::Expr>) -> bool {
+    exprs.all(|expr| is_const_evaluable(sema, &expr))
+}
+
+fn is_const_path(sema: &hir::Semantics<RootDatabase>, path: &ast::Path) -> bool {
+    let qualifier_ok = match path.qualifier() {
+        Some(qualifier) => sema.resolve_path(&qualifier).is_some(),
+        None => true,
+    };
+    qualifier_ok
+        && !mentions_generic_params(sema, path.syntax())
+        && match sema.resolve_path(path) {
+            Some(PathResolution::Def(def)) => match def {
+                ModuleDef::Const(_) => true,
+                ModuleDef::Function(it) => it.is_const(sema.db),
+                ModuleDef::Variant(_) => true,
+                ModuleDef::Adt(hir::Adt::Struct(it)) => it.kind(sema.db) != StructKind::Record,
+                _ => false,
+            },
+            Some(PathResolution::AssocItem(it)) => match it {
+                AssocItem::Const(_) => true,
+                AssocItem::Function(it) => it.is_const(sema.db),
+                AssocItem::TypeAlias(_) => false,
+            },
+            _ => false,
+        }
+}
+
+/// Whether a path in `node` refers to a type or const parameter, which items can't use.
+fn mentions_generic_params(sema: &hir::Semantics<RootDatabase>, node: &SyntaxNode) -> bool {
+    node.descendants().filter_map(ast::Path::cast).any(|path| {
+        matches!(
+            sema.resolve_path(&path),
+            Some(PathResolution::TypeParam(_)) | Some(PathResolution::ConstParam(_))
+        )
+    })
+}
+
+/// Whether `expr` refers to items declared inside of `fn_`, which aren't visible outside of it.
+fn uses_items_of(ctx: &AssistContext, fn_: &ast::Fn, expr: &ast::Expr) -> bool {
+    let db = ctx.db();
+    expr.syntax().descendants().filter_map(ast::Path::cast).any(|path| {
+        let module = match ctx.sema.resolve_path(&path) {
+            Some(PathResolution::Def(def)) => def.module(db),
+            Some(PathResolution::AssocItem(it)) => Some(it.module(db)),
+            _ => None,
+        };
+        let source = match module {
+            Some(it) => it.definition_source(db),
+            None => return false,
+        };
+        match source.value {
+            ModuleSource::BlockExpr(block) => {
+                source.file_id == ctx.frange.file_id.into()
+                    && fn_.syntax().text_range().contains_range(block.syntax().text_range())
+            }
+            _ => false,
+        }
+    })
+}
+
+fn with_leading_whitespace(node: &SyntaxNode) -> TextRange {
+    let range = node.text_range();
+    match node.prev_sibling_or_token() {
+        Some(prev) if prev.kind() == WHITESPACE => {
+            TextRange::new(prev.text_range().start(), range.end())
+        }
+        _ => range,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{
+        check_assist_by_label, check_assist_not_applicable, check_assist_not_applicable_by_label,
+    };
+
+    use super::*;
+
+    #[test]
+    fn extract_expression_in_function() {
+        check_assist_by_label(
+            extract_constant,
+            r#"
+fn area(r: f64) -> f64 {
+    let a = $03.14159$0 * r * r;
+    let b = 3.14159 * 2.0;
+    a + b
+}
+"#,
+            r#"
+fn area(r: f64) -> f64 {
+    const $0VAR_NAME: f64 = 3.14159;
+    let a = VAR_NAME * r * r;
+    let b = VAR_NAME * 2.0;
+    a + b
+}
+"#,
+            "Extract into constant in function",
+        );
+    }
+
+    #[test]
+    fn extract_expression_into_module() {
+        check_assist_by_label(
+            extract_constant,
+            r#"
+const fn kib(n: usize) -> usize { n * 1024 }
+mod buf {
+    fn alloc() -> Vec<u8> {
+        Vec::with_capacity($0super::kib(4) + 16$0)
+    }
+}
+"#,
+            r#"
+const fn kib(n: usize) -> usize { n * 1024 }
+mod buf {
+    const $0VAR_NAME: usize = super::kib(4) + 16;
+
+    fn alloc() -> Vec<u8> {
+        Vec::with_capacity(VAR_NAME)
+    }
+}
+"#,
+            "Extract into constant in module",
+        );
+    }
+
+    #[test]
+    fn promote_local_into_impl() {
+        check_assist_by_label(
+            extract_constant,
+            r#"
+struct Grid;
+impl Grid {
+    const SIZE: u32 = 8;
+
+    fn cells(&self) -> u32 {
+        let $0cells = Self::SIZE * Self::SIZE;
+        cells + 1
+    }
+}
+"#,
+            r#"
+struct Grid;
+impl Grid {
+    const $0CELLS: u32 = Self::SIZE * Self::SIZE;
+
+    const SIZE: u32 = 8;
+
+    fn cells(&self) -> u32 {
+        Self::CELLS + 1
+    }
+}
+"#,
+            "Extract into constant in impl",
+        );
+    }
+
+    #[test]
+    fn promote_local_into_static() {
+        check_assist_by_label(
+            extract_static,
+            r#"
+struct Point { x: i32, y: i32 }
+fn origin() -> Point {
+    let $0x: i32 = -1;
+    Point { x, y: x }
+}
+"#,
+            r#"
+struct Point { x: i32, y: i32 }
+static $0X: i32 = -1;
+
+fn origin() -> Point {
+    Point { x: X, y: X }
+}
+"#,
+            "Extract into static in module",
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_local_references() {
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+fn f(n: i32) -> i32 {
+    $0n * 2$0
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_non_const_calls() {
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+fn two() -> i32 { 2 }
+fn f() -> i32 {
+    let $0x = two() * 2;
+    x
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_generic_args() {
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+mod mem { pub const fn size_of<T>() -> usize { 0 } }
+fn f<T>() -> usize {
+    $0mem::size_of::<T>()$0
+}
+"#,
+        );
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+const fn id<const N: usize>() -> usize { N }
+fn f<const N: usize>() -> usize {
+    $0id::<N>()$0
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn local_items_only_extract_into_function() {
+        let before = r#"
+fn f() -> i32 {
+    const LIMIT: i32 = 10;
+    $0LIMIT * 2$0
+}
+"#;
+        check_assist_by_label(
+            extract_constant,
+            before,
+            r#"
+fn f() -> i32 {
+    const $0VAR_NAME: i32 = LIMIT * 2;
+    const LIMIT: i32 = 10;
+    VAR_NAME
+}
+"#,
+            "Extract into constant in function",
+        );
+        check_assist_not_applicable_by_label(
+            extract_constant,
+            before,
+            "Extract into constant in module",
+        );
+        check_assist_not_applicable(
+            extract_constant,
+            r#"
+struct Grid;
+impl Grid {
+    const SIZE: u32 = 8;
+
+    fn cells(&self) -> u32 {
+        const BORDER: u32 = 2;
+        $0Self::SIZE + BORDER$0
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn replace_all_occurrences() {
+        check_assist_by_label(
+            extract_constant,
+            r#"
+fn f(n: u64) -> u64 {
+    let a = n * $0(60 * 60)$0;
+    let b = a + (60 * 60);
+    let g = || (60 * 60);
+    b - (60*60) + g()
+}
+"#,
+            r#"
+fn f(n: u64) -> u64 {
+    const $0VAR_NAME: u64 = (60 * 60);
+    let a = n * VAR_NAME;
+    let b = a + VAR_NAME;
+    let g = || VAR_NAME;
+    b - VAR_NAME + g()
+}
+"#,
+            "Extract into constant in function",
+        );
+    }
+
+    #[test]
+    fn promote_local_used_in_field_shorthand() {
+        check_assist_by_label(
+            extract_constant,
+            r#"
+struct Size { width: u32, height: u32 }
+fn square() -> Size {
+    let $0width = 4;
+    let height = width;
+    Size { width, height }
+}
+"#,
+            r#"
+struct Size { width: u32, height: u32 }
+fn square() -> Size {
+    const $0WIDTH: u32 = 4;
+    let height = WIDTH;
+    Size { width: WIDTH, height }
+}
+"#,
+            "Extract into constant in function",
+        );
+    }
+}
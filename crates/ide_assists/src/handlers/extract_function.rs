@@ -262,7 +262,7 @@ struct Function {
 }
 
 #[derive(Debug)]
-struct Param {
+pub(crate) struct Param {
     var: Local,
     ty: hir::Type,
     has_usages_afterwards: bool,
@@ -323,7 +323,7 @@ impl Param {
         }
     }
 
-    fn to_arg(&self, ctx: &AssistContext) -> ast::Expr {
+    pub(crate) fn to_arg(&self, ctx: &AssistContext) -> ast::Expr {
         let var = path_expr_from_local(ctx, self.var);
         match self.kind() {
             ParamKind::Value | ParamKind::MutValue => var,
@@ -332,7 +332,7 @@ impl Param {
         }
     }
 
-    fn to_param(&self, ctx: &AssistContext, module: hir::Module) -> ast::Param {
+    pub(crate) fn to_param(&self, ctx: &AssistContext, module: hir::Module) -> ast::Param {
         let var = self.var.name(ctx.db()).unwrap().to_string();
         let var_name = make::name(&var);
         let pat = match self.kind() {
@@ -712,6 +712,27 @@ fn extracted_function_params(
         .collect()
 }
 
+/// find variables captured by `body` that become params when it is moved out of
+/// its function, e.g. when converting a closure to a fn
+///
+/// Captures are taken by reference if `by_ref` is set. Returns `None` if `body` uses `self`.
+pub(crate) fn captured_params(
+    ctx: &AssistContext,
+    body: &ast::Expr,
+    by_ref: bool,
+) -> Option<Vec<Param>> {
+    let body = FunctionBody::Expr(body.clone());
+    let vars_used_in_body = vars_used_in_body(ctx, &body);
+    if self_param_from_usages(ctx, &body, &vars_used_in_body).is_some() {
+        return None;
+    }
+    let params = extracted_function_params(ctx, &body, &vars_used_in_body)
+        .into_iter()
+        .map(|param| Param { has_usages_afterwards: by_ref, ..param })
+        .collect();
+    Some(params)
+}
+
 fn has_usages_after_body(usages: &LocalUsages, body: &FunctionBody) -> bool {
     usages.iter().any(|reference| body.preceedes_range(reference.range))
 }
@@ -745,7 +766,7 @@ fn reference_is_exclusive(
 }
 
 /// checks if this expr requires `&mut` access, recurses on field access
-fn expr_require_exclusive_access(ctx: &AssistContext, expr: &ast::Expr) -> Option<bool> {
+pub(crate) fn expr_require_exclusive_access(ctx: &AssistContext, expr: &ast::Expr) -> Option<bool> {
     match expr {
         ast::Expr::MacroCall(_) => {
             // FIXME: expand macro and check output for mutable usages of the variable?
@@ -1359,7 +1380,11 @@ fn rewrite_body_segment(
 }
 
 /// change all usages to account for added `&`/`&mut` for some params
-fn fix_param_usages(ctx: &AssistContext, params: &[Param], syntax: &SyntaxNode) -> SyntaxNode {
+pub(crate) fn fix_param_usages(
+    ctx: &AssistContext,
+    params: &[Param],
+    syntax: &SyntaxNode,
+) -> SyntaxNode {
     let mut usages_for_param: Vec<(&Param, Vec<ast::Expr>)> = Vec::new();
 
     let tm = TreeMutator::new(syntax);
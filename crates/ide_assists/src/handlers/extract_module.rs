@@ -0,0 +1,766 @@
+use hir::{HasVisibility, Module, ModuleDef, PathResolution, ScopeDef, Visibility};
+use ide_db::{
+    base_db::FileId,
+    defs::Definition,
+    search::{FileReference, UsageSearchResult},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use stdx::format_to;
+use syntax::{
+    ast::{self, edit::AstNodeEdit, edit::IndentLevel, AstNode, NameOwner},
+    NodeOrToken, SyntaxNode, TextRange, TextSize,
+};
+use text_edit::TextEdit;
+
+use crate::{
+    assist_context::AssistBuilder, utils::vis_offset, AssistContext, AssistId, AssistKind, Assists,
+};
+
+// Assist: extract_module
+//
+// Extracts the selected items into a new module.
+//
+// ```
+// $0fn foo(name: i32) -> i32 {
+//     name + 1
+// }$0
+//
+// fn bar(name: i32) -> i32 {
+//     foo(name) + 2
+// }
+// ```
+// ->
+// ```
+// mod $0modname {
+//     pub(super) fn foo(name: i32) -> i32 {
+//         name + 1
+//     }
+// }
+//
+// fn bar(name: i32) -> i32 {
+//     modname::foo(name) + 2
+// }
+// ```
+pub(crate) fn extract_module(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    if ctx.frange.range.is_empty() {
+        return None;
+    }
+
+    let items = selected_items(ctx)?;
+    let first = items.first()?;
+    let last = items.last()?;
+    let range =
+        TextRange::new(first.syntax().text_range().start(), last.syntax().text_range().end());
+    let parent_module = ctx.sema.scope(first.syntax()).module()?;
+
+    acc.add(
+        AssistId("extract_module", AssistKind::RefactorExtract),
+        "Extract into module",
+        range,
+        |builder| {
+            let module_name = "modname";
+            let selection = Selection { file_id: ctx.frange.file_id, range, parent_module };
+
+            let mut inner_edit = TextEdit::builder();
+            let mut outer_edits: FxHashMap<FileId, Vec<TextSize>> = FxHashMap::default();
+            for item in &items {
+                for (def, node) in definitions_of(ctx, item) {
+                    let usages = def.usages(&ctx.sema).all();
+                    if let Some(vis) = selection.required_visibility(ctx, def, &usages) {
+                        inner_edit.insert(vis_offset(&node) - range.start(), format!("{} ", vis));
+                    }
+                    if matches!(def, Definition::ModuleDef(_)) && node == *item.syntax() {
+                        for (file_id, offset) in selection.references_to_qualify(ctx, &usages) {
+                            outer_edits.entry(file_id).or_default().push(offset);
+                        }
+                    }
+                }
+            }
+
+            let declared_names = declared_names(&items);
+            let mut imports = Vec::new();
+            for item in &items {
+                adjust_paths(ctx, &selection, &declared_names, item, &mut inner_edit, &mut imports);
+            }
+
+            let current_file_edits = outer_edits.remove(&ctx.frange.file_id);
+            for (file_id, offsets) in outer_edits {
+                builder.edit_file(file_id);
+                qualify_references(builder, offsets, module_name);
+            }
+
+            builder.edit_file(ctx.frange.file_id);
+            if let Some(offsets) = current_file_edits {
+                qualify_references(builder, offsets, module_name);
+            }
+            let mut body = String::new();
+            for import in &imports {
+                format_to!(body, "use super::{};\n", import);
+            }
+            if !imports.is_empty() {
+                body.push('\n');
+            }
+            let mut items_text = first.syntax().parent().map_or_else(String::new, |parent| {
+                let text = parent.text();
+                let start = range.start() - parent.text_range().start();
+                text.slice(start..start + range.len()).to_string()
+            });
+            inner_edit.finish().apply(&mut items_text);
+            body.push_str(&items_text);
+
+            let indent = IndentLevel::from_node(first.syntax());
+            let body = ast::SourceFile::parse(&body).tree().indent(indent + 1);
+            let module =
+                |name: &str| format!("mod {} {{\n{}{}\n{}}}", name, indent + 1, body, indent);
+            match ctx.config.snippet_cap {
+                Some(cap) => {
+                    builder.replace_snippet(cap, range, module(&format!("$0{}", module_name)))
+                }
+                None => builder.replace(range, module(module_name)),
+            }
+        },
+    )
+}
+
+fn qualify_references(builder: &mut AssistBuilder, mut offsets: Vec<TextSize>, module_name: &str) {
+    offsets.sort();
+    offsets.dedup();
+    for offset in offsets {
+        builder.insert(offset, format!("{}::", module_name));
+    }
+}
+
+/// Returns the items fully covered by the selection. Items which are only
+/// partially selected make the assist unavailable.
+fn selected_items(ctx: &AssistContext) -> Option<Vec<ast::Item>> {
+    let node = match ctx.covering_element() {
+        NodeOrToken::Node(it) => it,
+        NodeOrToken::Token(it) => it.parent()?,
+    };
+    let container = node
+        .ancestors()
+        .find(|it| ast::SourceFile::can_cast(it.kind()) || ast::ItemList::can_cast(it.kind()))?;
+
+    let selection = ctx.frange.range;
+    let mut items = Vec::new();
+    for item in container.children().filter_map(ast::Item::cast) {
+        let item_range = item.syntax().text_range();
+        if item_range.intersect(selection).filter(|it| !it.is_empty()).is_none() {
+            continue;
+        }
+        if !selection.contains_range(item_range) {
+            return None;
+        }
+        // Moving an outline module would change the path of its file.
+        if let ast::Item::Module(module) = &item {
+            module.item_list()?;
+        }
+        items.push(item);
+    }
+    if items.is_empty() {
+        return None;
+    }
+    Some(items)
+}
+
+struct Selection {
+    file_id: FileId,
+    range: TextRange,
+    parent_module: Module,
+}
+
+impl Selection {
+    fn contains(&self, file_id: FileId, range: TextRange) -> bool {
+        file_id == self.file_id && self.range.contains_range(range)
+    }
+
+    /// Returns the visibility `def` needs so that it can still be used from
+    /// outside of the new module, if its current one isn't enough.
+    fn required_visibility(
+        &self,
+        ctx: &AssistContext,
+        def: Definition,
+        usages: &UsageSearchResult,
+    ) -> Option<&'static str> {
+        let db = ctx.db();
+        let visibility = match def {
+            Definition::ModuleDef(it) => it.definition_visibility(db)?,
+            Definition::Field(it) => it.visibility(db),
+            _ => return None,
+        };
+        if visibility != Visibility::Module(self.parent_module.into()) {
+            return None;
+        }
+
+        // A private item is only visible in the parent module and its
+        // descendants, so `pub(super)` is always enough.
+        let used_outside = usages.iter().any(|(&file_id, references)| {
+            references.iter().any(|reference| !self.contains(file_id, reference.range))
+        });
+        if used_outside {
+            Some("pub(super)")
+        } else {
+            None
+        }
+    }
+
+    /// Returns the offsets at which references to a moved item need to be
+    /// qualified with the new module.
+    fn references_to_qualify(
+        &self,
+        ctx: &AssistContext,
+        usages: &UsageSearchResult,
+    ) -> Vec<(FileId, TextSize)> {
+        let mut res = Vec::new();
+        for (&file_id, references) in usages.iter() {
+            let references: Vec<_> = references
+                .iter()
+                .filter(|it| !self.contains(file_id, it.range))
+                .filter_map(|it| Some((it, path_of(it)?)))
+                .collect();
+            for (reference, path) in &references {
+                let qualify = match qualifier_of(path) {
+                    Some(qualifier) => match ctx.sema.resolve_path(&qualifier) {
+                        Some(PathResolution::Def(ModuleDef::Module(module))) => {
+                            module == self.parent_module
+                        }
+                        _ => false,
+                    },
+                    None if is_in_use(path) => true,
+                    None => {
+                        let module = enclosing_module(path.syntax());
+                        if file_id == self.file_id && module == self.enclosing_module(ctx) {
+                            true
+                        } else {
+                            // The name was imported. Explicit imports are
+                            // updated themselves, glob imports also bring the
+                            // new module into scope.
+                            !references.iter().any(|(_, other)| {
+                                is_in_use(other) && enclosing_module(other.syntax()) == module
+                            })
+                        }
+                    }
+                };
+                if qualify {
+                    res.push((file_id, reference.range.start()));
+                }
+            }
+        }
+        res
+    }
+
+    fn enclosing_module(&self, ctx: &AssistContext) -> Option<ast::Module> {
+        let file = ctx.sema.parse(self.file_id);
+        let element = file.syntax().covering_element(self.range);
+        enclosing_module(&element.parent()?)
+    }
+}
+
+fn path_of(reference: &FileReference) -> Option<ast::Path> {
+    let name_ref = reference.name.as_name_ref()?;
+    ast::PathSegment::cast(name_ref.syntax().parent()?)?.parent_path().into()
+}
+
+/// Returns the path the last segment of `path` is relative to, taking use
+/// trees into account.
+fn qualifier_of(path: &ast::Path) -> Option<ast::Path> {
+    if let Some(qualifier) = path.qualifier() {
+        return Some(qualifier);
+    }
+    let use_tree = path.syntax().parent().and_then(ast::UseTree::cast)?;
+    let use_tree_list = use_tree.syntax().parent().and_then(ast::UseTreeList::cast)?;
+    use_tree_list.parent_use_tree().path()
+}
+
+pub(super) fn is_in_use(path: &ast::Path) -> bool {
+    path.syntax().ancestors().any(|it| ast::Use::can_cast(it.kind()))
+}
+
+fn enclosing_module(node: &SyntaxNode) -> Option<ast::Module> {
+    node.ancestors().skip(1).find_map(ast::Module::cast)
+}
+
+/// Returns the definitions inside of `item` whose visibility might need to be
+/// adjusted, together with the node the visibility belongs to.
+pub(super) fn definitions_of(
+    ctx: &AssistContext,
+    item: &ast::Item,
+) -> Vec<(Definition, SyntaxNode)> {
+    let sema = &ctx.sema;
+    let mut res = Vec::new();
+    let mut push = |def: Option<Definition>, node: &SyntaxNode| {
+        if let Some(def) = def {
+            res.push((def, node.clone()));
+        }
+    };
+    let node = item.syntax();
+    match item {
+        ast::Item::Fn(it) => push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node),
+        ast::Item::Enum(it) => {
+            push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node)
+        }
+        ast::Item::Trait(it) => {
+            push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node)
+        }
+        ast::Item::Const(it) => {
+            push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node)
+        }
+        ast::Item::Static(it) => {
+            push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node)
+        }
+        ast::Item::TypeAlias(it) => {
+            push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node)
+        }
+        ast::Item::Module(it) => {
+            push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node)
+        }
+        ast::Item::Struct(it) => {
+            push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node);
+            push_fields(ctx, it.field_list(), &mut push);
+        }
+        ast::Item::Union(it) => {
+            push(sema.to_def(it).map(|it| Definition::ModuleDef(it.into())), node);
+            push_fields(
+                ctx,
+                it.record_field_list().map(ast::FieldList::RecordFieldList),
+                &mut push,
+            );
+        }
+        ast::Item::Impl(it) => {
+            // Items of trait impls are as visible as the trait.
+            if it.trait_().is_some() {
+                return res;
+            }
+            for assoc_item in it.assoc_item_list().into_iter().flat_map(|it| it.assoc_items()) {
+                let def = match &assoc_item {
+                    ast::AssocItem::Fn(it) => sema.to_def(it).map(ModuleDef::from),
+                    ast::AssocItem::Const(it) => sema.to_def(it).map(ModuleDef::from),
+                    ast::AssocItem::TypeAlias(it) => sema.to_def(it).map(ModuleDef::from),
+                    ast::AssocItem::MacroCall(_) => None,
+                };
+                push(def.map(Definition::ModuleDef), assoc_item.syntax());
+            }
+        }
+        _ => (),
+    }
+    res
+}
+
+fn push_fields(
+    ctx: &AssistContext,
+    field_list: Option<ast::FieldList>,
+    push: &mut impl FnMut(Option<Definition>, &SyntaxNode),
+) {
+    match field_list {
+        Some(ast::FieldList::RecordFieldList(fields)) => {
+            for field in fields.fields() {
+                push(ctx.sema.to_def(&field).map(Definition::Field), field.syntax());
+            }
+        }
+        Some(ast::FieldList::TupleFieldList(fields)) => {
+            for field in fields.fields() {
+                push(ctx.sema.to_def(&field).map(Definition::Field), field.syntax());
+            }
+        }
+        None => (),
+    }
+}
+
+/// Returns the names the selected items bring into scope, either by declaring
+/// or by importing them.
+fn declared_names(items: &[ast::Item]) -> FxHashSet<String> {
+    let mut res = FxHashSet::default();
+    for item in items {
+        let name = match item {
+            ast::Item::Use(use_) => {
+                let use_trees = use_.syntax().descendants().filter_map(ast::UseTree::cast);
+                for use_tree in use_trees.filter(|it| it.is_simple_path()) {
+                    let name = match use_tree.rename() {
+                        Some(rename) => rename.name().map(|it| it.to_string()),
+                        None => use_tree
+                            .path()
+                            .and_then(|it| it.segment())
+                            .and_then(|it| it.name_ref())
+                            .map(|it| it.to_string()),
+                    };
+                    res.extend(name);
+                }
+                continue;
+            }
+            ast::Item::Fn(it) => it.name(),
+            ast::Item::Struct(it) => it.name(),
+            ast::Item::Enum(it) => it.name(),
+            ast::Item::Union(it) => it.name(),
+            ast::Item::Trait(it) => it.name(),
+            ast::Item::Const(it) => it.name(),
+            ast::Item::Static(it) => it.name(),
+            ast::Item::TypeAlias(it) => it.name(),
+            ast::Item::Module(it) => it.name(),
+            ast::Item::MacroRules(it) => it.name(),
+            _ => None,
+        };
+        res.extend(name.map(|it| it.to_string()));
+    }
+    res
+}
+
+/// Rewrites paths in `item` which would no longer resolve inside of the new
+/// module and collects the names which need to be imported from the parent
+/// module.
+fn adjust_paths(
+    ctx: &AssistContext,
+    selection: &Selection,
+    declared_names: &FxHashSet<String>,
+    item: &ast::Item,
+    edit: &mut text_edit::TextEditBuilder,
+    imports: &mut Vec<String>,
+) {
+    let db = ctx.db();
+    let parent_scope = selection.parent_module.scope(db, None);
+    let offset = selection.range.start();
+
+    for path in item.syntax().descendants().filter_map(ast::Path::cast) {
+        if path.qualifier().is_some() {
+            continue;
+        }
+        // Paths in nested modules are relative to those modules.
+        if path.syntax().ancestors().any(|it| {
+            ast::Module::can_cast(it.kind()) && selection.range.contains_range(it.text_range())
+        }) {
+            continue;
+        }
+        let segment = match path.segment() {
+            Some(it) => it,
+            None => continue,
+        };
+        let in_use_tree = is_in_use(&path);
+        if in_use_tree && !is_use_tree_prefix(&path) {
+            continue;
+        }
+        match segment.kind() {
+            Some(ast::PathSegmentKind::SuperKw) => {
+                edit.insert(segment.syntax().text_range().start() - offset, "super::".to_string());
+            }
+            Some(ast::PathSegmentKind::SelfKw) => {
+                let next = path
+                    .parent_path()
+                    .and_then(|it| it.segment())
+                    .and_then(|it| it.name_ref())
+                    .map(|it| it.to_string());
+                if matches!(next, Some(it) if !declared_names.contains(&it)) {
+                    edit.replace(segment.syntax().text_range() - offset, "super".to_string());
+                }
+            }
+            Some(ast::PathSegmentKind::Name(name_ref)) => {
+                let name = name_ref.to_string();
+                if declared_names.contains(&name) {
+                    continue;
+                }
+                let def = match ctx.sema.resolve_path(&path) {
+                    Some(PathResolution::Def(def)) => def,
+                    _ => continue,
+                };
+                let in_parent_scope = parent_scope.iter().any(|(scope_name, scope_def)| {
+                    scope_name.to_string() == name && *scope_def == ScopeDef::ModuleDef(def)
+                });
+                if !in_parent_scope {
+                    continue;
+                }
+                if in_use_tree {
+                    edit.insert(
+                        segment.syntax().text_range().start() - offset,
+                        "super::".to_string(),
+                    );
+                } else if !imports.contains(&name) {
+                    imports.push(name);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Whether `path` is the first segment of the path of a top-level use tree.
+pub(super) fn is_use_tree_prefix(path: &ast::Path) -> bool {
+    let top_path = std::iter::successors(Some(path.clone()), |it| it.parent_path()).last();
+    let use_tree = top_path.and_then(|it| it.syntax().parent()).and_then(ast::UseTree::cast);
+    matches!(
+        use_tree.and_then(|it| it.syntax().parent()),
+        Some(parent) if ast::Use::can_cast(parent.kind())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn not_applicable_without_selection() {
+        check_assist_not_applicable(
+            extract_module,
+            r"
+fn $0foo() {}
+",
+        );
+    }
+
+    #[test]
+    fn not_applicable_for_partially_selected_items() {
+        check_assist_not_applicable(
+            extract_module,
+            r"
+fn foo() {
+    $01
+}
+
+fn bar() {}$0
+",
+        );
+        check_assist_not_applicable(
+            extract_module,
+            r"
+fn foo() {
+    $0let x = 1;$0
+}
+",
+        );
+    }
+
+    #[test]
+    fn not_applicable_for_outline_modules() {
+        check_assist_not_applicable(
+            extract_module,
+            r"
+//- /main.rs
+$0mod foo;$0
+//- /foo.rs
+",
+        );
+    }
+
+    #[test]
+    fn extract_unused_items() {
+        check_assist(
+            extract_module,
+            r"
+$0struct Foo;
+
+fn foo() {}$0
+
+fn bar() {}
+",
+            r"
+mod $0modname {
+    struct Foo;
+
+    fn foo() {}
+}
+
+fn bar() {}
+",
+        );
+    }
+
+    #[test]
+    fn qualifies_references_and_adjusts_visibility() {
+        check_assist(
+            extract_module,
+            r"
+$0struct Foo {
+    x: i32,
+    pub y: i32,
+}
+
+impl Foo {
+    fn new() -> Foo {
+        Foo { x: 0, y: 0 }
+    }
+
+    fn unused(&self) {}
+}$0
+
+fn main() {
+    let foo = Foo::new();
+    let _ = foo.x;
+}
+",
+            r"
+mod $0modname {
+    pub(super) struct Foo {
+        pub(super) x: i32,
+        pub y: i32,
+    }
+
+    impl Foo {
+        pub(super) fn new() -> Foo {
+            Foo { x: 0, y: 0 }
+        }
+
+        fn unused(&self) {}
+    }
+}
+
+fn main() {
+    let foo = modname::Foo::new();
+    let _ = foo.x;
+}
+",
+        );
+    }
+
+    #[test]
+    fn imports_items_from_parent_module() {
+        check_assist(
+            extract_module,
+            r"
+//- /main.rs crate:main deps:std
+use std::collections::HashMap;
+
+struct Bar;
+
+mod inner {
+    pub fn baz() {}
+}
+
+$0fn foo(map: HashMap<u32, Bar>) {
+    inner::baz();
+    super::qux();
+}$0
+//- /std.rs crate:std
+pub mod collections {
+    pub struct HashMap<K, V>(K, V);
+}
+",
+            r"
+use std::collections::HashMap;
+
+struct Bar;
+
+mod inner {
+    pub fn baz() {}
+}
+
+mod $0modname {
+    use super::HashMap;
+    use super::Bar;
+    use super::inner;
+
+    fn foo(map: HashMap<u32, Bar>) {
+        inner::baz();
+        super::super::qux();
+    }
+}
+",
+        );
+    }
+
+    #[test]
+    fn keeps_references_between_extracted_items() {
+        check_assist(
+            extract_module,
+            r"
+$0struct Foo;
+
+fn foo() -> Foo {
+    self::Foo
+}$0
+",
+            r"
+mod $0modname {
+    struct Foo;
+
+    fn foo() -> Foo {
+        self::Foo
+    }
+}
+",
+        );
+    }
+
+    #[test]
+    fn uses_pub_super_for_references_from_child_modules() {
+        check_assist(
+            extract_module,
+            r"
+//- /main.rs
+mod foo;
+
+$0fn bar() {}$0
+//- /foo.rs
+fn baz() {
+    crate::bar();
+}
+",
+            r"
+//- /main.rs
+mod foo;
+
+mod $0modname {
+    pub(super) fn bar() {}
+}
+//- /foo.rs
+fn baz() {
+    crate::modname::bar();
+}
+",
+        );
+    }
+
+    #[test]
+    fn rewrites_imports_of_extracted_items() {
+        check_assist(
+            extract_module,
+            r"
+$0struct Foo;
+struct Bar;$0
+
+mod inner {
+    use super::{Foo, Bar};
+
+    fn f(_: Foo, _: Bar) {}
+}
+",
+            r"
+mod $0modname {
+    pub(super) struct Foo;
+    pub(super) struct Bar;
+}
+
+mod inner {
+    use super::{modname::Foo, modname::Bar};
+
+    fn f(_: Foo, _: Bar) {}
+}
+",
+        );
+    }
+
+    #[test]
+    fn extract_from_nested_module() {
+        check_assist(
+            extract_module,
+            r"
+mod outer {
+    $0fn foo() {}$0
+
+    fn bar() {
+        foo();
+    }
+}
+",
+            r"
+mod outer {
+    mod $0modname {
+        pub(super) fn foo() {}
+    }
+
+    fn bar() {
+        modname::foo();
+    }
+}
+",
+        );
+    }
+}
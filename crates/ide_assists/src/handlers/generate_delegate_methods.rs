@@ -0,0 +1,831 @@
+use hir::{AsAssocItem, AssocItemContainer, HasAttrs, HasSource, HasVisibility, ModuleDef};
+use ide_db::helpers::mod_path_to_ast;
+use rustc_hash::{FxHashMap, FxHashSet};
+use stdx::format_to;
+use syntax::{
+    ast::{self, AstNode, GenericParamsOwner, NameOwner, TypeBoundsOwner, VisibilityOwner},
+    SyntaxKind, SyntaxNode, T,
+};
+
+use crate::{
+    utils::{find_impl_block_end, find_struct_impl, generate_impl_text},
+    AssistContext, AssistId, AssistKind, Assists, GroupLabel,
+};
+
+// Assist: generate_delegate_methods
+//
+// Generate delegate methods.
+//
+// ```
+// struct Age(u8);
+// impl Age {
+//     fn age(&self) -> u8 {
+//         self.0
+//     }
+// }
+//
+// struct Person {
+//     ag$0e: Age,
+// }
+// ```
+// ->
+// ```
+// struct Age(u8);
+// impl Age {
+//     fn age(&self) -> u8 {
+//         self.0
+//     }
+// }
+//
+// struct Person {
+//     age: Age,
+// }
+//
+// impl Person {
+//     fn age(&self) -> u8 {
+//         self.age.age()
+//     }
+// }
+// ```
+pub(crate) fn generate_delegate_methods(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let strukt = ctx.find_node_at_offset::<ast::Struct>()?;
+
+    let (field_name, field_ty, field_def, target) = match ctx
+        .find_node_at_offset::<ast::RecordField>()
+    {
+        Some(field) => (
+            field.name()?.to_string(),
+            field.ty()?,
+            ctx.sema.to_def(&field)?,
+            field.syntax().text_range(),
+        ),
+        None => {
+            let field = ctx.find_node_at_offset::<ast::TupleField>()?;
+            let field_list = ctx.find_node_at_offset::<ast::TupleFieldList>()?;
+            let idx = field_list.fields().position(|it| it == field)?;
+            (idx.to_string(), field.ty()?, ctx.sema.to_def(&field)?, field.syntax().text_range())
+        }
+    };
+
+    let db = ctx.db();
+    let scope = ctx.sema.scope(strukt.syntax());
+    let module = scope.module()?;
+    let traits_in_scope = scope.traits_in_scope();
+
+    let mut seen_names = FxHashSet::default();
+    let mut methods = Vec::new();
+    field_def.ty(db).iterate_method_candidates(
+        db,
+        module.krate(),
+        &traits_in_scope,
+        None,
+        |_ty, func| {
+            if func.self_param(db).is_some()
+                && func.is_visible_from(db, module)
+                && seen_names.insert(func.name(db))
+            {
+                methods.push(func);
+            }
+            None::<()>
+        },
+    );
+
+    let adt = ast::Adt::Struct(strukt.clone());
+    let vis = strukt.visibility().map_or(String::new(), |v| format!("{} ", v));
+    let struct_params: FxHashSet<String> = strukt
+        .generic_param_list()
+        .map(|it| it.generic_params().filter_map(|it| generic_param_name(&it)).collect())
+        .unwrap_or_default();
+    for method in methods {
+        let method_name = method.name(db).to_string();
+        // Skip methods the outer type already has
+        let impl_def = match find_struct_impl(ctx, &adt, &method_name) {
+            Some(it) => it,
+            None => continue,
+        };
+        let delegate = match delegate_fn_text(
+            ctx,
+            module,
+            method,
+            &vis,
+            &field_name,
+            &field_ty,
+            &struct_params,
+        ) {
+            Some(it) => it,
+            None => continue,
+        };
+
+        acc.add_group(
+            &GroupLabel("Generate delegate methods…".to_owned()),
+            AssistId("generate_delegate_methods", AssistKind::Generate),
+            format!("Generate delegate for `{}.{}()`", field_name, method_name),
+            target,
+            |builder| {
+                let mut buf = String::with_capacity(512);
+                if impl_def.is_some() {
+                    buf.push('\n');
+                }
+                buf.push_str(&delegate);
+
+                let start_offset = impl_def
+                    .and_then(|impl_def| find_impl_block_end(impl_def, &mut buf))
+                    .unwrap_or_else(|| {
+                        buf = generate_impl_text(&adt, &buf);
+                        strukt.syntax().text_range().end()
+                    });
+
+                builder.insert(start_offset, buf);
+            },
+        );
+    }
+    Some(())
+}
+
+/// Renders a method on the outer type which forwards to `method` on the field.
+/// Returns `None` for methods whose signature can't be reproduced textually,
+/// like methods with typed `self` receivers, methods of generic traits or of
+/// traits which can't be named from `module`.
+fn delegate_fn_text(
+    ctx: &AssistContext,
+    module: hir::Module,
+    method: hir::Function,
+    vis: &str,
+    field_name: &str,
+    field_ty: &ast::Type,
+    struct_params: &FxHashSet<String>,
+) -> Option<String> {
+    let db = ctx.db();
+    let src = method.source(db)?.value;
+
+    // `Self` in the signature refers to the field type, and `Self::Assoc` to
+    // an associated type of the field type's trait impl.
+    let trait_ = method.as_assoc_item(db).and_then(|it| it.containing_trait(db));
+    let qualified_self = match trait_ {
+        Some(trait_) => {
+            if trait_.source(db)?.value.generic_param_list().is_some() {
+                return None;
+            }
+            let trait_path = module.find_use_path(db, ModuleDef::Trait(trait_))?;
+            Some(format!("<{} as {}>", field_ty, mod_path_to_ast(&trait_path)))
+        }
+        None => None,
+    };
+
+    // The generic parameters of an inherent impl are instantiated by the field type. Their
+    // bounds become bounds of the delegate, unless they don't depend on the outer type's
+    // parameters and so hold anyway.
+    let mut impl_args = FxHashMap::default();
+    let mut impl_bounds = Vec::new();
+    if let Some(AssocItemContainer::Impl(impl_)) =
+        method.as_assoc_item(db).map(|it| it.container(db))
+    {
+        let impl_src = impl_.source(db)?.value;
+        if let Some(generic_params) = impl_src.generic_param_list() {
+            let params: Vec<String> =
+                generic_params.generic_params().filter_map(|it| generic_param_name(&it)).collect();
+            let mut bindings = FxHashMap::default();
+            match_ty(&impl_src.self_ty()?, field_ty, &params, &mut bindings)?;
+            let generic: FxHashSet<String> = bindings
+                .iter()
+                .filter(|(_, ty)| mentions_any(ty, struct_params))
+                .map(|(param, _)| param.clone())
+                .collect();
+            impl_args = bindings.into_iter().map(|(param, ty)| (param, ty.to_string())).collect();
+
+            let subst = |node: &SyntaxNode| replace_self(node, "Self", &None, &impl_args);
+            for param in generic_params.generic_params() {
+                let bounds = match &param {
+                    ast::GenericParam::TypeParam(it) => it.type_bound_list(),
+                    ast::GenericParam::LifetimeParam(it) => it.type_bound_list(),
+                    ast::GenericParam::ConstParam(_) => None,
+                };
+                let name = generic_param_name(&param)?;
+                if let Some(bounds) = bounds {
+                    if generic.contains(&name) || mentions_any(bounds.syntax(), &generic) {
+                        impl_bounds.push(format!(
+                            "{}: {}",
+                            impl_args[&name],
+                            subst(bounds.syntax())
+                        ));
+                    }
+                }
+            }
+            for pred in impl_src.where_clause().into_iter().flat_map(|it| it.predicates()) {
+                if mentions_any(pred.syntax(), &generic) {
+                    impl_bounds.push(subst(pred.syntax()));
+                }
+            }
+        }
+    }
+    let subst =
+        |node: &SyntaxNode| replace_self(node, &field_ty.to_string(), &qualified_self, &impl_args);
+
+    let param_list = src.param_list()?;
+    let self_param = param_list.self_param()?;
+    if self_param.colon_token().is_some() {
+        return None;
+    }
+    let mut receiver = String::new();
+    if self_param.amp_token().is_some() {
+        receiver.push('&');
+        if let Some(lifetime) = self_param.lifetime() {
+            format_to!(receiver, "{} ", lifetime);
+        }
+        if self_param.mut_token().is_some() {
+            receiver.push_str("mut ");
+        }
+    }
+    receiver.push_str("self");
+
+    let mut params = vec![receiver];
+    let mut args = Vec::new();
+    for (idx, param) in param_list.params().enumerate() {
+        let ty = param.ty()?;
+        let name = match param.pat() {
+            Some(ast::Pat::IdentPat(pat)) if pat.pat().is_none() => pat.name()?.to_string(),
+            _ => format!("arg{}", idx),
+        };
+        params.push(format!("{}: {}", name, subst(ty.syntax())));
+        args.push(name);
+    }
+
+    let mut buf = String::new();
+    if let Some(docs) = method.docs(db) {
+        for line in docs.as_str().lines() {
+            if line.is_empty() {
+                buf.push_str("    ///\n");
+            } else {
+                format_to!(buf, "    /// {}\n", line);
+            }
+        }
+    }
+    buf.push_str("    ");
+    buf.push_str(vis);
+    if src.const_token().is_some() {
+        buf.push_str("const ");
+    }
+    if src.async_token().is_some() {
+        buf.push_str("async ");
+    }
+    if src.unsafe_token().is_some() {
+        buf.push_str("unsafe ");
+    }
+    format_to!(buf, "fn {}", method.name(db));
+
+    // Turbofish the method's own type and const parameters, inference can't
+    // always recover them (e.g. when they only appear in the return type).
+    let mut generic_args = Vec::new();
+    if let Some(generic_params) = src.generic_param_list() {
+        buf.push_str(&subst(generic_params.syntax()));
+        for param in generic_params.generic_params() {
+            match param {
+                ast::GenericParam::TypeParam(it) => generic_args.push(it.name()?.to_string()),
+                ast::GenericParam::ConstParam(it) => generic_args.push(it.name()?.to_string()),
+                ast::GenericParam::LifetimeParam(_) => (),
+            }
+        }
+    }
+    format_to!(buf, "({})", params.join(", "));
+    if let Some(ret_type) = src.ret_type() {
+        format_to!(buf, " {}", subst(ret_type.syntax()));
+    }
+    let mut predicates = impl_bounds;
+    if let Some(where_clause) = src.where_clause() {
+        predicates.extend(where_clause.predicates().map(|pred| subst(pred.syntax())));
+    }
+    if predicates.is_empty() {
+        buf.push_str(" {\n");
+    } else {
+        buf.push_str("\n    where\n");
+        for pred in predicates {
+            format_to!(buf, "        {},\n", pred);
+        }
+        buf.push_str("    {\n");
+    }
+
+    format_to!(buf, "        self.{}.{}", field_name, method.name(db));
+    if !generic_args.is_empty() {
+        format_to!(buf, "::<{}>", generic_args.join(", "));
+    }
+    format_to!(buf, "({})", args.join(", "));
+    if src.async_token().is_some() {
+        buf.push_str(".await");
+    }
+    buf.push_str("\n    }");
+    Some(buf)
+}
+
+/// Renders `node` with `Self` replaced by `self_ty` (or `qualified_self` in paths) and the
+/// generic parameters of the impl replaced by the arguments in `impl_args`.
+fn replace_self(
+    node: &SyntaxNode,
+    self_ty: &str,
+    qualified_self: &Option<String>,
+    impl_args: &FxHashMap<String, String>,
+) -> String {
+    let mut buf = String::new();
+    for token in node.descendants_with_tokens().filter_map(|it| it.into_token()) {
+        let is_name = matches!(token.kind(), SyntaxKind::IDENT | SyntaxKind::LIFETIME_IDENT);
+        if let Some(arg) = impl_args.get(token.text()).filter(|_| is_name) {
+            buf.push_str(arg);
+            continue;
+        }
+        if token.kind() != SyntaxKind::IDENT || token.text() != "Self" {
+            buf.push_str(token.text());
+            continue;
+        }
+        let is_qualifier = matches!(token.next_token(), Some(next) if next.kind() == T![::]);
+        match qualified_self {
+            Some(qualified_self) if is_qualifier => buf.push_str(qualified_self),
+            _ => buf.push_str(self_ty),
+        }
+    }
+    buf
+}
+
+/// The name of a generic parameter, with the `'` for lifetimes.
+fn generic_param_name(param: &ast::GenericParam) -> Option<String> {
+    let name = match param {
+        ast::GenericParam::TypeParam(it) => it.name()?.to_string(),
+        ast::GenericParam::ConstParam(it) => it.name()?.to_string(),
+        ast::GenericParam::LifetimeParam(it) => it.lifetime()?.to_string(),
+    };
+    Some(name)
+}
+
+fn mentions_any(node: &SyntaxNode, names: &FxHashSet<String>) -> bool {
+    node.descendants_with_tokens().filter_map(|it| it.into_token()).any(|token| {
+        matches!(token.kind(), SyntaxKind::IDENT | SyntaxKind::LIFETIME_IDENT)
+            && names.contains(token.text())
+    })
+}
+
+/// Matches the self type of an impl with generic `params` against `ty`, recording
+/// what each parameter stands for. Returns `None` if the types don't match.
+fn match_ty(
+    pattern: &ast::Type,
+    ty: &ast::Type,
+    params: &[String],
+    bindings: &mut FxHashMap<String, SyntaxNode>,
+) -> Option<()> {
+    if let Some(param) = param_name(pattern, params) {
+        return bind(param, ty.syntax(), bindings);
+    }
+    match (pattern, ty) {
+        (ast::Type::PathType(pattern), ast::Type::PathType(ty)) => {
+            let (pattern, ty) = (pattern.path()?.segment()?, ty.path()?.segment()?);
+            if pattern.name_ref()?.text() != ty.name_ref()?.text() {
+                return None;
+            }
+            let args = |segment: &ast::PathSegment| -> Vec<ast::GenericArg> {
+                segment.generic_arg_list().map_or(Vec::new(), |it| it.generic_args().collect())
+            };
+            let (pattern_args, ty_args) = (args(&pattern), args(&ty));
+            if pattern_args.len() != ty_args.len() {
+                return None;
+            }
+            for (pattern, arg) in pattern_args.iter().zip(&ty_args) {
+                match (pattern, arg) {
+                    (ast::GenericArg::TypeArg(pattern), ast::GenericArg::TypeArg(arg)) => {
+                        match_ty(&pattern.ty()?, &arg.ty()?, params, bindings)?
+                    }
+                    // Const parameters are parsed as types.
+                    (ast::GenericArg::TypeArg(pattern), arg) => {
+                        let param = param_name(&pattern.ty()?, params)?;
+                        bind(param, arg.syntax(), bindings)?
+                    }
+                    (ast::GenericArg::LifetimeArg(pattern), ast::GenericArg::LifetimeArg(arg)) => {
+                        match_lifetime(pattern.lifetime(), arg.lifetime(), params, bindings)?
+                    }
+                    _ if pattern.syntax().text() == arg.syntax().text() => (),
+                    _ => return None,
+                }
+            }
+            Some(())
+        }
+        (ast::Type::RefType(pattern), ast::Type::RefType(ty)) => {
+            if pattern.mut_token().is_some() != ty.mut_token().is_some() {
+                return None;
+            }
+            match_lifetime(pattern.lifetime(), ty.lifetime(), params, bindings)?;
+            match_ty(&pattern.ty()?, &ty.ty()?, params, bindings)
+        }
+        (ast::Type::TupleType(pattern), ast::Type::TupleType(ty)) => {
+            let (pattern, ty): (Vec<_>, Vec<_>) =
+                (pattern.fields().collect(), ty.fields().collect());
+            if pattern.len() != ty.len() {
+                return None;
+            }
+            pattern
+                .iter()
+                .zip(&ty)
+                .try_for_each(|(pattern, ty)| match_ty(pattern, ty, params, bindings))
+        }
+        (ast::Type::SliceType(pattern), ast::Type::SliceType(ty)) => {
+            match_ty(&pattern.ty()?, &ty.ty()?, params, bindings)
+        }
+        _ if pattern.syntax().text() == ty.syntax().text() => Some(()),
+        _ => None,
+    }
+}
+
+fn match_lifetime(
+    pattern: Option<ast::Lifetime>,
+    lifetime: Option<ast::Lifetime>,
+    params: &[String],
+    bindings: &mut FxHashMap<String, SyntaxNode>,
+) -> Option<()> {
+    match (pattern, lifetime) {
+        (Some(pattern), Some(lifetime)) if params.contains(&pattern.to_string()) => {
+            bind(pattern.to_string(), lifetime.syntax(), bindings)
+        }
+        (Some(pattern), Some(lifetime)) if pattern.to_string() == lifetime.to_string() => Some(()),
+        (None, None) => Some(()),
+        _ => None,
+    }
+}
+
+/// The generic parameter `ty` refers to, if it is one of `params`.
+fn param_name(ty: &ast::Type, params: &[String]) -> Option<String> {
+    let path = match ty {
+        ast::Type::PathType(it) => it.path()?,
+        _ => return None,
+    };
+    let segment = path.segment()?;
+    if path.qualifier().is_some() || segment.generic_arg_list().is_some() {
+        return None;
+    }
+    let name = segment.name_ref()?.to_string();
+    if params.contains(&name) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+fn bind(
+    param: String,
+    node: &SyntaxNode,
+    bindings: &mut FxHashMap<String, SyntaxNode>,
+) -> Option<()> {
+    match bindings.get(&param) {
+        Some(bound) if bound.text() != node.text() => None,
+        _ => {
+            bindings.insert(param, node.clone());
+            Some(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn test_generate_delegate_create_impl_block() {
+        check_assist(
+            generate_delegate_methods,
+            r#"
+struct Age(u8);
+impl Age {
+    fn age(&self) -> u8 {
+        self.0
+    }
+}
+
+struct Person {
+    ag$0e: Age,
+}"#,
+            r#"
+struct Age(u8);
+impl Age {
+    fn age(&self) -> u8 {
+        self.0
+    }
+}
+
+struct Person {
+    age: Age,
+}
+
+impl Person {
+    fn age(&self) -> u8 {
+        self.age.age()
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_generate_delegate_update_impl_block() {
+        check_assist(
+            generate_delegate_methods,
+            r#"
+struct Age(u8);
+impl Age {
+    fn age(&mut self, by: u8) -> u8 {
+        self.0 += by;
+        self.0
+    }
+}
+
+pub struct Person(Ag$0e);
+
+impl Person {}"#,
+            r#"
+struct Age(u8);
+impl Age {
+    fn age(&mut self, by: u8) -> u8 {
+        self.0 += by;
+        self.0
+    }
+}
+
+pub struct Person(Age);
+
+impl Person {
+    pub fn age(&mut self, by: u8) -> u8 {
+        self.0.age(by)
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_generate_delegate_trait_method() {
+        check_assist(
+            generate_delegate_methods,
+            r#"
+trait Named {
+    type Name;
+    /// Returns the name.
+    ///
+    /// Never empty.
+    fn name(&self, _: bool) -> Self::Name;
+}
+
+struct Age(u8);
+impl Named for Age {
+    type Name = String;
+    fn name(&self, _: bool) -> String {
+        String::new()
+    }
+}
+
+struct Person {
+    ag$0e: Age,
+}"#,
+            r#"
+trait Named {
+    type Name;
+    /// Returns the name.
+    ///
+    /// Never empty.
+    fn name(&self, _: bool) -> Self::Name;
+}
+
+struct Age(u8);
+impl Named for Age {
+    type Name = String;
+    fn name(&self, _: bool) -> String {
+        String::new()
+    }
+}
+
+struct Person {
+    age: Age,
+}
+
+impl Person {
+    /// Returns the name.
+    ///
+    /// Never empty.
+    fn name(&self, arg0: bool) -> <Age as Named>::Name {
+        self.age.name(arg0)
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_generate_delegate_trait_method_qualifies_trait() {
+        check_assist(
+            generate_delegate_methods,
+            r#"
+mod names {
+    pub trait Named {
+        type Name;
+        fn name(&self) -> Self::Name;
+    }
+}
+use names::Named as _;
+
+struct Age(u8);
+impl names::Named for Age {
+    type Name = String;
+    fn name(&self) -> String {
+        String::new()
+    }
+}
+
+struct Person {
+    ag$0e: Age,
+}"#,
+            r#"
+mod names {
+    pub trait Named {
+        type Name;
+        fn name(&self) -> Self::Name;
+    }
+}
+use names::Named as _;
+
+struct Age(u8);
+impl names::Named for Age {
+    type Name = String;
+    fn name(&self) -> String {
+        String::new()
+    }
+}
+
+struct Person {
+    age: Age,
+}
+
+impl Person {
+    fn name(&self) -> <Age as names::Named>::Name {
+        self.age.name()
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_generate_delegate_generics() {
+        check_assist(
+            generate_delegate_methods,
+            r#"
+struct Age(u8);
+impl Age {
+    async fn convert<'a, T>(&'a self, other: Self) -> T
+    where
+        T: From<&'a Self>,
+    {
+        T::from(self)
+    }
+}
+
+struct Person<P> {
+    ag$0e: Age,
+    p: P,
+}"#,
+            r#"
+struct Age(u8);
+impl Age {
+    async fn convert<'a, T>(&'a self, other: Self) -> T
+    where
+        T: From<&'a Self>,
+    {
+        T::from(self)
+    }
+}
+
+struct Person<P> {
+    age: Age,
+    p: P,
+}
+
+impl<P> Person<P> {
+    async fn convert<'a, T>(&'a self, other: Age) -> T
+    where
+        T: From<&'a Age>,
+    {
+        self.age.convert::<T>(other).await
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_generate_delegate_bounded_generic_impl() {
+        check_assist(
+            generate_delegate_methods,
+            r#"
+struct Wrapper<T>(T);
+impl<T: Clone> Wrapper<T>
+where
+    T: Default,
+{
+    fn get(&self) -> T {
+        self.0.clone()
+    }
+}
+
+struct Outer<U> {
+    w$0: Wrapper<U>,
+}"#,
+            r#"
+struct Wrapper<T>(T);
+impl<T: Clone> Wrapper<T>
+where
+    T: Default,
+{
+    fn get(&self) -> T {
+        self.0.clone()
+    }
+}
+
+struct Outer<U> {
+    w: Wrapper<U>,
+}
+
+impl<U> Outer<U> {
+    fn get(&self) -> U
+    where
+        U: Clone,
+        U: Default,
+    {
+        self.w.get()
+    }
+}"#,
+        );
+        check_assist(
+            generate_delegate_methods,
+            r#"
+struct Wrapper<T>(T);
+impl<T: Clone> Wrapper<T> {
+    fn get(&self) -> T {
+        self.0.clone()
+    }
+}
+
+struct Outer {
+    w$0: Wrapper<u8>,
+}"#,
+            r#"
+struct Wrapper<T>(T);
+impl<T: Clone> Wrapper<T> {
+    fn get(&self) -> T {
+        self.0.clone()
+    }
+}
+
+struct Outer {
+    w: Wrapper<u8>,
+}
+
+impl Outer {
+    fn get(&self) -> u8 {
+        self.w.get()
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_generate_delegate_skips_existing_methods() {
+        check_assist_not_applicable(
+            generate_delegate_methods,
+            r#"
+struct Age(u8);
+impl Age {
+    fn age(&self) -> u8 {
+        self.0
+    }
+}
+
+struct Person {
+    ag$0e: Age,
+}
+
+impl Person {
+    fn age(&self) -> u8 {
+        0
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_generate_delegate_skips_invisible_methods() {
+        check_assist_not_applicable(
+            generate_delegate_methods,
+            r#"
+mod foo {
+    pub struct Age(u8);
+    impl Age {
+        fn age(&self) -> u8 {
+            self.0
+        }
+    }
+}
+
+struct Person {
+    ag$0e: foo::Age,
+}"#,
+        );
+    }
+}
@@ -0,0 +1,482 @@
+use hir::{HasVisibility, ModuleDef, Visibility};
+use ide_db::RootDatabase;
+use itertools::Itertools;
+use stdx::{format_to, to_lower_snake_case};
+use syntax::{
+    ast::{self, edit::AstNodeEdit, AttrsOwner, NameOwner, VisibilityOwner},
+    AstNode, AstToken,
+};
+
+use crate::{AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: generate_documentation_template
+//
+// Adds a documentation template above a function or type definition. Exported
+// items get an `# Examples` section with a doctest using them.
+//
+// ```
+// pub fn my_$0func(a: i32, b: i32) -> Result<(), std::io::Error> {
+//     Ok(())
+// }
+// ```
+// ->
+// ```
+// /// My func.
+// ///
+// /// # Examples
+// ///
+// /// ```
+// /// use test::my_func;
+// ///
+// /// assert_eq!(my_func(a, b), );
+// /// ```
+// ///
+// /// # Errors
+// ///
+// /// This function will return an error if .
+// pub fn my_func(a: i32, b: i32) -> Result<(), std::io::Error> {
+//     Ok(())
+// }
+// ```
+pub(crate) fn generate_documentation_template(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    let name: ast::Name = ctx.find_node_at_offset()?;
+    let item = name.syntax().parent().and_then(ast::Item::cast)?;
+    let has_docs = item
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token().and_then(ast::Comment::cast))
+        .any(|it| it.kind().doc.is_some())
+        || item.attrs().any(|it| it.simple_name().as_deref() == Some("doc"));
+    if has_docs {
+        return None;
+    }
+    let (def, sections) = match &item {
+        ast::Item::Fn(fn_) => {
+            // Trait implementations are documented by the trait.
+            let impl_ = fn_.syntax().ancestors().find_map(ast::Impl::cast);
+            if matches!(impl_, Some(it) if it.trait_().is_some()) {
+                return None;
+            }
+            let function = ctx.sema.to_def(fn_)?;
+            (ModuleDef::Function(function), fn_sections(ctx, fn_, function))
+        }
+        ast::Item::Struct(it) => (ModuleDef::Adt(ctx.sema.to_def(it)?.into()), Vec::new()),
+        ast::Item::Enum(it) => (ModuleDef::Adt(ctx.sema.to_def(it)?.into()), Vec::new()),
+        ast::Item::Union(it) => (ModuleDef::Adt(ctx.sema.to_def(it)?.into()), Vec::new()),
+        ast::Item::Trait(it) => (ModuleDef::Trait(ctx.sema.to_def(it)?), Vec::new()),
+        ast::Item::TypeAlias(it) => (ModuleDef::TypeAlias(ctx.sema.to_def(it)?), Vec::new()),
+        _ => return None,
+    };
+
+    let mut lines = vec![format!("{}.", sentence(&to_lower_snake_case(&name.to_string())))];
+    if let Some(example) = example(ctx, &item, def) {
+        lines.push(String::new());
+        lines.push("# Examples".to_string());
+        lines.push(String::new());
+        lines.push("```".to_string());
+        lines.extend(example);
+        lines.push("```".to_string());
+    }
+    for (header, text) in sections {
+        lines.push(String::new());
+        lines.push(format!("# {}", header));
+        lines.push(String::new());
+        lines.push(text.to_string());
+    }
+
+    let indent = item.indent_level();
+    let offset = item.syntax().text_range().start();
+    acc.add(
+        AssistId("generate_documentation_template", AssistKind::Generate),
+        "Generate a documentation template",
+        name.syntax().text_range(),
+        |builder| {
+            let mut buf = String::new();
+            for line in &lines {
+                if line.is_empty() {
+                    format_to!(buf, "///\n{}", indent);
+                } else {
+                    format_to!(buf, "/// {}\n{}", line, indent);
+                }
+            }
+            builder.insert(offset, buf);
+        },
+    )
+}
+
+/// Returns the `# Errors`, `# Panics` and `# Safety` sections which apply to
+/// `fn_`.
+fn fn_sections(
+    ctx: &AssistContext,
+    fn_: &ast::Fn,
+    function: hir::Function,
+) -> Vec<(&'static str, &'static str)> {
+    let mut sections = Vec::new();
+    if returns_result(ctx, fn_, function) {
+        sections.push(("Errors", "This function will return an error if ."));
+    }
+    if matches!(fn_.body(), Some(body) if can_panic(&body)) {
+        sections.push(("Panics", "Panics if ."));
+    }
+    if fn_.unsafe_token().is_some() {
+        sections.push(("Safety", "The caller must ensure that ."));
+    }
+    sections
+}
+
+fn returns_result(ctx: &AssistContext, fn_: &ast::Fn, function: hir::Function) -> bool {
+    let db = ctx.db();
+    // Aliases like `io::Result` resolve to `Result` itself.
+    if let Some(adt) = function.ret_type(db).as_adt() {
+        return adt.name(db).to_string() == "Result";
+    }
+    let segment = match fn_.ret_type().and_then(|it| it.ty()) {
+        Some(ast::Type::PathType(ty)) => ty.path().and_then(|it| it.segment()),
+        _ => None,
+    };
+    matches!(segment.and_then(|it| it.name_ref()), Some(name) if name.text() == "Result")
+}
+
+fn can_panic(body: &ast::BlockExpr) -> bool {
+    const PANICKING_MACROS: &[&str] =
+        &["panic", "unreachable", "unimplemented", "assert", "assert_eq", "assert_ne"];
+    body.syntax().descendants().any(|node| {
+        if let Some(call) = ast::MacroCall::cast(node.clone()) {
+            let name = call.path().and_then(|it| it.segment()).and_then(|it| it.name_ref());
+            matches!(name, Some(name) if PANICKING_MACROS.contains(&&*name.text()))
+        } else if let Some(call) = ast::MethodCallExpr::cast(node) {
+            matches!(call.name_ref(), Some(name) if name.text() == "unwrap" || name.text() == "expect")
+        } else {
+            false
+        }
+    })
+}
+
+/// Returns the lines of a doctest importing `def` from its crate and using
+/// it, or `None` if `def` isn't reachable from other crates.
+fn example(ctx: &AssistContext, item: &ast::Item, def: ModuleDef) -> Option<Vec<String>> {
+    let db = ctx.db();
+    let fn_ = match item {
+        ast::Item::Fn(it) => Some(it),
+        _ => None,
+    };
+    // Associated functions are used through the type or trait they belong to.
+    let owner = match fn_.and_then(|it| it.syntax().ancestors().find_map(ast::AssocItemList::cast))
+    {
+        Some(assoc_items) => {
+            let parent = assoc_items.syntax().parent()?;
+            if let Some(trait_) = ast::Trait::cast(parent.clone()) {
+                ModuleDef::Trait(ctx.sema.to_def(&trait_)?)
+            } else {
+                // Private methods can't be used by other crates.
+                fn_?.visibility()?;
+                let impl_ = ast::Impl::cast(parent)?;
+                ModuleDef::Adt(ctx.sema.to_def(&impl_)?.self_ty(db).as_adt()?)
+            }
+        }
+        None => def,
+    };
+    let import = exported_path(ctx.sema.db, owner)?;
+
+    let mut lines = vec![format!("use {};", import)];
+    let fn_ = match fn_ {
+        Some(it) => it,
+        None => return Some(lines),
+    };
+    lines.push(String::new());
+    let args = fn_.param_list()?.params().map(|param| match param.pat() {
+        Some(ast::Pat::IdentPat(it)) => it.name().map_or("_".to_string(), |it| it.to_string()),
+        _ => "_".to_string(),
+    });
+    let mut call = match fn_.param_list()?.self_param() {
+        Some(self_param) => {
+            let owner_name = owner.name(db)?.to_string();
+            let receiver = to_lower_snake_case(&owner_name);
+            let mut_ = if self_param.mut_token().is_some() { "mut " } else { "" };
+            lines.push(format!("let {}{} = ;", mut_, receiver));
+            format!("{}.{}({})", receiver, fn_.name()?, args.format(", "))
+        }
+        None if owner != def => {
+            format!("{}::{}({})", owner.name(db)?, fn_.name()?, args.format(", "))
+        }
+        None => format!("{}({})", fn_.name()?, args.format(", ")),
+    };
+    if fn_.async_token().is_some() {
+        call.push_str(".await");
+    }
+    if fn_.unsafe_token().is_some() {
+        call = format!("unsafe {{ {} }}", call);
+    }
+    match fn_.ret_type() {
+        Some(_) => lines.push(format!("assert_eq!({}, );", call)),
+        None => lines.push(format!("{};", call)),
+    }
+    Some(lines)
+}
+
+/// Returns the path other crates import `def` with, if it's exported.
+fn exported_path(db: &RootDatabase, def: ModuleDef) -> Option<String> {
+    let module = def.module(db)?;
+    let krate = module.krate();
+    let crate_name = krate.display_name(db)?.to_string().replace('-', "_");
+    let path = krate.root_module(db).find_use_path(db, def)?;
+    let is_public = |def: ModuleDef| match def {
+        ModuleDef::Function(it) => it.visibility(db) == Visibility::Public,
+        ModuleDef::Adt(hir::Adt::Struct(it)) => it.visibility(db) == Visibility::Public,
+        ModuleDef::Adt(hir::Adt::Enum(it)) => it.visibility(db) == Visibility::Public,
+        ModuleDef::Adt(hir::Adt::Union(it)) => it.visibility(db) == Visibility::Public,
+        ModuleDef::Trait(it) => it.visibility(db) == Visibility::Public,
+        ModuleDef::TypeAlias(it) => it.visibility(db) == Visibility::Public,
+        ModuleDef::Module(it) => match it.parent(db) {
+            Some(parent) => parent.visibility_of(db, &def) == Some(Visibility::Public),
+            None => true,
+        },
+        _ => false,
+    };
+    // Items re-exported at the crate root are reachable regardless of the
+    // modules they are defined in.
+    let reachable = path.segments().len() == 1
+        || module.path_to_root(db).into_iter().all(|it| is_public(ModuleDef::Module(it)));
+    if !is_public(def) || !reachable {
+        return None;
+    }
+    Some(format!("{}::{}", crate_name, path))
+}
+
+fn sentence(snake_case: &str) -> String {
+    let mut words = snake_case.split('_').filter(|it| !it.is_empty());
+    let mut text = String::new();
+    if let Some(first) = words.next() {
+        let mut chars = first.chars();
+        text.extend(chars.next().map(|it| it.to_ascii_uppercase()));
+        text.extend(chars);
+    }
+    for word in words {
+        text.push(' ');
+        text.push_str(word);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn not_applicable_to_documented_items() {
+        check_assist_not_applicable(
+            generate_documentation_template,
+            r#"
+/// Does things.
+pub fn do_$0things() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_trait_impl_methods() {
+        check_assist_not_applicable(
+            generate_documentation_template,
+            r#"
+trait Tr { fn f(&self); }
+struct S;
+impl Tr for S {
+    fn $0f(&self) {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn private_function() {
+        check_assist(
+            generate_documentation_template,
+            r#"
+fn $0do_things() {
+    panic!();
+}
+"#,
+            r#"
+/// Do things.
+///
+/// # Panics
+///
+/// Panics if .
+fn do_things() {
+    panic!();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn exported_function() {
+        check_assist(
+            generate_documentation_template,
+            r#"
+//- /lib.rs crate:my-crate
+pub mod math {
+    pub unsafe fn $0add(a: u32, b: u32) -> u32 {
+        a.checked_add(b).unwrap()
+    }
+}
+"#,
+            r#"
+pub mod math {
+    /// Add.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_crate::math::add;
+    ///
+    /// assert_eq!(unsafe { add(a, b) }, );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if .
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that .
+    pub unsafe fn add(a: u32, b: u32) -> u32 {
+        a.checked_add(b).unwrap()
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_exported_from_private_module() {
+        check_assist(
+            generate_documentation_template,
+            r#"
+//- /lib.rs crate:my_crate
+mod math {
+    pub fn $0add(a: u32, b: u32) -> u32 {
+        a + b
+    }
+}
+"#,
+            r#"
+mod math {
+    /// Add.
+    pub fn add(a: u32, b: u32) -> u32 {
+        a + b
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn method() {
+        check_assist(
+            generate_documentation_template,
+            r#"
+//- /lib.rs crate:my_crate
+pub struct MyStruct;
+impl MyStruct {
+    pub fn set_value(&mut self, value: u32) -> Result<(), ()> {
+        Ok(())
+    }
+
+    pub fn n$0ew() -> MyStruct {
+        MyStruct
+    }
+}
+"#,
+            r#"
+pub struct MyStruct;
+impl MyStruct {
+    pub fn set_value(&mut self, value: u32) -> Result<(), ()> {
+        Ok(())
+    }
+
+    /// New.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_crate::MyStruct;
+    ///
+    /// assert_eq!(MyStruct::new(), );
+    /// ```
+    pub fn new() -> MyStruct {
+        MyStruct
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn method_with_self() {
+        check_assist(
+            generate_documentation_template,
+            r#"
+//- /lib.rs crate:my_crate
+pub enum Result<T, E> { Ok(T), Err(E) }
+pub struct MyStruct;
+impl MyStruct {
+    pub fn set_$0value(&mut self, value: u32) -> Result<(), ()> {
+        Result::Ok(())
+    }
+}
+"#,
+            r#"
+pub enum Result<T, E> { Ok(T), Err(E) }
+pub struct MyStruct;
+impl MyStruct {
+    /// Set value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_crate::MyStruct;
+    ///
+    /// let mut my_struct = ;
+    /// assert_eq!(my_struct.set_value(value), );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if .
+    pub fn set_value(&mut self, value: u32) -> Result<(), ()> {
+        Result::Ok(())
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn exported_struct() {
+        check_assist(
+            generate_documentation_template,
+            r#"
+//- /lib.rs crate:my_crate
+#[derive(Debug)]
+pub struct MyStr$0uct;
+"#,
+            r#"
+/// My struct.
+///
+/// # Examples
+///
+/// ```
+/// use my_crate::MyStruct;
+/// ```
+#[derive(Debug)]
+pub struct MyStruct;
+"#,
+        );
+    }
+}
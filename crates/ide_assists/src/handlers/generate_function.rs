@@ -128,7 +128,7 @@ impl FunctionBuilder {
                 file = in_file;
                 target
             }
-            None => next_space_for_fn_after_call_site(&call)?,
+            None => next_space_for_fn_after_call_site(call.syntax())?,
         };
         let needs_pub = target_module.is_some();
         let target_module = target_module.or_else(|| ctx.sema.scope(target.syntax()).module())?;
@@ -218,13 +218,13 @@ impl FunctionBuilder {
     }
 }
 
-enum GeneratedFunctionTarget {
+pub(crate) enum GeneratedFunctionTarget {
     BehindItem(SyntaxNode),
     InEmptyItemList(SyntaxNode),
 }
 
 impl GeneratedFunctionTarget {
-    fn syntax(&self) -> &SyntaxNode {
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
         match self {
             GeneratedFunctionTarget::BehindItem(it) => it,
             GeneratedFunctionTarget::InEmptyItemList(it) => it,
@@ -310,7 +310,7 @@ fn fn_arg_name(fn_arg: &ast::Expr) -> Option<String> {
     }
 }
 
-fn fn_arg_type(
+pub(crate) fn fn_arg_type(
     ctx: &AssistContext,
     target_module: hir::Module,
     fn_arg: &ast::Expr,
@@ -331,8 +331,10 @@ fn fn_arg_type(
 /// directly after the current block
 /// We want to write the generated function directly after
 /// fns, impls or macro calls, but inside mods
-fn next_space_for_fn_after_call_site(expr: &ast::CallExpr) -> Option<GeneratedFunctionTarget> {
-    let mut ancestors = expr.syntax().ancestors().peekable();
+pub(crate) fn next_space_for_fn_after_call_site(
+    expr: &SyntaxNode,
+) -> Option<GeneratedFunctionTarget> {
+    let mut ancestors = expr.ancestors().peekable();
     let mut last_ancestor: Option<SyntaxNode> = None;
     while let Some(next_ancestor) = ancestors.next() {
         match next_ancestor.kind() {
@@ -351,7 +353,7 @@ fn next_space_for_fn_after_call_site(expr: &ast::CallExpr) -> Option<GeneratedFu
     last_ancestor.map(GeneratedFunctionTarget::BehindItem)
 }
 
-fn next_space_for_fn_in_module(
+pub(crate) fn next_space_for_fn_in_module(
     db: &dyn hir::db::AstDatabase,
     module_source: &hir::InFile<hir::ModuleSource>,
 ) -> Option<(FileId, GeneratedFunctionTarget)> {
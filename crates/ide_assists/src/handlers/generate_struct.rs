@@ -0,0 +1,560 @@
+use hir::{HasSource, HirDisplay};
+use itertools::Itertools;
+use syntax::{
+    algo::non_trivia_sibling,
+    ast::{self, edit::IndentLevel, make, ArgListOwner, AstNode, NameOwner},
+    Direction, SyntaxNode, TextSize, T,
+};
+
+use crate::{
+    assist_context::AssistBuilder,
+    handlers::generate_function::{
+        fn_arg_type, next_space_for_fn_after_call_site, next_space_for_fn_in_module,
+        GeneratedFunctionTarget,
+    },
+    utils::{has_elided_lifetime, name_elided_lifetimes},
+    AssistContext, AssistId, AssistKind, Assists,
+};
+
+// Assist: generate_struct
+//
+// Adds a struct matching the unresolved struct literal or pattern under the cursor,
+// with field types inferred from the field values.
+//
+// ```
+// fn main() {
+//     let p = Point$0 { x: 1, y: 2 };
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let p = Point { x: 1, y: 2 };
+// }
+//
+// struct Point {
+//     x: i32,
+//     y: i32,
+// }
+// ```
+pub(crate) fn generate_struct(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let usage = Usage::find(ctx)?;
+    if matches!(usage.fields, Fields::Unit) {
+        return None;
+    }
+    let name = type_name(&usage.path)?;
+    let target_module = match usage.path.qualifier() {
+        Some(qualifier) => Some(resolve_module(ctx, &qualifier)?),
+        None => None,
+    };
+    let (file_id, target) = item_target(ctx, &usage.node, target_module)?;
+    let vis = if target_module.is_some() { "pub(crate) " } else { "" };
+    let module = target_module.or_else(|| ctx.sema.scope(target.syntax()).module())?;
+
+    let (generics, fields) = with_explicit_lifetimes(usage.fields.render(ctx, module));
+    let item = match fields {
+        Fields::Record(fields) if !fields.is_empty() => {
+            let fields =
+                fields.iter().map(|(name, ty)| format!("    {}{}: {},\n", vis, name, ty)).join("");
+            format!("{}struct {}{} {{\n{}}}", vis, name, generics, fields)
+        }
+        Fields::Record(_) => format!("{}struct {}{} {{}}", vis, name, generics),
+        Fields::Tuple(fields) => {
+            let fields = fields.iter().map(|ty| format!("{}{}", vis, ty)).join(", ");
+            format!("{}struct {}{}({});", vis, name, generics, fields)
+        }
+        Fields::Unit => return None,
+    };
+
+    acc.add(
+        AssistId("generate_struct", AssistKind::Generate),
+        format!("Generate `{}` struct", name),
+        usage.path.syntax().text_range(),
+        |builder| {
+            builder.edit_file(file_id);
+            insert_item(builder, target, &item);
+        },
+    )
+}
+
+// Assist: generate_enum_variant
+//
+// Adds the unresolved variant under the cursor to its enum, creating the enum if
+// it does not exist yet.
+//
+// ```
+// enum Shape {
+//     Square(f64),
+// }
+// fn main() {
+//     let s = Shape::Circle$0(1.0);
+// }
+// ```
+// ->
+// ```
+// enum Shape {
+//     Square(f64),
+//     Circle(f64),
+// }
+// fn main() {
+//     let s = Shape::Circle(1.0);
+// }
+// ```
+pub(crate) fn generate_enum_variant(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let usage = Usage::find(ctx)?;
+    let variant_name = usage.path.segment()?.name_ref()?.to_string();
+    let qualifier = usage.path.qualifier()?;
+    let db = ctx.db();
+
+    match ctx.sema.resolve_path(&qualifier) {
+        Some(hir::PathResolution::Def(hir::ModuleDef::Adt(hir::Adt::Enum(enum_)))) => {
+            let source = enum_.source(db)?;
+            if source.file_id.call_node(db).is_some() {
+                return None;
+            }
+            let file_id = source.file_id.original_file(db);
+            let variant_list = source.value.variant_list()?;
+            let variant = usage.fields.render(ctx, enum_.module(db)).to_variant(&variant_name);
+
+            let (offset, text) = match variant_list.variants().last() {
+                Some(last) => {
+                    let comma = non_trivia_sibling(last.syntax().clone().into(), Direction::Next)
+                        .filter(|it| it.kind() == T![,]);
+                    let sep = if variant_list.syntax().text().contains_char('\n') {
+                        format!("\n{}", IndentLevel::from_node(last.syntax()))
+                    } else {
+                        " ".to_string()
+                    };
+                    match comma {
+                        Some(comma) => (comma.text_range().end(), format!("{}{},", sep, variant)),
+                        None => (last.syntax().text_range().end(), format!(",{}{}", sep, variant)),
+                    }
+                }
+                None => {
+                    let indent = IndentLevel::from_node(source.value.syntax());
+                    let offset = variant_list.l_curly_token()?.text_range().end();
+                    (offset, format!("\n{}{},\n{}", indent + 1, variant, indent))
+                }
+            };
+
+            acc.add(
+                AssistId("generate_enum_variant", AssistKind::Generate),
+                format!("Generate `{}` variant", variant_name),
+                usage.path.syntax().text_range(),
+                |builder| {
+                    builder.edit_file(file_id);
+                    builder.insert(offset, text);
+                },
+            )
+        }
+        Some(_) => None,
+        None => {
+            let name = type_name(&qualifier)?;
+            let target_module = match qualifier.qualifier() {
+                Some(qualifier) => Some(resolve_module(ctx, &qualifier)?),
+                None => None,
+            };
+            let (file_id, target) = item_target(ctx, &usage.node, target_module)?;
+            let vis = if target_module.is_some() { "pub(crate) " } else { "" };
+            let module = target_module.or_else(|| ctx.sema.scope(target.syntax()).module())?;
+
+            let (generics, fields) = with_explicit_lifetimes(usage.fields.render(ctx, module));
+            let item = format!(
+                "{}enum {}{} {{\n    {},\n}}",
+                vis,
+                name,
+                generics,
+                fields.to_variant(&variant_name)
+            );
+
+            acc.add(
+                AssistId("generate_enum_variant", AssistKind::Generate),
+                format!("Generate `{}` enum with `{}` variant", name, variant_name),
+                usage.path.syntax().text_range(),
+                |builder| {
+                    builder.edit_file(file_id);
+                    insert_item(builder, target, &item);
+                },
+            )
+        }
+    }
+}
+
+/// An unresolved struct or variant path used in an expression or a pattern,
+/// together with the fields it is used with.
+struct Usage {
+    /// The whole expression or pattern.
+    node: SyntaxNode,
+    path: ast::Path,
+    fields: Fields<Value>,
+}
+
+enum Value {
+    Expr(ast::Expr),
+    Pat(ast::Pat),
+}
+
+enum Fields<T> {
+    Unit,
+    Tuple(Vec<T>),
+    Record(Vec<(String, T)>),
+}
+
+impl Usage {
+    fn find(ctx: &AssistContext) -> Option<Usage> {
+        let path = ctx.find_node_at_offset::<ast::Path>()?;
+        let path = std::iter::successors(Some(path), |it| it.parent_path()).last()?;
+        if path.segment()?.generic_arg_list().is_some() || ctx.sema.resolve_path(&path).is_some() {
+            return None;
+        }
+
+        let parent = path.syntax().parent()?;
+        let (node, fields) = if let Some(record) = ast::RecordExpr::cast(parent.clone()) {
+            let field_list = record.record_expr_field_list()?;
+            if field_list.spread().is_some() {
+                return None;
+            }
+            let fields = field_list
+                .fields()
+                .map(|it| Some((it.field_name()?.to_string(), Value::Expr(it.expr()?))))
+                .collect::<Option<_>>()?;
+            (record.syntax().clone(), Fields::Record(fields))
+        } else if let Some(path_expr) = ast::PathExpr::cast(parent.clone()) {
+            match path_expr.syntax().parent().and_then(ast::CallExpr::cast) {
+                Some(call) => {
+                    let args = call.arg_list()?.args().map(Value::Expr).collect();
+                    (call.syntax().clone(), Fields::Tuple(args))
+                }
+                None => (path_expr.syntax().clone(), Fields::Unit),
+            }
+        } else if let Some(record) = ast::RecordPat::cast(parent.clone()) {
+            let fields = record
+                .record_pat_field_list()?
+                .fields()
+                .map(|it| {
+                    let pat = it.pat()?;
+                    let name = match (it.name_ref(), &pat) {
+                        (Some(name_ref), _) => name_ref.to_string(),
+                        (None, ast::Pat::IdentPat(ident_pat)) => ident_pat.name()?.to_string(),
+                        (None, _) => return None,
+                    };
+                    Some((name, Value::Pat(pat)))
+                })
+                .collect::<Option<_>>()?;
+            (record.syntax().clone(), Fields::Record(fields))
+        } else if let Some(tuple) = ast::TupleStructPat::cast(parent.clone()) {
+            if tuple.fields().any(|it| matches!(it, ast::Pat::RestPat(_))) {
+                return None;
+            }
+            let fields = tuple.fields().map(Value::Pat).collect();
+            (tuple.syntax().clone(), Fields::Tuple(fields))
+        } else if ast::PathPat::can_cast(parent.kind()) {
+            (parent, Fields::Unit)
+        } else {
+            return None;
+        };
+        Some(Usage { node, path, fields })
+    }
+}
+
+impl Fields<Value> {
+    /// Renders the types of the field values as seen from `module`.
+    fn render(&self, ctx: &AssistContext, module: hir::Module) -> Fields<String> {
+        let ty = |value: &Value| {
+            let ty = match value {
+                Value::Expr(expr) => fn_arg_type(ctx, module, expr),
+                Value::Pat(pat) => ctx
+                    .sema
+                    .type_of_pat(pat)
+                    .filter(|it| !it.is_unknown())
+                    .and_then(|it| it.display_source_code(ctx.db(), module.into()).ok()),
+            };
+            ty.unwrap_or_else(|| "()".to_string())
+        };
+        match self {
+            Fields::Unit => Fields::Unit,
+            Fields::Tuple(fields) => Fields::Tuple(fields.iter().map(ty).collect()),
+            Fields::Record(fields) => {
+                Fields::Record(fields.iter().map(|(name, it)| (name.clone(), ty(it))).collect())
+            }
+        }
+    }
+}
+
+impl Fields<String> {
+    fn to_variant(&self, name: &str) -> String {
+        match self {
+            Fields::Unit => name.to_string(),
+            Fields::Tuple(fields) => format!("{}({})", name, fields.iter().format(", ")),
+            Fields::Record(fields) => {
+                let fields = fields.iter().map(|(name, ty)| format!("{}: {}", name, ty));
+                format!("{} {{ {} }}", name, fields.format(", "))
+            }
+        }
+    }
+}
+
+/// Names the elided lifetimes of field types, returning the generic parameter
+/// list the new item needs for them.
+fn with_explicit_lifetimes(fields: Fields<String>) -> (&'static str, Fields<String>) {
+    let types = match &fields {
+        Fields::Unit => return ("", fields),
+        Fields::Tuple(fields) => fields.iter().collect::<Vec<_>>(),
+        Fields::Record(fields) => fields.iter().map(|(_, ty)| ty).collect(),
+    };
+    if !types.into_iter().any(|ty| has_elided_lifetime(&make::ty(ty))) {
+        return ("", fields);
+    }
+    let explicit = |ty: String| name_elided_lifetimes(&make::ty(&ty), "'a");
+    let fields = match fields {
+        Fields::Unit => Fields::Unit,
+        Fields::Tuple(fields) => Fields::Tuple(fields.into_iter().map(explicit).collect()),
+        Fields::Record(fields) => {
+            Fields::Record(fields.into_iter().map(|(name, ty)| (name, explicit(ty))).collect())
+        }
+    };
+    ("<'a>", fields)
+}
+
+/// Returns the last segment of `path` if it looks like a type name.
+fn type_name(path: &ast::Path) -> Option<String> {
+    let segment = path.segment()?;
+    if segment.generic_arg_list().is_some() {
+        return None;
+    }
+    let name = segment.name_ref()?.to_string();
+    if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(name)
+}
+
+fn resolve_module(ctx: &AssistContext, path: &ast::Path) -> Option<hir::Module> {
+    match ctx.sema.resolve_path(path)? {
+        hir::PathResolution::Def(hir::ModuleDef::Module(module)) => Some(module),
+        _ => None,
+    }
+}
+
+/// Places a new item in `target_module`, or next to the item containing `node`.
+fn item_target(
+    ctx: &AssistContext,
+    node: &SyntaxNode,
+    target_module: Option<hir::Module>,
+) -> Option<(ide_db::base_db::FileId, GeneratedFunctionTarget)> {
+    match target_module {
+        Some(module) => next_space_for_fn_in_module(ctx.db(), &module.definition_source(ctx.db())),
+        None => Some((ctx.frange.file_id, next_space_for_fn_after_call_site(node)?)),
+    }
+}
+
+fn insert_item(builder: &mut AssistBuilder, target: GeneratedFunctionTarget, item: &str) {
+    let indented = |indent: IndentLevel| item.replace('\n', &format!("\n{}", indent));
+    match target {
+        GeneratedFunctionTarget::BehindItem(it) => {
+            let indent = IndentLevel::from_node(&it);
+            builder.insert(it.text_range().end(), format!("\n\n{}{}", indent, indented(indent)));
+        }
+        GeneratedFunctionTarget::InEmptyItemList(it) => {
+            let indent = IndentLevel::from_node(&it);
+            let offset = it.text_range().start() + TextSize::of('{');
+            builder.insert(offset, format!("\n{}{}\n{}", indent + 1, indented(indent + 1), indent));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn generates_struct_from_record_expr() {
+        check_assist(
+            generate_struct,
+            r#"
+fn main() {
+    let name = "a";
+    let f = Foo$0 { a: 1, name };
+}
+"#,
+            r#"
+fn main() {
+    let name = "a";
+    let f = Foo { a: 1, name };
+}
+
+struct Foo<'a> {
+    a: i32,
+    name: &'a str,
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn generates_tuple_struct_in_module() {
+        check_assist(
+            generate_struct,
+            r#"
+mod m {}
+fn main() {
+    let f = m::Foo$0(1.0, true);
+}
+"#,
+            r#"
+mod m {
+    pub(crate) struct Foo(pub(crate) f64, pub(crate) bool);
+}
+fn main() {
+    let f = m::Foo(1.0, true);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn generates_struct_from_pattern() {
+        check_assist(
+            generate_struct,
+            r#"
+fn f(foo: Foo) {
+    let Foo$0 { a, b: _ } = foo;
+}
+"#,
+            r#"
+fn f(foo: Foo) {
+    let Foo { a, b: _ } = foo;
+}
+
+struct Foo {
+    a: (),
+    b: (),
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn struct_not_applicable_to_resolved_or_lowercase_paths() {
+        check_assist_not_applicable(
+            generate_struct,
+            r#"
+struct Foo { a: i32 }
+fn main() {
+    let f = Foo$0 { a: 1 };
+}
+"#,
+        );
+        check_assist_not_applicable(
+            generate_struct,
+            r#"
+fn main() {
+    let f = foo$0(1);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn generates_variant_in_existing_enum() {
+        check_assist(
+            generate_enum_variant,
+            r#"
+enum Shape {
+    Square(f64)
+}
+fn main() {
+    let s = Shape::Rect$0 { w: 1.0, h: 2.0 };
+}
+"#,
+            r#"
+enum Shape {
+    Square(f64),
+    Rect { w: f64, h: f64 }
+}
+fn main() {
+    let s = Shape::Rect { w: 1.0, h: 2.0 };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn generates_variant_in_other_file() {
+        check_assist(
+            generate_enum_variant,
+            r#"
+//- /main.rs
+mod shape;
+fn f(s: shape::Shape) {
+    match s {
+        shape::Shape::Empty$0 => {}
+        _ => {}
+    }
+}
+//- /shape.rs
+pub enum Shape { Square(f64) }
+"#,
+            r#"
+pub enum Shape { Square(f64), Empty }
+"#,
+        );
+    }
+
+    #[test]
+    fn generates_variant_in_empty_enum() {
+        check_assist(
+            generate_enum_variant,
+            r#"
+enum Shape {}
+fn main() {
+    let s = Shape::Circle$0(1u8);
+}
+"#,
+            r#"
+enum Shape {
+    Circle(u8),
+}
+fn main() {
+    let s = Shape::Circle(1u8);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn generates_enum_with_variant() {
+        check_assist(
+            generate_enum_variant,
+            r#"
+fn main() {
+    let s = Shape::Circle$0(1.0);
+}
+"#,
+            r#"
+fn main() {
+    let s = Shape::Circle(1.0);
+}
+
+enum Shape {
+    Circle(f64),
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn variant_not_applicable_to_existing_variant() {
+        check_assist_not_applicable(
+            generate_enum_variant,
+            r#"
+enum Shape { Circle(f64) }
+fn main() {
+    let s = Shape::Circle$0(1.0);
+}
+"#,
+        );
+    }
+}
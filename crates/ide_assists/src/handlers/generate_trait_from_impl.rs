@@ -0,0 +1,546 @@
+use hir::ModuleDef;
+use ide_db::{
+    base_db::FileId,
+    defs::Definition,
+    helpers::{
+        insert_use::{insert_use, ImportScope},
+        mod_path_to_ast,
+    },
+};
+use itertools::Itertools;
+use rustc_hash::{FxHashMap, FxHashSet};
+use stdx::format_to;
+use syntax::{
+    algo::find_node_at_range,
+    ast::{
+        self, edit::AstNodeEdit, AstNode, AstToken, GenericParamsOwner, NameOwner, VisibilityOwner,
+    },
+    SyntaxElement, SyntaxKind, TextRange,
+};
+
+use crate::{assist_context::AssistBuilder, AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: generate_trait_from_impl
+//
+// Extracts the public methods of an inherent impl into a new trait, which is
+// then implemented for the type. Only the selected methods are extracted if
+// there is a selection.
+//
+// ```
+// struct Foo;
+//
+// imp$0l Foo {
+//     /// Returns zero.
+//     pub fn zero(&self) -> u32 {
+//         0
+//     }
+// }
+// ```
+// ->
+// ```
+// struct Foo;
+//
+// pub trait $0FooExt {
+//     /// Returns zero.
+//     fn zero(&self) -> u32;
+// }
+//
+// impl FooExt for Foo {
+//     fn zero(&self) -> u32 {
+//         0
+//     }
+// }
+// ```
+pub(crate) fn generate_trait_from_impl(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let impl_ = ctx.find_node_at_offset::<ast::Impl>()?;
+    if impl_.trait_().is_some() {
+        return None;
+    }
+    let assoc_items = impl_.assoc_item_list()?;
+    let self_ty = impl_.self_ty()?;
+    let self_name = match &self_ty {
+        ast::Type::PathType(it) => it.path()?.segment()?.name_ref()?.to_string(),
+        _ => return None,
+    };
+
+    let selection = ctx.frange.range;
+    let items: Vec<ast::AssocItem> = assoc_items.assoc_items().collect();
+    let extracted: Vec<ast::Fn> = if selection.is_empty() {
+        // Without a selection, the assist is only offered on the impl header.
+        if selection.start() >= assoc_items.syntax().text_range().start() {
+            return None;
+        }
+        items.iter().filter_map(|it| public_fn(it.clone())).collect()
+    } else {
+        if !assoc_items.syntax().text_range().contains_range(selection) {
+            return None;
+        }
+        items
+            .iter()
+            .filter(|it| it.syntax().text_range().intersect(selection).is_some())
+            .filter_map(|it| public_fn(it.clone()))
+            .collect()
+    };
+    if extracted.is_empty() {
+        return None;
+    }
+
+    let trait_name = format!("{}Ext", self_name);
+    let target = impl_.syntax().text_range();
+    acc.add(
+        AssistId("generate_trait_from_impl", AssistKind::Generate),
+        "Generate trait from impl",
+        target,
+        |builder| {
+            let indent = impl_.indent_level();
+            let vis = extracted[0].visibility().map_or(String::new(), |it| format!("{} ", it));
+            let generic_params = impl_.generic_param_list();
+            let generic_args = generic_params.as_ref().map_or(String::new(), |params| {
+                let args = params.generic_params().map(|param| match param {
+                    ast::GenericParam::LifetimeParam(it) => {
+                        it.lifetime().map_or(String::new(), |it| it.to_string())
+                    }
+                    ast::GenericParam::TypeParam(it) => {
+                        it.name().map_or(String::new(), |it| it.to_string())
+                    }
+                    ast::GenericParam::ConstParam(it) => {
+                        it.name().map_or(String::new(), |it| it.to_string())
+                    }
+                });
+                format!("<{}>", args.format(", "))
+            });
+            let generic_params = generic_params.map_or(String::new(), |it| it.to_string());
+            let open_brace = match impl_.where_clause() {
+                Some(it) => format!("\n{}{}\n{}{{", indent, it, indent),
+                None => " {".to_string(),
+            };
+
+            let mut trait_ = String::new();
+            // The name is also used by the impl and by imports, possibly in other files, so
+            // only the cursor is placed on it and renaming is left to the rename refactoring.
+            let name_placeholder = match ctx.config.snippet_cap {
+                Some(_) => format!("$0{}", trait_name),
+                None => trait_name.clone(),
+            };
+            format_to!(
+                trait_,
+                "{}trait {}{}{}\n",
+                vis,
+                name_placeholder,
+                generic_params,
+                open_brace
+            );
+            for fn_ in &extracted {
+                format_to!(trait_, "{}    {}\n", indent, fn_signature(fn_));
+            }
+            format_to!(trait_, "{}}}", indent);
+
+            let mut trait_impl = String::new();
+            format_to!(
+                trait_impl,
+                "impl{} {}{} for {}{}\n",
+                generic_params,
+                trait_name,
+                generic_args,
+                self_ty,
+                open_brace
+            );
+            let fns = extracted.iter().map(|fn_| format!("{}    {}", indent, fn_impl(fn_)));
+            format_to!(trait_impl, "{}\n{}}}", fns.format("\n\n"), indent);
+
+            let text = format!("{}\n\n{}{}", trait_, indent, trait_impl);
+            if extracted.len() == items.len() {
+                match ctx.config.snippet_cap {
+                    Some(cap) => builder.replace_snippet(cap, target, text),
+                    None => builder.replace(target, text),
+                }
+            } else {
+                for fn_ in &extracted {
+                    builder.delete(range_with_leading_whitespace(fn_.syntax().clone().into()));
+                }
+                let text = format!("{}\n\n{}", text, indent);
+                match ctx.config.snippet_cap {
+                    Some(cap) => builder.insert_snippet(cap, target.start(), text),
+                    None => builder.insert(target.start(), text),
+                }
+            }
+
+            import_trait(ctx, builder, &impl_, &extracted, &trait_name);
+        },
+    )
+}
+
+fn public_fn(item: ast::AssocItem) -> Option<ast::Fn> {
+    match item {
+        ast::AssocItem::Fn(it) if it.visibility().is_some() => Some(it),
+        _ => None,
+    }
+}
+
+/// Returns the trait item declaration of `fn_`: its signature, docs and
+/// attributes, without the visibility and the body.
+fn fn_signature(fn_: &ast::Fn) -> String {
+    let body = fn_.body().map(|it| it.syntax().text_range());
+    let mut elements = fn_text_without(
+        fn_,
+        |it| matches!((it.text_range(), body), (range, Some(body)) if range == body),
+    );
+    elements.truncate(elements.trim_end().len());
+    elements.push(';');
+    elements
+}
+
+/// Returns the implementation of `fn_`, without the visibility and the doc
+/// comments, which go to the trait declaration.
+fn fn_impl(fn_: &ast::Fn) -> String {
+    fn_text_without(fn_, |it| {
+        let comment = it.as_token().cloned().and_then(ast::Comment::cast);
+        matches!(comment, Some(it) if it.kind().doc.is_some())
+    })
+}
+
+/// Renders `fn_` without its visibility and the elements matching `skip`,
+/// along with the whitespace following them.
+fn fn_text_without(fn_: &ast::Fn, skip: impl Fn(&SyntaxElement) -> bool) -> String {
+    let mut buf = String::new();
+    let mut skip_whitespace = false;
+    for element in fn_.syntax().children_with_tokens() {
+        if element.kind() == SyntaxKind::WHITESPACE && skip_whitespace {
+            skip_whitespace = false;
+            continue;
+        }
+        skip_whitespace = element.kind() == SyntaxKind::VISIBILITY || skip(&element);
+        if !skip_whitespace {
+            buf.push_str(&element.to_string());
+        }
+    }
+    buf
+}
+
+fn range_with_leading_whitespace(element: SyntaxElement) -> TextRange {
+    let range = element.text_range();
+    match element.prev_sibling_or_token() {
+        Some(prev) if prev.kind() == SyntaxKind::WHITESPACE => {
+            TextRange::new(prev.text_range().start(), range.end())
+        }
+        _ => range,
+    }
+}
+
+/// Brings the new trait into scope of every module which calls the extracted
+/// methods, as they no longer resolve without it.
+fn import_trait(
+    ctx: &AssistContext,
+    builder: &mut AssistBuilder,
+    impl_: &ast::Impl,
+    extracted: &[ast::Fn],
+    trait_name: &str,
+) {
+    let db = ctx.db();
+    let trait_module = match ctx.sema.scope(impl_.syntax()).module() {
+        Some(it) => it,
+        None => return,
+    };
+
+    let mut scopes: FxHashMap<FileId, Vec<ImportScope>> = FxHashMap::default();
+    let mut seen = FxHashSet::default();
+    for fn_ in extracted {
+        let def = match ctx.sema.to_def(fn_) {
+            Some(it) => it,
+            None => continue,
+        };
+        let usages = Definition::ModuleDef(ModuleDef::Function(def)).usages(&ctx.sema).all();
+        for (file_id, references) in usages {
+            let file = ctx.sema.parse(file_id);
+            for reference in references {
+                let name_ref =
+                    match find_node_at_range::<ast::NameRef>(file.syntax(), reference.range) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                let module = match ctx.sema.scope(name_ref.syntax()).module() {
+                    Some(it) => it,
+                    None => continue,
+                };
+                if module == trait_module || module.krate() != trait_module.krate() {
+                    continue;
+                }
+                let scope = match ImportScope::find_insert_use_container(name_ref.syntax()) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                if seen.insert((file_id, scope.as_syntax_node().text_range())) {
+                    scopes.entry(file_id).or_default().push(scope);
+                }
+            }
+        }
+    }
+
+    let current_file = ctx.frange.file_id;
+    let mut files: Vec<_> = scopes.into_iter().collect();
+    // Edits to the current file have to be made before switching files.
+    files.sort_by_key(|(file_id, _)| *file_id != current_file);
+    for (file_id, scopes) in files {
+        builder.edit_file(file_id);
+        for scope in scopes {
+            let module = match ctx.sema.scope(scope.as_syntax_node()).module() {
+                Some(it) => it,
+                None => continue,
+            };
+            let path = match module.find_use_path_prefixed(
+                db,
+                ModuleDef::Module(trait_module),
+                ctx.config.insert_use.prefix_kind,
+            ) {
+                Some(it) => it,
+                None => continue,
+            };
+            let path =
+                ast::make::path_from_text(&format!("{}::{}", mod_path_to_ast(&path), trait_name));
+            let scope = match scope {
+                ImportScope::File(it) => ImportScope::File(builder.make_mut(it)),
+                ImportScope::Module(it) => ImportScope::Module(builder.make_mut(it)),
+            };
+            insert_use(&scope, path.clone_for_update(), ctx.config.insert_use);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn extracts_all_public_methods() {
+        check_assist(
+            generate_trait_from_impl,
+            r#"
+struct Foo<T>(T);
+
+impl<T: Clone> Foo<$0T>
+where
+    T: Copy,
+{
+    /// Returns the inner value.
+    pub(crate) fn get(&self) -> T {
+        self.0
+    }
+
+    pub(crate) fn set(&mut self, value: T) {
+        self.0 = value;
+    }
+}
+"#,
+            r#"
+struct Foo<T>(T);
+
+pub(crate) trait $0FooExt<T: Clone>
+where
+    T: Copy,
+{
+    /// Returns the inner value.
+    fn get(&self) -> T;
+    fn set(&mut self, value: T);
+}
+
+impl<T: Clone> FooExt<T> for Foo<T>
+where
+    T: Copy,
+{
+    fn get(&self) -> T {
+        self.0
+    }
+
+    fn set(&mut self, value: T) {
+        self.0 = value;
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn extracts_selected_methods() {
+        check_assist(
+            generate_trait_from_impl,
+            r#"
+struct Foo;
+
+impl Foo {
+    pub fn new() -> Foo {
+        Foo
+    }
+
+    $0pub fn a(&self) {}$0
+
+    pub fn b(&self) {}
+}
+"#,
+            r#"
+struct Foo;
+
+pub trait $0FooExt {
+    fn a(&self);
+}
+
+impl FooExt for Foo {
+    fn a(&self) {}
+}
+
+impl Foo {
+    pub fn new() -> Foo {
+        Foo
+    }
+
+    pub fn b(&self) {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn imports_trait_at_call_sites() {
+        check_assist(
+            generate_trait_from_impl,
+            r#"
+//- /main.rs
+mod foo;
+
+fn main() {
+    foo::Foo.zero();
+}
+//- /foo.rs
+pub struct Foo;
+
+$0impl Foo {
+    pub fn zero(&self) -> u32 {
+        0
+    }
+}
+
+fn bar() {
+    Foo.zero();
+}
+"#,
+            r#"
+//- /main.rs
+use foo::FooExt;
+
+mod foo;
+
+fn main() {
+    foo::Foo.zero();
+}
+//- /foo.rs
+pub struct Foo;
+
+pub trait $0FooExt {
+    fn zero(&self) -> u32;
+}
+
+impl FooExt for Foo {
+    fn zero(&self) -> u32 {
+        0
+    }
+}
+
+fn bar() {
+    Foo.zero();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn imports_trait_in_same_file() {
+        check_assist(
+            generate_trait_from_impl,
+            r#"
+struct Foo;
+
+$0impl Foo {
+    pub fn zero(&self) -> u32 {
+        0
+    }
+}
+
+mod bar {
+    fn bar() {
+        super::Foo.zero();
+    }
+}
+"#,
+            r#"
+struct Foo;
+
+pub trait $0FooExt {
+    fn zero(&self) -> u32;
+}
+
+impl FooExt for Foo {
+    fn zero(&self) -> u32 {
+        0
+    }
+}
+
+mod bar {
+    use crate::FooExt;
+
+    fn bar() {
+        super::Foo.zero();
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_trait_impls() {
+        check_assist_not_applicable(
+            generate_trait_from_impl,
+            r#"
+trait Zero {
+    fn zero(&self) -> u32;
+}
+struct Foo;
+$0impl Zero for Foo {
+    fn zero(&self) -> u32 {
+        0
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_without_public_methods() {
+        check_assist_not_applicable(
+            generate_trait_from_impl,
+            r#"
+struct Foo;
+$0impl Foo {
+    fn zero(&self) -> u32 {
+        0
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_inside_methods() {
+        check_assist_not_applicable(
+            generate_trait_from_impl,
+            r#"
+struct Foo;
+impl Foo {
+    pub fn zero(&self) -> u32 {
+        $00
+    }
+}
+"#,
+        );
+    }
+}
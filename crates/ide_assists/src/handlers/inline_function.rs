@@ -1,12 +1,25 @@
 use ast::make;
-use hir::{HasSource, PathResolution};
+use hir::{
+    AsAssocItem, AssocItemContainer, HasSource, HirDisplay, ModuleSource, PathResolution, Semantics,
+};
+use ide_db::{
+    base_db::FileId,
+    defs::Definition,
+    helpers::mod_path_to_ast,
+    search::{FileReference, SearchScope},
+    RootDatabase,
+};
+use rustc_hash::FxHashSet;
 use syntax::{
-    ast::{self, edit::AstNodeEdit, ArgListOwner},
-    AstNode,
+    algo::find_node_at_range,
+    ast::{self, edit::AstNodeEdit, ArgListOwner, GenericParamsOwner, NameOwner},
+    AstNode, SyntaxKind, TextRange,
 };
+use text_edit::TextEdit;
 
 use crate::{
     assist_context::{AssistContext, Assists},
+    utils::{range_with_whitespace, range_without_generic_args},
     AssistId, AssistKind,
 };
 
@@ -32,62 +45,615 @@ use crate::{
 // }
 // ```
 pub(crate) fn inline_function(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
-    let path_expr: ast::PathExpr = ctx.find_node_at_offset()?;
-    let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast)?;
-    let path = path_expr.path()?;
-
-    let function = match ctx.sema.resolve_path(&path)? {
-        PathResolution::Def(hir::ModuleDef::Function(f)) => f,
-        _ => return None,
+    let name_ref: ast::NameRef = ctx.find_node_at_offset()?;
+    let (call_info, function) = match name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast)
+    {
+        Some(call) => {
+            let function = ctx.sema.resolve_method_call(&call)?;
+            (CallInfo::from_method_call(call)?, function)
+        }
+        None => {
+            let path_expr = name_ref.syntax().ancestors().find_map(ast::PathExpr::cast)?;
+            let function = match ctx.sema.resolve_path(&path_expr.path()?)? {
+                PathResolution::Def(hir::ModuleDef::Function(f)) => f,
+                PathResolution::AssocItem(hir::AssocItem::Function(f)) => f,
+                _ => return None,
+            };
+            (CallInfo::from_path_expr(path_expr)?, function)
+        }
+    };
+    let label = match &call_info.node {
+        ast::Expr::CallExpr(call) => format!("Inline `{}`", call.expr()?),
+        _ => format!("Inline `{}`", name_ref),
     };
 
-    let function_source = function.source(ctx.db())?;
-    let arguments: Vec<_> = call.arg_list()?.args().collect();
-    let parameters = function_parameter_patterns(&function_source.value)?;
+    let function = InlineFunction::new(&ctx.sema, function)?;
+    let replacement = function.inline(&ctx.sema, &call_info)?;
+
+    acc.add(
+        AssistId("inline_function", AssistKind::RefactorInline),
+        label,
+        call_info.node.syntax().text_range(),
+        |builder| {
+            builder.replace_ast(call_info.node, replacement);
+        },
+    )
+}
 
-    if arguments.len() != parameters.len() {
-        // Can't inline the function because they've passed the wrong number of
-        // arguments to this function
-        cov_mark::hit!(inline_function_incorrect_number_of_arguments);
+// Assist: inline_into_callers
+//
+// Inlines a function body into all of its callers and removes the function.
+// Only applicable if every usage of the function is a call which can be inlined.
+//
+// ```
+// fn print(a: u32) { println!("{}", a); }
+// fn $0foo(a: u32, b: u32) -> u32 { a + b }
+// fn main() {
+//     let x = foo(1, 2);
+//     let y = foo(3, 4);
+// }
+// ```
+// ->
+// ```
+// fn print(a: u32) { println!("{}", a); }
+// fn main() {
+//     let x = {
+//         let a = 1;
+//         let b = 2;
+//         a + b
+//     };
+//     let y = {
+//         let a = 3;
+//         let b = 4;
+//         a + b
+//     };
+// }
+// ```
+pub(crate) fn inline_into_callers(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let name: ast::Name = ctx.find_node_at_offset()?;
+    let fn_ = name.syntax().parent().and_then(ast::Fn::cast)?;
+    let function = ctx.sema.to_def(&fn_)?;
+    // Trait methods may be called through generics, which can't be inlined.
+    if function.as_assoc_item(ctx.db()).and_then(|it| it.containing_trait(ctx.db())).is_some()
+        || fn_.syntax().ancestors().find_map(ast::Impl::cast).and_then(|it| it.trait_()).is_some()
+    {
+        return None;
+    }
+    let inline_function = InlineFunction::new(&ctx.sema, function)?;
+    let usages = Definition::ModuleDef(function.into()).usages(&ctx.sema).all();
+    if usages.is_empty() {
         return None;
     }
 
-    let new_bindings = parameters.into_iter().zip(arguments);
-
-    let body = function_source.value.body()?;
+    // The function is removed, so every usage has to be inlined.
+    let mut replacements: Vec<(FileId, Vec<(TextRange, ast::Expr)>)> = Vec::new();
+    for (file_id, references) in usages {
+        let calls = references
+            .iter()
+            .map(|reference| CallInfo::from_reference(&ctx.sema, file_id, reference))
+            .collect::<Option<Vec<_>>>()?;
+        let mut file_replacements = Vec::new();
+        for call_info in &calls {
+            let range = call_info.node.syntax().text_range();
+            // The arguments of the outer call are moved into `let` statements unchanged, so
+            // calls in them, as well as recursive calls, would be left behind.
+            let nested = calls.iter().any(|other| {
+                let other = other.node.syntax().text_range();
+                other != range && other.contains_range(range)
+            });
+            let recursive = file_id == inline_function.file_id
+                && fn_.syntax().text_range().contains_range(range);
+            if nested || recursive {
+                cov_mark::hit!(inline_into_callers_nested_call);
+                return None;
+            }
+            file_replacements.push((range, inline_function.inline(&ctx.sema, call_info)?));
+        }
+        replacements.push((file_id, file_replacements));
+    }
 
     acc.add(
-        AssistId("inline_function", AssistKind::RefactorInline),
-        format!("Inline `{}`", path),
-        call.syntax().text_range(),
+        AssistId("inline_into_callers", AssistKind::RefactorInline),
+        format!("Inline `{}` into all callers", name),
+        name.syntax().text_range(),
         |builder| {
-            let mut statements: Vec<ast::Stmt> = Vec::new();
+            for (file_id, file_replacements) in replacements {
+                builder.edit_file(file_id);
+                for (range, replacement) in file_replacements {
+                    builder.replace(range, replacement.to_string());
+                }
+            }
+            builder.edit_file(inline_function.file_id);
+            builder.delete(range_with_whitespace(fn_.syntax()));
+        },
+    )
+}
+
+/// A call site of the function: either `foo(args)`, `Foo::foo(this, args)` or
+/// `this.foo(args)`.
+struct CallInfo {
+    node: ast::Expr,
+    arguments: Vec<ast::Expr>,
+    /// The receiver of a method call; it is passed as `self` with autoref applied.
+    receiver: Option<ast::Expr>,
+}
 
-            for (pattern, value) in new_bindings {
-                statements.push(make::let_stmt(pattern, Some(value)).into());
+impl CallInfo {
+    fn from_reference(
+        sema: &Semantics<RootDatabase>,
+        file_id: FileId,
+        reference: &FileReference,
+    ) -> Option<CallInfo> {
+        let name_ref = match &reference.name {
+            ast::NameLike::NameRef(it) => it,
+            _ => return None,
+        };
+        // references from macro expansions can't be replaced
+        if name_ref.syntax().ancestors().last()? != *sema.parse(file_id).syntax() {
+            return None;
+        }
+        match name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast) {
+            Some(call) => Self::from_method_call(call),
+            None => {
+                let segment = name_ref.syntax().parent().and_then(ast::PathSegment::cast)?;
+                let path_expr = segment.parent_path().syntax().parent()?;
+                Self::from_path_expr(ast::PathExpr::cast(path_expr)?)
             }
+        }
+    }
 
-            statements.extend(body.statements());
+    fn from_method_call(call: ast::MethodCallExpr) -> Option<CallInfo> {
+        Some(CallInfo {
+            arguments: call.arg_list()?.args().collect(),
+            receiver: Some(call.receiver()?),
+            node: ast::Expr::MethodCallExpr(call),
+        })
+    }
 
-            let original_indentation = call.indent_level();
-            let replacement = make::block_expr(statements, body.tail_expr())
-                .reset_indent()
-                .indent(original_indentation);
+    fn from_path_expr(path_expr: ast::PathExpr) -> Option<CallInfo> {
+        let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast)?;
+        if call.expr()?.syntax() != path_expr.syntax() {
+            return None;
+        }
+        Some(CallInfo {
+            arguments: call.arg_list()?.args().collect(),
+            receiver: None,
+            node: ast::Expr::CallExpr(call),
+        })
+    }
+}
 
-            builder.replace_ast(ast::Expr::CallExpr(call), ast::Expr::BlockExpr(replacement));
-        },
-    )
+/// A function prepared for inlining: its body and the places in the body which
+/// refer to parameters, `self`, `Self` or other items.
+struct InlineFunction {
+    file_id: FileId,
+    body: ast::BlockExpr,
+    self_param: Option<ast::SelfParam>,
+    params: Vec<ast::Pat>,
+    /// Usages of `self` and of each parameter, in that order.
+    usages: Vec<Vec<Usage>>,
+    /// The `impl` containing the function, and whether it has generic parameters.
+    impl_: Option<(hir::Impl, bool)>,
+    /// Whether the function returns `Self`, whose type can then be taken from the call.
+    returns_self: bool,
+    /// Usages of `Self`, and whether each is part of an expression or pattern, where
+    /// generic arguments need a turbofish.
+    self_ty_usages: Vec<(TextRange, bool)>,
+    /// Paths referring to items, which have to be reachable from the call site.
+    item_paths: Vec<(ast::Path, hir::ModuleDef)>,
+    /// All names occurring in the body, which fresh names must not clash with.
+    names: FxHashSet<String>,
+}
+
+struct Usage {
+    range: TextRange,
+    /// `S { field }`, which has to become `S { field: new_name }`.
+    field_shorthand: bool,
 }
 
-fn function_parameter_patterns(value: &ast::Fn) -> Option<Vec<ast::Pat>> {
-    let mut patterns = Vec::new();
+impl InlineFunction {
+    fn new(sema: &Semantics<RootDatabase>, function: hir::Function) -> Option<InlineFunction> {
+        let source = function.source(sema.db)?;
+        let file_id = source.file_id.original_file(sema.db);
+        if source.file_id != file_id.into() {
+            return None;
+        }
+        // Re-find the function in the tree known to `sema`, so that its locals resolve.
+        let fn_: ast::Fn =
+            find_node_at_range(sema.parse(file_id).syntax(), source.value.syntax().text_range())?;
+        let body = fn_.body()?;
+        if has_early_return(&body) {
+            cov_mark::hit!(inline_function_with_early_return);
+            return None;
+        }
+        let param_list = fn_.param_list()?;
+        let self_param = param_list.self_param();
+        let params = param_list.params().map(|param| param.pat()).collect::<Option<Vec<_>>>()?;
+
+        let locals = self_param
+            .iter()
+            .map(|it| sema.to_def(it))
+            .chain(params.iter().map(|pat| match pat {
+                ast::Pat::IdentPat(it) => sema.to_def(it),
+                _ => None,
+            }))
+            .collect::<Vec<_>>();
+        let usages = locals
+            .into_iter()
+            .map(|local| {
+                let local = match local {
+                    Some(it) => it,
+                    None => return Vec::new(),
+                };
+                Definition::Local(local)
+                    .usages(sema)
+                    .in_scope(SearchScope::single_file(file_id))
+                    .all()
+                    .references
+                    .remove(&file_id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|it| body.syntax().text_range().contains_range(it.range))
+                    .map(|it| Usage {
+                        range: it.range,
+                        field_shorthand: match &it.name {
+                            ast::NameLike::NameRef(name_ref) => {
+                                matches!(
+                                    ast::RecordExprField::for_name_ref(name_ref),
+                                    Some(field) if field.name_ref().is_none()
+                                )
+                            }
+                            _ => false,
+                        },
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let impl_ = function
+            .as_assoc_item(sema.db)
+            .and_then(|it| match it.container(sema.db) {
+                AssocItemContainer::Impl(it) => Some(it),
+                AssocItemContainer::Trait(_) => None,
+            })
+            .map(|it| {
+                let generic = matches!(
+                    fn_.syntax().ancestors().find_map(ast::Impl::cast),
+                    Some(it) if it.generic_param_list().is_some()
+                );
+                (it, generic)
+            });
+        let returns_self = matches!(
+            fn_.ret_type().and_then(|it| it.ty()),
+            Some(ty) if ty.syntax().text() == "Self"
+        );
+        let self_ty_usages = body
+            .syntax()
+            .descendants()
+            .filter_map(ast::PathSegment::cast)
+            .filter_map(|it| it.name_ref())
+            .filter(|it| it.text() == "Self")
+            .map(|name_ref| {
+                let outermost_path = name_ref
+                    .syntax()
+                    .ancestors()
+                    .skip_while(|it| !ast::Path::can_cast(it.kind()))
+                    .take_while(|it| ast::Path::can_cast(it.kind()))
+                    .last();
+                let in_type = matches!(
+                    outermost_path.and_then(|it| it.parent()),
+                    Some(it) if ast::PathType::can_cast(it.kind())
+                );
+                (name_ref.syntax().text_range(), !in_type)
+            })
+            .collect();
+        let mut item_paths: Vec<(ast::Path, hir::ModuleDef)> = Vec::new();
+        for path in body.syntax().descendants().filter_map(ast::Path::cast) {
+            let range = path.syntax().text_range();
+            // Paths in generic arguments are checked on their own.
+            if item_paths.iter().any(|(it, _)| range_without_generic_args(it).contains_range(range))
+            {
+                continue;
+            }
+            let def = match sema.resolve_path(&path) {
+                Some(PathResolution::Def(def)) => def,
+                _ => continue,
+            };
+            // Items declared in the body come along with it.
+            let local_item = matches!(
+                def.module(sema.db).map(|it| it.definition_source(sema.db).value),
+                Some(ModuleSource::BlockExpr(_))
+            );
+            if !matches!(def, hir::ModuleDef::BuiltinType(_)) && !local_item {
+                item_paths.push((path, def));
+            }
+        }
+        let names = body
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == SyntaxKind::IDENT)
+            .map(|it| it.text().to_string())
+            .collect();
+
+        Some(InlineFunction {
+            file_id,
+            body,
+            self_param,
+            params,
+            usages,
+            impl_,
+            returns_self,
+            self_ty_usages,
+            item_paths,
+            names,
+        })
+    }
+
+    /// Returns the block replacing `call_info`, with a `let` statement for each argument,
+    /// in order, followed by the body.
+    fn inline(&self, sema: &Semantics<RootDatabase>, call_info: &CallInfo) -> Option<ast::Expr> {
+        let mut arguments = call_info.arguments.clone();
+        let self_arg = match (&self.self_param, &call_info.receiver) {
+            (Some(self_param), Some(receiver)) => {
+                let receiver_is_ref = matches!(
+                    sema.type_of_expr(receiver), Some(ty) if ty.remove_ref().is_some()
+                );
+                Some(match (self_param.kind(), receiver_is_ref) {
+                    (ast::SelfParamKind::Ref, false) => make::expr_ref(receiver.clone(), false),
+                    (ast::SelfParamKind::MutRef, false) => make::expr_ref(receiver.clone(), true),
+                    (ast::SelfParamKind::Owned, true) if self_param.ty().is_none() => {
+                        make::expr_prefix(syntax::T![*], receiver.clone())
+                    }
+                    _ => receiver.clone(),
+                })
+            }
+            (Some(_), None) if !arguments.is_empty() => Some(arguments.remove(0)),
+            (None, None) => None,
+            _ => return None,
+        };
+        if arguments.len() != self.params.len() {
+            // Can't inline the function because they've passed the wrong number of
+            // arguments to this function
+            cov_mark::hit!(inline_function_incorrect_number_of_arguments);
+            return None;
+        }
+
+        let bindings: Vec<(Vec<String>, ast::Expr)> = self_arg
+            .map(|arg| (vec!["this".to_string()], arg))
+            .into_iter()
+            .chain(self.params.iter().zip(arguments).map(|(pat, arg)| (pat_names(pat), arg)))
+            .collect();
+
+        // A binding must not capture a name used by an argument evaluated after it.
+        let mut used_names = self.names.clone();
+        for (_, arg) in &bindings {
+            used_names.extend(expr_names(arg));
+        }
+        let mut new_names: Vec<Option<String>> = Vec::new();
+        for (i, (names, _)) in bindings.iter().enumerate() {
+            let captured = bindings[i + 1..]
+                .iter()
+                .any(|(_, later)| expr_names(later).iter().any(|it| names.contains(it)));
+            // `self` is always renamed, to `this`
+            let is_self = i == 0 && self.self_param.is_some();
+            if !captured && !is_self {
+                new_names.push(None);
+                continue;
+            }
+            let name = match names.as_slice() {
+                [name] => name,
+                // only simple bindings can be renamed
+                _ => return None,
+            };
+            let new_name = if captured {
+                cov_mark::hit!(inline_function_renames_captured_binding);
+                fresh_name(name, &used_names)
+            } else if is_self && self.names.contains(name) {
+                fresh_name(name, &used_names)
+            } else {
+                name.clone()
+            };
+            used_names.insert(new_name.clone());
+            new_names.push(Some(new_name));
+        }
+
+        let mut statements: Vec<ast::Stmt> = Vec::new();
+        let patterns =
+            self.self_param.iter().map(self_pattern).chain(self.params.iter().cloned().map(Some));
+        for ((pattern, (_, arg)), new_name) in patterns.zip(bindings).zip(&new_names) {
+            let pattern = match (pattern, new_name) {
+                (Some(ast::Pat::IdentPat(pat)), Some(new_name)) => rename_pat(&pat, new_name)?,
+                (Some(pattern), _) => pattern,
+                (None, _) => return None,
+            };
+            statements.push(make::let_stmt(pattern, Some(arg)).into());
+        }
+
+        let path_replacements = self.path_replacements(sema, call_info)?;
+        let body = self.body_with_renames(&new_names, path_replacements)?;
+        statements.extend(body.statements());
+
+        let original_indentation = call_info.node.indent_level();
+        let block = make::block_expr(statements, body.tail_expr())
+            .reset_indent()
+            .indent(original_indentation);
+        // `{ .. } + 1` would be parsed as a statement followed by `+1`
+        let parent = call_info.node.syntax().parent();
+        let needs_parens = matches!(
+            parent, Some(it) if ast::Expr::can_cast(it.kind())
+                && !ast::ParenExpr::can_cast(it.kind())
+                && !ast::BlockExpr::can_cast(it.kind())
+        );
+        Some(if needs_parens { make::expr_paren(block.into()) } else { block.into() })
+    }
 
-    for param in value.param_list()?.params() {
-        let pattern = param.pat()?;
-        patterns.push(pattern);
+    /// Replacements making `Self` and the paths to items in the body refer to the same
+    /// types and items at the call site, or `None` if some of them aren't reachable from it.
+    fn path_replacements(
+        &self,
+        sema: &Semantics<RootDatabase>,
+        call_info: &CallInfo,
+    ) -> Option<Vec<(TextRange, String)>> {
+        let db = sema.db;
+        let scope = sema.scope(call_info.node.syntax());
+        let module = scope.module()?;
+        let mut res = Vec::new();
+        for (path, def) in &self.item_paths {
+            let mod_path = module.find_use_path(db, *def)?;
+            if scope.speculative_resolve(path) == Some(PathResolution::Def(*def)) {
+                continue;
+            }
+            // Generic arguments are replaced separately.
+            let range = range_without_generic_args(path);
+            res.push((range, mod_path_to_ast(&mod_path).to_string()));
+        }
+        if !self.self_ty_usages.is_empty() {
+            let self_ty = self.self_ty(sema, call_info)?;
+            let self_ty =
+                ast::Type::parse(&self_ty.display_source_code(db, module.into()).ok()?).ok()?;
+            for &(range, in_expr) in &self.self_ty_usages {
+                let text = if in_expr { expr_ty(&self_ty) } else { self_ty.to_string() };
+                res.push((range, text));
+            }
+        }
+        Some(res)
     }
 
-    Some(patterns)
+    /// The type `Self` stands for at the call site.
+    fn self_ty(&self, sema: &Semantics<RootDatabase>, call_info: &CallInfo) -> Option<hir::Type> {
+        let (impl_, generic) = self.impl_?;
+        let self_ty = impl_.self_ty(sema.db);
+        if !generic {
+            return Some(self_ty);
+        }
+        // The generic arguments are only known from the call.
+        let ty = match (&call_info.receiver, &self.self_param) {
+            (Some(receiver), _) => sema.type_of_expr(receiver)?.strip_references(),
+            (None, Some(self_param)) if self_param.ty().is_none() => {
+                sema.type_of_expr(call_info.arguments.first()?)?.strip_references()
+            }
+            (None, None) if self.returns_self => sema.type_of_expr(&call_info.node)?,
+            _ => return None,
+        };
+        if ty.as_adt() != self_ty.as_adt() {
+            return None;
+        }
+        Some(ty)
+    }
+
+    /// The body with `self` and the renamed parameters replaced by their new names and
+    /// the `path_replacements` applied.
+    fn body_with_renames(
+        &self,
+        new_names: &[Option<String>],
+        path_replacements: Vec<(TextRange, String)>,
+    ) -> Option<ast::BlockExpr> {
+        let mut replacements = path_replacements;
+        for (usages, new_name) in self.usages.iter().zip(new_names) {
+            let new_name = match new_name {
+                Some(it) => it,
+                None => continue,
+            };
+            for usage in usages {
+                if usage.field_shorthand {
+                    let old_name = &self.body.syntax().text().to_string()
+                        [usage.range - self.body.syntax().text_range().start()];
+                    replacements.push((usage.range, format!("{}: {}", old_name, new_name)));
+                } else {
+                    replacements.push((usage.range, new_name.clone()));
+                }
+            }
+        }
+        replacements.sort_by_key(|(range, _)| range.start());
+        if replacements.windows(2).any(|it| it[0].0.end() > it[1].0.start()) {
+            return None;
+        }
+        let offset = self.body.syntax().text_range().start();
+        let mut edit = TextEdit::builder();
+        for (range, text) in replacements {
+            edit.replace(range - offset, text);
+        }
+        let mut text = self.body.syntax().text().to_string();
+        edit.finish().apply(&mut text);
+        let body = match ast::Expr::parse(&text).ok()? {
+            ast::Expr::BlockExpr(it) => it,
+            _ => return None,
+        };
+        Some(body.dedent(self.body.indent_level()))
+    }
+}
+
+/// `ty` as it is written in expressions and patterns, `Foo::<T>` rather than `Foo<T>`.
+fn expr_ty(ty: &ast::Type) -> String {
+    let path = match ty {
+        ast::Type::PathType(it) => it.path(),
+        _ => None,
+    };
+    let path = match path {
+        Some(it) => it,
+        None => return format!("<{}>", ty),
+    };
+    match path.segment().and_then(|it| it.generic_arg_list()) {
+        Some(args) if args.coloncolon_token().is_none() => {
+            let offset = args.syntax().text_range().start() - path.syntax().text_range().start();
+            let mut text = path.to_string();
+            text.insert_str(offset.into(), "::");
+            text
+        }
+        _ => path.to_string(),
+    }
+}
+
+fn has_early_return(body: &ast::BlockExpr) -> bool {
+    body.syntax().descendants().any(|node| {
+        if !(ast::ReturnExpr::can_cast(node.kind()) || ast::TryExpr::can_cast(node.kind())) {
+            return false;
+        }
+        // returns from closures and nested functions stay where they are
+        let owner = node
+            .ancestors()
+            .find(|it| ast::ClosureExpr::can_cast(it.kind()) || ast::Fn::can_cast(it.kind()));
+        match owner {
+            Some(owner) => !body.syntax().text_range().contains_range(owner.text_range()),
+            None => true,
+        }
+    })
+}
+
+fn self_pattern(self_param: &ast::SelfParam) -> Option<ast::Pat> {
+    let text = if self_param.kind() == ast::SelfParamKind::Owned && self_param.mut_token().is_some()
+    {
+        "mut this"
+    } else {
+        "this"
+    };
+    ast::Pat::parse(text).ok()
+}
+
+fn pat_names(pat: &ast::Pat) -> Vec<String> {
+    pat.syntax()
+        .descendants()
+        .filter_map(ast::IdentPat::cast)
+        .filter_map(|it| it.name())
+        .map(|it| it.to_string())
+        .collect()
+}
+
+fn expr_names(expr: &ast::Expr) -> Vec<String> {
+    expr.syntax().descendants().filter_map(ast::NameRef::cast).map(|it| it.to_string()).collect()
+}
+
+fn fresh_name(name: &str, used_names: &FxHashSet<String>) -> String {
+    (1..).map(|i| format!("{}_{}", name, i)).find(|it| !used_names.contains(it)).unwrap()
+}
+
+fn rename_pat(pat: &ast::IdentPat, new_name: &str) -> Option<ast::Pat> {
+    let name = pat.name()?;
+    let range = name.syntax().text_range() - pat.syntax().text_range().start();
+    let mut text = pat.syntax().text().to_string();
+    text.replace_range(std::ops::Range::<usize>::from(range), new_name);
+    ast::Pat::parse(&text).ok()
 }
 
 #[cfg(test)]
@@ -140,15 +706,265 @@ fn main() {
     }
 
     #[test]
-    fn method_inlining_isnt_supported() {
-        check_assist_not_applicable(
+    fn method_call_autorefs_receiver() {
+        check_assist(
+            inline_function,
+            r#"
+struct Foo(u32);
+impl Foo {
+    fn add(&self, a: u32) -> u32 {
+        let x = self.0;
+        x + a
+    }
+}
+
+fn main() {
+    let foo = Foo(1);
+    let x = foo.add$0(2);
+}
+"#,
+            r#"
+struct Foo(u32);
+impl Foo {
+    fn add(&self, a: u32) -> u32 {
+        let x = self.0;
+        x + a
+    }
+}
+
+fn main() {
+    let foo = Foo(1);
+    let x = {
+        let this = &foo;
+        let a = 2;
+        let x = this.0;
+        x + a
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn method_call_on_reference_and_by_value() {
+        check_assist(
+            inline_function,
+            r#"
+#[derive(Clone, Copy)]
+struct Foo(u32);
+impl Foo {
+    fn get(mut self) -> u32 { self.0 += 1; self.0 }
+}
+
+fn main(foo: &Foo) {
+    let x = foo.get$0();
+}
+"#,
+            r#"
+#[derive(Clone, Copy)]
+struct Foo(u32);
+impl Foo {
+    fn get(mut self) -> u32 { self.0 += 1; self.0 }
+}
+
+fn main(foo: &Foo) {
+    let x = {
+        let mut this = *foo;
+        this.0 += 1;
+        this.0
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn mut_ref_method_call() {
+        check_assist(
+            inline_function,
+            r#"
+struct Foo(u32);
+impl Foo {
+    fn clear(&mut self) { self.0 = 0; }
+}
+
+fn main() {
+    let mut foo = Foo(1);
+    foo.clear$0();
+}
+"#,
+            r#"
+struct Foo(u32);
+impl Foo {
+    fn clear(&mut self) { self.0 = 0; }
+}
+
+fn main() {
+    let mut foo = Foo(1);
+    {
+        let this = &mut foo;
+        this.0 = 0;
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn ufcs_call_and_self_type() {
+        check_assist(
             inline_function,
-            r"
-struct Foo;
-impl Foo { fn bar(&self) {} }
+            r#"
+struct Foo(u32);
+impl Foo {
+    fn new(x: u32) -> Self { Self(x) }
+    fn with(&self, x: u32) -> Foo { Self::new(self.0 + x) }
+}
+
+fn main() {
+    let foo = Foo::new(1);
+    let bar = Foo::with$0(&foo, 2);
+}
+"#,
+            r#"
+struct Foo(u32);
+impl Foo {
+    fn new(x: u32) -> Self { Self(x) }
+    fn with(&self, x: u32) -> Foo { Self::new(self.0 + x) }
+}
 
-fn main() { Foo.bar$0(); }
-",
+fn main() {
+    let foo = Foo::new(1);
+    let bar = {
+        let this = &foo;
+        let x = 2;
+        Foo::new(this.0 + x)
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn associated_function_call() {
+        check_assist(
+            inline_function,
+            r#"
+struct Foo { x: u32 }
+impl Foo {
+    fn new(x: u32) -> Self { Self { x } }
+}
+
+fn main() {
+    let foo = Foo::new$0(1);
+}
+"#,
+            r#"
+struct Foo { x: u32 }
+impl Foo {
+    fn new(x: u32) -> Self { Self { x } }
+}
+
+fn main() {
+    let foo = {
+        let x = 1;
+        Foo { x }
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn renames_bindings_captured_by_later_arguments() {
+        cov_mark::check!(inline_function_renames_captured_binding);
+        check_assist(
+            inline_function,
+            r#"
+struct S { a: u32 }
+fn foo(a: u32, b: u32) -> S {
+    S { a: a - b }
+}
+fn bar(a: u32, b: u32) -> S {
+    let a = a + b;
+    S { a }
+}
+
+fn main() {
+    let (a, b) = (1, 2);
+    let x = bar$0(b, a);
+}
+"#,
+            r#"
+struct S { a: u32 }
+fn foo(a: u32, b: u32) -> S {
+    S { a: a - b }
+}
+fn bar(a: u32, b: u32) -> S {
+    let a = a + b;
+    S { a }
+}
+
+fn main() {
+    let (a, b) = (1, 2);
+    let x = {
+        let a_1 = b;
+        let b = a;
+        let a = a_1 + b;
+        S { a }
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn renames_shorthand_field_usage() {
+        check_assist(
+            inline_function,
+            r#"
+struct S { a: u32, b: u32 }
+fn foo(a: u32, b: u32) -> S {
+    S { a, b }
+}
+
+fn main() {
+    let (a, b) = (1, 2);
+    let x = foo$0(b, a);
+}
+"#,
+            r#"
+struct S { a: u32, b: u32 }
+fn foo(a: u32, b: u32) -> S {
+    S { a, b }
+}
+
+fn main() {
+    let (a, b) = (1, 2);
+    let x = {
+        let a_1 = b;
+        let b = a;
+        S { a: a_1, b }
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_early_return() {
+        cov_mark::check!(inline_function_with_early_return);
+        check_assist_not_applicable(
+            inline_function,
+            r#"
+fn foo(a: u32) -> u32 {
+    if a == 0 {
+        return 1;
+    }
+    a
+}
+fn main() { let x = foo$0(1); }
+"#,
         );
     }
 
@@ -195,6 +1011,265 @@ fn main() {
         x * y
     };
 }
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_removes_function() {
+        check_assist(
+            inline_into_callers,
+            r#"
+//- /lib.rs
+mod foo;
+struct Foo(u32);
+impl Foo {
+    fn $0get(&self) -> u32 {
+        self.0
+    }
+}
+fn main() {
+    let foo = Foo(1);
+    let x = foo.get();
+    let y = Foo::get(&foo);
+}
+//- /foo.rs
+fn bar(foo: &crate::Foo) -> u32 {
+    foo.get() + 1
+}
+"#,
+            r#"
+//- /lib.rs
+mod foo;
+struct Foo(u32);
+impl Foo {
+}
+fn main() {
+    let foo = Foo(1);
+    let x = {
+        let this = &foo;
+        this.0
+    };
+    let y = {
+        let this = &foo;
+        this.0
+    };
+}
+//- /foo.rs
+fn bar(foo: &crate::Foo) -> u32 {
+    ({
+        let this = foo;
+        this.0
+    }) + 1
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_not_applicable_with_remaining_usages() {
+        check_assist_not_applicable(
+            inline_into_callers,
+            r#"
+fn $0inc(a: u32) -> u32 { a + 1 }
+fn main() {
+    let f = inc;
+    let x = inc(1);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_not_applicable_with_nested_calls() {
+        cov_mark::check!(inline_into_callers_nested_call);
+        check_assist_not_applicable(
+            inline_into_callers,
+            r#"
+fn $0inc(a: u32) -> u32 { a + 1 }
+fn main() {
+    let x = inc(inc(1));
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_qualifies_items_from_other_modules() {
+        check_assist(
+            inline_into_callers,
+            r#"
+mod m {
+    pub(crate) struct S(pub(crate) u32);
+    pub(crate) fn helper(s: S) -> u32 { s.0 }
+    pub fn $0foo(a: u32) -> u32 { helper(S(a)) }
+    fn bar() {
+        let y = foo(1);
+    }
+}
+fn main() {
+    let x = m::foo(2);
+}
+"#,
+            r#"
+mod m {
+    pub(crate) struct S(pub(crate) u32);
+    pub(crate) fn helper(s: S) -> u32 { s.0 }
+    fn bar() {
+        let y = {
+            let a = 1;
+            helper(S(a))
+        };
+    }
+}
+fn main() {
+    let x = {
+        let a = 2;
+        m::helper(m::S(a))
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_qualifies_items_in_generic_args() {
+        check_assist(
+            inline_into_callers,
+            r#"
+mod m {
+    pub(crate) struct S;
+    pub(crate) fn size<T>() -> usize { 0 }
+    pub fn $0foo() -> usize { size::<S>() }
+}
+fn main() {
+    let x = m::foo();
+}
+"#,
+            r#"
+mod m {
+    pub(crate) struct S;
+    pub(crate) fn size<T>() -> usize { 0 }
+}
+fn main() {
+    let x = {
+        m::size::<m::S>()
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_not_applicable_with_private_items_in_generic_args() {
+        check_assist_not_applicable(
+            inline_into_callers,
+            r#"
+mod m {
+    struct S;
+    pub(crate) fn size<T>() -> usize { 0 }
+    pub fn $0foo() -> usize { size::<S>() }
+}
+fn main() {
+    let x = m::foo();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_not_applicable_with_private_items() {
+        check_assist_not_applicable(
+            inline_into_callers,
+            r#"
+mod m {
+    fn helper(a: u32) -> u32 { a }
+    pub fn $0foo(a: u32) -> u32 { helper(a) }
+    fn bar() -> u32 { foo(1) }
+}
+fn main() {
+    let x = m::foo(2);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_substitutes_self_in_generic_impl() {
+        check_assist(
+            inline_into_callers,
+            r#"
+struct Foo<T>(T);
+impl<T> Foo<T> {
+    fn $0new(x: T) -> Self {
+        let foo: Self = Self(x);
+        foo
+    }
+}
+fn main() {
+    let a = Foo::new(1u32);
+    let b: Foo<bool> = Foo::new(true);
+}
+"#,
+            r#"
+struct Foo<T>(T);
+impl<T> Foo<T> {
+}
+fn main() {
+    let a = {
+        let x = 1u32;
+        let foo: Foo<u32> = Foo::<u32>(x);
+        foo
+    };
+    let b: Foo<bool> = {
+        let x = true;
+        let foo: Foo<bool> = Foo::<bool>(x);
+        foo
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn method_call_in_generic_impl() {
+        check_assist(
+            inline_function,
+            r#"
+struct Foo<T>(T);
+impl<T: Copy> Foo<T> {
+    fn twice(&self) -> (Self, Self) { (Self(self.0), Self(self.0)) }
+}
+fn main() {
+    let foo = Foo(1u8);
+    let x = foo.twice$0();
+}
+"#,
+            r#"
+struct Foo<T>(T);
+impl<T: Copy> Foo<T> {
+    fn twice(&self) -> (Self, Self) { (Self(self.0), Self(self.0)) }
+}
+fn main() {
+    let foo = Foo(1u8);
+    let x = {
+        let this = &foo;
+        (Foo::<u8>(this.0), Foo::<u8>(this.0))
+    };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_into_callers_not_applicable_to_trait_methods() {
+        check_assist_not_applicable(
+            inline_into_callers,
+            r#"
+trait Tr { fn f(&self); }
+struct S;
+impl Tr for S { fn $0f(&self) {} }
+fn main() { S.f(); }
 "#,
         );
     }
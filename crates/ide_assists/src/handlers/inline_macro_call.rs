@@ -0,0 +1,926 @@
+use hir::MacroKind;
+use ide_db::helpers::mod_path_to_ast;
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::{
+    ast::{self, edit::IndentLevel, make, AstNode, NameOwner},
+    NodeOrToken, SyntaxElement, SyntaxKind,
+    SyntaxKind::*,
+    SyntaxNode, TextRange, T,
+};
+
+use crate::{AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: inline_macro_call
+//
+// Replaces a macro call with its recursive expansion. On a derive listed in `#[derive(..)]`, the
+// derive is replaced with the code it generates. Attribute macros are not supported, as name
+// resolution doesn't expand them yet.
+//
+// ```
+// macro_rules! num {
+//     (+$i:literal) => { $i };
+//     (-$i:literal) => { 0 - $i };
+// }
+//
+// fn f() -> i32 {
+//     2 * num$0!(-1)
+// }
+// ```
+// ->
+// ```
+// macro_rules! num {
+//     (+$i:literal) => { $i };
+//     (-$i:literal) => { 0 - $i };
+// }
+//
+// fn f() -> i32 {
+//     2 * (0 - 1)
+// }
+// ```
+pub(crate) fn inline_macro_call(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    match ctx.find_node_at_offset::<ast::MacroCall>() {
+        Some(macro_call) => inline_fn_like(acc, ctx, macro_call),
+        None => inline_derive(acc, ctx),
+    }
+}
+
+fn inline_fn_like(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+    macro_call: ast::MacroCall,
+) -> Option<()> {
+    let tt = macro_call.token_tree()?;
+    let header =
+        TextRange::new(macro_call.syntax().text_range().start(), tt.syntax().text_range().start());
+    if !header.contains_inclusive(ctx.offset()) {
+        return None;
+    }
+    let call_site = ctx.sema.scope(macro_call.syntax()).module()?;
+    let expansion = ctx.sema.expand(&macro_call)?;
+
+    let renames = hygienic_renames(ctx, &macro_call, &expansion);
+    let mut pieces = Vec::new();
+    flatten(ctx, call_site, &macro_call, &expansion, &renames, &mut pieces);
+    if pieces.is_empty() && expansion.kind() != MACRO_ITEMS && expansion.kind() != MACRO_STMTS {
+        return None;
+    }
+
+    let mut range = macro_call.syntax().text_range();
+    let semicolon = macro_call.semicolon_token().or_else(|| {
+        let stmt = macro_call.syntax().parent().and_then(ast::ExprStmt::cast)?;
+        stmt.semicolon_token()
+    });
+    let keep_semicolon = match expansion.kind() {
+        MACRO_ITEMS => false,
+        MACRO_STMTS => ast::MacroStmts::cast(expansion.clone())?.expr().is_some(),
+        _ => true,
+    };
+    if let Some(semicolon) = semicolon {
+        if keep_semicolon {
+            range = TextRange::new(range.start(), range.end().min(semicolon.text_range().start()));
+        } else {
+            range = range.cover(semicolon.text_range());
+        }
+    }
+
+    let mut text = format_pieces(&pieces);
+    if needs_parens(&macro_call, &expansion) {
+        text = format!("({})", text);
+    }
+    let text = reindent(&text, IndentLevel::from_node(macro_call.syntax()));
+
+    acc.add(
+        AssistId("inline_macro_call", AssistKind::RefactorInline),
+        "Inline macro",
+        macro_call.syntax().text_range(),
+        |builder| builder.replace(range, text),
+    )
+}
+
+fn inline_derive(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let ident = ctx.find_token_syntax_at_offset(IDENT)?;
+    let tt = ast::TokenTree::cast(ident.parent()?)?;
+    let attr = ast::Attr::cast(tt.syntax().parent()?)?;
+    if attr.simple_name()?.as_str() != "derive" {
+        return None;
+    }
+    let item = ast::Item::cast(attr.syntax().parent()?)?;
+
+    let mut derives = vec![Vec::new()];
+    for token in tt.syntax().children_with_tokens().filter_map(|it| it.into_token()) {
+        match token.kind() {
+            T![,] => derives.push(Vec::new()),
+            T!['('] | T![')'] | WHITESPACE => (),
+            _ => derives.last_mut()?.push(token),
+        }
+    }
+    derives.retain(|it| !it.is_empty());
+    let derive = derives.iter().find(|it| it.contains(&ident))?;
+    let (first, last) = (derive.first()?, derive.last()?);
+    let path_text = derive.iter().map(|it| it.text().to_string()).collect::<String>();
+    let path = make::path_from_text(&path_text);
+
+    // Builtin derives only expand to placeholder impls, use `replace_derive_with_manual_impl`.
+    if ctx.sema.resolve_derive_macro(&attr, &path)?.kind() != MacroKind::ProcMacro {
+        return None;
+    }
+    let expansion = ctx.sema.expand_derive_macro(&attr, &path)?;
+    let call_site = ctx.sema.scope(item.syntax()).module()?;
+    let mut pieces = Vec::new();
+    flatten_expansion(ctx, call_site, None, expansion.into(), &FxHashMap::default(), &mut pieces);
+    let indent = IndentLevel::from_node(item.syntax());
+    let text = reindent(&format_pieces(&pieces), indent);
+
+    let derive_range = TextRange::new(first.text_range().start(), last.text_range().end());
+    let removed_range = if derives.len() == 1 {
+        // Removing the last derive removes the attribute.
+        let end = match attr.syntax().next_sibling_or_token() {
+            Some(ws) if ws.kind() == WHITESPACE => ws.text_range().end(),
+            _ => attr.syntax().text_range().end(),
+        };
+        TextRange::new(attr.syntax().text_range().start(), end)
+    } else {
+        let next = last.next_token();
+        let separator_end =
+            next.filter(|it| it.kind() == T![,]).map(|comma| match comma.next_token() {
+                Some(ws) if ws.kind() == WHITESPACE => ws.text_range().end(),
+                _ => comma.text_range().end(),
+            });
+        match separator_end {
+            Some(end) => TextRange::new(derive_range.start(), end),
+            None => {
+                // The last derive takes the preceding comma along.
+                let comma = first
+                    .prev_token()
+                    .filter(|it| it.kind() == WHITESPACE)
+                    .and_then(|it| it.prev_token())
+                    .or_else(|| first.prev_token())
+                    .filter(|it| it.kind() == T![,])?;
+                TextRange::new(comma.text_range().start(), derive_range.end())
+            }
+        }
+    };
+
+    acc.add(
+        AssistId("inline_macro_call", AssistKind::RefactorInline),
+        format!("Inline derive `{}`", path_text),
+        derive_range,
+        |builder| {
+            builder.delete(removed_range);
+            builder.insert(item.syntax().text_range().end(), format!("\n\n{}{}", indent, text));
+        },
+    )
+}
+
+/// A token of the expansion, along with the context the formatting depends on.
+struct Piece {
+    kind: SyntaxKind,
+    text: String,
+    parent: SyntaxKind,
+    first_in_parent: bool,
+}
+
+/// Collects the tokens of the expansion of `macro_call`, replacing nested macro calls with their
+/// expansions.
+fn flatten(
+    ctx: &AssistContext,
+    call_site: hir::Module,
+    macro_call: &ast::MacroCall,
+    expansion: &SyntaxNode,
+    renames: &FxHashMap<TextRange, String>,
+    out: &mut Vec<Piece>,
+) {
+    let krate = crate_path(ctx, call_site, macro_call);
+    flatten_expansion(ctx, call_site, krate.as_deref(), expansion.clone().into(), renames, out)
+}
+
+fn flatten_expansion(
+    ctx: &AssistContext,
+    call_site: hir::Module,
+    krate: Option<&str>,
+    expansion: SyntaxElement,
+    renames: &FxHashMap<TextRange, String>,
+    out: &mut Vec<Piece>,
+) {
+    let node = match expansion {
+        NodeOrToken::Node(it) => it,
+        NodeOrToken::Token(token) => {
+            if token.kind().is_trivia() {
+                return;
+            }
+            let text = match renames.get(&token.text_range()) {
+                Some(it) => it.clone(),
+                None if token.text() == "$crate" => krate.unwrap_or("crate").to_string(),
+                None => token.text().to_string(),
+            };
+            out.push(Piece {
+                kind: token.kind(),
+                text,
+                parent: token.parent().map_or(ERROR, |it| it.kind()),
+                first_in_parent: token.prev_sibling_or_token().is_none(),
+            });
+            return;
+        }
+    };
+
+    let nested =
+        ast::MacroCall::cast(node.clone()).and_then(|call| Some((ctx.sema.expand(&call)?, call)));
+    match nested {
+        Some((nested_expansion, nested)) => {
+            let parens = needs_parens(&nested, &nested_expansion);
+            if parens {
+                out.push(Piece::synthetic(T!['('], "(", PAREN_EXPR, true));
+            }
+            flatten(ctx, call_site, &nested, &nested_expansion, &FxHashMap::default(), out);
+            if parens {
+                out.push(Piece::synthetic(T![')'], ")", PAREN_EXPR, false));
+            }
+        }
+        None => {
+            for child in node.children_with_tokens() {
+                flatten_expansion(ctx, call_site, krate, child, renames, out);
+            }
+        }
+    }
+}
+
+impl Piece {
+    fn synthetic(kind: SyntaxKind, text: &str, parent: SyntaxKind, first_in_parent: bool) -> Piece {
+        Piece { kind, text: text.to_string(), parent, first_in_parent }
+    }
+}
+
+/// The path `$crate` stands for when used in the module the expansion is inlined into.
+fn crate_path(
+    ctx: &AssistContext,
+    call_site: hir::Module,
+    macro_call: &ast::MacroCall,
+) -> Option<String> {
+    let def_crate = ctx.sema.resolve_macro_call(macro_call)?.module(ctx.db())?.krate();
+    if def_crate == call_site.krate() {
+        return Some("crate".to_string());
+    }
+    let root = hir::ModuleDef::Module(def_crate.root_module(ctx.db()));
+    let path = call_site.find_use_path(ctx.db(), root)?;
+    Some(mod_path_to_ast(&path).to_string())
+}
+
+/// Renames the bindings introduced by the macro definition itself which would capture or shadow
+/// names used at the call site once the macro hygiene is gone.
+fn hygienic_renames(
+    ctx: &AssistContext,
+    macro_call: &ast::MacroCall,
+    expansion: &SyntaxNode,
+) -> FxHashMap<TextRange, String> {
+    let mut renames = FxHashMap::default();
+    let tt_range = match macro_call.token_tree() {
+        Some(it) => it.syntax().text_range(),
+        None => return renames,
+    };
+    let from_call_site = |node: &SyntaxNode| {
+        let range = ctx.sema.original_range(node);
+        range.file_id == ctx.frange.file_id
+            && tt_range.contains_range(range.range)
+            && tt_range != range.range
+    };
+
+    let def_site_bindings = expansion
+        .descendants()
+        .filter_map(ast::IdentPat::cast)
+        .filter_map(|it| it.name())
+        .filter(|it| !from_call_site(it.syntax()))
+        .map(|it| it.text().to_string())
+        .collect::<FxHashSet<_>>();
+    if def_site_bindings.is_empty() {
+        return renames;
+    }
+
+    let mut taken = macro_call
+        .token_tree()
+        .into_iter()
+        .flat_map(|tt| tt.syntax().descendants_with_tokens())
+        .filter_map(|it| it.into_token())
+        .filter(|it| it.kind() == IDENT)
+        .map(|it| it.text().to_string())
+        .collect::<FxHashSet<_>>();
+    ctx.sema.scope(macro_call.syntax()).process_all_names(&mut |name, def| {
+        if let hir::ScopeDef::Local(_) = def {
+            taken.insert(name.to_string());
+        }
+    });
+
+    let mut new_names = FxHashMap::default();
+    let mut clashing =
+        def_site_bindings.iter().filter(|it| taken.contains(*it)).cloned().collect::<Vec<_>>();
+    clashing.sort();
+    for name in clashing {
+        let new_name = (1..)
+            .map(|i| format!("{}_{}", name, i))
+            .find(|it| !taken.contains(it) && !def_site_bindings.contains(it))
+            .unwrap();
+        taken.insert(new_name.clone());
+        new_names.insert(name.clone(), new_name);
+    }
+    if new_names.is_empty() {
+        return renames;
+    }
+
+    for token in expansion.descendants_with_tokens().filter_map(|it| it.into_token()) {
+        if token.kind() != IDENT {
+            continue;
+        }
+        let new_name = match new_names.get(token.text()) {
+            Some(it) => it,
+            None => continue,
+        };
+        match token.parent() {
+            Some(parent) if !from_call_site(&parent) => {
+                renames.insert(token.text_range(), new_name.clone());
+            }
+            _ => (),
+        }
+    }
+    renames
+}
+
+/// Whether an expression expansion binds weaker than its surroundings.
+fn needs_parens(macro_call: &ast::MacroCall, expansion: &SyntaxNode) -> bool {
+    if ast::Expr::cast(expansion.clone()).is_none() {
+        return false;
+    }
+    let atomic = matches!(
+        expansion.kind(),
+        LITERAL
+            | PATH_EXPR
+            | CALL_EXPR
+            | METHOD_CALL_EXPR
+            | PAREN_EXPR
+            | BLOCK_EXPR
+            | TUPLE_EXPR
+            | ARRAY_EXPR
+            | MACRO_CALL
+            | FIELD_EXPR
+            | INDEX_EXPR
+            | RECORD_EXPR
+            | TRY_EXPR
+    );
+    if atomic {
+        return false;
+    }
+    let parent = match macro_call.syntax().parent() {
+        Some(it) => it.kind(),
+        None => return false,
+    };
+    !matches!(
+        parent,
+        EXPR_STMT
+            | LET_STMT
+            | BLOCK_EXPR
+            | ARG_LIST
+            | PAREN_EXPR
+            | MACRO_STMTS
+            | RETURN_EXPR
+            | MATCH_ARM
+            | RECORD_EXPR_FIELD
+            | ARRAY_EXPR
+            | TUPLE_EXPR
+            | CONDITION
+    )
+}
+
+/// Renders the tokens with conventional spacing and line breaks, indenting with four spaces.
+fn format_pieces(pieces: &[Piece]) -> String {
+    let mut res = String::new();
+    let mut indent = 0usize;
+    let newline = |res: &mut String, indent: usize| {
+        let trimmed = res.trim_end_matches(' ').len();
+        res.truncate(trimmed);
+        res.push('\n');
+        res.push_str(&"    ".repeat(indent));
+    };
+
+    for (i, piece) in pieces.iter().enumerate() {
+        let next = match pieces.get(i + 1) {
+            Some(it) => it,
+            None => {
+                res.push_str(&piece.text);
+                break;
+            }
+        };
+        if next.kind == T!['}'] && !is_inline_braces(next.parent) && piece.kind != T!['{'] {
+            res.push_str(&piece.text);
+            indent = indent.saturating_sub(1);
+            newline(&mut res, indent);
+            continue;
+        }
+        res.push_str(&piece.text);
+        if is_spaced_op(next) {
+            res.push(' ');
+            continue;
+        }
+
+        match piece.kind {
+            T!['{'] if is_inline_braces(piece.parent) => {
+                if next.kind != T!['}'] && piece.parent != USE_TREE_LIST {
+                    res.push(' ');
+                }
+            }
+            T!['{'] => {
+                if next.kind != T!['}'] {
+                    indent += 1;
+                    newline(&mut res, indent);
+                }
+            }
+            T!['}'] if !is_inline_braces(piece.parent) => {
+                if next.kind == T![else] {
+                    res.push(' ');
+                } else if !matches!(
+                    next.kind,
+                    T![;] | T![,] | T![')'] | T![']'] | T![.] | T![?] | T!['}']
+                ) {
+                    newline(&mut res, indent);
+                }
+            }
+            T![;] if matches!(piece.parent, ARRAY_EXPR | ARRAY_TYPE) => res.push(' '),
+            T![;] => newline(&mut res, indent),
+            T![,] => {
+                if matches!(
+                    piece.parent,
+                    MATCH_ARM | MATCH_ARM_LIST | RECORD_FIELD_LIST | VARIANT_LIST
+                ) {
+                    newline(&mut res, indent);
+                } else if !matches!(next.kind, T![')'] | T![']'] | T![>] | T![|] | T!['}']) {
+                    res.push(' ');
+                }
+            }
+            T![']'] if piece.parent == ATTR => newline(&mut res, indent),
+            T![:] => res.push(' '),
+            T![|] if piece.parent == PARAM_LIST && !piece.first_in_parent => res.push(' '),
+            T![||] if piece.parent == PARAM_LIST => res.push(' '),
+            _ if is_spaced_op(piece) => res.push(' '),
+            _ => {
+                let space = if next.kind == T!['}'] {
+                    next.parent != USE_TREE_LIST
+                } else if next.kind == T!['{'] {
+                    !matches!(piece.kind, T!['('] | T!['['] | T![::] | T![!] | T![<])
+                } else if is_word(piece.kind) {
+                    is_word(next.kind) || (is_keyword_with_operand(piece) && !is_closing(next.kind))
+                } else {
+                    matches!(piece.kind, T![>] | T![')'] | T![']']) && is_word(next.kind)
+                };
+                if space {
+                    res.push(' ');
+                }
+            }
+        }
+    }
+    res.trim().to_string()
+}
+
+fn is_word(kind: SyntaxKind) -> bool {
+    kind.is_keyword() || kind.is_literal() || kind == IDENT || kind == LIFETIME_IDENT
+}
+
+/// Keywords like `return` or `in` that are followed by an expression, which may start with
+/// punctuation.
+fn is_keyword_with_operand(piece: &Piece) -> bool {
+    piece.kind.is_keyword()
+        && !matches!(
+            piece.kind,
+            T![self] | T![super] | T![crate] | T![fn] | T![pub] | T![true] | T![false]
+        )
+}
+
+fn is_closing(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        T![;]
+            | T![,]
+            | T![.]
+            | T![')']
+            | T![']']
+            | T!['}']
+            | T![?]
+            | T![::]
+            | T![:]
+            | T![<]
+            | T![>]
+    )
+}
+
+fn is_inline_braces(parent: SyntaxKind) -> bool {
+    matches!(parent, USE_TREE_LIST | RECORD_EXPR_FIELD_LIST | RECORD_PAT_FIELD_LIST | TOKEN_TREE)
+}
+
+fn is_spaced_op(piece: &Piece) -> bool {
+    match piece.kind {
+        T![=] | T![=>] | T![->] => true,
+        kind if is_word(kind) => false,
+        T![+] => matches!(piece.parent, BIN_EXPR | TYPE_BOUND_LIST),
+        T![|] => matches!(piece.parent, BIN_EXPR | OR_PAT),
+        _ => piece.parent == BIN_EXPR,
+    }
+}
+
+fn reindent(text: &str, indent: IndentLevel) -> String {
+    let mut lines = text.lines();
+    let mut res = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        res.push('\n');
+        if !line.is_empty() {
+            res.push_str(&format!("{}{}", indent, line));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ide_db::base_db::{Env, ProcMacro, ProcMacroExpander, ProcMacroKind};
+    use tt::{ExpansionError, Ident, Leaf, Subtree, TokenId, TokenTree};
+
+    use crate::tests::{check_assist, check_assist_not_applicable, check_assist_with_proc_macros};
+
+    use super::*;
+
+    #[test]
+    fn inline_expression_macro() {
+        check_assist(
+            inline_macro_call,
+            r#"
+macro_rules! add {
+    ($a:expr, $b:expr) => { $a + $b };
+}
+
+fn f() -> i32 {
+    let x = add$0!(1, 2);
+    x * add!(3, 4)
+}
+"#,
+            r#"
+macro_rules! add {
+    ($a:expr, $b:expr) => { $a + $b };
+}
+
+fn f() -> i32 {
+    let x = 1 + 2;
+    x * add!(3, 4)
+}
+"#,
+        );
+        check_assist(
+            inline_macro_call,
+            r#"
+macro_rules! add {
+    ($a:expr, $b:expr) => { $a + $b };
+}
+
+fn f(x: i32) -> i32 {
+    x * add$0!(3, 4)
+}
+"#,
+            r#"
+macro_rules! add {
+    ($a:expr, $b:expr) => { $a + $b };
+}
+
+fn f(x: i32) -> i32 {
+    x * (3 + 4)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_items_recursively() {
+        check_assist(
+            inline_macro_call,
+            r#"
+macro_rules! getter {
+    ($name:ident: $ty:ty) => {
+        fn $name(&self) -> $ty { self.$name }
+    };
+}
+macro_rules! getters {
+    ($($name:ident: $ty:ty),*) => {
+        impl Point { $(getter!($name: $ty);)* }
+    };
+}
+
+struct Point { x: i32, y: i32 }
+
+getters$0!(x: i32, y: i32);
+"#,
+            r#"
+macro_rules! getter {
+    ($name:ident: $ty:ty) => {
+        fn $name(&self) -> $ty { self.$name }
+    };
+}
+macro_rules! getters {
+    ($($name:ident: $ty:ty),*) => {
+        impl Point { $(getter!($name: $ty);)* }
+    };
+}
+
+struct Point { x: i32, y: i32 }
+
+impl Point {
+    fn x(&self) -> i32 {
+        self.x
+    }
+    fn y(&self) -> i32 {
+        self.y
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn format_closures_and_match() {
+        check_assist(
+            inline_macro_call,
+            r#"
+macro_rules! sum_some {
+    ($v:expr) => {
+        match $v.iter().map(|it| *it).sum::<u32>() {
+            0 => None,
+            n => Some(n),
+        }
+    };
+}
+
+fn f(v: Vec<u32>) -> Option<u32> {
+    sum_some$0!(v)
+}
+"#,
+            r#"
+macro_rules! sum_some {
+    ($v:expr) => {
+        match $v.iter().map(|it| *it).sum::<u32>() {
+            0 => None,
+            n => Some(n),
+        }
+    };
+}
+
+fn f(v: Vec<u32>) -> Option<u32> {
+    match v.iter().map(|it| *it).sum::<u32>() {
+        0 => None,
+        n => Some(n),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_statements() {
+        check_assist(
+            inline_macro_call,
+            r#"
+macro_rules! swap {
+    ($a:ident, $b:ident) => {
+        let tmp = $a;
+        $a = $b;
+        $b = tmp;
+    };
+}
+
+fn f() {
+    let mut a = 1;
+    let mut b = 2;
+    if a < b {
+        swap$0!(a, b);
+    }
+}
+"#,
+            r#"
+macro_rules! swap {
+    ($a:ident, $b:ident) => {
+        let tmp = $a;
+        $a = $b;
+        $b = tmp;
+    };
+}
+
+fn f() {
+    let mut a = 1;
+    let mut b = 2;
+    if a < b {
+        let tmp = a;
+        a = b;
+        b = tmp;
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn rename_hygienic_bindings() {
+        check_assist(
+            inline_macro_call,
+            r#"
+macro_rules! swap {
+    ($a:ident, $b:ident) => {
+        let tmp = $a;
+        $a = $b;
+        $b = tmp;
+    };
+}
+
+fn f() {
+    let mut tmp = 1;
+    let mut b = 2;
+    swap$0!(tmp, b);
+}
+"#,
+            r#"
+macro_rules! swap {
+    ($a:ident, $b:ident) => {
+        let tmp = $a;
+        $a = $b;
+        $b = tmp;
+    };
+}
+
+fn f() {
+    let mut tmp = 1;
+    let mut b = 2;
+    let tmp_1 = tmp;
+    tmp = b;
+    b = tmp_1;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn rewrite_dollar_crate() {
+        check_assist(
+            inline_macro_call,
+            r#"
+//- /main.rs crate:main deps:util
+fn f() -> u32 {
+    util::double$0!(2)
+}
+//- /util.rs crate:util
+pub fn double(x: u32) -> u32 { x * 2 }
+#[macro_export]
+macro_rules! double {
+    ($e:expr) => { $crate::double($e) };
+}
+"#,
+            r#"
+fn f() -> u32 {
+    util::double(2)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_inside_arguments() {
+        check_assist_not_applicable(
+            inline_macro_call,
+            r#"
+macro_rules! id {
+    ($e:expr) => { $e };
+}
+
+fn f() -> i32 {
+    id!(1 $0+ 2)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_builtin_derive() {
+        check_assist_not_applicable(
+            inline_macro_call,
+            r#"
+//- /main.rs crate:main deps:core
+#[derive(Cl$0one)]
+struct S;
+//- /core.rs crate:core
+#[rustc_builtin_macro]
+pub macro Clone {}
+"#,
+        );
+    }
+
+    /// Derives `impl Marked for $name {}` for the annotated struct.
+    #[derive(Debug)]
+    struct MarkerExpander;
+
+    impl ProcMacroExpander for MarkerExpander {
+        fn expand(
+            &self,
+            subtree: &Subtree,
+            _: Option<&Subtree>,
+            _: &Env,
+        ) -> Result<Subtree, ExpansionError> {
+            let ident = |text: &str| {
+                TokenTree::Leaf(Leaf::Ident(Ident {
+                    text: text.into(),
+                    id: TokenId::unspecified(),
+                }))
+            };
+            let name = subtree
+                .token_trees
+                .iter()
+                .skip_while(|it| it.to_string() != "struct")
+                .nth(1)
+                .ok_or_else(|| ExpansionError::Unknown("expected a struct".to_string()))?;
+            let token_trees = vec![
+                ident("impl"),
+                ident("Marked"),
+                ident("for"),
+                name.clone(),
+                TokenTree::Subtree(Subtree {
+                    delimiter: Some(tt::Delimiter {
+                        id: TokenId::unspecified(),
+                        kind: tt::DelimiterKind::Brace,
+                    }),
+                    token_trees: Vec::new(),
+                }),
+            ];
+            Ok(Subtree { delimiter: None, token_trees })
+        }
+    }
+
+    fn marker_proc_macro() -> Vec<ProcMacro> {
+        vec![ProcMacro {
+            name: "Marker".into(),
+            kind: ProcMacroKind::CustomDerive,
+            expander: Arc::new(MarkerExpander),
+        }]
+    }
+
+    #[test]
+    fn inline_proc_macro_derive() {
+        check_assist_with_proc_macros(
+            inline_macro_call,
+            marker_proc_macro(),
+            r#"
+//- /main.rs crate:main deps:marker
+use marker::Marker;
+
+trait Marked {}
+
+#[derive(Mark$0er)]
+struct Foo;
+//- /marker.rs crate:marker
+#[proc_macro_derive(Marker)]
+pub fn marker(input: TokenStream) -> TokenStream {
+    input
+}
+"#,
+            r#"
+use marker::Marker;
+
+trait Marked {}
+
+struct Foo;
+
+impl Marked for Foo {}
+"#,
+        );
+    }
+
+    #[test]
+    fn inline_one_of_several_derives() {
+        check_assist_with_proc_macros(
+            inline_macro_call,
+            marker_proc_macro(),
+            r#"
+//- /main.rs crate:main deps:marker,core
+trait Marked {}
+
+#[derive(Clone, marker::Mark$0er)]
+struct Foo;
+//- /marker.rs crate:marker
+#[proc_macro_derive(Marker)]
+pub fn marker(input: TokenStream) -> TokenStream {
+    input
+}
+//- /core.rs crate:core
+#[rustc_builtin_macro]
+pub macro Clone {}
+"#,
+            r#"
+trait Marked {}
+
+#[derive(Clone)]
+struct Foo;
+
+impl Marked for Foo {}
+"#,
+        );
+    }
+}
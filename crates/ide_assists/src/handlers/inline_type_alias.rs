@@ -0,0 +1,539 @@
+use hir::{AsAssocItem, HasSource, PathResolution};
+use ide_db::{base_db::FileId, defs::Definition, helpers::mod_path_to_ast};
+use rustc_hash::FxHashMap;
+use syntax::{
+    algo::find_node_at_range,
+    ast::{self, GenericParamsOwner, NameOwner},
+    AstNode, NodeOrToken, SyntaxNode, TextRange,
+};
+
+use crate::{
+    assist_context::{AssistContext, Assists},
+    utils::{range_with_whitespace, range_without_generic_args},
+    AssistId, AssistKind,
+};
+
+// Assist: inline_type_alias
+//
+// Replaces a type alias with its definition, substituting its generic
+// parameters.
+//
+// ```
+// type A<T = u32> = Vec<T>;
+// fn main() {
+//     let a: $0A;
+// }
+// ```
+// ->
+// ```
+// type A<T = u32> = Vec<T>;
+// fn main() {
+//     let a: Vec<u32>;
+// }
+// ```
+pub(crate) fn inline_type_alias(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let name_ref: ast::NameRef = ctx.find_node_at_offset()?;
+    let path_type = name_ref.syntax().ancestors().find_map(ast::PathType::cast)?;
+    let path = path_type.path()?;
+    if path.segment()?.name_ref()? != name_ref {
+        return None;
+    }
+    let alias = match ctx.sema.resolve_path(&path)? {
+        PathResolution::Def(hir::ModuleDef::TypeAlias(it)) => it,
+        _ => return None,
+    };
+    let alias = TypeAlias::new(ctx, alias)?;
+    let replacement = alias.inline(ctx, &path_type)?;
+
+    acc.add(
+        AssistId("inline_type_alias", AssistKind::RefactorInline),
+        format!("Inline type alias `{}`", name_ref),
+        path_type.syntax().text_range(),
+        |builder| builder.replace(path_type.syntax().text_range(), replacement),
+    )
+}
+
+// Assist: inline_type_alias_uses
+//
+// Inlines a type alias into all of its uses, removing the alias when every use
+// could be inlined. Uses from where the items in the alias aren't visible are
+// kept.
+//
+// ```
+// type $0A = i32;
+// fn id(x: A) -> A {
+//     x
+// }
+// ```
+// ->
+// ```
+// fn id(x: i32) -> i32 {
+//     x
+// }
+// ```
+pub(crate) fn inline_type_alias_uses(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let name: ast::Name = ctx.find_node_at_offset()?;
+    let alias_node = name.syntax().parent().and_then(ast::TypeAlias::cast)?;
+    let alias_def = ctx.sema.to_def(&alias_node)?;
+    let alias = TypeAlias::new(ctx, alias_def)?;
+    let usages = Definition::ModuleDef(alias_def.into()).usages(&ctx.sema).all();
+    if usages.is_empty() {
+        return None;
+    }
+
+    acc.add(
+        AssistId("inline_type_alias_uses", AssistKind::RefactorInline),
+        format!("Inline type alias `{}` into all uses", name),
+        name.syntax().text_range(),
+        |builder| {
+            let mut all_inlined = true;
+            for (file_id, references) in usages {
+                let file = ctx.sema.parse(file_id);
+                let path_types: Vec<Option<ast::PathType>> = references
+                    .iter()
+                    .map(|reference| {
+                        let name_ref =
+                            find_node_at_range::<ast::NameRef>(file.syntax(), reference.range)?;
+                        let path =
+                            ast::PathSegment::cast(name_ref.syntax().parent()?)?.parent_path();
+                        ast::PathType::cast(path.syntax().parent()?)
+                    })
+                    .collect();
+                builder.edit_file(file_id);
+                for path_type in &path_types {
+                    let path_type = match path_type {
+                        Some(it) => it,
+                        None => {
+                            all_inlined = false;
+                            continue;
+                        }
+                    };
+                    let range = path_type.syntax().text_range();
+                    // Uses in the generic arguments of other uses are kept, the
+                    // arguments of the outer use are copied unchanged.
+                    let nested = path_types.iter().flatten().any(|other| {
+                        let other = other.syntax().text_range();
+                        other != range && other.contains_range(range)
+                    });
+                    if nested {
+                        cov_mark::hit!(inline_type_alias_uses_skips_nested_use);
+                        all_inlined = false;
+                        continue;
+                    }
+                    match alias.inline(ctx, path_type) {
+                        Some(replacement) => builder.replace(range, replacement),
+                        None => all_inlined = false,
+                    }
+                }
+            }
+            if all_inlined {
+                builder.edit_file(alias.file_id);
+                builder.delete(range_with_whitespace(alias_node.syntax()));
+            }
+        },
+    )
+}
+
+struct TypeAlias {
+    file_id: FileId,
+    params: Vec<ast::GenericParam>,
+    ty: ast::Type,
+    /// Paths in the alias referring to items, which have to be reachable from the use site.
+    item_paths: Vec<(ast::Path, hir::ModuleDef)>,
+}
+
+impl TypeAlias {
+    fn new(ctx: &AssistContext, alias: hir::TypeAlias) -> Option<TypeAlias> {
+        let db = ctx.db();
+        // Associated types depend on the implementing type.
+        if alias.as_assoc_item(db).is_some() {
+            return None;
+        }
+        let source = alias.source(db)?;
+        if source.file_id.call_node(db).is_some() {
+            return None;
+        }
+        let file_id = source.file_id.original_file(db);
+        // Re-find the alias in the tree known to `sema`, so that its paths resolve.
+        let alias: ast::TypeAlias = find_node_at_range(
+            ctx.sema.parse(file_id).syntax(),
+            source.value.syntax().text_range(),
+        )?;
+        let params =
+            alias.generic_param_list().map_or(Vec::new(), |it| it.generic_params().collect());
+
+        let mut item_paths: Vec<(ast::Path, hir::ModuleDef)> = Vec::new();
+        for path in alias.syntax().descendants().filter_map(ast::Path::cast) {
+            let range = path.syntax().text_range();
+            // Paths in generic arguments are checked on their own.
+            if item_paths.iter().any(|(it, _)| range_without_generic_args(it).contains_range(range))
+            {
+                continue;
+            }
+            match ctx.sema.resolve_path(&path) {
+                Some(PathResolution::Def(hir::ModuleDef::BuiltinType(_))) => (),
+                Some(PathResolution::Def(def)) => item_paths.push((path, def)),
+                _ => (),
+            }
+        }
+
+        Some(TypeAlias { file_id, params, ty: alias.ty()?, item_paths })
+    }
+
+    /// Returns the definition of the alias with its generic parameters replaced
+    /// by the arguments of `path_type`, or by their defaults, and its paths
+    /// rewritten for the module of `path_type`.
+    fn inline(&self, ctx: &AssistContext, path_type: &ast::PathType) -> Option<String> {
+        let db = ctx.db();
+        let scope = ctx.sema.scope(path_type.syntax());
+        let module = scope.module()?;
+        let mut path_replacements = Vec::new();
+        for (path, def) in &self.item_paths {
+            let mod_path = module.find_use_path(db, *def)?;
+            if scope.speculative_resolve(path) == Some(PathResolution::Def(*def)) {
+                continue;
+            }
+            // Generic arguments are substituted separately.
+            let range = range_without_generic_args(path);
+            path_replacements.push((range, mod_path_to_ast(&mod_path).to_string()));
+        }
+
+        let mut lifetime_args = Vec::new();
+        let mut args = Vec::new();
+        let arg_list = path_type.path()?.segment()?.generic_arg_list();
+        for arg in arg_list.iter().flat_map(|it| it.generic_args()) {
+            match arg {
+                ast::GenericArg::LifetimeArg(it) => lifetime_args.push(it.lifetime()?.to_string()),
+                ast::GenericArg::TypeArg(it) => args.push(it.ty()?.to_string()),
+                ast::GenericArg::ConstArg(it) => args.push(it.expr()?.to_string()),
+                ast::GenericArg::AssocTypeArg(_) => return None,
+            }
+        }
+
+        let lifetime_count = self
+            .params
+            .iter()
+            .filter(|it| matches!(it, ast::GenericParam::LifetimeParam(_)))
+            .count();
+        if !lifetime_args.is_empty() && lifetime_args.len() != lifetime_count
+            || args.len() > self.params.len() - lifetime_count
+        {
+            return None;
+        }
+
+        let mut substs = FxHashMap::default();
+        let mut lifetime_args = lifetime_args.into_iter();
+        let mut args = args.into_iter();
+        for param in &self.params {
+            match param {
+                ast::GenericParam::LifetimeParam(it) => {
+                    // Elided lifetimes stay elided.
+                    let arg = lifetime_args.next().unwrap_or_else(|| "'_".to_string());
+                    substs.insert(it.lifetime()?.to_string(), arg);
+                }
+                ast::GenericParam::TypeParam(it) => {
+                    let arg = match args.next() {
+                        Some(arg) => arg,
+                        None => {
+                            substitute(it.default_type()?.syntax(), &substs, &path_replacements)?
+                        }
+                    };
+                    substs.insert(it.name()?.to_string(), arg);
+                }
+                ast::GenericParam::ConstParam(it) => {
+                    let arg = match args.next() {
+                        Some(arg) => arg,
+                        None => {
+                            // The parser wraps const defaults into a `CONST_ARG`.
+                            let default = it.syntax().children().find_map(ast::ConstArg::cast)?;
+                            substitute(default.expr()?.syntax(), &substs, &path_replacements)?
+                        }
+                    };
+                    substs.insert(it.name()?.to_string(), arg);
+                }
+            }
+        }
+        substitute(self.ty.syntax(), &substs, &path_replacements)
+    }
+}
+
+/// Renders `node` with the lifetimes and the type and const parameters named in
+/// `substs` replaced, as well as the ranges in `path_replacements`. Fails if a
+/// parameter is used as a path qualifier, as in `T::Assoc`, because the argument
+/// can't be spliced in there.
+fn substitute(
+    node: &SyntaxNode,
+    substs: &FxHashMap<String, String>,
+    path_replacements: &[(TextRange, String)],
+) -> Option<String> {
+    let mut replacements: Vec<(TextRange, &String)> = path_replacements
+        .iter()
+        .filter(|(range, _)| node.text_range().contains_range(*range))
+        .map(|(range, text)| (*range, text))
+        .collect();
+    for element in node.descendants_with_tokens() {
+        let node = match element {
+            NodeOrToken::Node(it) => it,
+            NodeOrToken::Token(_) => continue,
+        };
+        let subst = if let Some(lifetime) = ast::Lifetime::cast(node.clone()) {
+            substs.get(&lifetime.to_string())
+        } else if let Some(path) = ast::Path::cast(node.clone()) {
+            match param_name(&path).and_then(|it| substs.get(&it)) {
+                Some(_) if ast::Path::can_cast(node.parent()?.kind()) => return None,
+                subst => subst,
+            }
+        } else {
+            None
+        };
+        if let Some(subst) = subst {
+            replacements.push((node.text_range(), subst));
+        }
+    }
+
+    replacements.sort_by_key(|(range, _)| range.start());
+    let mut text = node.to_string();
+    let offset = node.text_range().start();
+    for (range, subst) in replacements.into_iter().rev() {
+        let range = range - offset;
+        text.replace_range(usize::from(range.start())..usize::from(range.end()), subst);
+    }
+    Some(text)
+}
+
+fn param_name(path: &ast::Path) -> Option<String> {
+    if path.qualifier().is_some() || path.coloncolon_token().is_some() {
+        return None;
+    }
+    let segment = path.segment()?;
+    if segment.generic_arg_list().is_some() {
+        return None;
+    }
+    Some(segment.name_ref()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn inlines_simple_alias() {
+        check_assist(
+            inline_type_alias,
+            r#"
+type A = (u8, u16);
+fn f(a: $0A) {}
+"#,
+            r#"
+type A = (u8, u16);
+fn f(a: (u8, u16)) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn substitutes_generic_arguments() {
+        check_assist(
+            inline_type_alias,
+            r#"
+struct Array<T, const N: usize>([T; N]);
+type A<'a, T, const N: usize> = &'a mut Array<T, N>;
+fn f<'b>(a: $0A<'b, Vec<u8>, 3>) {}
+"#,
+            r#"
+struct Array<T, const N: usize>([T; N]);
+type A<'a, T, const N: usize> = &'a mut Array<T, N>;
+fn f<'b>(a: &'b mut Array<Vec<u8>, 3>) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn uses_defaults_and_elided_lifetimes() {
+        check_assist(
+            inline_type_alias,
+            r#"
+type A<'a, T = u32, U = Option<T>, const N: usize = 4> = &'a [(T, U); N];
+fn f(a: $0A<i8>) {}
+"#,
+            r#"
+type A<'a, T = u32, U = Option<T>, const N: usize = 4> = &'a [(T, U); N];
+fn f(a: &'_ [(i8, Option<i8>); 4]) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn keeps_shadowing_paths_alone() {
+        check_assist(
+            inline_type_alias,
+            r#"
+mod m { pub struct T; }
+type A<T> = (T, m::T);
+fn f(a: $0A<u8>) {}
+"#,
+            r#"
+mod m { pub struct T; }
+type A<T> = (T, m::T);
+fn f(a: (u8, m::T)) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn qualifies_paths_for_the_use_site() {
+        check_assist(
+            inline_type_alias_uses,
+            r#"
+mod m {
+    pub struct Pub<T>(T);
+    struct Priv;
+    pub type $0A = Pub<Priv>;
+    pub type B = Pub<u8>;
+    fn f(a: A) {}
+}
+fn g(a: m::A) {}
+fn h(b: m::B) {}
+"#,
+            r#"
+mod m {
+    pub struct Pub<T>(T);
+    struct Priv;
+    pub type A = Pub<Priv>;
+    pub type B = Pub<u8>;
+    fn f(a: Pub<Priv>) {}
+}
+fn g(a: m::A) {}
+fn h(b: m::B) {}
+"#,
+        );
+        check_assist(
+            inline_type_alias,
+            r#"
+mod m {
+    pub struct Pub<T>(T);
+    pub type A<T = u8> = Pub<T>;
+}
+fn g(a: m::$0A) {}
+"#,
+            r#"
+mod m {
+    pub struct Pub<T>(T);
+    pub type A<T = u8> = Pub<T>;
+}
+fn g(a: m::Pub<u8>) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_invisible_items() {
+        check_assist_not_applicable(
+            inline_type_alias,
+            r#"
+mod m {
+    struct Priv;
+    pub type A = Priv;
+}
+fn f(a: m::$0A) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_missing_arguments() {
+        check_assist_not_applicable(
+            inline_type_alias,
+            r#"
+type A<T, U> = (T, U);
+fn f(a: $0A<u8>) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_param_qualifiers() {
+        check_assist_not_applicable(
+            inline_type_alias,
+            r#"
+trait Tr { type Assoc; }
+type A<T> = T::Assoc;
+fn f(a: $0A<u8>) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_associated_types() {
+        check_assist_not_applicable(
+            inline_type_alias,
+            r#"
+trait Tr { type Assoc; }
+struct S;
+impl Tr for S { type Assoc = u8; }
+fn f(a: <S as Tr>::$0Assoc) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn inlines_all_uses_and_removes_alias() {
+        check_assist(
+            inline_type_alias_uses,
+            r#"
+//- /main.rs
+mod foo;
+type $0A<T> = Vec<T>;
+fn f(a: A<u8>) -> A<u16> {}
+//- /foo.rs
+fn g(a: crate::A<bool>) {}
+"#,
+            r#"
+//- /main.rs
+mod foo;
+fn f(a: Vec<u8>) -> Vec<u16> {}
+//- /foo.rs
+fn g(a: Vec<bool>) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn keeps_alias_with_remaining_uses() {
+        cov_mark::check!(inline_type_alias_uses_skips_nested_use);
+        check_assist(
+            inline_type_alias_uses,
+            r#"
+type $0A<T> = Vec<T>;
+fn f(a: A<A<u8>>) {}
+"#,
+            r#"
+type A<T> = Vec<T>;
+fn f(a: Vec<A<u8>>) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn keeps_alias_used_in_imports() {
+        check_assist(
+            inline_type_alias_uses,
+            r#"
+mod m {
+    pub type $0A = u8;
+}
+use m::A;
+fn f(a: A) {}
+"#,
+            r#"
+mod m {
+    pub type A = u8;
+}
+use m::A;
+fn f(a: u8) {}
+"#,
+        );
+    }
+}
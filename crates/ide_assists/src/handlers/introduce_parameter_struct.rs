@@ -0,0 +1,440 @@
+use hir::ModuleDef;
+use ide_db::{defs::Definition, helpers::mod_path_to_ast};
+use itertools::Itertools;
+use rustc_hash::FxHashSet;
+use stdx::format_to;
+use syntax::{
+    algo::find_node_at_range,
+    ast::{
+        self, edit::AstNodeEdit, ArgListOwner, AstNode, GenericParamsOwner, NameOwner,
+        VisibilityOwner,
+    },
+    TextRange,
+};
+
+use crate::{
+    utils::{has_elided_lifetime, name_elided_lifetimes},
+    AssistContext, AssistId, AssistKind, Assists,
+};
+
+// Assist: introduce_parameter_struct
+//
+// Replaces the selected parameters of a function with a struct holding them,
+// updating the body and all callers.
+//
+// ```
+// fn draw(canvas: &mut Vec<u8>, $0x: u32, y: u32$0) {
+//     canvas.push((x + y) as u8);
+// }
+// fn main() {
+//     draw(&mut Vec::new(), 1, 2);
+// }
+// ```
+// ->
+// ```
+// struct DrawParams {
+//     x: u32,
+//     y: u32,
+// }
+//
+// fn draw(canvas: &mut Vec<u8>, params: DrawParams) {
+//     canvas.push((params.x + params.y) as u8);
+// }
+// fn main() {
+//     draw(&mut Vec::new(), DrawParams { x: 1, y: 2 });
+// }
+// ```
+pub(crate) fn introduce_parameter_struct(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    if ctx.frange.range.is_empty() {
+        return None;
+    }
+    let param_list = ctx.find_node_at_offset::<ast::ParamList>()?;
+    if !param_list.syntax().text_range().contains_range(ctx.frange.range) {
+        return None;
+    }
+    let fn_ = param_list.syntax().parent().and_then(ast::Fn::cast)?;
+    let impl_ = fn_
+        .syntax()
+        .parent()
+        .and_then(ast::AssocItemList::cast)
+        .and_then(|it| it.syntax().parent());
+    // The signatures of trait methods are fixed by the trait.
+    if let Some(parent) = &impl_ {
+        match ast::Impl::cast(parent.clone()) {
+            Some(impl_) if impl_.trait_().is_none() => (),
+            _ => return None,
+        }
+    }
+
+    let all_params: Vec<ast::Param> = param_list.params().collect();
+    let selected: Vec<(usize, ast::Param)> = all_params
+        .iter()
+        .cloned()
+        .enumerate()
+        .filter(|(_, it)| it.syntax().text_range().intersect(ctx.frange.range).is_some())
+        .collect();
+    let first = selected.first()?.0;
+    let last = selected.last()?.0;
+
+    let mut fields = Vec::new();
+    let mut locals = Vec::new();
+    for (_, param) in &selected {
+        let pat = match param.pat()? {
+            ast::Pat::IdentPat(it) if it.pat().is_none() && it.ref_token().is_none() => it,
+            _ => return None,
+        };
+        let ty = param.ty()?;
+        // Neither can be named in a struct outside of the function.
+        let unnameable = ty.syntax().descendants().any(|it| {
+            ast::ImplTraitType::can_cast(it.kind())
+                || matches!(ast::NameRef::cast(it), Some(it) if it.text() == "Self")
+        });
+        if unnameable {
+            return None;
+        }
+        fields.push((pat.name()?.to_string(), ty));
+        locals.push((ctx.sema.to_def(&pat)?, pat.mut_token().is_some()));
+    }
+
+    let fn_name = fn_.name()?.to_string();
+    let struct_name = format!("{}Params", camel_case(&fn_name));
+    let function = ctx.sema.to_def(&fn_)?;
+    let fn_module = ctx.sema.scope(fn_.syntax()).module()?;
+    let has_self = param_list.self_param().is_some();
+
+    // Find the callers first, the assist isn't applicable if the function is
+    // also used as a value.
+    let mut calls = Vec::new();
+    for (file_id, references) in Definition::ModuleDef(function.into()).usages(&ctx.sema).all() {
+        let file = ctx.sema.parse(file_id);
+        for reference in references {
+            if file_id == ctx.frange.file_id
+                && fn_.syntax().text_range().contains_range(reference.range)
+            {
+                // Recursive calls pass the parameters to themselves.
+                return None;
+            }
+            let name_ref = find_node_at_range::<ast::NameRef>(file.syntax(), reference.range)?;
+            let (args, offset): (Vec<ast::Expr>, usize) =
+                match name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast) {
+                    Some(call) => (call.arg_list()?.args().collect(), 0),
+                    None => {
+                        let path_expr =
+                            name_ref.syntax().ancestors().find_map(ast::PathExpr::cast)?;
+                        let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast);
+                        match call {
+                            Some(call) if call.expr()?.syntax() == path_expr.syntax() => {
+                                (call.arg_list()?.args().collect(), if has_self { 1 } else { 0 })
+                            }
+                            _ => {
+                                cov_mark::hit!(introduce_parameter_struct_fn_used_as_value);
+                                return None;
+                            }
+                        }
+                    }
+                };
+            if args.len() != all_params.len() + offset {
+                continue;
+            }
+            let module = ctx.sema.scope(name_ref.syntax()).module()?;
+            calls.push((file_id, module, args[first + offset..=last + offset].to_vec()));
+        }
+    }
+
+    let target = TextRange::new(
+        selected.first()?.1.syntax().text_range().start(),
+        selected.last()?.1.syntax().text_range().end(),
+    );
+    acc.add(
+        AssistId("introduce_parameter_struct", AssistKind::RefactorExtract),
+        "Introduce parameter struct",
+        target,
+        |builder| {
+            let db = ctx.db();
+            let generics = StructGenerics::new(&fn_, impl_.and_then(ast::Impl::cast), &fields);
+            let vis = fn_.visibility().map_or(String::new(), |it| format!("{} ", it));
+            let item = match fn_.syntax().ancestors().find_map(ast::Impl::cast) {
+                Some(impl_) => ast::Item::Impl(impl_),
+                None => ast::Item::Fn(fn_.clone()),
+            };
+            let indent = item.indent_level();
+
+            let mut strukt = format!("{}struct {}{} {{\n", vis, struct_name, generics.params);
+            for (name, ty) in &fields {
+                format_to!(strukt, "{}    {}{}: {},\n", indent, vis, name, generics.field_ty(ty));
+            }
+            format_to!(strukt, "{}}}\n\n{}", indent, indent);
+
+            builder.edit_file(ctx.frange.file_id);
+            builder.insert(item.syntax().text_range().start(), strukt);
+            let mut_ = if locals.iter().any(|(_, is_mut)| *is_mut) { "mut " } else { "" };
+            builder.replace(target, format!("{}params: {}{}", mut_, struct_name, generics.args));
+
+            for ((local, _), (name, _)) in locals.iter().zip(&fields) {
+                let usages = Definition::Local(*local).usages(&ctx.sema).all();
+                let file = ctx.sema.parse(ctx.frange.file_id);
+                for reference in usages.references.get(&ctx.frange.file_id).into_iter().flatten() {
+                    let shorthand =
+                        find_node_at_range::<ast::NameRef>(file.syntax(), reference.range)
+                            .and_then(|it| it.syntax().ancestors().find_map(ast::PathExpr::cast))
+                            .and_then(|it| it.syntax().parent())
+                            .and_then(ast::RecordExprField::cast)
+                            .filter(|it| it.colon_token().is_none());
+                    match shorthand {
+                        Some(field) => builder.replace(
+                            field.syntax().text_range(),
+                            format!("{}: params.{}", name, name),
+                        ),
+                        None => builder.replace(reference.range, format!("params.{}", name)),
+                    }
+                }
+            }
+
+            let mut calls = calls;
+            calls.sort_by_key(|(file_id, ..)| *file_id != ctx.frange.file_id);
+            for (file_id, module, args) in calls {
+                let path = if module == fn_module {
+                    struct_name.clone()
+                } else {
+                    match module.find_use_path(db, ModuleDef::Module(fn_module)) {
+                        Some(path) => format!("{}::{}", mod_path_to_ast(&path), struct_name),
+                        None => continue,
+                    }
+                };
+                let field_inits = fields.iter().zip(&args).map(|((name, _), arg)| {
+                    if arg.syntax().text() == name.as_str() {
+                        name.clone()
+                    } else {
+                        format!("{}: {}", name, arg)
+                    }
+                });
+                let range = TextRange::new(
+                    args[0].syntax().text_range().start(),
+                    args[args.len() - 1].syntax().text_range().end(),
+                );
+                builder.edit_file(file_id);
+                builder.replace(range, format!("{} {{ {} }}", path, field_inits.format(", ")));
+            }
+        },
+    )
+}
+
+/// The generic parameters of the function and its impl which the selected
+/// parameters use, plus a lifetime for elided ones.
+struct StructGenerics {
+    /// The parameter list of the struct, like `<'a, T>`.
+    params: String,
+    /// The arguments of the struct in the function signature, like `<'_, T>`.
+    args: String,
+    /// The lifetime elided lifetimes are replaced with in field types.
+    elided: String,
+}
+
+impl StructGenerics {
+    fn new(fn_: &ast::Fn, impl_: Option<ast::Impl>, fields: &[(String, ast::Type)]) -> Self {
+        let used_names: FxHashSet<String> = fields
+            .iter()
+            .flat_map(|(_, ty)| ty.syntax().descendants())
+            .filter_map(|it| {
+                ast::NameRef::cast(it.clone())
+                    .map(|it| it.to_string())
+                    .or_else(|| ast::Lifetime::cast(it).map(|it| it.to_string()))
+            })
+            .collect();
+        let has_elided = fields.iter().any(|(_, ty)| has_elided_lifetime(ty));
+
+        let all_params: Vec<ast::GenericParam> = impl_
+            .and_then(|it| it.generic_param_list())
+            .into_iter()
+            .chain(fn_.generic_param_list())
+            .flat_map(|it| it.generic_params())
+            .collect();
+        let param_name = |param: &ast::GenericParam| match param {
+            ast::GenericParam::LifetimeParam(it) => it.lifetime().map(|it| it.to_string()),
+            ast::GenericParam::TypeParam(it) => it.name().map(|it| it.to_string()),
+            ast::GenericParam::ConstParam(it) => it.name().map(|it| it.to_string()),
+        };
+        let names: FxHashSet<String> = all_params.iter().filter_map(param_name).collect();
+        let elided = ["'a", "'b", "'c", "'d"]
+            .iter()
+            .find(|it| !names.contains(**it))
+            .map_or("'params", |it| *it)
+            .to_string();
+
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        if has_elided {
+            params.push(elided.clone());
+            args.push("'_".to_string());
+        }
+        let used = all_params
+            .iter()
+            .filter_map(|param| Some((param, param_name(param)?)))
+            .filter(|(_, name)| used_names.contains(name));
+        // Lifetimes have to come first.
+        let (lifetimes, others): (Vec<_>, Vec<_>) =
+            used.partition(|(param, _)| matches!(param, ast::GenericParam::LifetimeParam(_)));
+        for (param, name) in lifetimes.into_iter().chain(others) {
+            match param {
+                ast::GenericParam::ConstParam(it) => match it.ty() {
+                    Some(ty) => params.push(format!("const {}: {}", name, ty)),
+                    None => continue,
+                },
+                _ => params.push(name.clone()),
+            }
+            args.push(name);
+        }
+        let wrap = |it: Vec<String>| {
+            if it.is_empty() {
+                String::new()
+            } else {
+                format!("<{}>", it.join(", "))
+            }
+        };
+        StructGenerics { params: wrap(params), args: wrap(args), elided }
+    }
+
+    /// Returns `ty` with its elided lifetimes made explicit.
+    fn field_ty(&self, ty: &ast::Type) -> String {
+        name_elided_lifetimes(ty, &self.elided)
+    }
+}
+
+fn camel_case(snake_case: &str) -> String {
+    snake_case
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn introduces_struct_and_updates_callers() {
+        check_assist(
+            introduce_parameter_struct,
+            r#"
+//- /main.rs
+mod other;
+macro_rules! id { ($e:expr) => { $e }; }
+pub fn draw_line(first: u8, $0x: u32, mut y: u32, label: &str$0) -> u32 {
+    y += 1;
+    let p = Point { x, y };
+    id!(label);
+    first as u32 + x + y
+}
+struct Point { x: u32, y: u32 }
+fn main() {
+    let x = 1;
+    draw_line(0, x, 2 + 3, "a");
+}
+//- /other.rs
+fn f() {
+    crate::draw_line(0, 1, 2, "b");
+}
+"#,
+            r#"
+//- /main.rs
+mod other;
+macro_rules! id { ($e:expr) => { $e }; }
+pub struct DrawLineParams<'a> {
+    pub x: u32,
+    pub y: u32,
+    pub label: &'a str,
+}
+
+pub fn draw_line(first: u8, mut params: DrawLineParams<'_>) -> u32 {
+    params.y += 1;
+    let p = Point { x: params.x, y: params.y };
+    id!(params.label);
+    first as u32 + params.x + params.y
+}
+struct Point { x: u32, y: u32 }
+fn main() {
+    let x = 1;
+    draw_line(0, DrawLineParams { x, y: 2 + 3, label: "a" });
+}
+//- /other.rs
+fn f() {
+    crate::draw_line(0, crate::DrawLineParams { x: 1, y: 2, label: "b" });
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn carries_over_generics() {
+        check_assist(
+            introduce_parameter_struct,
+            r#"
+struct S<T>(T);
+impl<T: Clone> S<T> {
+    fn set<'x, U: Copy>(&mut self, $0a: &'x T, b: U, c: &[U]$0, d: u8) {}
+}
+fn main() {
+    S(0).set(&1, 2, &[3], 4);
+}
+"#,
+            r#"
+struct S<T>(T);
+struct SetParams<'a, 'x, T, U> {
+    a: &'x T,
+    b: U,
+    c: &'a [U],
+}
+
+impl<T: Clone> S<T> {
+    fn set<'x, U: Copy>(&mut self, params: SetParams<'_, 'x, T, U>, d: u8) {}
+}
+fn main() {
+    S(0).set(SetParams { a: &1, b: 2, c: &[3] }, 4);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_trait_impls() {
+        check_assist_not_applicable(
+            introduce_parameter_struct,
+            r#"
+trait Tr { fn f(&self, a: u8, b: u8); }
+struct S;
+impl Tr for S {
+    fn f(&self, $0a: u8, b: u8$0) {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_impl_trait_params() {
+        check_assist_not_applicable(
+            introduce_parameter_struct,
+            r#"
+fn f($0a: u8, b: impl Copy$0) {}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_when_used_as_value() {
+        cov_mark::check!(introduce_parameter_struct_fn_used_as_value);
+        check_assist_not_applicable(
+            introduce_parameter_struct,
+            r#"
+fn f($0a: u8, b: u8$0) {}
+fn main() {
+    let g = f;
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,398 @@
+use hir::{HasSource, ModuleDef};
+use ide_db::{
+    base_db::FileId,
+    defs::{Definition, NameRefClass},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::{
+    ast::{self, AstNode, NameOwner},
+    TextSize,
+};
+
+use crate::{assist_context::AssistBuilder, AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: make_async
+//
+// Makes a function `async` and appends `.await` to its calls inside async contexts.
+// Another variant of this assist also makes the non-async callers `async`, recursively.
+//
+// ```
+// fn fetch() -> u32 { 92 }
+//
+// async fn run() -> u32 {
+//     fetch() + 1
+// }
+//
+// fn main() {
+//     $0fetch();
+// }
+// ```
+// ->
+// ```
+// async fn fetch() -> u32 { 92 }
+//
+// async fn run() -> u32 {
+//     fetch().await + 1
+// }
+//
+// fn main() {
+//     fetch();
+// }
+// ```
+pub(crate) fn make_async(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let function = match ctx.find_node_at_offset::<ast::NameRef>() {
+        Some(name_ref) => match NameRefClass::classify(&ctx.sema, &name_ref)? {
+            NameRefClass::Definition(Definition::ModuleDef(ModuleDef::Function(it))) => it,
+            _ => return None,
+        },
+        None => {
+            let fn_ = ctx.find_node_at_offset::<ast::Fn>()?;
+            if ctx.offset() >= fn_.param_list()?.syntax().text_range().start() {
+                return None;
+            }
+            ctx.sema.to_def(&fn_)?
+        }
+    };
+    let fn_ = function.source(ctx.db())?.value;
+    if !can_be_async(&fn_) {
+        return None;
+    }
+    let target = fn_.syntax().text_range();
+
+    acc.add(
+        AssistId("make_async", AssistKind::RefactorRewrite),
+        "Make function async",
+        target,
+        |builder| Conversion::run(ctx, function, false).apply(builder),
+    );
+
+    let deep = Conversion::run(ctx, function, true);
+    if deep.converted.len() > 1 || !deep.skipped.is_empty() {
+        let mut label = String::from("Make function and its callers async");
+        if !deep.skipped.is_empty() {
+            label.push_str(&format!(" (cannot convert {})", deep.skipped.join(", ")));
+        }
+        acc.add(AssistId("make_async", AssistKind::RefactorRewrite), label, target, |builder| {
+            deep.apply(builder)
+        });
+    }
+    Some(())
+}
+
+struct Conversion {
+    converted: FxHashSet<hir::Function>,
+    /// Descriptions of the callers which need to await but cannot become async.
+    skipped: Vec<String>,
+    inserts: FxHashMap<FileId, Vec<(TextSize, &'static str)>>,
+}
+
+impl Conversion {
+    fn run(ctx: &AssistContext, function: hir::Function, recursive: bool) -> Conversion {
+        let mut res = Conversion {
+            converted: Default::default(),
+            skipped: Vec::new(),
+            inserts: Default::default(),
+        };
+        let mut worklist = vec![function];
+        res.converted.insert(function);
+        while let Some(function) = worklist.pop() {
+            let source = match function.source(ctx.db()) {
+                Some(it) => it,
+                None => continue,
+            };
+            // Functions defined by macros are only awaited, not made async themselves.
+            if source.file_id.expansion_level(ctx.db()) == 0 {
+                if let Some(offset) = async_offset(&source.value) {
+                    let file_id = source.file_id.original_file(ctx.db());
+                    res.inserts.entry(file_id).or_default().push((offset, "async "));
+                }
+            }
+
+            let usages =
+                Definition::ModuleDef(ModuleDef::Function(function)).usages(&ctx.sema).all();
+            for (file_id, refs) in usages {
+                for reference in refs {
+                    let call = match reference.name.as_name_ref().and_then(call_expr) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    let await_at = (call.text_range().end(), ".await");
+                    match CallContext::of(&call) {
+                        Some(CallContext::Async) => {
+                            res.inserts.entry(file_id).or_default().push(await_at)
+                        }
+                        _ if !recursive => (),
+                        Some(CallContext::Fn(caller)) if can_be_async(&caller) => {
+                            res.inserts.entry(file_id).or_default().push(await_at);
+                            if let Some(caller) = ctx.sema.to_def(&caller) {
+                                if res.converted.insert(caller) {
+                                    worklist.push(caller);
+                                }
+                            }
+                        }
+                        Some(CallContext::Fn(caller)) => {
+                            res.skip(format!("`{}`", fn_name(&caller)));
+                        }
+                        Some(CallContext::Closure(caller)) => {
+                            res.skip(format!("closure in `{}`", fn_name(&caller)));
+                        }
+                        None => (),
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    fn skip(&mut self, description: String) {
+        if !self.skipped.contains(&description) {
+            self.skipped.push(description);
+        }
+    }
+
+    fn apply(&self, builder: &mut AssistBuilder) {
+        let mut files: Vec<_> = self.inserts.iter().collect();
+        files.sort_by_key(|(file_id, _)| **file_id);
+        for (&file_id, inserts) in files {
+            builder.edit_file(file_id);
+            let mut inserts = inserts.clone();
+            inserts.sort_by_key(|&(offset, _)| offset);
+            inserts.dedup();
+            for (offset, text) in inserts {
+                builder.insert(offset, text);
+            }
+        }
+    }
+}
+
+enum CallContext {
+    /// An `async fn`, `async` block or `async` closure.
+    Async,
+    Fn(ast::Fn),
+    /// A non-async closure inside of the given function.
+    Closure(ast::Fn),
+}
+
+impl CallContext {
+    fn of(call: &syntax::SyntaxNode) -> Option<CallContext> {
+        for node in call.ancestors().skip(1) {
+            if let Some(fn_) = ast::Fn::cast(node.clone()) {
+                return Some(if fn_.async_token().is_some() {
+                    CallContext::Async
+                } else {
+                    CallContext::Fn(fn_)
+                });
+            }
+            if let Some(closure) = ast::ClosureExpr::cast(node.clone()) {
+                if closure.async_token().is_some() {
+                    return Some(CallContext::Async);
+                }
+                let fn_ = closure.syntax().ancestors().find_map(ast::Fn::cast)?;
+                return Some(CallContext::Closure(fn_));
+            }
+            if let Some(effect) = ast::EffectExpr::cast(node) {
+                if effect.async_token().is_some() {
+                    return Some(CallContext::Async);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Returns the call or method call expression in which `name_ref` is the callee.
+fn call_expr(name_ref: &ast::NameRef) -> Option<syntax::SyntaxNode> {
+    if let Some(call) = name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast) {
+        return Some(call.syntax().clone());
+    }
+    let path_expr = name_ref.syntax().ancestors().nth(3).and_then(ast::PathExpr::cast)?;
+    let call = path_expr.syntax().parent().and_then(ast::CallExpr::cast)?;
+    if call.expr()?.syntax() != path_expr.syntax() {
+        return None;
+    }
+    Some(call.syntax().clone())
+}
+
+/// Checks that `async` can be added to the function without breaking its signature contract.
+fn can_be_async(fn_: &ast::Fn) -> bool {
+    if fn_.async_token().is_some() || fn_.const_token().is_some() || fn_.body().is_none() {
+        return false;
+    }
+    if fn_.name().filter(|name| name.text() == "main").is_some() {
+        return false;
+    }
+    match fn_.syntax().parent().and_then(ast::AssocItemList::cast) {
+        Some(items) => items
+            .syntax()
+            .parent()
+            .and_then(ast::Impl::cast)
+            .filter(|it| it.trait_().is_none())
+            .is_some(),
+        None => true,
+    }
+}
+
+fn async_offset(fn_: &ast::Fn) -> Option<TextSize> {
+    let token = fn_
+        .unsafe_token()
+        .or_else(|| fn_.abi().and_then(|abi| abi.syntax().first_token()))
+        .or_else(|| fn_.fn_token())?;
+    Some(token.text_range().start())
+}
+
+fn fn_name(fn_: &ast::Fn) -> String {
+    fn_.name().map(|name| name.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_by_label, check_assist_not_applicable};
+
+    use super::*;
+
+    #[test]
+    fn awaits_in_async_contexts_only() {
+        check_assist_by_label(
+            make_async,
+            r#"
+pub unsafe fn $0fetch() -> u32 { 92 }
+
+async fn run() -> u32 {
+    let a = unsafe { fetch() };
+    let f = async { unsafe { fetch() } };
+    a + f.await
+}
+
+fn sync() -> u32 {
+    unsafe { fetch() }
+}
+"#,
+            r#"
+pub async unsafe fn fetch() -> u32 { 92 }
+
+async fn run() -> u32 {
+    let a = unsafe { fetch().await };
+    let f = async { unsafe { fetch().await } };
+    a + f.await
+}
+
+fn sync() -> u32 {
+    unsafe { fetch() }
+}
+"#,
+            "Make function async",
+        );
+    }
+
+    #[test]
+    fn makes_callers_async_recursively() {
+        check_assist_by_label(
+            make_async,
+            r#"
+struct Client;
+impl Client {
+    fn $0get(&self) -> Option<u32> { Some(1) }
+}
+fn load(c: &Client) -> Option<u32> {
+    Some(c.get()? + 1)
+}
+fn handler(c: &Client) -> u32 {
+    load(c).unwrap_or(0) + load(c).unwrap_or(1)
+}
+async fn serve(c: Client) {
+    handler(&c);
+}
+"#,
+            r#"
+struct Client;
+impl Client {
+    async fn get(&self) -> Option<u32> { Some(1) }
+}
+async fn load(c: &Client) -> Option<u32> {
+    Some(c.get().await? + 1)
+}
+async fn handler(c: &Client) -> u32 {
+    load(c).await.unwrap_or(0) + load(c).await.unwrap_or(1)
+}
+async fn serve(c: Client) {
+    handler(&c).await;
+}
+"#,
+            "Make function and its callers async",
+        );
+    }
+
+    #[test]
+    fn reports_callers_which_cannot_be_converted() {
+        check_assist_by_label(
+            make_async,
+            r#"
+trait Handler {
+    fn handle(&self) -> u32;
+}
+struct S;
+fn $0compute() -> u32 { 1 }
+fn wrapped() -> u32 { compute() }
+impl Handler for S {
+    fn handle(&self) -> u32 { compute() }
+}
+fn apply() -> u32 {
+    let f = || wrapped();
+    f()
+}
+"#,
+            r#"
+trait Handler {
+    fn handle(&self) -> u32;
+}
+struct S;
+async fn compute() -> u32 { 1 }
+async fn wrapped() -> u32 { compute().await }
+impl Handler for S {
+    fn handle(&self) -> u32 { compute() }
+}
+fn apply() -> u32 {
+    let f = || wrapped();
+    f()
+}
+"#,
+            "Make function and its callers async (cannot convert `handle`, closure in `apply`)",
+        );
+    }
+
+    #[test]
+    fn from_call_site() {
+        check_assist(
+            make_async,
+            r#"
+fn foo() {}
+async fn bar() {
+    $0foo();
+}
+"#,
+            r#"
+async fn foo() {}
+async fn bar() {
+    foo().await;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable() {
+        check_assist_not_applicable(make_async, "async fn $0foo() {}");
+        check_assist_not_applicable(make_async, "const fn $0foo() {}");
+        check_assist_not_applicable(make_async, "fn $0main() {}");
+        check_assist_not_applicable(
+            make_async,
+            r#"
+trait T { fn foo(&self); }
+impl T for () {
+    fn $0foo(&self) {}
+}
+"#,
+        );
+        check_assist_not_applicable(make_async, "fn foo(x: u32) { $0x; }");
+    }
+}
@@ -0,0 +1,1278 @@
+use std::iter::successors;
+
+use hir::{
+    db::HirDatabase, Adt, HasVisibility, Module, ModuleDef, ModuleSource, Namespace,
+    PathResolution, ScopeDef, StructKind, Visibility,
+};
+use ide_db::{
+    base_db::FileId,
+    defs::Definition,
+    helpers::{
+        insert_use::{insert_use, ImportScope, InsertUseConfig},
+        mod_path_to_ast,
+    },
+    search::UsageSearchResult,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::{
+    algo::find_node_at_range,
+    ast::{
+        self,
+        edit::{AstNodeEdit, IndentLevel},
+        make, AstNode, ModuleItemOwner, VisibilityOwner,
+    },
+    ted::{self, Position},
+    SyntaxElement, SyntaxKind, TextRange, T,
+};
+use text_edit::{TextEdit, TextEditBuilder};
+
+use crate::{
+    assist_context::AssistBuilder,
+    handlers::extract_module::{definitions_of, is_in_use, is_use_tree_prefix},
+    utils::vis_offset,
+    AssistContext, AssistId, AssistKind, Assists, GroupLabel,
+};
+
+// Assist: move_item_to_module
+//
+// Moves an item, together with its impls, into the parent or a child module
+// and updates all references to it.
+//
+// ```
+// struct $0Foo;
+//
+// fn make() -> Foo { Foo }
+//
+// mod bar {}
+// ```
+// ->
+// ```
+// use bar::Foo;
+//
+// fn make() -> Foo { Foo }
+//
+// mod bar {
+//     pub(super) struct Foo;
+// }
+// ```
+pub(crate) fn move_item_to_module(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let name = ctx.find_node_at_offset::<ast::Name>()?;
+    let item = MovedItem::new(ctx, name.syntax().parent().and_then(ast::Item::cast)?)?;
+
+    let db = ctx.db();
+    let mut targets: Vec<_> = item
+        .module
+        .parent(db)
+        .into_iter()
+        .chain(item.module.children(db))
+        .filter(|&it| item.check_target(ctx, it).is_ok())
+        .map(|it| (module_path(ctx, it), it))
+        .collect();
+    targets.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let group = GroupLabel("Move item to module…".to_owned());
+    for (path, target) in targets {
+        acc.add_group(
+            &group,
+            AssistId("move_item_to_module", AssistKind::Refactor),
+            format!("Move `{}` to `{}`", item.name, path),
+            name.syntax().text_range(),
+            |builder| item.move_to(ctx, builder, target, false),
+        );
+    }
+    Some(())
+}
+
+/// Moves the item whose name is at the cursor into the module `destination`
+/// resolves to. With `reexport`, a `use` with the item's visibility is left
+/// in its place, so that its old path keeps working.
+pub(crate) fn move_item_to(
+    ctx: &AssistContext,
+    builder: &mut AssistBuilder,
+    destination: &str,
+    reexport: bool,
+) -> Result<(), String> {
+    let item = ctx
+        .find_node_at_offset::<ast::Name>()
+        .and_then(|it| it.syntax().parent())
+        .and_then(ast::Item::cast)
+        .and_then(|it| MovedItem::new(ctx, it))
+        .ok_or_else(|| "no movable item at the cursor".to_string())?;
+
+    let path = ast::Path::parse(destination)
+        .map_err(|()| format!("`{}` is not a valid path", destination))?;
+    let target = match ctx.sema.scope(item.item.syntax()).speculative_resolve(&path) {
+        Some(PathResolution::Def(ModuleDef::Module(it))) => it,
+        _ => return Err(format!("`{}` is not a module", destination)),
+    };
+    item.check_target(ctx, target)?;
+
+    item.move_to(ctx, builder, target, reexport);
+    Ok(())
+}
+
+struct MovedItem {
+    item: ast::Item,
+    def: ModuleDef,
+    name: hir::Name,
+    module: Module,
+    file_id: FileId,
+    /// The item itself, followed by the impls moving along with it.
+    nodes: Vec<ast::Item>,
+}
+
+impl MovedItem {
+    fn new(ctx: &AssistContext, item: ast::Item) -> Option<MovedItem> {
+        let sema = &ctx.sema;
+        let def = item_def(ctx, &item)?;
+        let db = ctx.db();
+        let name = def.name(db)?;
+        let module = def.module(db)?;
+
+        // Items inside of function bodies have no path to be moved along.
+        let container = item.syntax().parent()?;
+        if !ast::SourceFile::can_cast(container.kind())
+            && !ast::ItemList::can_cast(container.kind())
+        {
+            return None;
+        }
+
+        let mut nodes = vec![item.clone()];
+        if let ModuleDef::Adt(adt) = def {
+            let impls = container.children().filter_map(ast::Impl::cast).filter(
+                |it| matches!(sema.to_def(it), Some(imp) if imp.self_ty(db).as_adt() == Some(adt)),
+            );
+            nodes.extend(impls.map(ast::Item::Impl));
+        }
+
+        Some(MovedItem { item, def, name, module, file_id: ctx.frange.file_id, nodes })
+    }
+
+    fn contains(&self, file_id: FileId, range: TextRange) -> bool {
+        file_id == self.file_id
+            && self.nodes.iter().any(|it| it.syntax().text_range().contains_range(range))
+    }
+
+    fn check_target(&self, ctx: &AssistContext, target: Module) -> Result<(), String> {
+        let db = ctx.db();
+        if target == self.module {
+            return Err(format!("`{}` is already in `{}`", self.name, module_path(ctx, target)));
+        }
+        if target.krate() != self.module.krate() {
+            return Err("items can only be moved within their crate".to_string());
+        }
+        // Imports of the item itself go away with the move.
+        let item_namespaces = namespaces(db, ScopeDef::ModuleDef(self.def));
+        let conflicts = target.scope(db, None).into_iter().any(|(name, def)| {
+            name == self.name
+                && def != ScopeDef::ModuleDef(self.def)
+                && namespaces(db, def).iter().any(|ns| item_namespaces.contains(ns))
+        });
+        if conflicts {
+            return Err(format!(
+                "`{}` already contains an item named `{}`",
+                module_path(ctx, target),
+                self.name
+            ));
+        }
+        let source = target.definition_source(db);
+        if source.file_id.call_node(db).is_some()
+            || matches!(source.value, ModuleSource::BlockExpr(_))
+        {
+            return Err(format!("cannot move items into `{}`", module_path(ctx, target)));
+        }
+        Ok(())
+    }
+
+    fn move_to(
+        &self,
+        ctx: &AssistContext,
+        builder: &mut AssistBuilder,
+        target: Module,
+        reexport: bool,
+    ) {
+        let (target_file_id, target_scope) = match target_scope(ctx, target) {
+            Some(it) => it,
+            None => return,
+        };
+        let mut edits: FxHashMap<FileId, Vec<Edit>> = FxHashMap::default();
+
+        let mut imports = Vec::new();
+        let mut exposed = Vec::new();
+        let mut items = Vec::new();
+        let indent = match &target_scope {
+            ImportScope::File(_) => IndentLevel(0),
+            ImportScope::Module(it) => IndentLevel::from_node(it.syntax()) + 1,
+        };
+        for node in &self.nodes {
+            let mut edit = TextEdit::builder();
+            for (def, def_node) in definitions_of(ctx, node) {
+                let usages = def.usages(&ctx.sema).all();
+                let is_item = def_node == *self.item.syntax();
+                if let Some(vis) =
+                    self.required_visibility(ctx, target, def, &usages, is_item && reexport)
+                {
+                    let offset = vis_offset(&def_node) - node.syntax().text_range().start();
+                    edit.insert(offset, format!("{} ", vis));
+                }
+                if is_item {
+                    self.update_references(ctx, target, &usages, reexport, &mut edits);
+                }
+            }
+            self.adjust_paths(ctx, target, node, &mut edit, &mut imports, &mut exposed);
+
+            let mut text = node.syntax().to_string();
+            edit.finish().apply(&mut text);
+            let moved = match ast::SourceFile::parse(&text).tree().items().next() {
+                Some(it) => it,
+                None => continue,
+            };
+            let moved = moved.dedent(IndentLevel::from_node(node.syntax())).indent(indent);
+            items.push(moved.clone_for_update());
+        }
+
+        let source_edits = edits.entry(self.file_id).or_default();
+        source_edits.extend(exposed);
+        for node in &self.nodes {
+            match self.reexport(ctx, target, node, reexport) {
+                Some(use_) => source_edits.push(Edit::ReplaceItem(node.clone(), use_)),
+                None => source_edits.push(Edit::RemoveItem(node.clone())),
+            }
+        }
+
+        let target_edits = edits.entry(target_file_id).or_default();
+        target_edits.push(Edit::AddItems(target_scope.clone(), items));
+        for import in imports {
+            target_edits.push(Edit::Import(target_scope.clone(), mod_path_to_ast(&import)));
+        }
+
+        for (file_id, mut edits) in edits {
+            builder.edit_file(file_id);
+            // Imports go last, so that they don't get merged into `use` items
+            // which are about to be changed.
+            edits.sort_by_key(|it| matches!(it, Edit::Import(..)));
+            let edits: Vec<_> = edits.into_iter().map(|it| it.make_mut(builder)).collect();
+            for edit in edits {
+                edit.apply(ctx.config.insert_use);
+            }
+        }
+    }
+
+    /// Returns the `use` item which keeps the moved item reachable under its
+    /// old path, if one was requested.
+    fn reexport(
+        &self,
+        ctx: &AssistContext,
+        target: Module,
+        node: &ast::Item,
+        reexport: bool,
+    ) -> Option<ast::Use> {
+        if !reexport || node != &self.item {
+            return None;
+        }
+        let path = self.module.find_use_path(ctx.db(), ModuleDef::Module(target))?;
+        let path = make::path_from_text(&format!("{}::{}", mod_path_to_ast(&path), self.name));
+        let visibility = match &self.item {
+            ast::Item::Fn(it) => it.visibility(),
+            ast::Item::Struct(it) => it.visibility(),
+            ast::Item::Enum(it) => it.visibility(),
+            ast::Item::Union(it) => it.visibility(),
+            ast::Item::Trait(it) => it.visibility(),
+            ast::Item::Const(it) => it.visibility(),
+            ast::Item::Static(it) => it.visibility(),
+            ast::Item::TypeAlias(it) => it.visibility(),
+            _ => None,
+        };
+        Some(make::use_(visibility, make::use_tree(path, None, None, false)).clone_for_update())
+    }
+
+    /// Returns the visibility `def` needs so that it can still be used from
+    /// outside of `target`, if its current one isn't enough.
+    fn required_visibility(
+        &self,
+        ctx: &AssistContext,
+        target: Module,
+        def: Definition,
+        usages: &UsageSearchResult,
+        used_by_reexport: bool,
+    ) -> Option<&'static str> {
+        let db = ctx.db();
+        let visibility = match def {
+            Definition::ModuleDef(it) => it.definition_visibility(db)?,
+            Definition::Field(it) => it.visibility(db),
+            _ => return None,
+        };
+        if visibility != Visibility::Module(self.module.into()) {
+            return None;
+        }
+
+        let mut used_in = Vec::new();
+        for (&file_id, references) in usages.iter() {
+            for reference in references {
+                if self.contains(file_id, reference.range) {
+                    continue;
+                }
+                used_in.extend(ctx.sema.scope(reference.name.syntax()).module());
+            }
+        }
+        if used_by_reexport {
+            used_in.push(self.module);
+        }
+
+        let mut res = None;
+        for module in used_in {
+            let ancestors = module.path_to_root(db);
+            if ancestors.contains(&target) {
+                continue;
+            }
+            match target.parent(db) {
+                Some(parent) if ancestors.contains(&parent) => res = Some("pub(super)"),
+                _ => return Some("pub(crate)"),
+            }
+        }
+        res
+    }
+
+    /// Collects the edits which make references outside of the moved items
+    /// point to the new location.
+    fn update_references(
+        &self,
+        ctx: &AssistContext,
+        target: Module,
+        usages: &UsageSearchResult,
+        reexport: bool,
+        edits: &mut FxHashMap<FileId, Vec<Edit>>,
+    ) {
+        let db = ctx.db();
+        let prefix_kind = ctx.config.insert_use.prefix_kind;
+        // Paths in `use` items follow the configured prefix, other ones are
+        // kept as short as possible.
+        let new_path = |module: Module, in_use: bool| {
+            let target = ModuleDef::Module(target);
+            let path = if in_use {
+                module.find_use_path_prefixed(db, target, prefix_kind)?
+            } else {
+                module.find_use_path(db, target)?
+            };
+            Some(make::path_from_text(&format!("{}::{}", mod_path_to_ast(&path), self.name)))
+        };
+
+        for (&file_id, references) in usages.iter() {
+            let file = ctx.sema.parse(file_id);
+            let paths: Vec<_> = references
+                .iter()
+                .filter(|it| !self.contains(file_id, it.range))
+                .filter_map(|it| {
+                    let name_ref = find_node_at_range::<ast::NameRef>(file.syntax(), it.range)?;
+                    let path = ast::PathSegment::cast(name_ref.syntax().parent()?)?.parent_path();
+                    let module = ctx.sema.scope(path.syntax()).module()?;
+                    Some((path, module))
+                })
+                .collect();
+            let importing_modules: Vec<_> =
+                paths.iter().filter(|(path, _)| is_in_use(path)).map(|&(_, it)| it).collect();
+
+            let file_edits = edits.entry(file_id).or_default();
+            let mut imported_scopes = FxHashSet::default();
+            for (path, module) in paths {
+                if is_in_use(&path) {
+                    let top_path = successors(Some(path.clone()), |it| it.parent_path()).last();
+                    let use_tree = match top_path
+                        .clone()
+                        .and_then(|it| it.syntax().parent())
+                        .and_then(ast::UseTree::cast)
+                    {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    let imports_item = top_path.as_ref() == Some(&path)
+                        && use_tree.use_tree_list().is_none()
+                        && use_tree.star_token().is_none();
+                    if module == target && imports_item {
+                        file_edits.push(Edit::RemoveUseTree(use_tree));
+                        continue;
+                    }
+                    let new_path = if module == target {
+                        make::path_from_text(&format!("self::{}", self.name))
+                    } else {
+                        match new_path(module, true) {
+                            Some(it) => it,
+                            None => continue,
+                        }
+                    };
+                    if is_use_tree_prefix(&path) {
+                        file_edits.push(Edit::ReplacePath(path, new_path));
+                        continue;
+                    }
+                    // The path is relative to the prefix of an enclosing use
+                    // tree, so it needs a `use` item of its own.
+                    let use_ = use_tree.syntax().ancestors().find_map(ast::Use::cast);
+                    let scope = ImportScope::find_insert_use_container(use_tree.syntax());
+                    match (use_, scope) {
+                        (Some(use_), Some(scope))
+                            if imports_item
+                                && use_tree.rename().is_none()
+                                && use_.visibility().is_none() =>
+                        {
+                            file_edits.push(Edit::RemoveUseTree(use_tree));
+                            file_edits.push(Edit::Import(scope, new_path));
+                        }
+                        (Some(use_), _) => {
+                            let mut text = use_tree.syntax().to_string();
+                            let range =
+                                path.syntax().text_range() - use_tree.syntax().text_range().start();
+                            text.replace_range(
+                                usize::from(range.start())..usize::from(range.end()),
+                                &new_path.to_string(),
+                            );
+                            let visibility =
+                                use_.visibility().map(|it| format!("{} ", it)).unwrap_or_default();
+                            let new_use =
+                                ast::SourceFile::parse(&format!("{}use {};", visibility, text))
+                                    .tree()
+                                    .syntax()
+                                    .descendants()
+                                    .find_map(ast::Use::cast);
+                            if let Some(new_use) = new_use {
+                                file_edits.push(Edit::SplitUseTree(use_tree, use_, new_use));
+                            }
+                        }
+                        (None, _) => (),
+                    }
+                } else if let Some(qualifier) = path.qualifier() {
+                    if module == target {
+                        file_edits.push(Edit::RemoveQualifier(path));
+                    } else if let Some(new_path) = new_path(module, false) {
+                        let new_qualifier = new_path.qualifier().unwrap_or(new_path);
+                        file_edits.push(Edit::ReplacePath(qualifier, new_qualifier));
+                    }
+                } else {
+                    // Unqualified references keep resolving through the
+                    // re-export, through the new location, or through an
+                    // import which is updated on its own.
+                    if reexport || module == target || importing_modules.contains(&module) {
+                        continue;
+                    }
+                    let (scope, new_path) = match (
+                        ImportScope::find_insert_use_container(path.syntax()),
+                        new_path(module, true),
+                    ) {
+                        (Some(scope), Some(new_path)) => (scope, new_path),
+                        _ => continue,
+                    };
+                    if imported_scopes.insert(scope.as_syntax_node().text_range()) {
+                        file_edits.push(Edit::Import(scope, new_path));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rewrites paths in `node` which would no longer resolve inside of
+    /// `target` and collects the imports the moved items need there.
+    fn adjust_paths(
+        &self,
+        ctx: &AssistContext,
+        target: Module,
+        node: &ast::Item,
+        edit: &mut TextEditBuilder,
+        imports: &mut Vec<hir::ModPath>,
+        exposed: &mut Vec<Edit>,
+    ) {
+        let db = ctx.db();
+        let prefix_kind = ctx.config.insert_use.prefix_kind;
+        let source_scope = self.module.scope(db, None);
+        let target_scope = target.scope(db, None);
+        let offset = node.syntax().text_range().start();
+
+        for path in node.syntax().descendants().filter_map(ast::Path::cast) {
+            if path.qualifier().is_some() {
+                continue;
+            }
+            // Paths in nested modules are relative to those modules.
+            if path
+                .syntax()
+                .ancestors()
+                .take_while(|it| it != node.syntax())
+                .any(|it| ast::Module::can_cast(it.kind()))
+            {
+                continue;
+            }
+            let segment = match path.segment() {
+                Some(it) => it,
+                None => continue,
+            };
+            let in_use_tree = is_in_use(&path);
+            if in_use_tree && !is_use_tree_prefix(&path) {
+                continue;
+            }
+            match segment.kind() {
+                Some(ast::PathSegmentKind::SelfKw) | Some(ast::PathSegmentKind::SuperKw) => {
+                    let mut prefix = path.clone();
+                    let mut module = self.module;
+                    if segment.super_token().is_some() {
+                        module = match module.parent(db) {
+                            Some(it) => it,
+                            None => continue,
+                        };
+                        while let Some(parent) = prefix.parent_path() {
+                            match parent.segment().and_then(|it| it.super_token()) {
+                                Some(_) => {
+                                    module = match module.parent(db) {
+                                        Some(it) => it,
+                                        None => break,
+                                    };
+                                    prefix = parent;
+                                }
+                                None => break,
+                            }
+                        }
+                    }
+                    let refers_to_item = matches!(
+                        prefix.parent_path().and_then(|it| ctx.sema.resolve_path(&it)),
+                        Some(PathResolution::Def(def)) if def == self.def
+                    );
+                    let new_prefix = if module == target || refers_to_item {
+                        "self".to_string()
+                    } else {
+                        match target.find_use_path(db, ModuleDef::Module(module)) {
+                            Some(it) => mod_path_to_ast(&it).to_string(),
+                            None => continue,
+                        }
+                    };
+                    edit.replace(prefix.syntax().text_range() - offset, new_prefix);
+                }
+                Some(ast::PathSegmentKind::Name(name_ref)) => {
+                    let def = match ctx.sema.resolve_path(&path) {
+                        Some(PathResolution::Def(def)) if def != self.def => def,
+                        _ => continue,
+                    };
+                    let name = name_ref.text();
+                    let in_scope = |scope: &[(hir::Name, ScopeDef)]| {
+                        scope.iter().any(|(scope_name, scope_def)| {
+                            scope_name.to_string() == name.as_str()
+                                && *scope_def == ScopeDef::ModuleDef(def)
+                        })
+                    };
+                    if !in_scope(&source_scope) || in_scope(&target_scope) {
+                        continue;
+                    }
+                    let name_taken =
+                        target_scope.iter().any(|(it, _)| it.to_string() == name.as_str());
+                    let import = if in_use_tree || !name_taken {
+                        target.find_use_path_prefixed(db, def, prefix_kind)
+                    } else {
+                        target.find_use_path(db, def)
+                    };
+                    let import = match import.or_else(|| {
+                        self.expose(ctx, target, def, in_use_tree || !name_taken, exposed)
+                    }) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    if in_use_tree || name_taken {
+                        edit.replace(
+                            name_ref.syntax().text_range() - offset,
+                            mod_path_to_ast(&import).to_string(),
+                        );
+                    } else if !imports.contains(&import) {
+                        imports.push(import);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Makes a private item of the source module visible from `target` and
+    /// returns the path under which it can be used there.
+    fn expose(
+        &self,
+        ctx: &AssistContext,
+        target: Module,
+        def: ModuleDef,
+        prefixed: bool,
+        exposed: &mut Vec<Edit>,
+    ) -> Option<hir::ModPath> {
+        let db = ctx.db();
+        if def.definition_visibility(db)? != Visibility::Module(self.module.into()) {
+            return None;
+        }
+        let container = self.item.syntax().parent()?;
+        let decl = container
+            .children()
+            .filter_map(ast::Item::cast)
+            .find(|it| item_def(ctx, it) == Some(def))?;
+
+        let module = ModuleDef::Module(self.module);
+        let mut path = if prefixed {
+            target.find_use_path_prefixed(db, module, ctx.config.insert_use.prefix_kind)?
+        } else {
+            target.find_use_path(db, module)?
+        };
+        path.push_segment(def.name(db)?);
+
+        if !exposed.iter().any(|it| matches!(it, Edit::AddVisibility(item, _) if *item == decl)) {
+            let in_parent =
+                self.module.parent(db).filter(|it| target.path_to_root(db).contains(it));
+            let visibility = match in_parent {
+                Some(_) => make::visibility_pub_super(),
+                None => make::visibility_pub_crate(),
+            };
+            exposed.push(Edit::AddVisibility(decl, visibility));
+        }
+        Some(path)
+    }
+}
+
+enum Edit {
+    ReplacePath(ast::Path, ast::Path),
+    RemoveQualifier(ast::Path),
+    RemoveUseTree(ast::UseTree),
+    /// Replaces a nested use tree with a new `use` item, placed after the
+    /// one containing the tree.
+    SplitUseTree(ast::UseTree, ast::Use, ast::Use),
+    Import(ImportScope, ast::Path),
+    AddVisibility(ast::Item, ast::Visibility),
+    RemoveItem(ast::Item),
+    ReplaceItem(ast::Item, ast::Use),
+    AddItems(ImportScope, Vec<ast::Item>),
+}
+
+impl Edit {
+    fn make_mut(self, builder: &mut AssistBuilder) -> Edit {
+        let scope_mut = |builder: &mut AssistBuilder, scope| match scope {
+            ImportScope::File(it) => ImportScope::File(builder.make_mut(it)),
+            ImportScope::Module(it) => ImportScope::Module(builder.make_mut(it)),
+        };
+        match self {
+            Edit::ReplacePath(old, new) => Edit::ReplacePath(builder.make_mut(old), new),
+            Edit::RemoveQualifier(path) => Edit::RemoveQualifier(builder.make_mut(path)),
+            Edit::RemoveUseTree(tree) => Edit::RemoveUseTree(builder.make_mut(tree)),
+            Edit::SplitUseTree(tree, use_, new_use) => {
+                Edit::SplitUseTree(builder.make_mut(tree), builder.make_mut(use_), new_use)
+            }
+            Edit::Import(scope, path) => Edit::Import(scope_mut(builder, scope), path),
+            Edit::AddVisibility(item, vis) => Edit::AddVisibility(builder.make_mut(item), vis),
+            Edit::RemoveItem(item) => Edit::RemoveItem(builder.make_mut(item)),
+            Edit::ReplaceItem(item, use_) => Edit::ReplaceItem(builder.make_mut(item), use_),
+            Edit::AddItems(scope, items) => Edit::AddItems(scope_mut(builder, scope), items),
+        }
+    }
+
+    fn apply(self, config: InsertUseConfig) {
+        match self {
+            Edit::ReplacePath(old, new) => {
+                ted::replace(old.syntax(), new.clone_for_update().syntax())
+            }
+            Edit::RemoveQualifier(path) => {
+                if let (Some(qualifier), Some(coloncolon)) =
+                    (path.qualifier(), path.coloncolon_token())
+                {
+                    ted::remove_all(qualifier.syntax().clone().into()..=coloncolon.into());
+                }
+            }
+            Edit::RemoveUseTree(tree) => remove_use_tree(tree),
+            Edit::SplitUseTree(tree, use_, new_use) => {
+                let new_use = new_use.clone_for_update();
+                let indent = IndentLevel::from_node(use_.syntax());
+                ted::insert_all(
+                    Position::after(use_.syntax()),
+                    vec![
+                        make::tokens::whitespace(&format!("\n{}", indent)).into(),
+                        new_use.syntax().clone().into(),
+                    ],
+                );
+                remove_use_tree(tree);
+            }
+            Edit::Import(scope, path) => insert_use(&scope, path.clone_for_update(), config),
+            Edit::AddVisibility(item, vis) => {
+                let first = item.syntax().children_with_tokens().find(|it| {
+                    !matches!(
+                        it.kind(),
+                        SyntaxKind::WHITESPACE | SyntaxKind::COMMENT | SyntaxKind::ATTR
+                    )
+                });
+                if let Some(first) = first {
+                    ted::insert_all_raw(
+                        Position::before(first),
+                        vec![
+                            vis.clone_for_update().syntax().clone().into(),
+                            make::tokens::single_space().into(),
+                        ],
+                    );
+                }
+            }
+            Edit::RemoveItem(item) => remove_item(&item),
+            Edit::ReplaceItem(item, use_) => ted::replace(item.syntax(), use_.syntax()),
+            Edit::AddItems(scope, items) => {
+                for item in items {
+                    match &scope {
+                        ImportScope::Module(item_list) => item_list.add_item(item),
+                        ImportScope::File(file) => match file.items().last() {
+                            Some(last_item) => ted::insert_all(
+                                Position::after(last_item.syntax()),
+                                vec![
+                                    make::tokens::blank_line().into(),
+                                    item.syntax().clone().into(),
+                                ],
+                            ),
+                            None => {
+                                ted::insert(Position::first_child_of(file.syntax()), item.syntax())
+                            }
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Removes `tree`, together with the trees and the `use` item which would be
+/// left empty.
+fn remove_use_tree(mut tree: ast::UseTree) {
+    while let Some(list) = tree.syntax().parent().and_then(ast::UseTreeList::cast) {
+        if list.use_trees().nth(1).is_some() {
+            return tree.remove();
+        }
+        tree = list.parent_use_tree();
+    }
+    if let Some(use_) = tree.syntax().parent().and_then(ast::Use::cast) {
+        remove_item(&ast::Item::Use(use_));
+    }
+}
+
+/// Removes `item`, together with the whitespace separating it from its
+/// neighbours.
+fn remove_item(item: &ast::Item) {
+    let is_whitespace = |it: &SyntaxElement| it.kind() == SyntaxKind::WHITESPACE;
+    let prev = item.syntax().prev_sibling_or_token().filter(is_whitespace);
+    let next = item.syntax().next_sibling_or_token().filter(is_whitespace);
+    let first = match prev.as_ref().and_then(|it| it.prev_sibling_or_token()) {
+        Some(it) => it.kind() == T!['{'],
+        None => true,
+    };
+    let last = match next.as_ref().and_then(|it| it.next_sibling_or_token()) {
+        Some(it) => it.kind() == T!['}'],
+        None => true,
+    };
+    let whitespace = if first && !last { next } else { prev.or(next) };
+    if let Some(whitespace) = whitespace {
+        ted::remove(whitespace);
+    }
+    ted::remove(item.syntax());
+}
+
+fn item_def(ctx: &AssistContext, item: &ast::Item) -> Option<ModuleDef> {
+    let sema = &ctx.sema;
+    let def = match item {
+        ast::Item::Fn(it) => sema.to_def(it)?.into(),
+        ast::Item::Struct(it) => sema.to_def(it)?.into(),
+        ast::Item::Enum(it) => sema.to_def(it)?.into(),
+        ast::Item::Union(it) => sema.to_def(it)?.into(),
+        ast::Item::Trait(it) => sema.to_def(it)?.into(),
+        ast::Item::Const(it) => sema.to_def(it)?.into(),
+        ast::Item::Static(it) => sema.to_def(it)?.into(),
+        ast::Item::TypeAlias(it) => sema.to_def(it)?.into(),
+        _ => return None,
+    };
+    Some(def)
+}
+
+/// The namespaces `def` is defined in, two items only clash if they share one.
+fn namespaces(db: &dyn HirDatabase, def: ScopeDef) -> Vec<Namespace> {
+    let struct_kind = |kind| match kind {
+        StructKind::Record => vec![Namespace::Types],
+        StructKind::Tuple | StructKind::Unit => vec![Namespace::Types, Namespace::Values],
+    };
+    match def {
+        ScopeDef::ModuleDef(def) => match def {
+            ModuleDef::Module(_)
+            | ModuleDef::Adt(Adt::Enum(_))
+            | ModuleDef::Adt(Adt::Union(_))
+            | ModuleDef::Trait(_)
+            | ModuleDef::TypeAlias(_)
+            | ModuleDef::BuiltinType(_) => vec![Namespace::Types],
+            ModuleDef::Adt(Adt::Struct(it)) => struct_kind(it.kind(db)),
+            ModuleDef::Variant(it) => struct_kind(it.kind(db)),
+            ModuleDef::Function(_) | ModuleDef::Const(_) | ModuleDef::Static(_) => {
+                vec![Namespace::Values]
+            }
+        },
+        ScopeDef::MacroDef(_) => vec![Namespace::Macros],
+        _ => vec![Namespace::Types, Namespace::Values, Namespace::Macros],
+    }
+}
+
+fn target_scope(ctx: &AssistContext, target: Module) -> Option<(FileId, ImportScope)> {
+    let db = ctx.db();
+    let source = target.definition_source(db);
+    let scope = match source.value {
+        ModuleSource::SourceFile(it) => ImportScope::File(it),
+        ModuleSource::Module(it) => ImportScope::Module(it.item_list()?),
+        ModuleSource::BlockExpr(_) => return None,
+    };
+    Some((source.file_id.original_file(db), scope))
+}
+
+fn module_path(ctx: &AssistContext, module: Module) -> String {
+    let db = ctx.db();
+    let mut res = "crate".to_string();
+    for module in module.path_to_root(db).into_iter().rev() {
+        if let Some(name) = module.name(db) {
+            res.push_str("::");
+            res.push_str(&name.to_string());
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use ide_db::{
+        base_db::{fixture::WithFixture, SourceDatabaseExt},
+        RootDatabase,
+    };
+    use stdx::trim_indent;
+    use test_utils::assert_eq_text;
+
+    use crate::tests::{
+        check_assist, check_assist_by_label, check_assist_not_applicable, TEST_CONFIG,
+    };
+
+    use super::*;
+
+    #[test]
+    fn not_applicable_outside_of_item_name() {
+        check_assist_not_applicable(
+            move_item_to_module,
+            r#"
+$0struct Foo { x: i32 }
+
+mod bar {}
+"#,
+        );
+        check_assist_not_applicable(
+            move_item_to_module,
+            r#"
+struct Foo { $0x: i32 }
+
+mod bar {}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_without_other_modules() {
+        check_assist_not_applicable(move_item_to_module, r#"fn $0foo() {}"#);
+    }
+
+    #[test]
+    fn not_applicable_on_name_conflict() {
+        check_assist_not_applicable(
+            move_item_to_module,
+            r#"
+fn $0foo() {}
+
+mod bar {
+    fn foo() {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_on_name_conflict_in_one_namespace() {
+        check_assist_not_applicable(
+            move_item_to_module,
+            r#"
+struct $0Foo;
+
+mod bar {
+    fn Foo() {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn applicable_on_same_name_in_other_namespace() {
+        check_assist(
+            move_item_to_module,
+            r#"
+fn $0foo() {}
+
+mod bar {
+    mod foo {}
+}
+"#,
+            r#"
+mod bar {
+    mod foo {}
+
+    fn foo() {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_for_local_items() {
+        check_assist_not_applicable(
+            move_item_to_module,
+            r#"
+fn main() {
+    fn $0foo() {}
+}
+
+mod bar {}
+"#,
+        );
+    }
+
+    #[test]
+    fn move_unused_fn_into_child() {
+        check_assist(
+            move_item_to_module,
+            r#"
+fn $0foo() {}
+
+mod bar {
+    fn baz() {}
+}
+"#,
+            r#"
+mod bar {
+    fn baz() {}
+
+    fn foo() {}
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn move_struct_with_impls() {
+        check_assist(
+            move_item_to_module,
+            r#"
+mod bar {}
+
+struct $0Foo {
+    x: i32,
+}
+
+impl Foo {
+    fn new() -> Foo {
+        Foo { x: 0 }
+    }
+}
+
+impl Default for Foo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn make() -> Foo {
+    Foo::new()
+}
+"#,
+            r#"
+use bar::Foo;
+
+mod bar {
+    pub(super) struct Foo {
+        x: i32,
+    }
+
+    impl Foo {
+        pub(super) fn new() -> Foo {
+            Foo { x: 0 }
+        }
+    }
+
+    impl Default for Foo {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+fn make() -> Foo {
+    Foo::new()
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn move_into_parent_module() {
+        check_assist_by_label(
+            move_item_to_module,
+            r#"
+//- /main.rs crate:main deps:std
+mod foo {
+    use std::collections::HashMap;
+
+    struct Bar;
+
+    pub fn $0make(map: HashMap<u32, Bar>) -> Bar {
+        Bar
+    }
+
+    fn other() {
+        make(HashMap::new());
+    }
+}
+
+fn main() {
+    foo::make(Default::default());
+}
+//- /std.rs crate:std
+pub mod collections {
+    pub struct HashMap<K, V>(K, V);
+}
+"#,
+            r#"
+use std::collections::HashMap;
+
+use foo::Bar;
+
+mod foo {
+    use std::collections::HashMap;
+
+    use crate::make;
+
+    pub(super) struct Bar;
+
+    fn other() {
+        make(HashMap::new());
+    }
+}
+
+fn main() {
+    make(Default::default());
+}
+
+pub fn make(map: HashMap<u32, Bar>) -> Bar {
+    Bar
+}
+"#,
+            "Move `make` to `crate`",
+        );
+    }
+
+    #[test]
+    fn updates_imports_and_qualified_paths() {
+        check_assist(
+            move_item_to_module,
+            r#"
+//- /main.rs
+mod a;
+mod b;
+
+fn main() {
+    let _ = a::Foo;
+}
+//- /a.rs
+pub struct $0Foo;
+
+pub fn foo() {}
+//- /b.rs
+use crate::a::{foo, Foo};
+
+fn bar() -> Foo {
+    foo();
+    Foo
+}
+"#,
+            r#"
+//- /main.rs
+mod a;
+mod b;
+
+fn main() {
+    let _ = Foo;
+}
+
+pub struct Foo;
+//- /a.rs
+pub fn foo() {}
+//- /b.rs
+use crate::{Foo, a::{foo}};
+
+fn bar() -> Foo {
+    foo();
+    Foo
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn removes_imports_of_the_new_location() {
+        check_assist(
+            move_item_to_module,
+            r#"
+struct $0Foo;
+
+mod bar {
+    use super::Foo;
+
+    fn make() -> Foo {
+        Foo
+    }
+}
+"#,
+            r#"
+mod bar {
+    fn make() -> Foo {
+        Foo
+    }
+
+    struct Foo;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn splits_nested_use_trees() {
+        check_assist_by_label(
+            move_item_to_module,
+            r#"
+mod a {
+    pub struct $0Foo;
+
+    pub fn foo() {}
+
+    pub mod b {}
+}
+
+pub use a::{foo, Foo as Renamed};
+
+fn make() -> Renamed {
+    Renamed
+}
+"#,
+            r#"
+mod a {
+    pub fn foo() {}
+
+    pub mod b {
+        pub struct Foo;
+    }
+}
+
+pub use a::{foo};
+pub use a::b::Foo as Renamed;
+
+fn make() -> Renamed {
+    Renamed
+}
+"#,
+            "Move `Foo` to `crate::a::b`",
+        );
+    }
+
+    #[test]
+    fn rewrites_prefixes_of_moved_paths() {
+        check_assist_by_label(
+            move_item_to_module,
+            r#"
+mod a {
+    pub mod inner {
+        pub fn helper() {}
+    }
+
+    pub fn $0foo() {
+        self::inner::helper();
+        super::bar();
+    }
+}
+
+mod c {}
+
+fn bar() {}
+"#,
+            r#"
+mod a {
+    pub mod inner {
+        pub fn helper() {}
+    }
+}
+
+mod c {}
+
+fn bar() {}
+
+pub fn foo() {
+    a::inner::helper();
+    self::bar();
+}
+"#,
+            "Move `foo` to `crate`",
+        );
+    }
+
+    fn check_move(ra_fixture_before: &str, destination: &str, ra_fixture_after: &str) {
+        let (db, position) = RootDatabase::with_position(ra_fixture_before);
+        let source_change =
+            crate::move_item_to_module(&db, &TEST_CONFIG, position, destination, true).unwrap();
+        let mut text = db.file_text(position.file_id).to_string();
+        for (file_id, edit) in source_change.source_file_edits {
+            assert_eq!(file_id, position.file_id);
+            edit.apply(&mut text);
+        }
+        assert_eq_text!(&trim_indent(ra_fixture_after), &text);
+    }
+
+    #[test]
+    fn leaves_reexport() {
+        check_move(
+            r#"
+pub mod a {
+    pub mod b {}
+
+    pub fn $0foo() {}
+}
+
+fn main() {
+    a::foo();
+}
+"#,
+            "crate::a::b",
+            r#"
+pub mod a {
+    pub mod b {
+        pub fn foo() {}
+    }
+
+    pub use b::foo;
+}
+
+fn main() {
+    a::b::foo();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn reports_invalid_destinations() {
+        let (db, position) = RootDatabase::with_position(
+            r#"
+fn $0foo() {}
+
+fn bar() {}
+"#,
+        );
+        let error = |destination| {
+            crate::move_item_to_module(&db, &TEST_CONFIG, position, destination, false).unwrap_err()
+        };
+        assert_eq!(error("bar"), "`bar` is not a module");
+        assert_eq!(error("crate"), "`foo` is already in `crate`");
+        assert_eq!(error("a::"), "`a::` is not a valid path");
+    }
+}
@@ -10,28 +10,29 @@ use syntax::{
 use crate::{
     assist_context::{AssistBuilder, AssistContext, Assists},
     utils::{
-        add_trait_assoc_items_to_impl, filter_assoc_items, generate_trait_impl_text,
-        render_snippet, Cursor, DefaultMethods,
+        add_trait_assoc_items_to_impl, filter_assoc_items, gen_trait_fn_body::gen_trait_fn_body,
+        generate_trait_impl_text, render_snippet, Cursor, DefaultMethods,
     },
     AssistId, AssistKind,
 };
 
 // Assist: replace_derive_with_manual_impl
 //
-// Converts a `derive` impl into a manual one.
+// Converts a `derive` impl into a manual one. For the standard derivable traits, the
+// implementation the derive would have generated is filled in.
 //
 // ```
-// # trait Debug { fn fmt(&self, f: &mut Formatter) -> Result<()>; }
-// #[derive(Deb$0ug, Display)]
+// # trait Display { fn fmt(&self, f: &mut Formatter) -> Result<()>; }
+// #[derive(Debug, Disp$0lay)]
 // struct S;
 // ```
 // ->
 // ```
-// # trait Debug { fn fmt(&self, f: &mut Formatter) -> Result<()>; }
-// #[derive(Display)]
+// # trait Display { fn fmt(&self, f: &mut Formatter) -> Result<()>; }
+// #[derive(Debug)]
 // struct S;
 //
-// impl Debug for S {
+// impl Display for S {
 //     fn fmt(&self, f: &mut Formatter) -> Result<()> {
 //         ${0:todo!()}
 //     }
@@ -111,13 +112,16 @@ fn add_assist(
         |builder| {
             let insert_pos = adt.syntax().text_range().end();
             let impl_def_with_items =
-                impl_def_from_trait(&ctx.sema, &annotated_name, trait_, trait_path);
+                impl_def_from_trait(&ctx.sema, adt, &annotated_name, trait_, trait_path);
             update_attribute(builder, &input, &trait_name, &attr);
             let trait_path = format!("{}", trait_path);
             match (ctx.config.snippet_cap, impl_def_with_items) {
-                (None, _) => {
+                (None, None) => {
                     builder.insert(insert_pos, generate_trait_impl_text(adt, &trait_path, ""))
                 }
+                (None, Some((impl_def, _))) => {
+                    builder.insert(insert_pos, format!("\n\n{}", impl_def.syntax()))
+                }
                 (Some(cap), None) => builder.insert_snippet(
                     cap,
                     insert_pos,
@@ -149,6 +153,7 @@ fn add_assist(
 
 fn impl_def_from_trait(
     sema: &hir::Semantics<ide_db::RootDatabase>,
+    adt: &ast::Adt,
     annotated_name: &ast::Name,
     trait_: Option<hir::Trait>,
     trait_path: &ast::Path,
@@ -163,6 +168,19 @@ fn impl_def_from_trait(
         make::impl_trait(trait_path.clone(), make::ext::ident_path(&annotated_name.text()));
     let (impl_def, first_assoc_item) =
         add_trait_assoc_items_to_impl(sema, trait_items, trait_, impl_def, target_scope);
+
+    // Generate the body the derive would have expanded to, where we know how.
+    if let ast::AssocItem::Fn(func) = &first_assoc_item {
+        let adt = match adt {
+            ast::Adt::Struct(it) => sema.to_def(it).map(hir::Adt::Struct),
+            ast::Adt::Enum(it) => sema.to_def(it).map(hir::Adt::Enum),
+            ast::Adt::Union(it) => sema.to_def(it).map(hir::Adt::Union),
+        };
+        if let Some(adt) = adt {
+            // Without a body, the `todo!()` placeholder is kept.
+            gen_trait_fn_body(sema.db, func, trait_path, adt);
+        }
+    }
     Some((impl_def, first_assoc_item))
 }
 
@@ -202,7 +220,7 @@ fn update_attribute(
 
 #[cfg(test)]
 mod tests {
-    use crate::tests::{check_assist, check_assist_not_applicable};
+    use crate::tests::{check_assist, check_assist_no_snippet_cap, check_assist_not_applicable};
 
     use super::*;
 
@@ -240,13 +258,404 @@ struct Foo {
 }
 
 impl fmt::Debug for Foo {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        ${0:todo!()}
+    $0fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Foo").field("bar", &self.bar).finish()
     }
 }
 "#,
         )
     }
+
+    #[test]
+    fn add_custom_impl_debug_tuple_struct_and_enum() {
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Debug { fn fmt(&self, f: &mut Formatter) -> Result; }
+#[derive(Debu$0g)]
+enum Foo {
+    Bar(usize, String),
+    Baz { x: u32 },
+    Qux,
+}
+"#,
+            r#"
+trait Debug { fn fmt(&self, f: &mut Formatter) -> Result; }
+enum Foo {
+    Bar(usize, String),
+    Baz { x: u32 },
+    Qux,
+}
+
+impl Debug for Foo {
+    $0fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            Self::Bar(arg0, arg1) => f.debug_tuple("Bar").field(arg0).field(arg1).finish(),
+            Self::Baz { x } => f.debug_struct("Baz").field("x", x).finish(),
+            Self::Qux => f.write_str("Qux"),
+        }
+    }
+}
+"#,
+        );
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Debug { fn fmt(&self, f: &mut Formatter) -> Result; }
+#[derive(Debu$0g)]
+struct Foo(usize, String);
+"#,
+            r#"
+trait Debug { fn fmt(&self, f: &mut Formatter) -> Result; }
+struct Foo(usize, String);
+
+impl Debug for Foo {
+    $0fn fmt(&self, f: &mut Formatter) -> Result {
+        f.debug_tuple("Foo").field(&self.0).field(&self.1).finish()
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_custom_impl_clone() {
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Clone { fn clone(&self) -> Self; }
+#[derive(Clo$0ne)]
+enum Foo {
+    Bar(usize),
+    Baz { x: u32, y: String },
+    Qux,
+}
+"#,
+            r#"
+trait Clone { fn clone(&self) -> Self; }
+enum Foo {
+    Bar(usize),
+    Baz { x: u32, y: String },
+    Qux,
+}
+
+impl Clone for Foo {
+    $0fn clone(&self) -> Self {
+        match self {
+            Self::Bar(arg0) => Self::Bar(arg0.clone()),
+            Self::Baz { x, y } => Self::Baz { x: x.clone(), y: y.clone() },
+            Self::Qux => Self::Qux,
+        }
+    }
+}
+"#,
+        );
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Clone { fn clone(&self) -> Self; }
+#[derive(Clo$0ne)]
+struct Foo { bin: usize, bar: String }
+"#,
+            r#"
+trait Clone { fn clone(&self) -> Self; }
+struct Foo { bin: usize, bar: String }
+
+impl Clone for Foo {
+    $0fn clone(&self) -> Self {
+        Self { bin: self.bin.clone(), bar: self.bar.clone() }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_custom_impl_partial_eq() {
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait PartialEq { fn eq(&self, other: &Self) -> bool; }
+#[derive(Partial$0Eq)]
+struct Foo { bin: usize, bar: String }
+"#,
+            r#"
+trait PartialEq { fn eq(&self, other: &Self) -> bool; }
+struct Foo { bin: usize, bar: String }
+
+impl PartialEq for Foo {
+    $0fn eq(&self, other: &Self) -> bool {
+        self.bin == other.bin && self.bar == other.bar
+    }
+}
+"#,
+        );
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait PartialEq { fn eq(&self, other: &Self) -> bool; }
+#[derive(Partial$0Eq)]
+enum Foo {
+    Bar(usize),
+    Baz { x: u32 },
+    Qux,
+}
+"#,
+            r#"
+trait PartialEq { fn eq(&self, other: &Self) -> bool; }
+enum Foo {
+    Bar(usize),
+    Baz { x: u32 },
+    Qux,
+}
+
+impl PartialEq for Foo {
+    $0fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Bar(l0), Self::Bar(r0)) => l0 == r0,
+            (Self::Baz { x: l_x }, Self::Baz { x: r_x }) => l_x == r_x,
+            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_custom_impl_hash() {
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+mod hash {
+    pub trait Hash { fn hash<H: Hasher>(&self, state: &mut H); }
+}
+#[derive(Ha$0sh)]
+enum Foo {
+    Bar(usize, String),
+    Qux,
+}
+"#,
+            r#"
+mod hash {
+    pub trait Hash { fn hash<H: Hasher>(&self, state: &mut H); }
+}
+enum Foo {
+    Bar(usize, String),
+    Qux,
+}
+
+impl hash::Hash for Foo {
+    $0fn hash<H: Hasher>(&self, state: &mut H) {
+        hash::Hash::hash(&core::mem::discriminant(self), state);
+        match self {
+            Self::Bar(arg0, arg1) => {
+                hash::Hash::hash(arg0, state);
+                hash::Hash::hash(arg1, state);
+            }
+            _ => {}
+        }
+    }
+}
+"#,
+        );
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Hash { fn hash<H: Hasher>(&self, state: &mut H); }
+#[derive(Ha$0sh)]
+struct Foo;
+"#,
+            r#"
+trait Hash { fn hash<H: Hasher>(&self, state: &mut H); }
+struct Foo;
+
+impl Hash for Foo {
+    $0fn hash<H: Hasher>(&self, state: &mut H) {}
+}
+"#,
+        );
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Hash { fn hash<H: Hasher>(&self, state: &mut H); }
+#[derive(Ha$0sh)]
+struct Foo(usize, String);
+"#,
+            r#"
+trait Hash { fn hash<H: Hasher>(&self, state: &mut H); }
+struct Foo(usize, String);
+
+impl Hash for Foo {
+    $0fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.0, state);
+        Hash::hash(&self.1, state);
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_custom_impl_hash_field_named_like_param() {
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Hash { fn hash<H: Hasher>(&self, state: &mut H); }
+#[derive(Ha$0sh)]
+enum Foo {
+    A { state: u8, other: u8 },
+}
+"#,
+            r#"
+trait Hash { fn hash<H: Hasher>(&self, state: &mut H); }
+enum Foo {
+    A { state: u8, other: u8 },
+}
+
+impl Hash for Foo {
+    $0fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&core::mem::discriminant(self), state);
+        match self {
+            Self::A { state: state_, other } => {
+                Hash::hash(state_, state);
+                Hash::hash(other, state);
+            }
+        }
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_custom_impl_without_snippet_cap() {
+        check_assist_no_snippet_cap(
+            replace_derive_with_manual_impl,
+            r#"
+trait Clone { fn clone(&self) -> Self; }
+#[derive(Clo$0ne)]
+struct Foo(usize);
+"#,
+            r#"
+trait Clone { fn clone(&self) -> Self; }
+struct Foo(usize);
+
+impl Clone for Foo {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_custom_impl_default() {
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Default { fn default() -> Self; }
+#[derive(Defau$0lt)]
+struct Foo { bin: usize, bar: String }
+"#,
+            r#"
+trait Default { fn default() -> Self; }
+struct Foo { bin: usize, bar: String }
+
+impl Default for Foo {
+    $0fn default() -> Self {
+        Self { bin: Default::default(), bar: Default::default() }
+    }
+}
+"#,
+        );
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Default { fn default() -> Self; }
+#[derive(Defau$0lt)]
+enum Foo { Bar, Baz }
+"#,
+            r#"
+trait Default { fn default() -> Self; }
+enum Foo { Bar, Baz }
+
+impl Default for Foo {
+    fn default() -> Self {
+        ${0:todo!()}
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_custom_impl_ord() {
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait Ord { fn cmp(&self, other: &Self) -> Ordering; }
+#[derive(O$0rd)]
+struct Foo { bin: usize, bar: String }
+"#,
+            r#"
+trait Ord { fn cmp(&self, other: &Self) -> Ordering; }
+struct Foo { bin: usize, bar: String }
+
+impl Ord for Foo {
+    $0fn cmp(&self, other: &Self) -> Ordering {
+        match self.bin.cmp(&other.bin) {
+            core::cmp::Ordering::Equal => {}
+            ord => return ord,
+        }
+        self.bar.cmp(&other.bar)
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_custom_impl_partial_ord_enum() {
+        check_assist(
+            replace_derive_with_manual_impl,
+            r#"
+trait PartialOrd { fn partial_cmp(&self, other: &Self) -> Option<Ordering>; }
+#[derive(Partial$0Ord)]
+enum Foo {
+    Bar(usize, String),
+    Qux,
+}
+"#,
+            r#"
+trait PartialOrd { fn partial_cmp(&self, other: &Self) -> Option<Ordering>; }
+enum Foo {
+    Bar(usize, String),
+    Qux,
+}
+
+impl PartialOrd for Foo {
+    $0fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let variant_index = |it: &Self| match it {
+            Self::Bar(..) => 0,
+            Self::Qux => 1,
+        };
+        match (self, other) {
+            (Self::Bar(l0, l1), Self::Bar(r0, r1)) => {
+                match l0.partial_cmp(r0) {
+                    Some(core::cmp::Ordering::Equal) => {}
+                    ord => return ord,
+                }
+                l1.partial_cmp(r1)
+            }
+            _ => variant_index(self).partial_cmp(&variant_index(other)),
+        }
+    }
+}
+"#,
+        );
+    }
     #[test]
     fn add_custom_impl_all() {
         check_assist(
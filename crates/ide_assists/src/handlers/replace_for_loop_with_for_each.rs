@@ -42,24 +42,7 @@ pub(crate) fn replace_for_loop_with_for_each(acc: &mut Assists, ctx: &AssistCont
         "Replace this for loop with `Iterator::for_each`",
         for_loop.syntax().text_range(),
         |builder| {
-            let mut buf = String::new();
-
-            if let Some((expr_behind_ref, method)) =
-                is_ref_and_impls_iter_method(&ctx.sema, &iterable)
-            {
-                // We have either "for x in &col" and col implements a method called iter
-                //             or "for x in &mut col" and col implements a method called iter_mut
-                format_to!(buf, "{}.{}()", expr_behind_ref, method);
-            } else if impls_core_iter(&ctx.sema, &iterable) {
-                format_to!(buf, "{}", iterable);
-            } else {
-                if let ast::Expr::RefExpr(_) = iterable {
-                    format_to!(buf, "({}).into_iter()", iterable);
-                } else {
-                    format_to!(buf, "{}.into_iter()", iterable);
-                }
-            }
-
+            let mut buf = iterator_of(&ctx.sema, &iterable);
             format_to!(buf, ".for_each(|{}| {});", pat, body);
 
             builder.replace(for_loop.syntax().text_range(), buf)
@@ -67,6 +50,28 @@ pub(crate) fn replace_for_loop_with_for_each(acc: &mut Assists, ctx: &AssistCont
     )
 }
 
+/// Renders an iterator over the items a `for` loop over `iterable` visits.
+pub(crate) fn iterator_of(
+    sema: &hir::Semantics<ide_db::RootDatabase>,
+    iterable: &ast::Expr,
+) -> String {
+    let mut buf = String::new();
+    if let Some((expr_behind_ref, method)) = is_ref_and_impls_iter_method(sema, iterable) {
+        // We have either "for x in &col" and col implements a method called iter
+        //             or "for x in &mut col" and col implements a method called iter_mut
+        format_to!(buf, "{}.{}()", expr_behind_ref, method);
+    } else if impls_core_iter(sema, iterable) {
+        format_to!(buf, "{}", iterable);
+    } else {
+        if let ast::Expr::RefExpr(_) = iterable {
+            format_to!(buf, "({}).into_iter()", iterable);
+        } else {
+            format_to!(buf, "{}.into_iter()", iterable);
+        }
+    }
+    buf
+}
+
 /// If iterable is a reference where the expression behind the reference implements a method
 /// returning an Iterator called iter or iter_mut (depending on the type of reference) then return
 /// the expression behind the reference and the method name
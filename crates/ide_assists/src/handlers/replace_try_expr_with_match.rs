@@ -0,0 +1,482 @@
+use std::iter;
+
+use ide_db::ty_filter::TryEnum;
+use itertools::Itertools;
+use syntax::{
+    ast::{
+        self,
+        edit::{AstNodeEdit, IndentLevel},
+        make, ArgListOwner, NameOwner,
+    },
+    AstNode, SyntaxKind, SyntaxNode,
+};
+
+use crate::{utils::extract_trivial_expression, AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: replace_try_expr_with_match
+//
+// Replaces a `?` expression with the equivalent `match` expression.
+//
+// ```
+// enum Result<T, E> { Ok(T), Err(E) }
+// use Result::*;
+// fn handle(x: Result<i32, ()>) -> Result<i32, ()> {
+//     let y = x$0?;
+//     Ok(y)
+// }
+// ```
+// ->
+// ```
+// enum Result<T, E> { Ok(T), Err(E) }
+// use Result::*;
+// fn handle(x: Result<i32, ()>) -> Result<i32, ()> {
+//     let y = match x {
+//         Ok(it) => it,
+//         Err(err) => return Err(From::from(err)),
+//     };
+//     Ok(y)
+// }
+// ```
+pub(crate) fn replace_try_expr_with_match(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let try_expr: ast::TryExpr = ctx.find_node_at_offset()?;
+    let operand = try_expr.expr()?;
+    let ty = ctx.sema.type_of_expr(&operand)?;
+    let try_enum = TryEnum::from_ty(&ctx.sema, &ty)?;
+
+    let target = try_expr.syntax().text_range();
+    acc.add(
+        AssistId("replace_try_expr_with_match", AssistKind::RefactorRewrite),
+        "Replace `?` with `match`",
+        target,
+        |builder| {
+            let happy_pat = make::tuple_struct_pat(
+                make::ext::ident_path(try_enum.happy_case()),
+                iter::once(make::ident_pat(make::name("it")).into()),
+            );
+            let happy_arm = make::match_arm(
+                iter::once(happy_pat.into()),
+                make::expr_path(make::ext::ident_path("it")),
+            );
+
+            let sad_arm = match try_enum {
+                TryEnum::Result => {
+                    let err_pat = make::tuple_struct_pat(
+                        make::ext::ident_path("Err"),
+                        iter::once(make::ident_pat(make::name("err")).into()),
+                    );
+                    let converted = make::expr_call(
+                        make::expr_path(make::path_from_text("From::from")),
+                        make::arg_list(iter::once(make::expr_path(make::ext::ident_path("err")))),
+                    );
+                    let err = make::expr_call(
+                        make::expr_path(make::ext::ident_path("Err")),
+                        make::arg_list(iter::once(converted)),
+                    );
+                    make::match_arm(iter::once(err_pat.into()), make::expr_return(Some(err)))
+                }
+                TryEnum::Option => make::match_arm(
+                    iter::once(make::ident_pat(make::name("None")).into()),
+                    make::expr_return(Some(make::expr_path(make::ext::ident_path("None")))),
+                ),
+            };
+
+            let match_arm_list = make::match_arm_list(vec![happy_arm, sad_arm]);
+            let mut match_expr = make::expr_match(operand.clone(), match_arm_list);
+            if try_expr.syntax().parent().filter(is_postfix_parent).is_some() {
+                match_expr = make::expr_paren(match_expr);
+            }
+            let match_expr = match_expr.indent(IndentLevel::from_node(try_expr.syntax()));
+
+            builder.replace_ast::<ast::Expr>(try_expr.into(), match_expr);
+        },
+    )
+}
+
+// Assist: replace_match_with_try_expr
+//
+// Replaces a `match` which propagates the `Err` or `None` case with a `?` expression.
+//
+// ```
+// enum Result<T, E> { Ok(T), Err(E) }
+// use Result::*;
+// fn handle(x: Result<i32, ()>) -> Result<i32, ()> {
+//     let y = $0match x {
+//         Ok(y) => y,
+//         Err(e) => return Err(e),
+//     };
+//     Ok(y)
+// }
+// ```
+// ->
+// ```
+// enum Result<T, E> { Ok(T), Err(E) }
+// use Result::*;
+// fn handle(x: Result<i32, ()>) -> Result<i32, ()> {
+//     let y = x?;
+//     Ok(y)
+// }
+// ```
+pub(crate) fn replace_match_with_try_expr(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let match_expr: ast::MatchExpr = ctx.find_node_at_offset()?;
+    let mut arms = match_expr.match_arm_list()?.arms();
+    let first_arm = arms.next()?;
+    let second_arm = arms.next()?;
+    if arms.next().is_some() || first_arm.guard().is_some() || second_arm.guard().is_some() {
+        return None;
+    }
+    let scrutinee = match_expr.expr()?;
+    let ty = ctx.sema.type_of_expr(&scrutinee)?;
+    let try_enum = TryEnum::from_ty(&ctx.sema, &ty)?;
+
+    let is_desugared = |happy: &ast::MatchArm, sad: &ast::MatchArm| {
+        is_happy_arm(try_enum, happy).is_some() && is_sad_arm(try_enum, sad).is_some()
+    };
+    if !is_desugared(&first_arm, &second_arm) && !is_desugared(&second_arm, &first_arm) {
+        return None;
+    }
+
+    let target = match_expr.syntax().text_range();
+    acc.add(
+        AssistId("replace_match_with_try_expr", AssistKind::RefactorRewrite),
+        "Replace `match` with `?`",
+        target,
+        |builder| {
+            let operand = if is_postfix_operand(&scrutinee) {
+                scrutinee
+            } else {
+                make::expr_paren(scrutinee)
+            };
+            builder.replace_ast::<ast::Expr>(match_expr.into(), make::expr_try(operand));
+        },
+    )
+}
+
+/// Checks that the arm is `Ok(x) => x` (or `Some(x) => x`).
+fn is_happy_arm(try_enum: TryEnum, arm: &ast::MatchArm) -> Option<()> {
+    let binding = single_binding(&arm.pat()?, try_enum.happy_case())?;
+    match arm_expr(arm)? {
+        ast::Expr::PathExpr(path) if is_path_to(&path, &binding) => Some(()),
+        _ => None,
+    }
+}
+
+/// Checks that the arm is `Err(e) => return Err(e)` (or `None => return None`), allowing the
+/// error to be converted with `From::from`, `Into::into` or `.into()`.
+fn is_sad_arm(try_enum: TryEnum, arm: &ast::MatchArm) -> Option<()> {
+    let returned = match arm_expr(arm)? {
+        ast::Expr::ReturnExpr(ret) => ret.expr()?,
+        _ => return None,
+    };
+    match try_enum {
+        TryEnum::Option => {
+            if arm.pat()?.syntax().text() != try_enum.sad_pattern().syntax().text() {
+                return None;
+            }
+            match returned {
+                ast::Expr::PathExpr(path) if is_path_to(&path, "None") => Some(()),
+                _ => None,
+            }
+        }
+        TryEnum::Result => {
+            let binding = single_binding(&arm.pat()?, "Err")?;
+            let call = match returned {
+                ast::Expr::CallExpr(call) => call,
+                _ => return None,
+            };
+            match call.expr()? {
+                ast::Expr::PathExpr(path) if is_path_to(&path, "Err") => (),
+                _ => return None,
+            }
+            let (err,) = call.arg_list()?.args().collect_tuple()?;
+            is_error_conversion(&err, &binding)
+        }
+    }
+}
+
+fn is_error_conversion(expr: &ast::Expr, binding: &str) -> Option<()> {
+    match expr {
+        ast::Expr::PathExpr(path) if is_path_to(path, binding) => Some(()),
+        ast::Expr::CallExpr(call) => {
+            let callee = match call.expr()? {
+                ast::Expr::PathExpr(path) => path.path()?,
+                _ => return None,
+            };
+            let callee = callee.syntax().text();
+            if callee != "From::from" && callee != "Into::into" {
+                return None;
+            }
+            let (arg,) = call.arg_list()?.args().collect_tuple()?;
+            match arg {
+                ast::Expr::PathExpr(path) if is_path_to(&path, binding) => Some(()),
+                _ => None,
+            }
+        }
+        ast::Expr::MethodCallExpr(call) => {
+            if call.name_ref()?.text() != "into" || call.arg_list()?.args().next().is_some() {
+                return None;
+            }
+            match call.receiver()? {
+                ast::Expr::PathExpr(path) if is_path_to(&path, binding) => Some(()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Matches `Variant(binding)` where `Variant` may be qualified, returning the binding's name.
+fn single_binding(pat: &ast::Pat, variant: &str) -> Option<String> {
+    let pat = match pat {
+        ast::Pat::TupleStructPat(it) => it,
+        _ => return None,
+    };
+    if pat.path()?.segment()?.name_ref()?.text() != variant {
+        return None;
+    }
+    match pat.fields().collect_tuple()? {
+        (ast::Pat::IdentPat(ident),)
+            if ident.ref_token().is_none()
+                && ident.mut_token().is_none()
+                && ident.pat().is_none() =>
+        {
+            Some(ident.name()?.text().to_string())
+        }
+        _ => None,
+    }
+}
+
+fn arm_expr(arm: &ast::MatchArm) -> Option<ast::Expr> {
+    match arm.expr()? {
+        ast::Expr::BlockExpr(block) => extract_trivial_expression(&block),
+        expr => Some(expr),
+    }
+}
+
+fn is_path_to(path: &ast::PathExpr, name: &str) -> bool {
+    path.path().filter(|path| path.syntax().text() == name).is_some()
+}
+
+fn is_postfix_parent(parent: &SyntaxNode) -> bool {
+    matches!(
+        parent.kind(),
+        SyntaxKind::METHOD_CALL_EXPR
+            | SyntaxKind::FIELD_EXPR
+            | SyntaxKind::TRY_EXPR
+            | SyntaxKind::AWAIT_EXPR
+            | SyntaxKind::INDEX_EXPR
+    )
+}
+
+fn is_postfix_operand(expr: &ast::Expr) -> bool {
+    matches!(
+        expr,
+        ast::Expr::PathExpr(_)
+            | ast::Expr::CallExpr(_)
+            | ast::Expr::MethodCallExpr(_)
+            | ast::Expr::FieldExpr(_)
+            | ast::Expr::IndexExpr(_)
+            | ast::Expr::TryExpr(_)
+            | ast::Expr::AwaitExpr(_)
+            | ast::Expr::ParenExpr(_)
+            | ast::Expr::TupleExpr(_)
+            | ast::Expr::ArrayExpr(_)
+            | ast::Expr::Literal(_)
+            | ast::Expr::MacroCall(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{check_assist, check_assist_not_applicable, check_assist_target};
+
+    use super::*;
+
+    #[test]
+    fn replace_result_try_with_match() {
+        check_assist(
+            replace_try_expr_with_match,
+            r#"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn parse() -> Result<i32, ()> { Ok(92) }
+fn f() -> Result<i32, ()> {
+    let x = parse()?$0;
+    Ok(x)
+}
+"#,
+            r#"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn parse() -> Result<i32, ()> { Ok(92) }
+fn f() -> Result<i32, ()> {
+    let x = match parse() {
+        Ok(it) => it,
+        Err(err) => return Err(From::from(err)),
+    };
+    Ok(x)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn replace_option_try_with_match_in_method_chain() {
+        check_assist(
+            replace_try_expr_with_match,
+            r#"
+enum Option<T> { Some(T), None }
+use Option::*;
+struct S;
+impl S { fn len(&self) -> usize { 0 } }
+fn f(s: Option<S>) -> Option<usize> {
+    Some(s$0?.len())
+}
+"#,
+            r#"
+enum Option<T> { Some(T), None }
+use Option::*;
+struct S;
+impl S { fn len(&self) -> usize { 0 } }
+fn f(s: Option<S>) -> Option<usize> {
+    Some((match s {
+        Some(it) => it,
+        None => return None,
+    }).len())
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn try_target() {
+        check_assist_target(
+            replace_try_expr_with_match,
+            r#"
+enum Option<T> { Some(T), None }
+fn f(s: Option<i32>) -> Option<i32> {
+    let x = s$0?;
+    s
+}
+"#,
+            "s?",
+        );
+    }
+
+    #[test]
+    fn try_not_applicable_to_other_types() {
+        check_assist_not_applicable(
+            replace_try_expr_with_match,
+            r#"
+struct Poll<T>(T);
+fn f(s: Poll<i32>) -> Poll<i32> {
+    let x = s$0?;
+    s
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn replace_match_with_try() {
+        check_assist(
+            replace_match_with_try_expr,
+            r#"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn parse() -> Result<i32, ()> { Ok(92) }
+fn f() -> Result<i32, ()> {
+    let x = $0match parse() {
+        Err(e) => { return Err(From::from(e)); }
+        Result::Ok(x) => x,
+    };
+    Ok(x)
+}
+"#,
+            r#"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn parse() -> Result<i32, ()> { Ok(92) }
+fn f() -> Result<i32, ()> {
+    let x = parse()?;
+    Ok(x)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn replace_option_match_with_try() {
+        check_assist(
+            replace_match_with_try_expr,
+            r#"
+enum Option<T> { Some(T), None }
+use Option::*;
+fn f(a: Option<&i32>) -> Option<i32> {
+    let x = *$0match a {
+        Some(it) => it,
+        None => return None,
+    };
+    Some(x)
+}
+"#,
+            r#"
+enum Option<T> { Some(T), None }
+use Option::*;
+fn f(a: Option<&i32>) -> Option<i32> {
+    let x = *a?;
+    Some(x)
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn replace_match_with_try_adds_parens() {
+        check_assist(
+            replace_match_with_try_expr,
+            r#"
+enum Option<T> { Some(T), None }
+use Option::*;
+fn f(a: &Option<i32>) -> Option<i32> {
+    match *a {
+        Some(it) => Some(it),
+        None => return None,
+    };
+    $0match *a {
+        Some(it) => it,
+        None => return None,
+    }
+}
+"#,
+            r#"
+enum Option<T> { Some(T), None }
+use Option::*;
+fn f(a: &Option<i32>) -> Option<i32> {
+    match *a {
+        Some(it) => Some(it),
+        None => return None,
+    };
+    (*a)?
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn match_with_try_not_applicable_to_other_shapes() {
+        check_assist_not_applicable(
+            replace_match_with_try_expr,
+            r#"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn f(r: Result<i32, i32>) -> Result<i32, i32> {
+    let x = $0match r {
+        Ok(x) => x,
+        Err(e) => return Err(e + 1),
+    };
+    Ok(x)
+}
+"#,
+        );
+    }
+}
@@ -20,7 +20,7 @@ pub mod path_transform;
 use std::str::FromStr;
 
 use hir::Semantics;
-use ide_db::base_db::FileRange;
+use ide_db::base_db::{FilePosition, FileRange};
 use ide_db::{label::Label, source_change::SourceChange, RootDatabase};
 use syntax::TextRange;
 
@@ -170,6 +170,24 @@ impl Assist {
     }
 }
 
+/// Moves the item whose name is at `position` into the module `destination`
+/// resolves to, updating all references to it. With `reexport`, the item
+/// stays reachable under its old path through a `use` item.
+pub fn move_item_to_module(
+    db: &RootDatabase,
+    config: &AssistConfig,
+    position: FilePosition,
+    destination: &str,
+    reexport: bool,
+) -> Result<SourceChange, String> {
+    let sema = Semantics::new(db);
+    let frange = FileRange { file_id: position.file_id, range: TextRange::empty(position.offset) };
+    let ctx = AssistContext::new(sema, config, frange);
+    let mut builder = assist_context::AssistBuilder::new(position.file_id);
+    handlers::move_item_to_module::move_item_to(&ctx, &mut builder, destination, reexport)?;
+    Ok(builder.finish())
+}
+
 mod handlers {
     use crate::{AssistContext, Assists};
 
@@ -184,12 +202,19 @@ mod handlers {
     mod change_visibility;
     mod convert_integer_literal;
     mod convert_comment_block;
+    mod convert_bool_to_enum;
+    mod convert_closure_to_fn;
+    mod convert_for_loop_to_iterator_chain;
     mod convert_iter_for_each_to_for;
     mod convert_into_to_from;
+    mod convert_named_struct_to_tuple_struct;
     mod convert_tuple_struct_to_named_struct;
+    mod destructure_binding;
     mod early_return;
     mod expand_glob_import;
+    mod extract_constant;
     mod extract_function;
+    mod extract_module;
     mod extract_struct_from_enum_variant;
     mod extract_type_alias;
     mod extract_variable;
@@ -200,8 +225,10 @@ mod handlers {
     mod flip_trait_bound;
     mod generate_default_from_enum_variant;
     mod generate_default_from_new;
+    mod generate_delegate_methods;
     mod generate_is_empty_from_len;
     mod generate_deref;
+    mod generate_documentation_template;
     mod generate_derive;
     mod generate_enum_is_method;
     mod generate_enum_projection_method;
@@ -212,15 +239,22 @@ mod handlers {
     mod generate_impl;
     mod generate_new;
     mod generate_setter;
+    mod generate_struct;
+    mod generate_trait_from_impl;
     mod infer_function_return_type;
     mod inline_function;
     mod inline_local_variable;
+    mod inline_macro_call;
+    mod inline_type_alias;
     mod introduce_named_lifetime;
+    mod introduce_parameter_struct;
     mod invert_if;
+    mod make_async;
     mod merge_imports;
     mod merge_match_arms;
     mod move_bounds;
     mod move_guard;
+    pub(crate) mod move_item_to_module;
     mod move_module_to_file;
     mod pull_assignment_up;
     mod qualify_path;
@@ -237,6 +271,7 @@ mod handlers {
     mod replace_let_with_if_let;
     mod replace_qualified_name_with_use;
     mod replace_string_with_char;
+    mod replace_try_expr_with_match;
     mod replace_unwrap_with_match;
     mod split_import;
     mod toggle_ignore;
@@ -255,11 +290,21 @@ mod handlers {
             change_visibility::change_visibility,
             convert_integer_literal::convert_integer_literal,
             convert_comment_block::convert_comment_block,
+            convert_bool_to_enum::convert_bool_to_enum,
+            convert_closure_to_fn::convert_closure_to_fn,
+            convert_closure_to_fn::convert_fn_to_closure,
+            convert_for_loop_to_iterator_chain::convert_for_loop_to_iterator_chain,
+            convert_for_loop_to_iterator_chain::convert_iterator_chain_to_for_loop,
             convert_iter_for_each_to_for::convert_iter_for_each_to_for,
             convert_into_to_from::convert_into_to_from,
+            convert_named_struct_to_tuple_struct::convert_named_struct_to_tuple_struct,
             convert_tuple_struct_to_named_struct::convert_tuple_struct_to_named_struct,
+            destructure_binding::destructure_binding,
             early_return::convert_to_guarded_return,
             expand_glob_import::expand_glob_import,
+            extract_constant::extract_constant,
+            extract_constant::extract_static,
+            extract_module::extract_module,
             extract_struct_from_enum_variant::extract_struct_from_enum_variant,
             extract_type_alias::extract_type_alias,
             fill_match_arms::fill_match_arms,
@@ -269,8 +314,10 @@ mod handlers {
             flip_trait_bound::flip_trait_bound,
             generate_default_from_enum_variant::generate_default_from_enum_variant,
             generate_default_from_new::generate_default_from_new,
+            generate_delegate_methods::generate_delegate_methods,
             generate_is_empty_from_len::generate_is_empty_from_len,
             generate_deref::generate_deref,
+            generate_documentation_template::generate_documentation_template,
             generate_derive::generate_derive,
             generate_enum_is_method::generate_enum_is_method,
             generate_enum_projection_method::generate_enum_as_method,
@@ -282,16 +329,26 @@ mod handlers {
             generate_impl::generate_impl,
             generate_new::generate_new,
             generate_setter::generate_setter,
+            generate_struct::generate_struct,
+            generate_struct::generate_enum_variant,
+            generate_trait_from_impl::generate_trait_from_impl,
             infer_function_return_type::infer_function_return_type,
             inline_function::inline_function,
+            inline_function::inline_into_callers,
             inline_local_variable::inline_local_variable,
+            inline_macro_call::inline_macro_call,
+            inline_type_alias::inline_type_alias,
+            inline_type_alias::inline_type_alias_uses,
             introduce_named_lifetime::introduce_named_lifetime,
+            introduce_parameter_struct::introduce_parameter_struct,
             invert_if::invert_if,
+            make_async::make_async,
             merge_imports::merge_imports,
             merge_match_arms::merge_match_arms,
             move_bounds::move_bounds_to_where_clause,
             move_guard::move_arm_cond_to_match_guard,
             move_guard::move_guard_to_arm_body,
+            move_item_to_module::move_item_to_module,
             move_module_to_file::move_module_to_file,
             pull_assignment_up::pull_assignment_up,
             qualify_path::qualify_path,
@@ -310,6 +367,8 @@ mod handlers {
             replace_impl_trait_with_generic::replace_impl_trait_with_generic,
             replace_let_with_if_let::replace_let_with_if_let,
             replace_qualified_name_with_use::replace_qualified_name_with_use,
+            replace_try_expr_with_match::replace_try_expr_with_match,
+            replace_try_expr_with_match::replace_match_with_try_expr,
             replace_unwrap_with_match::replace_unwrap_with_match,
             split_import::split_import,
             toggle_ignore::toggle_ignore,
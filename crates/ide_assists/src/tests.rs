@@ -3,7 +3,10 @@ mod generated;
 use expect_test::expect;
 use hir::Semantics;
 use ide_db::{
-    base_db::{fixture::WithFixture, FileId, FileRange, SourceDatabaseExt},
+    base_db::{
+        fixture::{ChangeFixture, WithFixture},
+        FileId, FileRange, ProcMacro, SourceDatabaseExt,
+    },
     helpers::{
         insert_use::{ImportGranularity, InsertUseConfig},
         SnippetCap,
@@ -31,6 +34,9 @@ pub(crate) const TEST_CONFIG: AssistConfig = AssistConfig {
     },
 };
 
+pub(crate) const TEST_CONFIG_NO_SNIPPET_CAP: AssistConfig =
+    AssistConfig { snippet_cap: None, ..TEST_CONFIG };
+
 pub(crate) fn with_single_file(text: &str) -> (RootDatabase, FileId) {
     RootDatabase::with_single_file(text)
 }
@@ -40,6 +46,42 @@ pub(crate) fn check_assist(assist: Handler, ra_fixture_before: &str, ra_fixture_
     check(assist, ra_fixture_before, ExpectedResult::After(&ra_fixture_after), None);
 }
 
+#[track_caller]
+pub(crate) fn check_assist_no_snippet_cap(
+    assist: Handler,
+    ra_fixture_before: &str,
+    ra_fixture_after: &str,
+) {
+    let ra_fixture_after = trim_indent(ra_fixture_after);
+    check_with_config(
+        TEST_CONFIG_NO_SNIPPET_CAP,
+        assist,
+        ra_fixture_before,
+        ExpectedResult::After(&ra_fixture_after),
+        None,
+    );
+}
+
+// Proc macros can't be declared in the fixture itself, so the expanders are
+// passed in and given to every crate of the fixture.
+#[track_caller]
+pub(crate) fn check_assist_with_proc_macros(
+    assist: Handler,
+    proc_macros: Vec<ProcMacro>,
+    ra_fixture_before: &str,
+    ra_fixture_after: &str,
+) {
+    let ra_fixture_after = trim_indent(ra_fixture_after);
+    check_with_proc_macros(
+        TEST_CONFIG,
+        proc_macros,
+        assist,
+        ra_fixture_before,
+        ExpectedResult::After(&ra_fixture_after),
+        None,
+    );
+}
+
 // There is no way to choose what assist within a group you want to test against,
 // so this is here to allow you choose.
 pub(crate) fn check_assist_by_label(
@@ -65,6 +107,11 @@ pub(crate) fn check_assist_not_applicable(assist: Handler, ra_fixture: &str) {
     check(assist, ra_fixture, ExpectedResult::NotApplicable, None);
 }
 
+#[track_caller]
+pub(crate) fn check_assist_not_applicable_by_label(assist: Handler, ra_fixture: &str, label: &str) {
+    check(assist, ra_fixture, ExpectedResult::NotApplicable, Some(label));
+}
+
 /// Check assist in unresolved state. Useful to check assists for lazy computation.
 #[track_caller]
 pub(crate) fn check_assist_unresolved(assist: Handler, ra_fixture: &str) {
@@ -114,13 +161,40 @@ enum ExpectedResult<'a> {
 
 #[track_caller]
 fn check(handler: Handler, before: &str, expected: ExpectedResult, assist_label: Option<&str>) {
-    let (db, file_with_caret_id, range_or_offset) = RootDatabase::with_range_or_offset(before);
+    check_with_config(TEST_CONFIG, handler, before, expected, assist_label);
+}
+
+#[track_caller]
+fn check_with_config(
+    config: AssistConfig,
+    handler: Handler,
+    before: &str,
+    expected: ExpectedResult,
+    assist_label: Option<&str>,
+) {
+    check_with_proc_macros(config, Vec::new(), handler, before, expected, assist_label);
+}
+
+#[track_caller]
+fn check_with_proc_macros(
+    config: AssistConfig,
+    proc_macros: Vec<ProcMacro>,
+    handler: Handler,
+    before: &str,
+    expected: ExpectedResult,
+    assist_label: Option<&str>,
+) {
+    let fixture = ChangeFixture::parse_with_proc_macros(before, proc_macros);
+    let mut db = RootDatabase::default();
+    fixture.change.apply(&mut db);
+    let (file_with_caret_id, range_or_offset) = fixture
+        .file_position
+        .expect("Could not find file position in fixture. Did you forget to add an `$0`?");
     let text_without_caret = db.file_text(file_with_caret_id).to_string();
 
     let frange = FileRange { file_id: file_with_caret_id, range: range_or_offset.into() };
 
     let sema = Semantics::new(&db);
-    let config = TEST_CONFIG;
     let ctx = AssistContext::new(sema, &config, frange);
     let resolve = match expected {
         ExpectedResult::Unresolved => AssistResolveStrategy::None,
@@ -218,6 +292,7 @@ fn assist_order_field_struct() {
     assert_eq!(assists.next().expect("expected assist").label, "Generate a mut getter method");
     assert_eq!(assists.next().expect("expected assist").label, "Generate a getter method");
     assert_eq!(assists.next().expect("expected assist").label, "Generate a setter method");
+    assert_eq!(assists.next().expect("expected assist").label, "Convert to tuple struct");
     assert_eq!(assists.next().expect("expected assist").label, "Add `#[derive]`");
 }
 
@@ -240,6 +315,8 @@ pub fn test_some_range(a: int) -> bool {
 
     expect![[r#"
         Convert integer base
+        Extract into constant…
+        Extract into static…
         Extract into variable
         Extract into function
         Replace with match
@@ -269,6 +346,8 @@ pub fn test_some_range(a: int) -> bool {
 
         expect![[r#"
             Convert integer base
+            Extract into constant…
+            Extract into static…
             Extract into variable
             Extract into function
             Replace with match
@@ -283,6 +362,8 @@ pub fn test_some_range(a: int) -> bool {
         let expected = labels(&assists);
 
         expect![[r#"
+            Extract into constant…
+            Extract into static…
             Extract into variable
             Extract into function
         "#]]
@@ -318,9 +399,18 @@ pub fn test_some_range(a: int) -> bool {
 
     {
         let assists = Assist::get(&db, &cfg, AssistResolveStrategy::None, frange);
-        assert_eq!(2, assists.len());
+        assert_eq!(6, assists.len());
         let mut assists = assists.into_iter();
 
+        for label in [
+            "Extract into constant in function",
+            "Extract into constant in module",
+            "Extract into static in function",
+            "Extract into static in module",
+        ] {
+            assert_eq!(assists.next().unwrap().label, label);
+        }
+
         let extract_into_variable_assist = assists.next().unwrap();
         expect![[r#"
             Assist {
@@ -362,9 +452,18 @@ pub fn test_some_range(a: int) -> bool {
             }),
             frange,
         );
-        assert_eq!(2, assists.len());
+        assert_eq!(6, assists.len());
         let mut assists = assists.into_iter();
 
+        for label in [
+            "Extract into constant in function",
+            "Extract into constant in module",
+            "Extract into static in function",
+            "Extract into static in module",
+        ] {
+            assert_eq!(assists.next().unwrap().label, label);
+        }
+
         let extract_into_variable_assist = assists.next().unwrap();
         expect![[r#"
             Assist {
@@ -406,9 +505,18 @@ pub fn test_some_range(a: int) -> bool {
             }),
             frange,
         );
-        assert_eq!(2, assists.len());
+        assert_eq!(6, assists.len());
         let mut assists = assists.into_iter();
 
+        for label in [
+            "Extract into constant in function",
+            "Extract into constant in module",
+            "Extract into static in function",
+            "Extract into static in module",
+        ] {
+            assert_eq!(assists.next().unwrap().label, label);
+        }
+
         let extract_into_variable_assist = assists.next().unwrap();
         expect![[r#"
             Assist {
@@ -463,9 +571,18 @@ pub fn test_some_range(a: int) -> bool {
 
     {
         let assists = Assist::get(&db, &cfg, AssistResolveStrategy::All, frange);
-        assert_eq!(2, assists.len());
+        assert_eq!(6, assists.len());
         let mut assists = assists.into_iter();
 
+        for label in [
+            "Extract into constant in function",
+            "Extract into constant in module",
+            "Extract into static in function",
+            "Extract into static in module",
+        ] {
+            assert_eq!(assists.next().unwrap().label, label);
+        }
+
         let extract_into_variable_assist = assists.next().unwrap();
         expect![[r#"
             Assist {
@@ -191,6 +191,112 @@ pub(crate) fn frobnicate() {}
     )
 }
 
+#[test]
+fn doctest_convert_bool_to_enum() {
+    check_doc_test(
+        "convert_bool_to_enum",
+        r#####"
+fn open(path: &str, $0create: bool) {
+    if create {
+        make_file(path);
+    }
+}
+
+fn main() {
+    open("a.txt", true);
+}
+"#####,
+        r#####"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Create {
+    Yes,
+    No,
+}
+
+fn open(path: &str, create: Create) {
+    if create == Create::Yes {
+        make_file(path);
+    }
+}
+
+fn main() {
+    open("a.txt", Create::Yes);
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_closure_to_fn() {
+    check_doc_test(
+        "convert_closure_to_fn",
+        r#####"
+struct Config { step: i32 }
+fn main() {
+    let config = Config { step: 2 };
+    let next = |$0x: i32| x + config.step;
+    next(1);
+}
+"#####,
+        r#####"
+struct Config { step: i32 }
+fn main() {
+    let config = Config { step: 2 };
+    fn next(x: i32, config: &Config) -> i32 {
+        x + config.step
+    }
+    next(1, &config);
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_fn_to_closure() {
+    check_doc_test(
+        "convert_fn_to_closure",
+        r#####"
+fn apply(f: impl Fn(i32) -> i32) {}
+fn main() {
+    fn double(x: i32) -> i32 {
+        x * 2
+    }
+    apply(double$0);
+}
+"#####,
+        r#####"
+fn apply(f: impl Fn(i32) -> i32) {}
+fn main() {
+    apply(|x| x * 2);
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_for_loop_to_iterator_chain() {
+    check_doc_test(
+        "convert_for_loop_to_iterator_chain",
+        r#####"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut doubled = Vec::new();
+    for$0 x in xs {
+        if x > 1 {
+            doubled.push(x * 2);
+        }
+    }
+}
+"#####,
+        r#####"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let doubled: Vec<_> = xs.into_iter().filter(|x| *x > 1).map(|x| x * 2).collect();
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_convert_integer_literal() {
     check_doc_test(
@@ -266,6 +372,70 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_convert_iterator_chain_to_for_loop() {
+    check_doc_test(
+        "convert_iterator_chain_to_for_loop",
+        r#####"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let total: i32 = xs.into_iter().map(|x| x * 2).sum$0();
+}
+"#####,
+        r#####"
+fn main() {
+    let xs = vec![1, 2, 3];
+    let mut total: i32 = 0;
+    for x in xs.into_iter() {
+        let x = x * 2;
+        total += x;
+    }
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_convert_named_struct_to_tuple_struct() {
+    check_doc_test(
+        "convert_named_struct_to_tuple_struct",
+        r#####"
+struct Point$0 { x: f32, y: f32 }
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Point { x, y }
+    }
+
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+}
+"#####,
+        r#####"
+struct Point(f32, f32);
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Point(x, y)
+    }
+
+    pub fn x(&self) -> f32 {
+        self.0
+    }
+
+    pub fn y(&self) -> f32 {
+        self.1
+    }
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_convert_to_guarded_return() {
     check_doc_test(
@@ -331,6 +501,25 @@ impl Point {
     )
 }
 
+#[test]
+fn doctest_destructure_binding() {
+    check_doc_test(
+        "destructure_binding",
+        r#####"
+struct Point { x: i32, y: i32 }
+fn len($0p: Point) -> i32 {
+    p.x * p.x + p.y * p.y
+}
+"#####,
+        r#####"
+struct Point { x: i32, y: i32 }
+fn len(Point { x, y }: Point) -> i32 {
+    x * x + y * y
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_expand_glob_import() {
     check_doc_test(
@@ -358,6 +547,24 @@ fn qux(bar: Bar, baz: Baz) {}
     )
 }
 
+#[test]
+fn doctest_extract_constant() {
+    check_doc_test(
+        "extract_constant",
+        r#####"
+fn area(r: f64) -> f64 {
+    $03.14159$0 * r * r
+}
+"#####,
+        r#####"
+fn area(r: f64) -> f64 {
+    const $0VAR_NAME: f64 = 3.14159;
+    VAR_NAME * r * r
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_extract_function() {
     check_doc_test(
@@ -385,6 +592,52 @@ fn $0fun_name(n: i32) {
     )
 }
 
+#[test]
+fn doctest_extract_module() {
+    check_doc_test(
+        "extract_module",
+        r#####"
+$0fn foo(name: i32) -> i32 {
+    name + 1
+}$0
+
+fn bar(name: i32) -> i32 {
+    foo(name) + 2
+}
+"#####,
+        r#####"
+mod $0modname {
+    pub(super) fn foo(name: i32) -> i32 {
+        name + 1
+    }
+}
+
+fn bar(name: i32) -> i32 {
+    modname::foo(name) + 2
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_extract_static() {
+    check_doc_test(
+        "extract_static",
+        r#####"
+fn greet() -> &'static str {
+    let $0greeting = "Hello";
+    greeting
+}
+"#####,
+        r#####"
+fn greet() -> &'static str {
+    static $0GREETING: &str = "Hello";
+    GREETING
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_extract_struct_from_enum_variant() {
     check_doc_test(
@@ -591,6 +844,43 @@ impl Default for Example {
     )
 }
 
+#[test]
+fn doctest_generate_delegate_methods() {
+    check_doc_test(
+        "generate_delegate_methods",
+        r#####"
+struct Age(u8);
+impl Age {
+    fn age(&self) -> u8 {
+        self.0
+    }
+}
+
+struct Person {
+    ag$0e: Age,
+}
+"#####,
+        r#####"
+struct Age(u8);
+impl Age {
+    fn age(&self) -> u8 {
+        self.0
+    }
+}
+
+struct Person {
+    age: Age,
+}
+
+impl Person {
+    fn age(&self) -> u8 {
+        self.age.age()
+    }
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_generate_deref() {
     check_doc_test(
@@ -638,6 +928,36 @@ struct Point {
     )
 }
 
+#[test]
+fn doctest_generate_documentation_template() {
+    check_doc_test(
+        "generate_documentation_template",
+        r#####"
+pub fn my_$0func(a: i32, b: i32) -> Result<(), std::io::Error> {
+    Ok(())
+}
+"#####,
+        r#####"
+/// My func.
+///
+/// # Examples
+///
+/// ```
+/// use test::my_func;
+///
+/// assert_eq!(my_func(a, b), );
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if .
+pub fn my_func(a: i32, b: i32) -> Result<(), std::io::Error> {
+    Ok(())
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_generate_enum_as_method() {
     check_doc_test(
@@ -724,6 +1044,30 @@ impl Value {
     )
 }
 
+#[test]
+fn doctest_generate_enum_variant() {
+    check_doc_test(
+        "generate_enum_variant",
+        r#####"
+enum Shape {
+    Square(f64),
+}
+fn main() {
+    let s = Shape::Circle$0(1.0);
+}
+"#####,
+        r#####"
+enum Shape {
+    Square(f64),
+    Circle(f64),
+}
+fn main() {
+    let s = Shape::Circle(1.0);
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_generate_from_impl_for_enum() {
     check_doc_test(
@@ -913,6 +1257,59 @@ impl Person {
     )
 }
 
+#[test]
+fn doctest_generate_struct() {
+    check_doc_test(
+        "generate_struct",
+        r#####"
+fn main() {
+    let p = Point$0 { x: 1, y: 2 };
+}
+"#####,
+        r#####"
+fn main() {
+    let p = Point { x: 1, y: 2 };
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_generate_trait_from_impl() {
+    check_doc_test(
+        "generate_trait_from_impl",
+        r#####"
+struct Foo;
+
+imp$0l Foo {
+    /// Returns zero.
+    pub fn zero(&self) -> u32 {
+        0
+    }
+}
+"#####,
+        r#####"
+struct Foo;
+
+pub trait $0FooExt {
+    /// Returns zero.
+    fn zero(&self) -> u32;
+}
+
+impl FooExt for Foo {
+    fn zero(&self) -> u32 {
+        0
+    }
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_infer_function_return_type() {
     check_doc_test(
@@ -949,6 +1346,36 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_inline_into_callers() {
+    check_doc_test(
+        "inline_into_callers",
+        r#####"
+fn print(a: u32) { println!("{}", a); }
+fn $0foo(a: u32, b: u32) -> u32 { a + b }
+fn main() {
+    let x = foo(1, 2);
+    let y = foo(3, 4);
+}
+"#####,
+        r#####"
+fn print(a: u32) { println!("{}", a); }
+fn main() {
+    let x = {
+        let a = 1;
+        let b = 2;
+        a + b
+    };
+    let y = {
+        let a = 3;
+        let b = 4;
+        a + b
+    };
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_inline_local_variable() {
     check_doc_test(
@@ -967,6 +1394,70 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_inline_macro_call() {
+    check_doc_test(
+        "inline_macro_call",
+        r#####"
+macro_rules! num {
+    (+$i:literal) => { $i };
+    (-$i:literal) => { 0 - $i };
+}
+
+fn f() -> i32 {
+    2 * num$0!(-1)
+}
+"#####,
+        r#####"
+macro_rules! num {
+    (+$i:literal) => { $i };
+    (-$i:literal) => { 0 - $i };
+}
+
+fn f() -> i32 {
+    2 * (0 - 1)
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_inline_type_alias() {
+    check_doc_test(
+        "inline_type_alias",
+        r#####"
+type A<T = u32> = Vec<T>;
+fn main() {
+    let a: $0A;
+}
+"#####,
+        r#####"
+type A<T = u32> = Vec<T>;
+fn main() {
+    let a: Vec<u32>;
+}
+"#####,
+    )
+}
+
+#[test]
+fn doctest_inline_type_alias_uses() {
+    check_doc_test(
+        "inline_type_alias_uses",
+        r#####"
+type $0A = i32;
+fn id(x: A) -> A {
+    x
+}
+"#####,
+        r#####"
+fn id(x: i32) -> i32 {
+    x
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_introduce_named_lifetime() {
     check_doc_test(
@@ -992,6 +1483,34 @@ impl<'a> Cursor<'a> {
     )
 }
 
+#[test]
+fn doctest_introduce_parameter_struct() {
+    check_doc_test(
+        "introduce_parameter_struct",
+        r#####"
+fn draw(canvas: &mut Vec<u8>, $0x: u32, y: u32$0) {
+    canvas.push((x + y) as u8);
+}
+fn main() {
+    draw(&mut Vec::new(), 1, 2);
+}
+"#####,
+        r#####"
+struct DrawParams {
+    x: u32,
+    y: u32,
+}
+
+fn draw(canvas: &mut Vec<u8>, params: DrawParams) {
+    canvas.push((params.x + params.y) as u8);
+}
+fn main() {
+    draw(&mut Vec::new(), DrawParams { x: 1, y: 2 });
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_invert_if() {
     check_doc_test(
@@ -1009,6 +1528,35 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_make_async() {
+    check_doc_test(
+        "make_async",
+        r#####"
+fn fetch() -> u32 { 92 }
+
+async fn run() -> u32 {
+    fetch() + 1
+}
+
+fn main() {
+    $0fetch();
+}
+"#####,
+        r#####"
+async fn fetch() -> u32 { 92 }
+
+async fn run() -> u32 {
+    fetch().await + 1
+}
+
+fn main() {
+    fetch();
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_make_raw_string() {
     check_doc_test(
@@ -1156,6 +1704,29 @@ fn handle(action: Action) {
     )
 }
 
+#[test]
+fn doctest_move_item_to_module() {
+    check_doc_test(
+        "move_item_to_module",
+        r#####"
+struct $0Foo;
+
+fn make() -> Foo { Foo }
+
+mod bar {}
+"#####,
+        r#####"
+use bar::Foo;
+
+fn make() -> Foo { Foo }
+
+mod bar {
+    pub(super) struct Foo;
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_move_module_to_file() {
     check_doc_test(
@@ -1346,16 +1917,16 @@ fn doctest_replace_derive_with_manual_impl() {
     check_doc_test(
         "replace_derive_with_manual_impl",
         r#####"
-trait Debug { fn fmt(&self, f: &mut Formatter) -> Result<()>; }
-#[derive(Deb$0ug, Display)]
+trait Display { fn fmt(&self, f: &mut Formatter) -> Result<()>; }
+#[derive(Debug, Disp$0lay)]
 struct S;
 "#####,
         r#####"
-trait Debug { fn fmt(&self, f: &mut Formatter) -> Result<()>; }
-#[derive(Display)]
+trait Display { fn fmt(&self, f: &mut Formatter) -> Result<()>; }
+#[derive(Debug)]
 struct S;
 
-impl Debug for S {
+impl Display for S {
     fn fmt(&self, f: &mut Formatter) -> Result<()> {
         ${0:todo!()}
     }
@@ -1482,6 +2053,32 @@ fn handle(action: Action) {
     )
 }
 
+#[test]
+fn doctest_replace_match_with_try_expr() {
+    check_doc_test(
+        "replace_match_with_try_expr",
+        r#####"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn handle(x: Result<i32, ()>) -> Result<i32, ()> {
+    let y = $0match x {
+        Ok(y) => y,
+        Err(e) => return Err(e),
+    };
+    Ok(y)
+}
+"#####,
+        r#####"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn handle(x: Result<i32, ()>) -> Result<i32, ()> {
+    let y = x?;
+    Ok(y)
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_replace_qualified_name_with_use() {
     check_doc_test(
@@ -1514,6 +2111,32 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_replace_try_expr_with_match() {
+    check_doc_test(
+        "replace_try_expr_with_match",
+        r#####"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn handle(x: Result<i32, ()>) -> Result<i32, ()> {
+    let y = x$0?;
+    Ok(y)
+}
+"#####,
+        r#####"
+enum Result<T, E> { Ok(T), Err(E) }
+use Result::*;
+fn handle(x: Result<i32, ()>) -> Result<i32, ()> {
+    let y = match x {
+        Ok(it) => it,
+        Err(err) => return Err(From::from(err)),
+    };
+    Ok(y)
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_replace_unwrap_with_match() {
     check_doc_test(
@@ -1,6 +1,7 @@
 //! Assorted functions shared by several assists.
 
 pub(crate) mod suggest_name;
+pub(crate) mod gen_trait_fn_body;
 
 use std::ops;
 
@@ -19,7 +20,7 @@ use syntax::{
     ast::{self, edit, make, ArgListOwner, GenericParamsOwner},
     ted, AstNode, Direction, SmolStr,
     SyntaxKind::*,
-    SyntaxNode, TextSize, T,
+    SyntaxNode, TextRange, TextSize, T,
 };
 
 use crate::{
@@ -215,6 +216,28 @@ pub(crate) fn vis_offset(node: &SyntaxNode) -> TextSize {
         .unwrap_or_else(|| node.text_range().start())
 }
 
+/// Returns the range of `node` along with one adjacent whitespace token, so
+/// that removing an item doesn't leave a blank line behind.
+pub(crate) fn range_with_whitespace(node: &SyntaxNode) -> TextRange {
+    let range = node.text_range();
+    let prev = node.prev_sibling_or_token();
+    let next = node.next_sibling_or_token();
+    match prev.into_iter().chain(next).find(|it| it.kind() == WHITESPACE) {
+        Some(ws) => range.cover(ws.text_range()),
+        None => range,
+    }
+}
+
+/// Returns the range of `path` without the generic arguments of its last segment,
+/// which is the part naming the item.
+pub(crate) fn range_without_generic_args(path: &ast::Path) -> TextRange {
+    let range = path.syntax().text_range();
+    match path.segment().and_then(|it| it.generic_arg_list()) {
+        Some(args) => TextRange::new(range.start(), args.syntax().text_range().start()),
+        None => range,
+    }
+}
+
 pub(crate) fn invert_boolean_expression(
     sema: &Semantics<RootDatabase>,
     expr: ast::Expr,
@@ -498,3 +521,36 @@ pub(crate) fn add_method_to_adt(
 
     builder.insert(start_offset, buf);
 }
+
+/// Returns `ty` with its elided lifetimes, `&T` and `'_`, replaced with `lifetime`.
+pub(crate) fn name_elided_lifetimes(ty: &ast::Type, lifetime: &str) -> String {
+    let mut edits: Vec<(TextRange, String)> = Vec::new();
+    for node in ty.syntax().descendants() {
+        if let Some(ref_type) = ast::RefType::cast(node.clone()) {
+            if let (None, Some(amp)) = (ref_type.lifetime(), ref_type.amp_token()) {
+                let offset = amp.text_range().end();
+                edits.push((TextRange::empty(offset), format!("{} ", lifetime)));
+            }
+        } else if let Some(it) = ast::Lifetime::cast(node) {
+            if it.text() == "'_" {
+                edits.push((it.syntax().text_range(), lifetime.to_string()));
+            }
+        }
+    }
+
+    let mut text = ty.to_string();
+    let start: TextSize = ty.syntax().text_range().start();
+    edits.sort_by_key(|(range, _)| range.start());
+    for (range, replacement) in edits.into_iter().rev() {
+        let range = range - start;
+        text.replace_range(usize::from(range.start())..usize::from(range.end()), &replacement);
+    }
+    text
+}
+
+pub(crate) fn has_elided_lifetime(ty: &ast::Type) -> bool {
+    ty.syntax().descendants().any(|it| {
+        matches!(ast::RefType::cast(it.clone()), Some(it) if it.lifetime().is_none())
+            || matches!(ast::Lifetime::cast(it), Some(it) if it.text() == "'_")
+    })
+}
@@ -0,0 +1,382 @@
+//! This module contains functions to generate default trait impl function bodies where possible.
+
+use hir::StructKind;
+use itertools::Itertools;
+use syntax::{
+    ast::{self, edit::AstNodeEdit, edit::IndentLevel},
+    ted, AstNode,
+};
+
+/// Generate custom trait bodies where possible.
+///
+/// Returns `Option` so that we can use `?` rather than `if let Some`. Returning
+/// `None` means that generating a custom trait body failed, and the body keeps its
+/// placeholder instead.
+pub(crate) fn gen_trait_fn_body(
+    db: &dyn hir::db::HirDatabase,
+    func: &ast::Fn,
+    trait_path: &ast::Path,
+    adt: hir::Adt,
+) -> Option<()> {
+    let param_names: Vec<String> = func
+        .param_list()?
+        .params()
+        .map(|param| param.pat().map(|it| it.to_string()))
+        .collect::<Option<_>>()?;
+    let shape = match adt {
+        hir::Adt::Struct(it) => {
+            let name = it.name(db).to_string();
+            AdtShape::Struct(Fields::new(db, name, it.kind(db), it.fields(db), &param_names))
+        }
+        hir::Adt::Enum(it) => AdtShape::Enum(
+            it.variants(db)
+                .into_iter()
+                .map(|it| {
+                    let name = it.name(db).to_string();
+                    Fields::new(db, name, it.kind(db), it.fields(db), &param_names)
+                })
+                .collect(),
+        ),
+        hir::Adt::Union(_) => return None,
+    };
+
+    let trait_name = trait_path.segment()?.name_ref()?.to_string();
+    let body = match (trait_name.as_str(), param_names.as_slice()) {
+        ("Clone", []) => gen_clone_impl(&shape),
+        ("Debug", [f]) => gen_debug_impl(&shape, f),
+        ("Default", []) => gen_default_impl(&shape)?,
+        ("Hash", [state]) => gen_hash_impl(&shape, trait_path, state),
+        ("PartialEq", [other]) => gen_partial_eq(&shape, other),
+        ("PartialOrd", [other]) => gen_ord(&shape, other, Ordering::Partial),
+        ("Ord", [other]) => gen_ord(&shape, other, Ordering::Total),
+        _ => return None,
+    };
+
+    let body =
+        if body.is_empty() { "{}".to_string() } else { format!("{{\n{}\n}}", indent(&body)) };
+    let body = match ast::Expr::parse(&body).ok()? {
+        ast::Expr::BlockExpr(it) => it,
+        _ => return None,
+    };
+    let body = body.indent(IndentLevel(1)).clone_for_update();
+    ted::replace(func.body()?.syntax(), body.syntax());
+    Some(())
+}
+
+enum AdtShape {
+    Struct(Fields),
+    Enum(Vec<Fields>),
+}
+
+/// The fields of a struct or of an enum variant.
+struct Fields {
+    name: String,
+    kind: StructKind,
+    /// Field names, or indices of tuple fields.
+    names: Vec<String>,
+    /// The parameters of the method, which bindings must not shadow.
+    params: Vec<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    This,
+    Left,
+    Right,
+}
+
+impl Fields {
+    fn new(
+        db: &dyn hir::db::HirDatabase,
+        name: String,
+        kind: StructKind,
+        fields: Vec<hir::Field>,
+        params: &[String],
+    ) -> Fields {
+        let names = fields.into_iter().map(|it| it.name(db).to_string()).collect();
+        Fields { name, kind, names, params: params.to_vec() }
+    }
+
+    /// The name of the variable a field is bound to in a variant pattern.
+    fn binding(&self, side: Side, field: &str) -> String {
+        match (self.kind, side) {
+            (StructKind::Record, Side::This) if self.params.iter().any(|it| it == field) => {
+                format!("{}_", field)
+            }
+            (StructKind::Record, Side::This) => field.to_string(),
+            (StructKind::Record, Side::Left) => format!("l_{}", field),
+            (StructKind::Record, Side::Right) => format!("r_{}", field),
+            (_, Side::This) => format!("arg{}", field),
+            (_, Side::Left) => format!("l{}", field),
+            (_, Side::Right) => format!("r{}", field),
+        }
+    }
+
+    fn bindings(&self, side: Side) -> Vec<String> {
+        self.names.iter().map(|it| self.binding(side, it)).collect()
+    }
+
+    /// A `Self::Variant { .. }` pattern binding every field.
+    fn variant_pat(&self, side: Side) -> String {
+        self.constructor(&format!("Self::{}", self.name), self.bindings(side).into_iter())
+    }
+
+    /// `path { field: value, .. }`, `path(value, ..)` or `path`, with `value` computed per field.
+    fn constructor(&self, path: &str, values: impl Iterator<Item = String>) -> String {
+        match self.kind {
+            StructKind::Record => {
+                let fields = self.names.iter().zip(values).map(|(name, value)| {
+                    if *name == value {
+                        value
+                    } else {
+                        format!("{}: {}", name, value)
+                    }
+                });
+                format!("{} {{ {} }}", path, fields.format(", "))
+            }
+            StructKind::Tuple => format!("{}({})", path, values.format(", ")),
+            StructKind::Unit => path.to_string(),
+        }
+    }
+
+    fn self_fields(&self, receiver: &str) -> Vec<String> {
+        self.names.iter().map(|it| format!("{}.{}", receiver, it)).collect()
+    }
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("    {}", line) })
+        .join("\n")
+}
+
+/// A `match` over the variants of an enum; an enum without variants can't be matched on
+/// by reference.
+fn gen_match(scrutinee: &str, arms: Vec<String>) -> String {
+    if arms.is_empty() {
+        return "match *self {}".to_string();
+    }
+    let arms = arms.iter().map(|arm| indent(arm)).join("\n");
+    format!("match {} {{\n{}\n}}", scrutinee, arms)
+}
+
+fn gen_clone_impl(shape: &AdtShape) -> String {
+    match shape {
+        AdtShape::Struct(fields) => fields.constructor(
+            "Self",
+            fields.self_fields("self").into_iter().map(|it| format!("{}.clone()", it)),
+        ),
+        AdtShape::Enum(variants) => {
+            let arms = variants.iter().map(|variant| {
+                let values =
+                    variant.bindings(Side::This).into_iter().map(|it| format!("{}.clone()", it));
+                let path = format!("Self::{}", variant.name);
+                format!(
+                    "{} => {},",
+                    variant.variant_pat(Side::This),
+                    variant.constructor(&path, values)
+                )
+            });
+            gen_match("self", arms.collect())
+        }
+    }
+}
+
+fn gen_debug_impl(shape: &AdtShape, f: &str) -> String {
+    let debug = |fields: &Fields, values: Vec<String>| match fields.kind {
+        StructKind::Record => {
+            let calls = fields
+                .names
+                .iter()
+                .zip(values)
+                .map(|(name, value)| format!(".field(\"{}\", {})", name, value));
+            format!("{}.debug_struct(\"{}\"){}.finish()", f, fields.name, calls.format(""))
+        }
+        StructKind::Tuple => {
+            let calls = values.into_iter().map(|value| format!(".field({})", value));
+            format!("{}.debug_tuple(\"{}\"){}.finish()", f, fields.name, calls.format(""))
+        }
+        StructKind::Unit => format!("{}.write_str(\"{}\")", f, fields.name),
+    };
+    match shape {
+        AdtShape::Struct(fields) => {
+            let values = fields.self_fields("self").into_iter().map(|it| format!("&{}", it));
+            debug(fields, values.collect())
+        }
+        AdtShape::Enum(variants) => {
+            let arms = variants.iter().map(|variant| {
+                let body = debug(variant, variant.bindings(Side::This));
+                format!("{} => {},", variant.variant_pat(Side::This), body)
+            });
+            gen_match("self", arms.collect())
+        }
+    }
+}
+
+fn gen_default_impl(shape: &AdtShape) -> Option<String> {
+    match shape {
+        AdtShape::Struct(fields) => Some(
+            fields
+                .constructor("Self", fields.names.iter().map(|_| "Default::default()".to_string())),
+        ),
+        // There is no obvious default variant.
+        AdtShape::Enum(_) => None,
+    }
+}
+
+fn gen_hash_impl(shape: &AdtShape, trait_path: &ast::Path, state: &str) -> String {
+    // `Hash` is not in the prelude, so it is called through the trait path.
+    let hash = |place: &str, is_ref: bool| {
+        let place = if is_ref { place.to_string() } else { format!("&{}", place) };
+        format!("{}::hash({}, {});", trait_path, place, state)
+    };
+    match shape {
+        AdtShape::Struct(fields) => {
+            fields.self_fields("self").iter().map(|it| hash(it, false)).join("\n")
+        }
+        AdtShape::Enum(variants) => {
+            let mut res = hash("core::mem::discriminant(self)", false);
+            let arms: Vec<String> = variants
+                .iter()
+                .filter(|variant| !variant.names.is_empty())
+                .map(|variant| {
+                    let stmts =
+                        variant.bindings(Side::This).iter().map(|it| hash(it, true)).join("\n");
+                    format!("{} => {{\n{}\n}}", variant.variant_pat(Side::This), indent(&stmts))
+                })
+                .collect();
+            if !arms.is_empty() {
+                let mut arms = arms;
+                if arms.len() < variants.len() {
+                    arms.push("_ => {}".to_string());
+                }
+                res.push('\n');
+                res.push_str(&gen_match("self", arms));
+            }
+            res
+        }
+    }
+}
+
+fn gen_partial_eq(shape: &AdtShape, other: &str) -> String {
+    let eq = |pairs: Vec<(String, String)>| {
+        if pairs.is_empty() {
+            "true".to_string()
+        } else {
+            pairs.into_iter().map(|(l, r)| format!("{} == {}", l, r)).join(" && ")
+        }
+    };
+    let discriminant_eq =
+        format!("core::mem::discriminant(self) == core::mem::discriminant({})", other);
+    match shape {
+        AdtShape::Struct(fields) => {
+            eq(fields.self_fields("self").into_iter().zip(fields.self_fields(other)).collect())
+        }
+        AdtShape::Enum(variants) if variants.iter().all(|it| it.names.is_empty()) => {
+            discriminant_eq
+        }
+        AdtShape::Enum(variants) => {
+            let mut arms: Vec<String> = variants
+                .iter()
+                .filter(|variant| !variant.names.is_empty())
+                .map(|variant| {
+                    let pairs =
+                        variant.bindings(Side::Left).into_iter().zip(variant.bindings(Side::Right));
+                    format!(
+                        "({}, {}) => {},",
+                        variant.variant_pat(Side::Left),
+                        variant.variant_pat(Side::Right),
+                        eq(pairs.collect())
+                    )
+                })
+                .collect();
+            if variants.len() > 1 {
+                arms.push(format!("_ => {},", discriminant_eq));
+            }
+            gen_match(&format!("(self, {})", other), arms)
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Ordering {
+    Partial,
+    Total,
+}
+
+fn gen_ord(shape: &AdtShape, other: &str, ordering: Ordering) -> String {
+    let (method, equal) = match ordering {
+        Ordering::Partial => ("partial_cmp", "Some(core::cmp::Ordering::Equal)"),
+        Ordering::Total => ("cmp", "core::cmp::Ordering::Equal"),
+    };
+    // Compares the pairs lexicographically, stopping at the first one that differs.
+    let cmp = |pairs: Vec<(String, String)>| {
+        let mut pairs = pairs.into_iter().map(|(l, r)| format!("{}.{}({})", l, method, r));
+        let last = match pairs.next_back() {
+            Some(it) => it,
+            None => return equal.to_string(),
+        };
+        let mut res = String::new();
+        for pair in pairs {
+            res.push_str(&format!(
+                "match {} {{\n    {} => {{}}\n    ord => return ord,\n}}\n",
+                pair, equal
+            ));
+        }
+        res.push_str(&last);
+        res
+    };
+    let arm_body = |body: String| {
+        if body.contains('\n') {
+            format!("{{\n{}\n}}", indent(&body))
+        } else {
+            format!("{},", body)
+        }
+    };
+    match shape {
+        AdtShape::Struct(fields) => {
+            let others = fields.self_fields(other).into_iter().map(|it| format!("&{}", it));
+            cmp(fields.self_fields("self").into_iter().zip(others).collect())
+        }
+        AdtShape::Enum(variants) => {
+            let mut arms: Vec<String> = variants
+                .iter()
+                .filter(|variant| !variant.names.is_empty() || variants.len() == 1)
+                .map(|variant| {
+                    let pairs =
+                        variant.bindings(Side::Left).into_iter().zip(variant.bindings(Side::Right));
+                    format!(
+                        "({}, {}) => {}",
+                        variant.variant_pat(Side::Left),
+                        variant.variant_pat(Side::Right),
+                        arm_body(cmp(pairs.collect()))
+                    )
+                })
+                .collect();
+            if variants.len() <= 1 {
+                return gen_match(&format!("(self, {})", other), arms);
+            }
+            // Variants compare by their order of declaration.
+            let indices = variants.iter().enumerate().map(|(i, variant)| {
+                let pat = match variant.kind {
+                    StructKind::Record => format!("Self::{} {{ .. }}", variant.name),
+                    StructKind::Tuple => format!("Self::{}(..)", variant.name),
+                    StructKind::Unit => format!("Self::{}", variant.name),
+                };
+                format!("{} => {},", pat, i)
+            });
+            let mut res = format!(
+                "let variant_index = |it: &Self| {};\n",
+                gen_match("it", indices.collect())
+            );
+            let index_cmp = format!("variant_index(self).{}(&variant_index({}))", method, other);
+            if arms.is_empty() {
+                res.push_str(&index_cmp);
+            } else {
+                arms.push(format!("_ => {},", index_cmp));
+                res.push_str(&gen_match(&format!("(self, {})", other), arms));
+            }
+            res
+        }
+    }
+}
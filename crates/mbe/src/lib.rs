@@ -39,7 +39,9 @@ pub enum ExpandError {
     BindingError(String),
     ConversionError,
     ProcMacroError(tt::ExpansionError),
-    UnresolvedProcMacro,
+    /// The proc macro could not be found, with the reason why its crate's
+    /// proc macros failed to load, if known.
+    UnresolvedProcMacro(Option<String>),
     Other(String),
 }
 
@@ -57,7 +59,10 @@ impl fmt::Display for ExpandError {
             ExpandError::BindingError(e) => f.write_str(e),
             ExpandError::ConversionError => f.write_str("could not convert tokens"),
             ExpandError::ProcMacroError(e) => e.fmt(f),
-            ExpandError::UnresolvedProcMacro => f.write_str("unresolved proc macro"),
+            ExpandError::UnresolvedProcMacro(None) => f.write_str("unresolved proc macro"),
+            ExpandError::UnresolvedProcMacro(Some(reason)) => {
+                write!(f, "unresolved proc macro: {}", reason)
+            }
             ExpandError::Other(e) => f.write_str(e),
         }
     }
@@ -56,7 +56,7 @@ impl base_db::ProcMacroExpander for ProcMacroProcessExpander {
             env: env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
         };
 
-        let result: ExpansionResult = self.process.send_task(msg::Request::ExpansionMacro(task))?;
+        let result = self.process.expand(task)?;
         Ok(result.expansion)
     }
 }
@@ -76,32 +76,26 @@ impl ProcMacroClient {
         Ok(ProcMacroClient { process: Arc::new(process), thread })
     }
 
-    pub fn by_dylib_path(&self, dylib_path: &Path) -> Vec<ProcMacro> {
+    /// Loads the proc macros of the given dylib.
+    ///
+    /// Returns a human readable error if the dylib could not be loaded, for
+    /// example because it was built by a rustc whose proc-macro ABI is not
+    /// supported.
+    pub fn by_dylib_path(&self, dylib_path: &Path) -> Result<Vec<ProcMacro>, String> {
         let _p = profile::span("ProcMacroClient::by_dylib_path");
-        match version::read_dylib_info(dylib_path) {
-            Ok(info) => {
-                if info.version.0 < 1 || info.version.1 < 47 {
-                    eprintln!("proc-macro {} built by {:#?} is not supported by Rust Analyzer, please update your rust version.", dylib_path.to_string_lossy(), info);
-                }
-            }
-            Err(err) => {
-                eprintln!(
-                    "proc-macro {} failed to find the given version. Reason: {}",
-                    dylib_path.to_string_lossy(),
-                    err
-                );
-            }
-        }
-
         let macros = match self.process.find_proc_macros(dylib_path) {
             Err(err) => {
                 eprintln!("Failed to find proc macros. Error: {:#?}", err);
-                return vec![];
+                let reason = match err {
+                    tt::ExpansionError::ExpansionError(msg) => msg,
+                    err => err.to_string(),
+                };
+                return Err(reason);
             }
             Ok(macros) => macros,
         };
 
-        macros
+        Ok(macros
             .into_iter()
             .map(|(name, kind)| {
                 let name = SmolStr::new(&name);
@@ -118,6 +112,6 @@ impl ProcMacroClient {
 
                 ProcMacro { name, kind, expander }
             })
-            .collect()
+            .collect())
     }
 }
@@ -1,25 +1,61 @@
 //! Handle process life-time and message passing for proc-macro client
 
 use std::{
+    collections::HashMap,
     convert::{TryFrom, TryInto},
     ffi::{OsStr, OsString},
+    fmt,
     io::{self, BufRead, BufReader, Write},
     path::{Path, PathBuf},
-    process::{Child, Command, Stdio},
-    sync::{Arc, Weak},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
 };
 
-use crossbeam_channel::{bounded, Receiver, Sender};
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender};
 use stdx::JodChild;
 
 use crate::{
     msg::{ErrorCode, Message, Request, Response, ResponseError},
-    rpc::{ListMacrosResult, ListMacrosTask, ProcMacroKind},
+    rpc::{ExpansionResult, ExpansionTask, ListMacrosResult, ListMacrosTask, ProcMacroKind},
 };
 
-#[derive(Debug, Default)]
+/// How long a single expansion may take before the server process is killed
+/// (and restarted for the next request).
+const EXPANSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of timeouts or crashes after which a proc macro is disabled for the
+/// rest of the session.
+const MAX_EXPANSION_FAILURES: u32 = 3;
+
+#[derive(Default)]
 pub(crate) struct ProcMacroProcessSrv {
     inner: Weak<Sender<Task>>,
+    /// The running server process, shared with the client thread so that we
+    /// can kill it if an expansion hangs.
+    server: Arc<Mutex<ServerState>>,
+    /// Source of the ids used to tell requests apart.
+    next_request_id: AtomicU64,
+    expansion_timeout: Duration,
+    /// Number of times each proc macro, identified by its dylib and name, made
+    /// the server time out or crash.
+    failures: Mutex<HashMap<(PathBuf, String), u32>>,
+}
+
+#[derive(Default)]
+struct ServerState {
+    child: Option<JodChild>,
+    /// Id of the request the server is busy with, if any.
+    current_request: Option<u64>,
+}
+
+impl fmt::Debug for ProcMacroProcessSrv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcMacroProcessSrv").field("inner", &self.inner).finish()
+    }
 }
 
 #[derive(Debug)]
@@ -34,7 +70,8 @@ impl ProcMacroProcessSrv {
         process_path: PathBuf,
         args: impl IntoIterator<Item = impl AsRef<OsStr>>,
     ) -> io::Result<(ProcMacroProcessThread, ProcMacroProcessSrv)> {
-        let process = Process::run(process_path, args)?;
+        let server = Arc::new(Mutex::new(ServerState::default()));
+        let process = Process::run(process_path, args, server.clone())?;
 
         let (task_tx, task_rx) = bounded(0);
         let handle = jod_thread::spawn(move || {
@@ -42,7 +79,13 @@ impl ProcMacroProcessSrv {
         });
 
         let task_tx = Arc::new(task_tx);
-        let srv = ProcMacroProcessSrv { inner: Arc::downgrade(&task_tx) };
+        let srv = ProcMacroProcessSrv {
+            inner: Arc::downgrade(&task_tx),
+            server,
+            next_request_id: AtomicU64::new(0),
+            expansion_timeout: EXPANSION_TIMEOUT,
+            failures: Default::default(),
+        };
         let thread = ProcMacroProcessThread { handle, sender: task_tx };
 
         Ok((thread, srv))
@@ -58,86 +101,224 @@ impl ProcMacroProcessSrv {
         Ok(result.macros)
     }
 
+    /// Expands a macro, giving up after [`EXPANSION_TIMEOUT`].
+    ///
+    /// Macros which repeatedly make the server hang or crash are not sent to
+    /// the server anymore.
+    pub(crate) fn expand(
+        &self,
+        task: ExpansionTask,
+    ) -> Result<ExpansionResult, tt::ExpansionError> {
+        let key = (task.lib.clone(), task.macro_name.clone());
+        if self.failures.lock().unwrap().get(&key).copied().unwrap_or(0) >= MAX_EXPANSION_FAILURES {
+            return Err(tt::ExpansionError::Unknown(format!(
+                "proc macro `{}` is disabled after failing repeatedly",
+                key.1
+            )));
+        }
+
+        let res = self.request(Request::ExpansionMacro(task), Some(self.expansion_timeout));
+        res.map_err(|err| {
+            if err.is_server_failure() {
+                *self.failures.lock().unwrap().entry(key).or_default() += 1;
+            }
+            err.into()
+        })
+    }
+
     pub(crate) fn send_task<R>(&self, req: Request) -> Result<R, tt::ExpansionError>
     where
         R: TryFrom<Response, Error = &'static str>,
     {
+        self.request(req, None).map_err(Into::into)
+    }
+
+    fn request<R>(&self, req: Request, timeout: Option<Duration>) -> Result<R, RequestError>
+    where
+        R: TryFrom<Response, Error = &'static str>,
+    {
+        let macro_name = match &req {
+            Request::ExpansionMacro(task) => Some(task.macro_name.clone()),
+            Request::ListMacro(_) => None,
+        };
         let (result_tx, result_rx) = bounded(0);
+        let crashed = || RequestError::from("proc macro server crashed".to_string());
         let sender = match self.inner.upgrade() {
-            None => return Err(tt::ExpansionError::Unknown("proc macro process is closed".into())),
+            None => return Err("proc macro process is closed".to_string().into()),
             Some(it) => it,
         };
-        sender
-            .send(Task { req, result_tx })
-            .map_err(|_| tt::ExpansionError::Unknown("proc macro server crashed".into()))?;
+        let id = self.next_request_id.fetch_add(1, Ordering::SeqCst);
+        sender.send(Task { id, req, result_tx }).map_err(|_| crashed())?;
 
-        let res = result_rx
-            .recv()
-            .map_err(|_| tt::ExpansionError::Unknown("proc macro server crashed".into()))?;
+        let res = match timeout {
+            Some(timeout) => loop {
+                match result_rx.recv_timeout(timeout) {
+                    Ok(res) => break res,
+                    Err(RecvTimeoutError::Timeout) => {
+                        let mut server = self.server.lock().unwrap();
+                        // Only kill the server if it is still busy with this
+                        // request; otherwise the response is on its way.
+                        if server.current_request != Some(id) {
+                            continue;
+                        }
+                        // Killing the server unblocks the client thread, which
+                        // restarts the server for the next request.
+                        if let Some(mut child) = server.child.take() {
+                            let _ = child.kill();
+                        }
+                        let msg = match macro_name {
+                            Some(name) => format!("proc macro `{}` expansion timed out", name),
+                            None => "proc macro expansion timed out".to_string(),
+                        };
+                        return Err(RequestError::Timeout(msg));
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return Err(crashed()),
+                }
+            },
+            None => result_rx.recv().map_err(|_| crashed())?,
+        };
 
         match res {
+            Some(Response::Error(ResponseError { code: ErrorCode::ServerErrorEnd, message })) => {
+                Err(RequestError::Io(message))
+            }
             Some(Response::Error(err)) => {
-                return Err(tt::ExpansionError::ExpansionError(err.message));
+                Err(RequestError::Other(tt::ExpansionError::ExpansionError(err.message)))
             }
             Some(res) => Ok(res.try_into().map_err(|err| {
-                tt::ExpansionError::Unknown(format!("Fail to get response, reason : {:#?} ", err))
+                RequestError::from(format!("Fail to get response, reason : {:#?} ", err))
             })?),
-            None => Err(tt::ExpansionError::Unknown("Empty result".into())),
+            None => Err("Empty result".to_string().into()),
         }
     }
 }
 
-fn client_loop(task_rx: Receiver<Task>, mut process: Process) {
-    let (mut stdin, mut stdout) = process.stdio().expect("couldn't access child stdio");
+/// Why a request to the server failed.
+enum RequestError {
+    /// The server did not respond in time and was killed.
+    Timeout(String),
+    /// The server died or its pipes broke while handling the request.
+    Io(String),
+    Other(tt::ExpansionError),
+}
+
+impl RequestError {
+    /// Whether the server hung or died while handling the request, as opposed
+    /// to the macro itself reporting an error.
+    fn is_server_failure(&self) -> bool {
+        matches!(self, RequestError::Timeout(_) | RequestError::Io(_))
+    }
+}
+
+impl From<RequestError> for tt::ExpansionError {
+    fn from(err: RequestError) -> Self {
+        match err {
+            RequestError::Timeout(msg) | RequestError::Io(msg) => tt::ExpansionError::Unknown(msg),
+            RequestError::Other(err) => err,
+        }
+    }
+}
+
+impl From<String> for RequestError {
+    fn from(msg: String) -> Self {
+        RequestError::Other(tt::ExpansionError::Unknown(msg))
+    }
+}
 
+fn client_loop(task_rx: Receiver<Task>, mut process: Process) {
     let mut buf = String::new();
 
-    for Task { req, result_tx } in task_rx {
-        match send_request(&mut stdin, &mut stdout, req, &mut buf) {
-            Ok(res) => result_tx.send(res).unwrap(),
-            Err(err) => {
+    for Task { id, req, result_tx } in task_rx {
+        process.server.lock().unwrap().current_request = Some(id);
+        let res = process.send_request(req, &mut buf);
+        process.server.lock().unwrap().current_request = None;
+        match res {
+            Ok(Some(res)) => {
+                // The requester might have given up waiting already.
+                let _ = result_tx.send(Some(res));
+            }
+            res => {
+                let err = match res {
+                    Err(err) => err,
+                    _ => io::Error::new(io::ErrorKind::UnexpectedEof, "no response"),
+                };
                 log::error!(
                     "proc macro server crashed, server process state: {:?}, server request error: {:?}",
-                    process.child.try_wait(),
+                    process.try_wait(),
                     err
                 );
+                // Drop the dead (or killed) process, a new one is started for
+                // the next request.
+                process.kill();
                 let res = Response::Error(ResponseError {
                     code: ErrorCode::ServerErrorEnd,
                     message: "proc macro server crashed".into(),
                 });
-                result_tx.send(res.into()).unwrap();
-                // Exit the thread.
-                break;
+                let _ = result_tx.send(res.into());
             }
         }
     }
 }
 
 struct Task {
+    id: u64,
     req: Request,
     result_tx: Sender<Option<Response>>,
 }
 
 struct Process {
-    child: JodChild,
+    path: PathBuf,
+    args: Vec<OsString>,
+    server: Arc<Mutex<ServerState>>,
+    stdio: Option<(ChildStdin, BufReader<ChildStdout>)>,
 }
 
 impl Process {
     fn run(
         path: PathBuf,
         args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+        server: Arc<Mutex<ServerState>>,
     ) -> io::Result<Process> {
         let args: Vec<OsString> = args.into_iter().map(|s| s.as_ref().into()).collect();
-        let child = JodChild(mk_child(&path, &args)?);
-        Ok(Process { child })
+        let mut process = Process { path, args, server, stdio: None };
+        process.restart()?;
+        Ok(process)
+    }
+
+    fn restart(&mut self) -> io::Result<()> {
+        let mut child = JodChild(mk_child(&self.path, &self.args)?);
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let (stdin, stdout) = match stdin.zip(stdout) {
+            Some(it) => it,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "couldn't access child stdio",
+                ))
+            }
+        };
+        self.stdio = Some((stdin, BufReader::new(stdout)));
+        self.server.lock().unwrap().child = Some(child);
+        Ok(())
+    }
+
+    fn kill(&mut self) {
+        self.stdio = None;
+        self.server.lock().unwrap().child = None;
     }
 
-    fn stdio(&mut self) -> Option<(impl Write, impl BufRead)> {
-        let stdin = self.child.stdin.take()?;
-        let stdout = self.child.stdout.take()?;
-        let read = BufReader::new(stdout);
+    fn try_wait(&self) -> Option<io::Result<Option<std::process::ExitStatus>>> {
+        self.server.lock().unwrap().child.as_mut().map(|child| child.try_wait())
+    }
 
-        Some((stdin, read))
+    fn send_request(&mut self, req: Request, buf: &mut String) -> io::Result<Option<Response>> {
+        if self.stdio.is_none() {
+            log::info!("restarting proc macro server");
+            self.restart()?;
+        }
+        let (stdin, stdout) = self.stdio.as_mut().unwrap();
+        send_request(stdin, stdout, req, buf)
     }
 }
 
@@ -159,3 +340,94 @@ fn send_request(
     req.write(&mut writer)?;
     Response::read(&mut reader, buf)
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// Starts a fake server which answers expansion requests depending on the
+    /// macro name: `hang` never answers, `crash` exits, `panic` reports an
+    /// expansion error and anything else expands to nothing.
+    fn fake_server() -> (ProcMacroProcessThread, ProcMacroProcessSrv) {
+        let ok = serde_json::to_string(&Response::ExpansionMacro(ExpansionResult::default()));
+        let panic = serde_json::to_string(&Response::Error(ResponseError {
+            code: ErrorCode::ExpansionError,
+            message: "proc macro panicked".into(),
+        }));
+        let script = format!(
+            r#"while read -r line; do
+                case "$line" in
+                    *'"macro_name":"hang"'*) exec sleep 30;;
+                    *'"macro_name":"crash"'*) exit 1;;
+                    *'"macro_name":"panic"'*) echo '{}';;
+                    *) echo '{}';;
+                esac
+            done"#,
+            panic.unwrap(),
+            ok.unwrap()
+        );
+        let (thread, mut srv) = ProcMacroProcessSrv::run("sh".into(), &["-c", &script]).unwrap();
+        srv.expansion_timeout = Duration::from_millis(500);
+        (thread, srv)
+    }
+
+    fn expand(srv: &ProcMacroProcessSrv, macro_name: &str) -> Result<(), tt::ExpansionError> {
+        let task = ExpansionTask {
+            macro_body: Default::default(),
+            macro_name: macro_name.to_string(),
+            attributes: None,
+            lib: "libfoo.so".into(),
+            env: Default::default(),
+        };
+        srv.expand(task).map(drop)
+    }
+
+    #[test]
+    fn restarts_server_after_timeout_and_disables_hanging_macro() {
+        let (_thread, srv) = fake_server();
+        assert_eq!(expand(&srv, "ok"), Ok(()));
+
+        for _ in 0..MAX_EXPANSION_FAILURES {
+            assert_eq!(
+                expand(&srv, "hang"),
+                Err(tt::ExpansionError::Unknown("proc macro `hang` expansion timed out".into()))
+            );
+            assert_eq!(expand(&srv, "ok"), Ok(()));
+        }
+
+        assert_eq!(
+            expand(&srv, "hang"),
+            Err(tt::ExpansionError::Unknown(
+                "proc macro `hang` is disabled after failing repeatedly".into()
+            ))
+        );
+        assert_eq!(expand(&srv, "ok"), Ok(()));
+    }
+
+    #[test]
+    fn only_server_failures_disable_macros() {
+        let (_thread, srv) = fake_server();
+
+        for _ in 0..=MAX_EXPANSION_FAILURES {
+            assert_eq!(
+                expand(&srv, "panic"),
+                Err(tt::ExpansionError::ExpansionError("proc macro panicked".into()))
+            );
+        }
+
+        for _ in 0..MAX_EXPANSION_FAILURES {
+            assert_eq!(
+                expand(&srv, "crash"),
+                Err(tt::ExpansionError::Unknown("proc macro server crashed".into()))
+            );
+            assert_eq!(expand(&srv, "ok"), Ok(()));
+        }
+        assert_eq!(
+            expand(&srv, "crash"),
+            Err(tt::ExpansionError::Unknown(
+                "proc macro `crash` is disabled after failing repeatedly".into()
+            ))
+        );
+        assert_eq!(expand(&srv, "ok"), Ok(()));
+    }
+}
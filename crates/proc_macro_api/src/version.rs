@@ -1,6 +1,7 @@
 //! Reading proc-macro rustc version information from binary data
 
 use std::{
+    convert::TryInto,
     fs::File,
     io::{self, Read},
     path::Path,
@@ -20,13 +21,19 @@ pub struct RustCInfo {
 
 /// Read rustc dylib information
 pub fn read_dylib_info(dylib_path: &Path) -> io::Result<RustCInfo> {
+    let ver_str = read_version(dylib_path)?;
+    parse_version_string(&ver_str)
+}
+
+/// Parses the `rustc 1.47.0-nightly (commit date)` version string stored in
+/// the ".rustc" section.
+fn parse_version_string(ver_str: &str) -> io::Result<RustCInfo> {
     macro_rules! err {
         ($e:literal) => {
             io::Error::new(io::ErrorKind::InvalidData, $e)
         };
     }
 
-    let ver_str = read_version(dylib_path)?;
     let mut items = ver_str.split_whitespace();
     let tag = items.next().ok_or(err!("version format error"))?;
     if tag != "rustc" {
@@ -46,10 +53,11 @@ pub fn read_dylib_info(dylib_path: &Path) -> io::Result<RustCInfo> {
     let commit = commit[1..].to_string();
     let date = items.next().ok_or(err!("no date info"))?;
     // remove )
+    let date = date.trim_end_matches(')');
     if date.len() == 0 {
         return Err(err!("date format error"));
     }
-    let date = date[0..date.len() - 2].to_string();
+    let date = date.to_string();
 
     let version_numbers = version
         .split('.')
@@ -80,16 +88,18 @@ fn read_section<'a>(dylib_binary: &'a [u8], section_name: &str) -> io::Result<&'
 ///
 /// binary file.
 /// A proc macro crate binary's ".rustc" section has following byte layout:
-/// * [b'r',b'u',b's',b't',0,0,0,5] is the first 8 bytes
-/// * ff060000 734e6150 is followed, it's the snappy format magic bytes,
-///   means bytes from here(including this sequence) are compressed in
-///   snappy compression format. Version info is inside here, so decompress
-///   this.
+/// * [b'r',b'u',b's',b't',0,0,0,V] is the first 8 bytes, where V is the
+///   metadata version
+/// * for versions 7 and 8, the next 4 bytes store the length of the data,
+///   for versions 9 and later, the next 8 bytes do
+/// * for versions up to 8, the data is compressed in snappy format (starting
+///   with the ff060000 734e6150 snappy magic bytes). Version info is inside
+///   here, so decompress this. Later versions store the data uncompressed.
 /// The bytes you get after decompressing the snappy format portion has
 /// following layout:
-/// * [b'r',b'u',b's',b't',0,0,0,5] is the first 8 bytes(again)
-/// * [crate root bytes] next 4 bytes is to store crate root position,
-///   according to rustc's source code comment
+/// * [b'r',b'u',b's',b't',0,0,0,V] is the first 8 bytes(again)
+/// * [crate root bytes] next 4 bytes (8 bytes since version 9) is to store
+///   crate root position, according to rustc's source code comment
 /// * [length byte] next 1 byte tells us how many bytes we should read next
 ///   for the version string's utf8 bytes
 /// * [version string bytes encoded in utf8] <- GET THIS BOI
@@ -101,33 +111,131 @@ fn read_version(dylib_path: &Path) -> io::Result<String> {
     let dylib_mmaped = unsafe { Mmap::map(&dylib_file) }?;
 
     let dot_rustc = read_section(&dylib_mmaped, ".rustc")?;
+    read_version_from_section(dot_rustc)
+}
 
-    let header = &dot_rustc[..8];
-    const EXPECTED_HEADER: [u8; 8] = [b'r', b'u', b's', b't', 0, 0, 0, 5];
-    // check if header is valid
-    if header != EXPECTED_HEADER {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("only metadata version 5 is supported, section header was: {:?}", header),
-        ));
+fn read_version_from_section(dot_rustc: &[u8]) -> io::Result<String> {
+    let header = dot_rustc.get(..8).ok_or_else(|| err_invalid_data("section too short"))?;
+    if header[..4] != *b"rust" {
+        return Err(err_invalid_data(format!("unknown section header: {:?}", header)));
     }
+    let version = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+
+    // `data` is the (possibly compressed) portion containing the version
+    // string, `bytes_before_version` the number of bytes preceding the length
+    // byte of the version string inside of it:
+    // 8 bytes for [b'r',b'u',b's',b't',0,0,0,V]
+    // 4 or 8 bytes for [crate root bytes]
+    let (data, bytes_before_version) = match version {
+        5 | 6 => (&dot_rustc[8..], 12),
+        7 | 8 => {
+            let len_bytes = dot_rustc.get(8..12).ok_or_else(|| err_invalid_data("no length"))?;
+            let data_len = u32::from_be_bytes(len_bytes.try_into().unwrap()).try_into().ok();
+            (data_after_header(dot_rustc, 12, data_len)?, 12)
+        }
+        9 | 10 => {
+            let len_bytes = dot_rustc.get(8..16).ok_or_else(|| err_invalid_data("no length"))?;
+            let data_len = u64::from_le_bytes(len_bytes.try_into().unwrap()).try_into().ok();
+            (data_after_header(dot_rustc, 16, data_len)?, 16)
+        }
+        _ => {
+            return Err(err_invalid_data(format!(
+                "unsupported metadata version {}, section header was: {:?}",
+                version, header
+            )));
+        }
+    };
+
+    let mut data: Box<dyn Read> =
+        if data.starts_with(b"rust") { Box::new(data) } else { Box::new(SnapDecoder::new(data)) };
+
+    let mut bytes_before_version_string = vec![0u8; bytes_before_version + 1];
+    data.read_exact(&mut bytes_before_version_string)?;
+    let length = bytes_before_version_string[bytes_before_version];
 
-    let snappy_portion = &dot_rustc[8..];
+    let mut version_string_utf8 = vec![0u8; length as usize];
+    data.read_exact(&mut version_string_utf8)?;
+    let version_string = String::from_utf8(version_string_utf8);
+    version_string.map_err(err_invalid_data)
+}
 
-    let mut snappy_decoder = SnapDecoder::new(snappy_portion);
+/// Returns the `data_len` bytes following the header of the ".rustc" section.
+fn data_after_header(
+    dot_rustc: &[u8],
+    header_len: usize,
+    data_len: Option<usize>,
+) -> io::Result<&[u8]> {
+    data_len
+        .and_then(|len| len.checked_add(header_len))
+        .and_then(|end| dot_rustc.get(header_len..end))
+        .ok_or_else(|| err_invalid_data("data length exceeds the section"))
+}
 
-    // the bytes before version string bytes, so this basically is:
-    // 8 bytes for [b'r',b'u',b's',b't',0,0,0,5]
-    // 4 bytes for [crate root bytes]
-    // 1 byte for length of version string
-    // so 13 bytes in total, and we should check the 13th byte
-    // to know the length
-    let mut bytes_before_version = [0u8; 13];
-    snappy_decoder.read_exact(&mut bytes_before_version)?;
-    let length = bytes_before_version[12]; // what? can't use -1 indexing?
+fn err_invalid_data(e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
 
-    let mut version_string_utf8 = vec![0u8; length as usize];
-    snappy_decoder.read_exact(&mut version_string_utf8)?;
-    let version_string = String::from_utf8(version_string_utf8);
-    version_string.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_version_string() {
+        let info = parse_version_string("rustc 1.56.0-nightly (9c25eb7aa 2021-07-25)").unwrap();
+        assert_eq!(info.version, (1, 56, 0));
+        assert_eq!(info.channel, "nightly");
+        assert_eq!(info.commit, "9c25eb7aa");
+        assert_eq!(info.date, "2021-07-25");
+
+        let info = parse_version_string("rustc 1.53.0 (53cb7b09b 2021-06-17)").unwrap();
+        assert_eq!(info.version, (1, 53, 0));
+        assert_eq!(info.channel, "");
+    }
+
+    #[test]
+    fn rejects_malformed_version_string() {
+        for ver_str in [
+            "",
+            "cargo 1.53.0 (53cb7b09b 2021-06-17)",
+            "rustc 1.53 (53cb7b09b 2021-06-17)",
+            "rustc 1.x.0 (53cb7b09b 2021-06-17)",
+            "rustc 1.53.0",
+            "rustc 1.53.0 (53cb7b09b",
+        ]
+        .iter()
+        {
+            assert!(parse_version_string(ver_str).is_err(), "{:?}", ver_str);
+        }
+    }
+
+    fn section(metadata_version: u8, data_len: u64, version_string: &str) -> Vec<u8> {
+        let mut section = b"rust\0\0\0".to_vec();
+        section.push(metadata_version);
+        section.extend_from_slice(&data_len.to_le_bytes());
+        section.extend_from_slice(b"rust\0\0\0");
+        section.push(metadata_version);
+        section.extend_from_slice(&[0; 8]);
+        section.push(version_string.len() as u8);
+        section.extend_from_slice(version_string.as_bytes());
+        section
+    }
+
+    #[test]
+    fn reads_uncompressed_section() {
+        let version_string = "rustc 1.95.0 (59807616e 2026-04-14)";
+        let data_len = 17 + version_string.len() as u64;
+        let section = section(10, data_len, version_string);
+        assert_eq!(read_version_from_section(&section).unwrap(), version_string);
+    }
+
+    #[test]
+    fn rejects_malformed_section() {
+        let version_string = "rustc 1.95.0 (59807616e 2026-04-14)";
+        assert!(read_version_from_section(b"rust").is_err());
+        assert!(read_version_from_section(b"rast\0\0\0\x0a").is_err());
+        assert!(read_version_from_section(&section(42, 0, version_string)).is_err());
+        assert!(read_version_from_section(&section(10, u64::MAX, version_string)).is_err());
+        assert!(read_version_from_section(&section(10, 1024, version_string)).is_err());
+        assert!(read_version_from_section(&section(10, 4, version_string)).is_err());
+    }
 }
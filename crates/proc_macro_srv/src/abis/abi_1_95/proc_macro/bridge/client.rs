@@ -0,0 +1,86 @@
+//! lib-proc-macro Client-side types.
+//!
+//! Copy from https://github.com/rust-lang/rust/blob/59807616e1fa2540724bfbac14d7976d7e4a3860/library/proc_macro/src/bridge/client.rs
+//! augmented with removing unstable features
+
+use std::marker::PhantomData;
+use std::sync::atomic::AtomicU32;
+
+use super::*;
+
+#[repr(C)]
+pub(super) struct HandleCounters {
+    pub(super) token_stream: AtomicU32,
+    pub(super) span: AtomicU32,
+}
+
+// NOTE(rust-analyzer): the client side of the bridge runs inside of the proc-macro
+// dylib, the server only needs these types to mark the handles it hands out.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TokenStream;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Span;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Symbol;
+
+/// A client-side RPC entry-point, which may be using a different `proc_macro`
+/// from the one used by the server, but can be invoked compatibly.
+///
+/// Note that the (phantom) `I` ("input") and `O` ("output") type parameters
+/// decorate the `Client<I, O>` with the RPC "interface" of the entry-point, but
+/// do not themselves participate in ABI, at all, only facilitate type-checking.
+///
+/// E.g. `Client<TokenStream, TokenStream>` is the common proc macro interface,
+/// used for `#[proc_macro] fn foo(input: TokenStream) -> TokenStream`,
+/// indicating that the RPC input and output will be serialized token streams,
+/// and forcing the use of APIs that take/return `S::TokenStream`, server-side.
+#[repr(C)]
+pub struct Client<I, O> {
+    pub(super) handle_counters: &'static HandleCounters,
+
+    pub(super) run: extern "C" fn(BridgeConfig<'_>) -> Buffer,
+
+    pub(super) _marker: PhantomData<fn(I) -> O>,
+}
+
+impl<I, O> Copy for Client<I, O> {}
+impl<I, O> Clone for Client<I, O> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub enum ProcMacro {
+    CustomDerive {
+        trait_name: &'static str,
+        attributes: &'static [&'static str],
+        client: Client<super::super::TokenStream, super::super::TokenStream>,
+    },
+
+    Attr {
+        name: &'static str,
+        client: Client<
+            (super::super::TokenStream, super::super::TokenStream),
+            super::super::TokenStream,
+        >,
+    },
+
+    Bang {
+        name: &'static str,
+        client: Client<super::super::TokenStream, super::super::TokenStream>,
+    },
+}
+
+impl ProcMacro {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcMacro::CustomDerive { trait_name, .. } => trait_name,
+            ProcMacro::Attr { name, .. } => name,
+            ProcMacro::Bang { name, .. } => name,
+        }
+    }
+}
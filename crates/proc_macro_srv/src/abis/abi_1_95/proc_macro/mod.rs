@@ -0,0 +1,44 @@
+//! lib-proc-macro main module
+//!
+//! Copy from https://github.com/rust-lang/rust/blob/59807616e1fa2540724bfbac14d7976d7e4a3860/library/proc_macro/src/lib.rs
+//! augmented with removing unstable features
+//!
+//! Only the parts the bridge server depends on are kept: the public API of
+//! `proc_macro` runs inside of the proc-macro dylib and never on our side.
+
+#[doc(hidden)]
+#[allow(unused_macros)]
+#[allow(unused_variables)]
+pub mod bridge;
+
+/// The token stream a proc-macro entry-point receives and returns, only used
+/// to type `bridge::client::Client`.
+pub struct TokenStream(Option<bridge::client::TokenStream>);
+
+/// Describes how a sequence of token trees is delimited.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Delimiter {
+    /// `( ... )`
+    Parenthesis,
+    /// `{ ... }`
+    Brace,
+    /// `[ ... ]`
+    Bracket,
+    /// `∅ ... ∅`
+    /// An invisible delimiter, that may, for example, appear around tokens coming from a
+    /// "macro variable" `$var`.
+    None,
+}
+
+/// An enum representing a diagnostic level.
+#[derive(Copy, Clone, Debug)]
+pub enum Level {
+    /// An error.
+    Error,
+    /// A warning.
+    Warning,
+    /// A note.
+    Note,
+    /// A help message.
+    Help,
+}
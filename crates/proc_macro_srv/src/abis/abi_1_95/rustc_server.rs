@@ -0,0 +1,611 @@
+//! Rustc proc-macro server implementation with tt
+//!
+//! Same as the server of `abi_1_47`, adapted to the bridge of later rustc
+//! versions which passes token trees, literals and symbols by value instead of
+//! through a handle per kind of token.
+//!
+//! FIXME: No span and source file information is implemented yet
+
+use super::proc_macro::bridge::{self, server, LitKind};
+
+use std::iter::FromIterator;
+use std::ops::{Bound, Range};
+
+type TokenTree = tt::TokenTree;
+type Spacing = tt::Spacing;
+type Span = tt::TokenId;
+type Symbol = tt::SmolStr;
+
+type BridgeTokenTree = bridge::TokenTree<TokenStream, Span, Symbol>;
+type BridgeLiteral = bridge::Literal<Span, Symbol>;
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenStream {
+    pub token_trees: Vec<TokenTree>,
+}
+
+impl TokenStream {
+    pub fn new() -> Self {
+        TokenStream { token_trees: Default::default() }
+    }
+
+    pub fn with_subtree(subtree: tt::Subtree) -> Self {
+        if subtree.delimiter.is_some() {
+            TokenStream { token_trees: vec![TokenTree::Subtree(subtree)] }
+        } else {
+            TokenStream { token_trees: subtree.token_trees }
+        }
+    }
+
+    pub fn into_subtree(self) -> tt::Subtree {
+        tt::Subtree { delimiter: None, token_trees: self.token_trees }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.token_trees.is_empty()
+    }
+}
+
+/// Creates a token stream containing a single token tree.
+impl From<TokenTree> for TokenStream {
+    fn from(tree: TokenTree) -> TokenStream {
+        TokenStream { token_trees: vec![tree] }
+    }
+}
+
+/// Collects a number of token trees into a single stream.
+impl FromIterator<TokenTree> for TokenStream {
+    fn from_iter<I: IntoIterator<Item = TokenTree>>(trees: I) -> Self {
+        let mut stream = TokenStream::new();
+        stream.extend(trees.into_iter().map(TokenStream::from));
+        stream
+    }
+}
+
+impl Extend<TokenStream> for TokenStream {
+    fn extend<I: IntoIterator<Item = TokenStream>>(&mut self, streams: I) {
+        for item in streams {
+            for tkn in item.token_trees {
+                match tkn {
+                    tt::TokenTree::Subtree(subtree) if subtree.delimiter.is_none() => {
+                        self.token_trees.extend(subtree.token_trees);
+                    }
+                    _ => {
+                        self.token_trees.push(tkn);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Public implementation details for the `TokenStream` type.
+pub mod token_stream {
+    use std::str::FromStr;
+
+    use super::TokenStream;
+
+    type LexError = String;
+
+    /// Attempts to break the string into tokens and parse those tokens into a token stream.
+    /// May fail for a number of reasons, for example, if the string contains unbalanced delimiters
+    /// or characters not existing in the language.
+    /// All tokens in the parsed stream get `Span::call_site()` spans.
+    impl FromStr for TokenStream {
+        type Err = LexError;
+
+        fn from_str(src: &str) -> Result<TokenStream, LexError> {
+            let (subtree, _token_map) =
+                mbe::parse_to_token_tree(src).ok_or("Failed to parse from mbe")?;
+
+            let subtree = subtree_replace_token_ids_with_unspecified(subtree);
+            Ok(TokenStream::with_subtree(subtree))
+        }
+    }
+
+    impl ToString for TokenStream {
+        fn to_string(&self) -> String {
+            return tokentrees_to_text(&self.token_trees[..]);
+
+            fn tokentrees_to_text(tkns: &[tt::TokenTree]) -> String {
+                tkns.iter()
+                    .fold((String::new(), true), |(last, last_to_joint), tkn| {
+                        let s = [last, tokentree_to_text(tkn)].join(if last_to_joint {
+                            ""
+                        } else {
+                            " "
+                        });
+                        let mut is_joint = false;
+                        if let tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) = tkn {
+                            if punct.spacing == tt::Spacing::Joint {
+                                is_joint = true;
+                            }
+                        }
+                        (s, is_joint)
+                    })
+                    .0
+            }
+
+            fn tokentree_to_text(tkn: &tt::TokenTree) -> String {
+                match tkn {
+                    tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => ident.text.clone().into(),
+                    tt::TokenTree::Leaf(tt::Leaf::Literal(literal)) => literal.text.clone().into(),
+                    tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) => format!("{}", punct.char),
+                    tt::TokenTree::Subtree(subtree) => {
+                        let content = tokentrees_to_text(&subtree.token_trees);
+                        let (open, close) = match subtree.delimiter.map(|it| it.kind) {
+                            None => ("", ""),
+                            Some(tt::DelimiterKind::Brace) => ("{", "}"),
+                            Some(tt::DelimiterKind::Parenthesis) => ("(", ")"),
+                            Some(tt::DelimiterKind::Bracket) => ("[", "]"),
+                        };
+                        format!("{}{}{}", open, content, close)
+                    }
+                }
+            }
+        }
+    }
+
+    fn subtree_replace_token_ids_with_unspecified(subtree: tt::Subtree) -> tt::Subtree {
+        tt::Subtree {
+            delimiter: subtree
+                .delimiter
+                .map(|d| tt::Delimiter { id: tt::TokenId::unspecified(), ..d }),
+            token_trees: subtree
+                .token_trees
+                .into_iter()
+                .map(token_tree_replace_token_ids_with_unspecified)
+                .collect(),
+        }
+    }
+
+    fn token_tree_replace_token_ids_with_unspecified(tt: tt::TokenTree) -> tt::TokenTree {
+        match tt {
+            tt::TokenTree::Leaf(leaf) => {
+                tt::TokenTree::Leaf(leaf_replace_token_ids_with_unspecified(leaf))
+            }
+            tt::TokenTree::Subtree(subtree) => {
+                tt::TokenTree::Subtree(subtree_replace_token_ids_with_unspecified(subtree))
+            }
+        }
+    }
+
+    fn leaf_replace_token_ids_with_unspecified(leaf: tt::Leaf) -> tt::Leaf {
+        match leaf {
+            tt::Leaf::Literal(lit) => {
+                tt::Leaf::Literal(tt::Literal { id: tt::TokenId::unspecified(), ..lit })
+            }
+            tt::Leaf::Punct(punct) => {
+                tt::Leaf::Punct(tt::Punct { id: tt::TokenId::unspecified(), ..punct })
+            }
+            tt::Leaf::Ident(ident) => {
+                tt::Leaf::Ident(tt::Ident { id: tt::TokenId::unspecified(), ..ident })
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Rustc {
+    // FIXME: store span information here.
+}
+
+impl server::Server for Rustc {
+    type TokenStream = TokenStream;
+    type Span = Span;
+    type Symbol = Symbol;
+
+    fn globals(&mut self) -> bridge::ExpnGlobals<Self::Span> {
+        // FIXME handle span
+        bridge::ExpnGlobals {
+            def_site: tt::TokenId::unspecified(),
+            call_site: tt::TokenId::unspecified(),
+            mixed_site: tt::TokenId::unspecified(),
+        }
+    }
+
+    fn intern_symbol(ident: &str) -> Self::Symbol {
+        Symbol::new(ident)
+    }
+
+    fn with_symbol_string(symbol: &Self::Symbol, f: impl FnOnce(&str)) {
+        f(symbol.as_str())
+    }
+
+    fn injected_env_var(&mut self, _var: &str) -> Option<String> {
+        None
+    }
+
+    fn track_env_var(&mut self, _var: &str, _value: Option<&str>) {
+        // FIXME: track env var accesses
+        // https://github.com/rust-lang/rust/pull/71858
+    }
+
+    fn track_path(&mut self, _path: &str) {}
+
+    fn literal_from_str(&mut self, s: &str) -> Result<BridgeLiteral, String> {
+        use std::str::FromStr;
+
+        let stream = TokenStream::from_str(s)?;
+        let (minus, literal) = match stream.token_trees.as_slice() {
+            [tt::TokenTree::Leaf(tt::Leaf::Literal(literal))] => (false, literal),
+            [tt::TokenTree::Leaf(tt::Leaf::Punct(tt::Punct { char: '-', .. })), tt::TokenTree::Leaf(tt::Leaf::Literal(literal))] => {
+                (true, literal)
+            }
+            _ => return Err(format!("`{}` is not a literal", s)),
+        };
+        let mut literal = literal_to_external(&literal.text, tt::TokenId::unspecified());
+        if minus {
+            if !matches!(literal.kind, LitKind::Integer | LitKind::Float) {
+                return Err(format!("`{}` is not a literal", s));
+            }
+            literal.symbol = format!("-{}", literal.symbol).into();
+        }
+        Ok(literal)
+    }
+
+    fn emit_diagnostic(&mut self, _diagnostic: bridge::Diagnostic<Self::Span>) {
+        // FIXME handle diagnostic
+    }
+
+    fn ts_drop(&mut self, stream: Self::TokenStream) {
+        drop(stream)
+    }
+
+    fn ts_clone(&mut self, stream: &Self::TokenStream) -> Self::TokenStream {
+        stream.clone()
+    }
+
+    fn ts_is_empty(&mut self, stream: &Self::TokenStream) -> bool {
+        stream.is_empty()
+    }
+
+    fn ts_expand_expr(&mut self, stream: &Self::TokenStream) -> Result<Self::TokenStream, ()> {
+        // FIXME: expand macro calls in the expression
+        Ok(stream.clone())
+    }
+
+    fn ts_from_str(&mut self, src: &str) -> Result<Self::TokenStream, String> {
+        src.parse()
+    }
+
+    fn ts_to_string(&mut self, stream: &Self::TokenStream) -> String {
+        stream.to_string()
+    }
+
+    fn ts_from_token_tree(&mut self, tree: BridgeTokenTree) -> Self::TokenStream {
+        TokenStream::from_iter(vec![token_tree_to_internal(tree)])
+    }
+
+    fn ts_concat_trees(
+        &mut self,
+        base: Option<Self::TokenStream>,
+        trees: Vec<BridgeTokenTree>,
+    ) -> Self::TokenStream {
+        let mut stream = base.unwrap_or_default();
+        stream
+            .extend(trees.into_iter().map(|tree| TokenStream::from(token_tree_to_internal(tree))));
+        stream
+    }
+
+    fn ts_concat_streams(
+        &mut self,
+        base: Option<Self::TokenStream>,
+        streams: Vec<Self::TokenStream>,
+    ) -> Self::TokenStream {
+        let mut stream = base.unwrap_or_default();
+        stream.extend(streams);
+        stream
+    }
+
+    fn ts_into_trees(&mut self, stream: Self::TokenStream) -> Vec<BridgeTokenTree> {
+        stream.token_trees.into_iter().map(token_tree_to_external).collect()
+    }
+
+    fn span_debug(&mut self, span: Self::Span) -> String {
+        format!("{:?}", span.0)
+    }
+
+    fn span_parent(&mut self, _span: Self::Span) -> Option<Self::Span> {
+        // FIXME handle span
+        None
+    }
+
+    fn span_source(&mut self, span: Self::Span) -> Self::Span {
+        // FIXME handle span
+        span
+    }
+
+    fn span_byte_range(&mut self, _span: Self::Span) -> Range<usize> {
+        // FIXME handle span
+        0..0
+    }
+
+    fn span_start(&mut self, span: Self::Span) -> Self::Span {
+        // FIXME handle span
+        span
+    }
+
+    fn span_end(&mut self, span: Self::Span) -> Self::Span {
+        // FIXME handle span
+        span
+    }
+
+    fn span_line(&mut self, _span: Self::Span) -> usize {
+        // FIXME handle span
+        1
+    }
+
+    fn span_column(&mut self, _span: Self::Span) -> usize {
+        // FIXME handle span
+        1
+    }
+
+    fn span_file(&mut self, _span: Self::Span) -> String {
+        // FIXME handle span
+        String::new()
+    }
+
+    fn span_local_file(&mut self, _span: Self::Span) -> Option<String> {
+        // FIXME handle span
+        None
+    }
+
+    fn span_join(&mut self, _span: Self::Span, _other: Self::Span) -> Option<Self::Span> {
+        None
+    }
+
+    fn span_subspan(
+        &mut self,
+        _span: Self::Span,
+        _start: Bound<usize>,
+        _end: Bound<usize>,
+    ) -> Option<Self::Span> {
+        // FIXME handle span
+        None
+    }
+
+    fn span_resolved_at(&mut self, span: Self::Span, _at: Self::Span) -> Self::Span {
+        // FIXME handle span
+        span
+    }
+
+    fn span_source_text(&mut self, _span: Self::Span) -> Option<String> {
+        None
+    }
+
+    fn span_save_span(&mut self, _span: Self::Span) -> usize {
+        // FIXME handle span
+        0
+    }
+
+    fn span_recover_proc_macro_span(&mut self, _id: usize) -> Self::Span {
+        // FIXME handle span
+        tt::TokenId::unspecified()
+    }
+
+    fn symbol_normalize_and_validate_ident(&mut self, string: &str) -> Result<Self::Symbol, ()> {
+        // FIXME: NFC-normalize the identifier
+        Ok(Symbol::new(string))
+    }
+}
+
+fn token_tree_to_internal(tree: BridgeTokenTree) -> TokenTree {
+    match tree {
+        bridge::TokenTree::Group(group) => TokenTree::Subtree(tt::Subtree {
+            delimiter: delim_to_internal(group.delimiter, group.span.entire),
+            token_trees: group.stream.map(|it| it.token_trees).unwrap_or_default(),
+        }),
+        bridge::TokenTree::Punct(punct) => {
+            let spacing = if punct.joint { Spacing::Joint } else { Spacing::Alone };
+            let punct = tt::Punct { char: punct.ch as char, spacing, id: punct.span };
+            TokenTree::from(tt::Leaf::from(punct))
+        }
+        bridge::TokenTree::Ident(ident) => {
+            let text = if ident.is_raw { format!("r#{}", ident.sym).into() } else { ident.sym };
+            TokenTree::from(tt::Leaf::from(tt::Ident { text, id: ident.span }))
+        }
+        bridge::TokenTree::Literal(literal) => {
+            let literal = tt::Literal { text: literal_to_internal(&literal), id: literal.span };
+            TokenTree::from(tt::Leaf::from(literal))
+        }
+    }
+}
+
+fn token_tree_to_external(tree: TokenTree) -> BridgeTokenTree {
+    match tree {
+        TokenTree::Subtree(subtree) => {
+            let span = subtree.delimiter.map_or(tt::TokenId::unspecified(), |it| it.id);
+            let stream = Some(TokenStream { token_trees: subtree.token_trees })
+                .filter(|stream| !stream.is_empty());
+            bridge::TokenTree::Group(bridge::Group {
+                delimiter: delim_to_external(subtree.delimiter),
+                stream,
+                span: bridge::DelimSpan::from_single(span),
+            })
+        }
+        TokenTree::Leaf(tt::Leaf::Punct(punct)) => bridge::TokenTree::Punct(bridge::Punct {
+            ch: punct.char as u8,
+            joint: punct.spacing == Spacing::Joint,
+            span: punct.id,
+        }),
+        TokenTree::Leaf(tt::Leaf::Ident(ident)) => {
+            let (sym, is_raw) = match ident.text.strip_prefix("r#") {
+                Some(text) => (text.into(), true),
+                None => (ident.text, false),
+            };
+            bridge::TokenTree::Ident(bridge::Ident { sym, is_raw, span: ident.id })
+        }
+        TokenTree::Leaf(tt::Leaf::Literal(literal)) => {
+            bridge::TokenTree::Literal(literal_to_external(&literal.text, literal.id))
+        }
+    }
+}
+
+fn delim_to_internal(d: bridge::Delimiter, span: Span) -> Option<tt::Delimiter> {
+    let kind = match d {
+        bridge::Delimiter::Parenthesis => tt::DelimiterKind::Parenthesis,
+        bridge::Delimiter::Brace => tt::DelimiterKind::Brace,
+        bridge::Delimiter::Bracket => tt::DelimiterKind::Bracket,
+        bridge::Delimiter::None => return None,
+    };
+    Some(tt::Delimiter { id: span, kind })
+}
+
+fn delim_to_external(d: Option<tt::Delimiter>) -> bridge::Delimiter {
+    match d.map(|it| it.kind) {
+        Some(tt::DelimiterKind::Parenthesis) => bridge::Delimiter::Parenthesis,
+        Some(tt::DelimiterKind::Brace) => bridge::Delimiter::Brace,
+        Some(tt::DelimiterKind::Bracket) => bridge::Delimiter::Bracket,
+        None => bridge::Delimiter::None,
+    }
+}
+
+/// Reassembles the source text of a literal the bridge passes as its parts.
+fn literal_to_internal(literal: &BridgeLiteral) -> tt::SmolStr {
+    let symbol = &literal.symbol;
+    let hashes = |n: u8| "#".repeat(n as usize);
+    let text = match literal.kind {
+        LitKind::Byte => format!("b'{}'", symbol),
+        LitKind::Char => format!("'{}'", symbol),
+        LitKind::Integer | LitKind::Float | LitKind::ErrWithGuar => symbol.to_string(),
+        LitKind::Str => format!("\"{}\"", symbol),
+        LitKind::StrRaw(n) => format!("r{0}\"{1}\"{0}", hashes(n), symbol),
+        LitKind::ByteStr => format!("b\"{}\"", symbol),
+        LitKind::ByteStrRaw(n) => format!("br{0}\"{1}\"{0}", hashes(n), symbol),
+        LitKind::CStr => format!("c\"{}\"", symbol),
+        LitKind::CStrRaw(n) => format!("cr{0}\"{1}\"{0}", hashes(n), symbol),
+    };
+    match &literal.suffix {
+        Some(suffix) => format!("{}{}", text, suffix).into(),
+        None => text.into(),
+    }
+}
+
+/// Splits the source text of a literal into the kind, symbol and suffix the
+/// bridge expects, the way rustc's lexer does.
+fn literal_to_external(text: &str, span: Span) -> BridgeLiteral {
+    let (kind, symbol, suffix) = split_literal(text);
+    let suffix = Some(suffix).filter(|it| !it.is_empty()).map(Symbol::new);
+    bridge::Literal { kind, symbol: Symbol::new(symbol), suffix, span }
+}
+
+fn split_literal(text: &str) -> (LitKind, &str, &str) {
+    let raw_strings = [
+        ("r", LitKind::StrRaw as fn(u8) -> LitKind),
+        ("br", LitKind::ByteStrRaw),
+        ("cr", LitKind::CStrRaw),
+    ];
+    for &(prefix, kind) in raw_strings.iter() {
+        let rest = match text.strip_prefix(prefix) {
+            Some(it) => it,
+            None => continue,
+        };
+        let n_hashes = rest.bytes().take_while(|&b| b == b'#').count();
+        let body = match rest[n_hashes..].strip_prefix('"') {
+            Some(it) => it,
+            None => continue,
+        };
+        let close = format!("\"{}", "#".repeat(n_hashes));
+        if let Some(end) = body.rfind(&close) {
+            return (kind(n_hashes as u8), &body[..end], &body[end + close.len()..]);
+        }
+    }
+
+    let quoted = [
+        ("'", LitKind::Char, '\''),
+        ("b'", LitKind::Byte, '\''),
+        ("\"", LitKind::Str, '"'),
+        ("b\"", LitKind::ByteStr, '"'),
+        ("c\"", LitKind::CStr, '"'),
+    ];
+    for &(prefix, kind, quote) in quoted.iter() {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            if let Some(end) = rest.rfind(quote) {
+                return (kind, &rest[..end], &rest[end + 1..]);
+            }
+        }
+    }
+
+    split_number(text)
+}
+
+fn split_number(text: &str) -> (LitKind, &str, &str) {
+    let bytes = text.as_bytes();
+    let mut end = if text.starts_with('-') { 1 } else { 0 };
+    let (is_prefixed, is_hex) = match text.get(end..end + 2) {
+        Some("0x") => (true, true),
+        Some("0o") | Some("0b") => (true, false),
+        _ => (false, false),
+    };
+    if is_prefixed {
+        end += 2;
+    }
+    let mut is_float = false;
+    while let Some(&b) = bytes.get(end) {
+        match b {
+            b'0'..=b'9' | b'_' => (),
+            b'a'..=b'f' | b'A'..=b'F' if is_hex => (),
+            b'.' if !is_prefixed => is_float = true,
+            b'e' | b'E' if !is_prefixed => {
+                is_float = true;
+                if matches!(bytes.get(end + 1), Some(b'+') | Some(b'-')) {
+                    end += 1;
+                }
+            }
+            _ => break,
+        }
+        end += 1;
+    }
+    let kind = if is_float { LitKind::Float } else { LitKind::Integer };
+    (kind, &text[..end], &text[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rustc_server_literals() {
+        let mut srv = Rustc::default();
+        let check = |srv: &mut Rustc, text: &str, kind: LitKind, symbol: &str, suffix: &str| {
+            let literal = server::Server::literal_from_str(srv, text).unwrap();
+            assert_eq!(literal.kind, kind, "{}", text);
+            assert_eq!(literal.symbol, symbol, "{}", text);
+            assert_eq!(literal.suffix.as_deref().unwrap_or(""), suffix, "{}", text);
+            assert_eq!(literal_to_internal(&literal), text);
+        };
+
+        check(&mut srv, "1234", LitKind::Integer, "1234", "");
+        check(&mut srv, "12u8", LitKind::Integer, "12", "u8");
+        check(&mut srv, "0xffi32", LitKind::Integer, "0xff", "i32");
+        check(&mut srv, "0b1010_u8", LitKind::Integer, "0b1010_", "u8");
+        check(&mut srv, "1f32", LitKind::Integer, "1", "f32");
+        check(&mut srv, "-1", LitKind::Integer, "-1", "");
+        check(&mut srv, "15684.5867", LitKind::Float, "15684.5867", "");
+        check(&mut srv, "1e-10f64", LitKind::Float, "1e-10", "f64");
+        check(&mut srv, "'c'", LitKind::Char, "c", "");
+        check(&mut srv, "b'\\n'", LitKind::Byte, "\\n", "");
+        check(&mut srv, "\"hello_world\"", LitKind::Str, "hello_world", "");
+        check(&mut srv, "\"a\\\"b\"suffix", LitKind::Str, "a\\\"b", "suffix");
+        check(&mut srv, "r#\"raw \"\"#", LitKind::StrRaw(1), "raw \"", "");
+        check(&mut srv, "b\"1234586\\x88\"", LitKind::ByteStr, "1234586\\x88", "");
+        check(&mut srv, "br\"bytes\"", LitKind::ByteStrRaw(0), "bytes", "");
+
+        assert!(server::Server::literal_from_str(&mut srv, "foo").is_err());
+        assert!(server::Server::literal_from_str(&mut srv, "-\"str\"").is_err());
+        assert!(server::Server::literal_from_str(&mut srv, "1 2").is_err());
+    }
+
+    #[test]
+    fn test_rustc_server_token_trees_round_trip() {
+        use std::str::FromStr;
+
+        let stream = TokenStream::from_str("struct r#S { a: [u8; 2], b: &'static str }").unwrap();
+        let mut srv = Rustc::default();
+        let trees = server::Server::ts_into_trees(&mut srv, stream.clone());
+        let round_tripped = server::Server::ts_concat_trees(&mut srv, None, trees);
+        assert_eq!(round_tripped.to_string(), stream.to_string());
+        assert_eq!(round_tripped.token_trees, stream.token_trees);
+    }
+}
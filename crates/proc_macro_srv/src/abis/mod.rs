@@ -0,0 +1,152 @@
+//! Procedural macros are implemented by compiling the macro providing crate
+//! to a dynamic library with a particular ABI which the compiler uses to expand
+//! macros. Unfortunately this ABI is not specified and can change from version
+//! to version of the compiler. To support this we copy the ABI from the rust
+//! compiler into submodules of this module (e.g `proc_macro_srv::abis::abi_1_47`).
+//!
+//! All of these ABIs are subsumed in the `Abi` enum, which exposes a simple
+//! interface the rest of rust-analyzer can use to talk to the macro
+//! provider.
+//!
+//! # Adding a new ABI
+//!
+//! To add a new ABI you'll need to copy the source of the target proc_macro
+//! crate from the source tree of the Rust compiler into this directory tree.
+//! Then you'll need to modify it
+//! - Remove any feature! or other things which won't compile on stable
+//! - change any absolute imports to relative imports within the ABI tree
+//! - drop the parts only the client needs, like the public `proc_macro` API
+//!   (see `abi_1_95`)
+//!
+//! Then you'll need to add a branch to the `Abi` enum and an implementation of
+//! `Abi::expand`, `Abi::list_macros` and `Abi::from_lib` for the new ABI. See
+//! `proc_macro_srv/src/abis/abi_1_47/mod.rs` for an example. Finally you'll
+//! need to update the conditionals in `Abi::from_lib` to return your new ABI
+//! for the relevant versions of the rust compiler.
+
+mod abi_1_47;
+mod abi_1_95;
+
+use libloading::Library;
+use proc_macro_api::{ProcMacroKind, RustCInfo};
+
+use crate::dylib::LoadProcMacroDylibError;
+
+use self::{abi_1_47::Abi as Abi_1_47, abi_1_95::Abi as Abi_1_95};
+
+// Used by `crate::tests`.
+#[cfg(test)]
+pub(crate) use self::abi_1_47::TokenStream;
+
+/// The `proc_macro::bridge::PanicMessage` of the ABI which expanded the macro,
+/// reduced to its message.
+#[derive(Debug)]
+pub struct PanicMessage {
+    message: Option<String>,
+}
+
+impl PanicMessage {
+    pub fn as_str(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+pub(crate) enum Abi {
+    Abi1_47(Abi_1_47),
+    Abi1_95(Abi_1_95),
+}
+
+/// The vendored copies of the proc-macro bridge, see `Abi::from_lib`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AbiKind {
+    Abi1_47,
+    Abi1_95,
+}
+
+impl AbiKind {
+    const LATEST: AbiKind = AbiKind::Abi1_95;
+
+    /// Picks the bridge a proc-macro dylib compiled by the given rustc speaks.
+    ///
+    /// The bridge was rewritten several times between 1.54 and 1.95, those
+    /// versions have no vendored copy yet. 1.95 up to 1.97 share the same one.
+    fn for_version(version: (usize, usize, usize)) -> Option<AbiKind> {
+        match version {
+            (1, 47..=54, _) => Some(AbiKind::Abi1_47),
+            (1, 95..=97, _) => Some(AbiKind::Abi1_95),
+            _ => None,
+        }
+    }
+}
+
+impl Abi {
+    /// Load a new ABI.
+    ///
+    /// # Arguments
+    ///
+    /// * `lib` - The dynamic library containing the macro implementations
+    /// * `symbol_name` - The symbol under which the macros are exported
+    /// * `info` - RustCInfo about the compiler that was used to compile the
+    ///            macro crate. This is the information we use to figure out
+    ///            which ABI to return, the latest ABI is used if it is unknown
+    pub fn from_lib(
+        lib: &Library,
+        symbol_name: String,
+        info: Option<RustCInfo>,
+    ) -> Result<Abi, LoadProcMacroDylibError> {
+        let kind = match info {
+            Some(info) => match AbiKind::for_version(info.version) {
+                Some(kind) => kind,
+                None => return Err(LoadProcMacroDylibError::UnsupportedABI(info)),
+            },
+            None => AbiKind::LATEST,
+        };
+        match kind {
+            AbiKind::Abi1_47 => {
+                let inner = unsafe { Abi_1_47::from_lib(lib, symbol_name) }?;
+                Ok(Abi::Abi1_47(inner))
+            }
+            AbiKind::Abi1_95 => {
+                let inner = unsafe { Abi_1_95::from_lib(lib, symbol_name) }?;
+                Ok(Abi::Abi1_95(inner))
+            }
+        }
+    }
+
+    pub fn expand(
+        &self,
+        macro_name: &str,
+        macro_body: &tt::Subtree,
+        attributes: Option<&tt::Subtree>,
+    ) -> Result<tt::Subtree, PanicMessage> {
+        match self {
+            Self::Abi1_47(abi) => abi.expand(macro_name, macro_body, attributes),
+            Self::Abi1_95(abi) => abi.expand(macro_name, macro_body, attributes),
+        }
+    }
+
+    pub fn list_macros(&self) -> Vec<(String, ProcMacroKind)> {
+        match self {
+            Self::Abi1_47(abi) => abi.list_macros(),
+            Self::Abi1_95(abi) => abi.list_macros(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_abi_by_rustc_version() {
+        assert_eq!(AbiKind::for_version((1, 46, 0)), None);
+        assert_eq!(AbiKind::for_version((1, 47, 0)), Some(AbiKind::Abi1_47));
+        assert_eq!(AbiKind::for_version((1, 54, 2)), Some(AbiKind::Abi1_47));
+        assert_eq!(AbiKind::for_version((1, 55, 0)), None);
+        assert_eq!(AbiKind::for_version((1, 94, 1)), None);
+        assert_eq!(AbiKind::for_version((1, 95, 0)), Some(AbiKind::Abi1_95));
+        assert_eq!(AbiKind::for_version((1, 97, 0)), Some(AbiKind::Abi1_95));
+        assert_eq!(AbiKind::for_version((1, 98, 0)), None);
+        assert_eq!(AbiKind::for_version((2, 0, 0)), None);
+    }
+}
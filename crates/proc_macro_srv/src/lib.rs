@@ -8,23 +8,19 @@
 //! * We use `tt` for proc-macro `TokenStream` server, it is easier to manipulate and interact with
 //!   RA than `proc-macro2` token stream.
 //! * By **copying** the whole rustc `lib_proc_macro` code, we are able to build this with `stable`
-//!   rustc rather than `unstable`. (Although in general ABI compatibility is still an issue)…
+//!   rustc rather than `unstable`. The ABI is not stable, so we keep a copy per supported rustc
+//!   version, see the `abis` module.
 #![allow(unreachable_pub)]
 
-#[allow(dead_code)]
-#[doc(hidden)]
-mod proc_macro;
-
-#[doc(hidden)]
-mod rustc_server;
-
 mod dylib;
 
-use proc_macro::bridge::client::TokenStream;
+mod abis;
+
 use proc_macro_api::{ExpansionResult, ExpansionTask, ListMacrosResult, ListMacrosTask};
 use std::{
     collections::{hash_map::Entry, HashMap},
     env, fs,
+    panic::{self, AssertUnwindSafe},
     path::{Path, PathBuf},
     time::SystemTime,
 };
@@ -44,7 +40,10 @@ impl ProcMacroSrv {
             env::set_var(k, v);
         }
 
-        let result = expander.expand(&task.macro_name, &task.macro_body, task.attributes.as_ref());
+        // Don't let a panic escaping the proc-macro bridge take down the whole server.
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            expander.expand(&task.macro_name, &task.macro_body, task.attributes.as_ref())
+        }));
 
         for (k, _) in &task.env {
             match &prev_env[k.as_str()] {
@@ -54,11 +53,19 @@ impl ProcMacroSrv {
         }
 
         match result {
-            Ok(expansion) => Ok(ExpansionResult { expansion }),
-            Err(msg) => {
+            Ok(Ok(expansion)) => Ok(ExpansionResult { expansion }),
+            Ok(Err(msg)) => {
                 let msg = msg.as_str().unwrap_or("<unknown error>");
                 Err(format!("proc-macro panicked: {}", msg))
             }
+            Err(payload) => {
+                let msg = payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("<unknown error>");
+                Err(format!("proc-macro server panicked: {}", msg))
+            }
         }
     }
 
@@ -74,7 +81,7 @@ impl ProcMacroSrv {
 
         Ok(match self.expanders.entry((path.to_path_buf(), time)) {
             Entry::Vacant(v) => v.insert(dylib::Expander::new(path).map_err(|err| {
-                format!("Cannot create expander for {}: {:?}", path.display(), err)
+                format!("Cannot create expander for {}: {}", path.display(), err)
             })?),
             Entry::Occupied(e) => e.into_mut(),
         })
@@ -63,3 +63,17 @@ fn test_version_check() {
     let info = proc_macro_api::read_dylib_info(&path).unwrap();
     assert!(info.version.1 >= 50);
 }
+
+#[test]
+fn test_unsupported_abi_error() {
+    let info = proc_macro_api::RustCInfo {
+        version: (1, 30, 0),
+        channel: String::new(),
+        commit: String::new(),
+        date: String::new(),
+    };
+    assert_eq_text!(
+        "proc-macro ABI of rustc 1.30.0 is not supported by rust-analyzer",
+        &crate::dylib::LoadProcMacroDylibError::UnsupportedABI(info).to_string()
+    );
+}
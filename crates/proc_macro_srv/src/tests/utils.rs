@@ -23,8 +23,14 @@ pub mod fixtures {
             match message.unwrap() {
                 Message::CompilerArtifact(artifact) => {
                     if artifact.target.kind.contains(&"proc-macro".to_string()) {
-                        let repr = format!("{} {}", crate_name, version);
-                        if artifact.package_id.repr.starts_with(&repr) {
+                        let repr = &artifact.package_id.repr;
+                        // Newer cargo versions use `source#name@version` package ids.
+                        let package_version =
+                            repr.rsplit(&['#', '@'][..]).next().unwrap_or_default();
+                        if repr.starts_with(&format!("{} {}", crate_name, version))
+                            || (artifact.target.name == crate_name
+                                && package_version.starts_with(version))
+                        {
                             return PathBuf::from(&artifact.filenames[0]);
                         }
                     }
@@ -37,8 +43,8 @@ pub mod fixtures {
     }
 }
 
-fn parse_string(code: &str) -> Option<crate::rustc_server::TokenStream> {
-    Some(crate::rustc_server::TokenStream::from_str(code).unwrap())
+fn parse_string(code: &str) -> Option<crate::abis::TokenStream> {
+    Some(crate::abis::TokenStream::from_str(code).unwrap())
 }
 
 pub fn assert_expand(
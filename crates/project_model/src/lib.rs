@@ -39,7 +39,7 @@ pub use crate::{
         CargoConfig, CargoWorkspace, Package, PackageData, PackageDependency, RustcSource, Target,
         TargetData, TargetKind,
     },
-    project_json::{ProjectJson, ProjectJsonData},
+    project_json::{CheckCommand, ProjectJson, ProjectJsonData},
     sysroot::Sysroot,
     workspace::{PackageRoot, ProjectWorkspace},
 };
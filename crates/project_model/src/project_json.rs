@@ -37,6 +37,34 @@ pub struct Crate {
     pub(crate) is_workspace_member: bool,
     pub(crate) include: Vec<AbsPathBuf>,
     pub(crate) exclude: Vec<AbsPathBuf>,
+    pub(crate) label: Option<String>,
+    pub(crate) check: Option<CheckCommand>,
+}
+
+/// A build-system specific command used to check a single crate on save.
+///
+/// The `{label}` and `{saved_file}` placeholders in `args` are substituted
+/// with the crate's label and the path of the saved file respectively.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+pub struct CheckCommand {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Crate {
+    /// Returns the build-system label of this crate, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+    /// Returns the command used to check this crate, if any.
+    pub fn check(&self) -> Option<&CheckCommand> {
+        self.check.as_ref()
+    }
+    /// Returns the directories containing the source files of this crate.
+    pub fn include_dirs(&self) -> &[AbsPathBuf] {
+        &self.include
+    }
 }
 
 impl ProjectJson {
@@ -96,6 +124,8 @@ impl ProjectJson {
                         is_workspace_member,
                         include,
                         exclude,
+                        label: crate_data.label,
+                        check: crate_data.check,
                     }
                 })
                 .collect::<Vec<_>>(),
@@ -135,6 +165,8 @@ struct CrateData {
     proc_macro_dylib_path: Option<PathBuf>,
     is_workspace_member: Option<bool>,
     source: Option<CrateSource>,
+    label: Option<String>,
+    check: Option<CheckCommand>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -180,3 +212,68 @@ where
     let name = String::deserialize(de)?;
     CrateName::new(&name).map_err(|err| de::Error::custom(format!("invalid crate name: {:?}", err)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(json: serde_json::Value) -> ProjectJson {
+        let data: ProjectJsonData = serde_json::from_value(json).unwrap();
+        let base = AbsPathBuf::assert(std::env::current_dir().unwrap());
+        ProjectJson::new(&base, data)
+    }
+
+    #[test]
+    fn deserializes_label_and_check_command() {
+        let project = project(serde_json::json!({
+            "crates": [
+                {
+                    "root_module": "foo/lib.rs",
+                    "edition": "2018",
+                    "deps": [],
+                    "label": "//foo:lib",
+                    "check": {
+                        "program": "build-tool",
+                        "args": ["check", "{label}", "--file={saved_file}"]
+                    }
+                },
+                {
+                    "root_module": "bar/lib.rs",
+                    "edition": "2018",
+                    "deps": [],
+                    "check": { "program": "bar-check" }
+                }
+            ]
+        }));
+        let crates: Vec<&Crate> = project.crates().map(|(_, krate)| krate).collect();
+
+        assert_eq!(crates[0].label(), Some("//foo:lib"));
+        assert_eq!(
+            crates[0].check(),
+            Some(&CheckCommand {
+                program: "build-tool".to_string(),
+                args: vec![
+                    "check".to_string(),
+                    "{label}".to_string(),
+                    "--file={saved_file}".to_string()
+                ],
+            })
+        );
+
+        assert_eq!(crates[1].label(), None);
+        assert_eq!(
+            crates[1].check(),
+            Some(&CheckCommand { program: "bar-check".to_string(), args: Vec::new() })
+        );
+    }
+
+    #[test]
+    fn label_and_check_are_optional() {
+        let project = project(serde_json::json!({
+            "crates": [{ "root_module": "lib.rs", "edition": "2021", "deps": [] }]
+        }));
+        let (_, krate) = project.crates().next().unwrap();
+        assert_eq!(krate.label(), None);
+        assert_eq!(krate.check(), None);
+    }
+}
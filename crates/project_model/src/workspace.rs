@@ -5,7 +5,9 @@
 use std::{collections::VecDeque, fmt, fs, path::Path, process::Command};
 
 use anyhow::{Context, Result};
-use base_db::{CrateDisplayName, CrateGraph, CrateId, CrateName, Edition, Env, FileId, ProcMacro};
+use base_db::{
+    CrateDisplayName, CrateGraph, CrateId, CrateName, Edition, Env, FileId, ProcMacroLoadResult,
+};
 use cargo_workspace::DepKind;
 use cfg::CfgOptions;
 use paths::{AbsPath, AbsPathBuf};
@@ -246,7 +248,7 @@ impl ProjectWorkspace {
         let _p = profile::span("ProjectWorkspace::to_crate_graph");
         let proc_macro_loader = |path: &Path| match proc_macro_client {
             Some(client) => client.by_dylib_path(path),
-            None => Vec::new(),
+            None => Ok(Vec::new()),
         };
 
         let mut crate_graph = match self {
@@ -288,7 +290,7 @@ impl ProjectWorkspace {
 
 fn project_json_to_crate_graph(
     rustc_cfg: Vec<CfgFlag>,
-    proc_macro_loader: &dyn Fn(&Path) -> Vec<ProcMacro>,
+    proc_macro_loader: &dyn Fn(&Path) -> ProcMacroLoadResult,
     load: &mut dyn FnMut(&AbsPath) -> Option<FileId>,
     project: &ProjectJson,
     sysroot: &Option<Sysroot>,
@@ -327,7 +329,7 @@ fn project_json_to_crate_graph(
                     krate.display_name.clone(),
                     cfg_options,
                     env,
-                    proc_macro.unwrap_or_default(),
+                    proc_macro.unwrap_or_else(|| Ok(Vec::new())),
                 ),
             )
         })
@@ -353,7 +355,7 @@ fn project_json_to_crate_graph(
 
 fn cargo_to_crate_graph(
     rustc_cfg: Vec<CfgFlag>,
-    proc_macro_loader: &dyn Fn(&Path) -> Vec<ProcMacro>,
+    proc_macro_loader: &dyn Fn(&Path) -> ProcMacroLoadResult,
     load: &mut dyn FnMut(&AbsPath) -> Option<FileId>,
     cargo: &CargoWorkspace,
     build_data_map: Option<&WorkspaceBuildData>,
@@ -480,7 +482,7 @@ fn handle_rustc_crates(
     crate_graph: &mut CrateGraph,
     rustc_build_data_map: Option<&WorkspaceBuildData>,
     cfg_options: &CfgOptions,
-    proc_macro_loader: &dyn Fn(&Path) -> Vec<ProcMacro>,
+    proc_macro_loader: &dyn Fn(&Path) -> ProcMacroLoadResult,
     pkg_to_lib_crate: &mut FxHashMap<la_arena::Idx<crate::PackageData>, CrateId>,
     public_deps: &[(CrateName, CrateId)],
     cargo: &CargoWorkspace,
@@ -572,7 +574,7 @@ fn add_target_crate_root(
     pkg: &cargo_workspace::PackageData,
     build_data: Option<&PackageBuildData>,
     cfg_options: &CfgOptions,
-    proc_macro_loader: &dyn Fn(&Path) -> Vec<ProcMacro>,
+    proc_macro_loader: &dyn Fn(&Path) -> ProcMacroLoadResult,
     file_id: FileId,
     cargo_name: &str,
 ) -> CrateId {
@@ -599,7 +601,7 @@ fn add_target_crate_root(
         .as_ref()
         .and_then(|it| it.proc_macro_dylib_path.as_ref())
         .map(|it| proc_macro_loader(&it))
-        .unwrap_or_default();
+        .unwrap_or_else(|| Ok(Vec::new()));
 
     let display_name = CrateDisplayName::from_canonical_name(cargo_name.to_string());
     let crate_id = crate_graph.add_crate_root(
@@ -629,7 +631,7 @@ fn sysroot_to_crate_graph(
             let file_id = load(&sysroot[krate].root)?;
 
             let env = Env::default();
-            let proc_macro = vec![];
+            let proc_macro = Ok(vec![]);
             let display_name = CrateDisplayName::from_canonical_name(sysroot[krate].name.clone());
             let crate_id = crate_graph.add_crate_root(
                 file_id,
@@ -11,9 +11,12 @@ use std::{ffi::OsString, iter, path::PathBuf};
 
 use flycheck::FlycheckConfig;
 use ide::{AssistConfig, CompletionConfig, DiagnosticsConfig, HoverConfig, InlayHintsConfig};
-use ide_db::helpers::{
-    insert_use::{ImportGranularity, InsertUseConfig, PrefixKind},
-    SnippetCap,
+use ide_db::{
+    base_db::ProcMacroBehavior,
+    helpers::{
+        insert_use::{ImportGranularity, InsertUseConfig, PrefixKind},
+        SnippetCap,
+    },
 };
 use lsp_types::{ClientCapabilities, MarkupKind};
 use project_model::{CargoConfig, ProjectJson, ProjectJsonData, ProjectManifest, RustcSource};
@@ -194,6 +197,11 @@ config_data! {
 
         /// Enable support for procedural macros, implies `#rust-analyzer.cargo.runBuildScripts#`.
         procMacro_enable: bool                     = "true",
+        /// Overrides how individual attribute and derive proc macros are
+        /// treated, by proc-macro crate name and macro name. For example,
+        /// `{ "async-trait": { "async_trait": "ignore" } }` analyzes items
+        /// annotated with `#[async_trait]` as if the attribute wasn't there.
+        procMacro_overrides: FxHashMap<String, FxHashMap<String, ProcMacroBehaviorDef>> = "{}",
         /// Internal config, path to proc-macro server executable (typically,
         /// this is rust-analyzer itself, but we override this in tests).
         procMacro_server: Option<PathBuf>          = "null",
@@ -523,6 +531,22 @@ impl Config {
         let path = self.data.procMacro_server.clone().or_else(|| std::env::current_exe().ok())?;
         Some((path, vec!["proc-macro".into()]))
     }
+    /// User overrides for proc macros, as `(proc-macro crate, macro, behavior)`.
+    pub fn proc_macro_overrides(&self) -> Vec<(String, String, ProcMacroBehavior)> {
+        let mut res = Vec::new();
+        for (krate, macros) in &self.data.procMacro_overrides {
+            for (name, behavior) in macros {
+                let behavior = match behavior {
+                    ProcMacroBehaviorDef::Expand => ProcMacroBehavior::Expand,
+                    ProcMacroBehaviorDef::Ignore => ProcMacroBehavior::Ignore,
+                    ProcMacroBehaviorDef::Disable => ProcMacroBehavior::Disable,
+                };
+                res.push((krate.clone(), name.clone(), behavior));
+            }
+        }
+        res.sort_by(|(k1, n1, _), (k2, n2, _)| (k1, n1).cmp(&(k2, n2)));
+        res
+    }
     pub fn files(&self) -> FilesConfig {
         FilesConfig {
             watcher: match self.data.files_watcher.as_str() {
@@ -770,6 +794,14 @@ enum ImportPrefixDef {
     ByCrate,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ProcMacroBehaviorDef {
+    Expand,
+    Ignore,
+    Disable,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 enum WorskpaceSymbolSearchScopeDef {
@@ -911,6 +943,21 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
         "FxHashMap<String, String>" => set! {
             "type": "object",
         },
+        "FxHashMap<String, FxHashMap<String, ProcMacroBehaviorDef>>" => set! {
+            "type": "object",
+            "additionalProperties": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "string",
+                    "enum": ["expand", "ignore", "disable"],
+                    "enumDescriptions": [
+                        "Expand the macro as usual.",
+                        "Analyze the item as if the attribute wasn't there. Derives produce nothing.",
+                        "Drop the item the attribute is applied to. Derives produce nothing."
+                    ],
+                },
+            },
+        },
         "Option<usize>" => set! {
             "type": ["null", "integer"],
             "minimum": 0,
@@ -8,7 +8,7 @@ use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::lsp_ext;
 
-pub(crate) type CheckFixes = Arc<FxHashMap<FileId, Vec<Fix>>>;
+pub(crate) type CheckFixes = Arc<FxHashMap<usize, FxHashMap<FileId, Vec<Fix>>>>;
 
 #[derive(Debug, Default, Clone)]
 pub struct DiagnosticsMapConfig {
@@ -22,7 +22,8 @@ pub(crate) struct DiagnosticCollection {
     // FIXME: should be FxHashMap<FileId, Vec<ra_id::Diagnostic>>
     pub(crate) native: FxHashMap<FileId, Vec<lsp_types::Diagnostic>>,
     // FIXME: should be Vec<flycheck::Diagnostic>
-    pub(crate) check: FxHashMap<FileId, Vec<lsp_types::Diagnostic>>,
+    /// Check diagnostics, keyed by the id of the flycheck that produced them.
+    pub(crate) check: FxHashMap<usize, FxHashMap<FileId, Vec<lsp_types::Diagnostic>>>,
     pub(crate) check_fixes: CheckFixes,
     changes: FxHashSet<FileId>,
 }
@@ -34,18 +35,31 @@ pub(crate) struct Fix {
 }
 
 impl DiagnosticCollection {
-    pub(crate) fn clear_check(&mut self) {
+    /// Clears the check diagnostics produced by the flycheck with the given id.
+    pub(crate) fn clear_check(&mut self, flycheck_id: usize) {
+        if let Some(it) = Arc::make_mut(&mut self.check_fixes).get_mut(&flycheck_id) {
+            it.clear();
+        }
+        if let Some(it) = self.check.get_mut(&flycheck_id) {
+            self.changes.extend(it.drain().map(|(key, _value)| key));
+        }
+    }
+
+    pub(crate) fn clear_check_all(&mut self) {
         Arc::make_mut(&mut self.check_fixes).clear();
-        self.changes.extend(self.check.drain().map(|(key, _value)| key))
+        for (_, diagnostics) in self.check.drain() {
+            self.changes.extend(diagnostics.keys().copied());
+        }
     }
 
     pub(crate) fn add_check_diagnostic(
         &mut self,
+        flycheck_id: usize,
         file_id: FileId,
         diagnostic: lsp_types::Diagnostic,
         fixes: Vec<lsp_ext::CodeAction>,
     ) {
-        let diagnostics = self.check.entry(file_id).or_default();
+        let diagnostics = self.check.entry(flycheck_id).or_default().entry(file_id).or_default();
         for existing_diagnostic in diagnostics.iter() {
             if are_diagnostics_equal(&existing_diagnostic, &diagnostic) {
                 return;
@@ -54,6 +68,8 @@ impl DiagnosticCollection {
 
         let check_fixes = Arc::make_mut(&mut self.check_fixes);
         check_fixes
+            .entry(flycheck_id)
+            .or_default()
             .entry(file_id)
             .or_default()
             .extend(fixes.into_iter().map(|action| Fix { range: diagnostic.range, action }));
@@ -86,7 +102,7 @@ impl DiagnosticCollection {
         file_id: FileId,
     ) -> impl Iterator<Item = &lsp_types::Diagnostic> {
         let native = self.native.get(&file_id).into_iter().flatten();
-        let check = self.check.get(&file_id).into_iter().flatten();
+        let check = self.check.values().filter_map(move |it| it.get(&file_id)).flatten();
         native.chain(check)
     }
 
@@ -104,3 +120,60 @@ fn are_diagnostics_equal(left: &lsp_types::Diagnostic, right: &lsp_types::Diagno
         && left.range == right.range
         && left.message == right.message
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(message: &str) -> lsp_types::Diagnostic {
+        lsp_types::Diagnostic {
+            message: message.to_string(),
+            source: Some("rustc".to_string()),
+            ..lsp_types::Diagnostic::default()
+        }
+    }
+
+    fn fix(title: &str) -> lsp_ext::CodeAction {
+        lsp_ext::CodeAction {
+            title: title.to_string(),
+            group: None,
+            kind: None,
+            edit: None,
+            is_preferred: None,
+            data: None,
+        }
+    }
+
+    fn messages(diagnostics: &DiagnosticCollection, file_id: FileId) -> Vec<String> {
+        let mut res: Vec<_> =
+            diagnostics.diagnostics_for(file_id).map(|it| it.message.clone()).collect();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn flychecks_clear_only_their_own_diagnostics() {
+        let (first, second) = (FileId(0), FileId(1));
+        let mut diagnostics = DiagnosticCollection::default();
+        diagnostics.add_check_diagnostic(0, first, diagnostic("a"), vec![fix("fix a")]);
+        diagnostics.add_check_diagnostic(1, first, diagnostic("b"), vec![fix("fix b")]);
+        diagnostics.add_check_diagnostic(1, second, diagnostic("c"), Vec::new());
+        assert_eq!(messages(&diagnostics, first), vec!["a", "b"]);
+        let changes = diagnostics.take_changes().unwrap();
+        assert!(changes.contains(&first) && changes.contains(&second));
+
+        diagnostics.clear_check(1);
+        assert_eq!(messages(&diagnostics, first), vec!["a"]);
+        assert!(messages(&diagnostics, second).is_empty());
+        assert_eq!(diagnostics.check_fixes[&0][&first].len(), 1);
+        assert!(diagnostics.check_fixes[&1].is_empty());
+        let changes = diagnostics.take_changes().unwrap();
+        assert!(changes.contains(&first) && changes.contains(&second));
+
+        diagnostics.add_check_diagnostic(1, second, diagnostic("d"), Vec::new());
+        diagnostics.clear_check(0);
+        assert!(messages(&diagnostics, first).is_empty());
+        assert_eq!(messages(&diagnostics, second), vec!["d"]);
+        assert!(diagnostics.check_fixes[&0].is_empty());
+    }
+}
@@ -12,7 +12,8 @@ use ide_db::base_db::{CrateId, VfsPath};
 use lsp_types::{SemanticTokens, Url};
 use parking_lot::{Mutex, RwLock};
 use project_model::{
-    BuildDataCollector, BuildDataResult, CargoWorkspace, ProcMacroClient, ProjectWorkspace, Target,
+    BuildDataCollector, BuildDataResult, CargoWorkspace, PackageRoot, ProcMacroClient,
+    ProjectWorkspace, Target,
 };
 use rustc_hash::FxHashMap;
 use vfs::AnchoredPathBuf;
@@ -82,6 +83,8 @@ pub(crate) struct GlobalState {
     pub(crate) workspaces: Arc<Vec<ProjectWorkspace>>,
     pub(crate) fetch_workspaces_queue: OpQueue<(), Vec<anyhow::Result<ProjectWorkspace>>>,
     pub(crate) workspace_build_data: Option<BuildDataResult>,
+    /// The member package roots of each workspace, indexed like `workspaces`.
+    pub(crate) workspace_member_roots: Vec<Vec<PackageRoot>>,
     pub(crate) fetch_build_data_queue:
         OpQueue<BuildDataCollector, Option<anyhow::Result<BuildDataResult>>>,
     pub(crate) prime_caches_queue: OpQueue<(), ()>,
@@ -147,6 +150,7 @@ impl GlobalState {
             workspaces: Arc::new(Vec::new()),
             fetch_workspaces_queue: OpQueue::default(),
             workspace_build_data: None,
+            workspace_member_roots: Vec::new(),
             prime_caches_queue: OpQueue::default(),
 
             fetch_build_data_queue: OpQueue::default(),
@@ -10,7 +10,7 @@ use std::{
 use ide::{
     AnnotationConfig, AssistKind, AssistResolveStrategy, FileId, FilePosition, FileRange,
     HoverAction, HoverGotoTypeData, Query, RangeInfo, Runnable, RunnableKind, SearchScope,
-    SingleResolve, SourceChange, TextEdit,
+    SignatureParam, SingleResolve, SourceChange, TextEdit,
 };
 use ide_db::SymbolKind;
 use itertools::Itertools;
@@ -1092,7 +1092,7 @@ pub(crate) fn handle_code_action(
     }
 
     // Fixes from `cargo check`.
-    for fix in snap.check_fixes.get(&frange.file_id).into_iter().flatten() {
+    for fix in snap.check_fixes.values().filter_map(|it| it.get(&frange.file_id)).flatten() {
         // FIXME: this mapping is awkward and shouldn't exist. Refactor
         // `snap.check_fixes` to not convert to LSP prematurely.
         let fix_range = from_proto::text_range(&line_index, fix.range);
@@ -1554,6 +1554,51 @@ pub(crate) fn handle_move_item(
     }
 }
 
+pub(crate) fn handle_move_item_to_module(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::MoveItemToModuleParams,
+) -> Result<lsp_types::WorkspaceEdit> {
+    let _p = profile::span("handle_move_item_to_module");
+    let position = from_proto::file_position(&snap, params.position)?;
+    let source_change = snap
+        .analysis
+        .move_item_to_module(&snap.config.assist(), position, &params.destination, params.reexport)?
+        .map_err(|message| LspError::new(ErrorCode::InvalidParams as i32, message))?;
+    to_proto::workspace_edit(&snap, source_change)
+}
+
+pub(crate) fn handle_change_signature(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::ChangeSignatureParams,
+) -> Result<lsp_types::WorkspaceEdit> {
+    let _p = profile::span("handle_change_signature");
+    let position = from_proto::file_position(&snap, params.position)?;
+    let params = params
+        .parameters
+        .into_iter()
+        .map(|param| match param {
+            lsp_ext::SignatureParameter { old_name: Some(old_name), name, .. } => {
+                Ok(SignatureParam::Existing { old_name, new_name: name })
+            }
+            lsp_ext::SignatureParameter {
+                old_name: None,
+                name,
+                ty: Some(ty),
+                default_value: Some(default_value),
+            } => Ok(SignatureParam::New { name, ty, default_value }),
+            lsp_ext::SignatureParameter { name, .. } => Err(LspError::new(
+                ErrorCode::InvalidParams as i32,
+                format!("new parameter `{}` needs a type and a default value", name),
+            )),
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let source_change = snap
+        .analysis
+        .change_signature(position, &params)?
+        .map_err(|err| LspError::new(ErrorCode::InvalidParams as i32, err.to_string()))?;
+    to_proto::workspace_edit(&snap, source_change)
+}
+
 fn to_command_link(command: lsp_types::Command, tooltip: String) -> lsp_ext::CommandLink {
     lsp_ext::CommandLink { tooltip: Some(tooltip), command }
 }
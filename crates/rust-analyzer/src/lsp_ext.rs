@@ -454,6 +454,54 @@ pub enum MoveItemDirection {
     Down,
 }
 
+pub enum MoveItemToModule {}
+
+impl Request for MoveItemToModule {
+    type Params = MoveItemToModuleParams;
+    type Result = lsp_types::WorkspaceEdit;
+    const METHOD: &'static str = "experimental/moveItemToModule";
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveItemToModuleParams {
+    #[serde(flatten)]
+    pub position: lsp_types::TextDocumentPositionParams,
+    /// Path of the module to move the item to, resolved relative to the item.
+    pub destination: String,
+    /// Whether to leave a `use` re-export of the item in its old module.
+    pub reexport: bool,
+}
+
+pub enum ChangeSignature {}
+
+impl Request for ChangeSignature {
+    type Params = ChangeSignatureParams;
+    type Result = lsp_types::WorkspaceEdit;
+    const METHOD: &'static str = "experimental/changeSignature";
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSignatureParams {
+    #[serde(flatten)]
+    pub position: lsp_types::TextDocumentPositionParams,
+    /// The parameters of the new signature, in order.
+    pub parameters: Vec<SignatureParameter>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureParameter {
+    /// Name of the existing parameter, absent for new parameters.
+    pub old_name: Option<String>,
+    pub name: String,
+    /// Type of a new parameter.
+    pub ty: Option<String>,
+    /// Argument passed by existing callers for a new parameter.
+    pub default_value: Option<String>,
+}
+
 #[derive(Debug)]
 pub enum WorkspaceSymbol {}
 
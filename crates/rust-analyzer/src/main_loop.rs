@@ -349,7 +349,7 @@ impl GlobalState {
                 let _p = profile::span("GlobalState::handle_event/flycheck");
                 loop {
                     match task {
-                        flycheck::Message::AddDiagnostic { workspace_root, diagnostic } => {
+                        flycheck::Message::AddDiagnostic { id, workspace_root, diagnostic } => {
                             let diagnostics =
                                 crate::diagnostics::to_proto::map_rust_diagnostic_to_lsp(
                                     &self.config.diagnostics_map(),
@@ -359,6 +359,7 @@ impl GlobalState {
                             for diag in diagnostics {
                                 match url_to_file_id(&self.vfs.read().0, &diag.url) {
                                     Ok(file_id) => self.diagnostics.add_check_diagnostic(
+                                        id,
                                         file_id,
                                         diag.diagnostic,
                                         diag.fixes,
@@ -376,12 +377,13 @@ impl GlobalState {
                         flycheck::Message::Progress { id, progress } => {
                             let (state, message) = match progress {
                                 flycheck::Progress::DidStart => {
-                                    self.diagnostics.clear_check();
+                                    self.diagnostics.clear_check(id);
                                     (Progress::Begin, None)
                                 }
-                                flycheck::Progress::DidCheckCrate(target) => {
-                                    (Progress::Report, Some(target))
-                                }
+                                flycheck::Progress::DidCheckPackage { package, n_checked } => (
+                                    Progress::Report,
+                                    Some(format!("{} ({} checked)", package, n_checked)),
+                                ),
                                 flycheck::Progress::DidCancel => (Progress::End, None),
                                 flycheck::Progress::DidFinish(result) => {
                                     if let Err(err) = result {
@@ -526,6 +528,8 @@ impl GlobalState {
             .on::<lsp_ext::ExternalDocs>(handlers::handle_open_docs)
             .on::<lsp_ext::OpenCargoToml>(handlers::handle_open_cargo_toml)
             .on::<lsp_ext::MoveItem>(handlers::handle_move_item)
+            .on::<lsp_ext::MoveItemToModule>(handlers::handle_move_item_to_module)
+            .on::<lsp_ext::ChangeSignature>(handlers::handle_change_signature)
             .on::<lsp_ext::WorkspaceSymbol>(handlers::handle_workspace_symbol)
             .on::<lsp_types::request::OnTypeFormatting>(handlers::handle_on_type_formatting)
             .on::<lsp_types::request::DocumentSymbolRequest>(handlers::handle_document_symbol)
@@ -657,11 +661,27 @@ impl GlobalState {
                 Ok(())
             })?
             .on::<lsp_types::notification::DidSaveTextDocument>(|this, params| {
-                for flycheck in &this.flycheck {
-                    flycheck.update();
-                }
-                if let Ok(abs_path) = from_proto::abs_path(&params.text_document.uri) {
-                    this.maybe_refresh(&[(abs_path, ChangeKind::Modify)]);
+                match from_proto::abs_path(&params.text_document.uri) {
+                    Ok(abs_path) => {
+                        // Only re-check the workspaces containing the saved file. If none of
+                        // them does, the file might be a new one, so re-check everything.
+                        let owners: Vec<_> = this
+                            .flycheck
+                            .iter()
+                            .filter(|flycheck| this.workspace_contains(flycheck.id(), &abs_path))
+                            .collect();
+                        let flychecks =
+                            if owners.is_empty() { this.flycheck.iter().collect() } else { owners };
+                        for flycheck in flychecks {
+                            flycheck.update_for_file(abs_path.clone().into());
+                        }
+                        this.maybe_refresh(&[(abs_path, ChangeKind::Modify)]);
+                    }
+                    Err(_) => {
+                        for flycheck in &this.flycheck {
+                            flycheck.update();
+                        }
+                    }
                 }
                 Ok(())
             })?
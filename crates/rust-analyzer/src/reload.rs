@@ -1,10 +1,12 @@
 //! Project loading & configuration updates
 use std::{mem, sync::Arc};
 
-use flycheck::{FlycheckConfig, FlycheckHandle};
+use flycheck::{CrateCheckCommand, FlycheckConfig, FlycheckHandle};
 use ide::Change;
-use ide_db::base_db::{CrateGraph, SourceRoot, VfsPath};
-use project_model::{BuildDataCollector, BuildDataResult, ProcMacroClient, ProjectWorkspace};
+use ide_db::base_db::{CrateGraph, ProcMacroBehavior, SourceDatabase, SourceRoot, VfsPath};
+use project_model::{
+    BuildDataCollector, BuildDataResult, ProcMacroClient, ProjectJson, ProjectWorkspace,
+};
 use vfs::{file_set::FileSetConfig, AbsPath, AbsPathBuf, ChangeKind};
 
 use crate::{
@@ -47,6 +49,19 @@ impl GlobalState {
         } else if self.config.flycheck() != old_config.flycheck() {
             self.reload_flycheck();
         }
+        let proc_macro_overrides = self.config.proc_macro_overrides();
+        if proc_macro_overrides != old_config.proc_macro_overrides() {
+            let mut crate_graph = (*self.analysis_host.raw_database().crate_graph()).clone();
+            // Reset the previous overrides before applying the new ones.
+            let reset = old_config
+                .proc_macro_overrides()
+                .into_iter()
+                .map(|(krate, name, _)| (krate, name, ProcMacroBehavior::Expand));
+            apply_proc_macro_overrides(&mut crate_graph, reset.chain(proc_macro_overrides));
+            let mut change = Change::new();
+            change.set_crate_graph(crate_graph);
+            self.analysis_host.apply_change(change);
+        }
     }
     pub(crate) fn maybe_refresh(&mut self, changes: &[(AbsPathBuf, ChangeKind)]) {
         if !changes.iter().any(|(path, kind)| is_interesting(path, *kind)) {
@@ -342,11 +357,22 @@ impl GlobalState {
                 ));
             }
 
+            apply_proc_macro_overrides(&mut crate_graph, self.config.proc_macro_overrides());
+
             crate_graph
         };
         change.set_crate_graph(crate_graph);
 
         self.source_root_config = project_folders.source_root_config;
+        self.workspace_member_roots = workspaces
+            .iter()
+            .map(|ws| {
+                ws.to_roots(workspace_build_data.as_ref())
+                    .into_iter()
+                    .filter(|root| root.is_member)
+                    .collect()
+            })
+            .collect();
         self.workspaces = Arc::new(workspaces);
         self.workspace_build_data = workspace_build_data;
 
@@ -382,8 +408,23 @@ impl GlobalState {
         }
     }
 
+    /// Checks whether `path` belongs to one of the member packages of the
+    /// workspace with the given index.
+    pub(crate) fn workspace_contains(&self, workspace_id: usize, path: &AbsPath) -> bool {
+        let roots = match self.workspace_member_roots.get(workspace_id) {
+            Some(it) => it,
+            None => return false,
+        };
+        roots.iter().any(|root| {
+            root.include.iter().any(|dir| path.starts_with(dir))
+                && !root.exclude.iter().any(|dir| path.starts_with(dir))
+        })
+    }
+
     fn reload_flycheck(&mut self) {
         let _p = profile::span("GlobalState::reload_flycheck");
+        // Flycheck ids are workspace indices, which might have changed.
+        self.diagnostics.clear_check_all();
         let config = match self.config.flycheck() {
             Some(it) => it,
             None => {
@@ -398,22 +439,34 @@ impl GlobalState {
             .iter()
             .enumerate()
             .filter_map(|(id, w)| match w {
-                ProjectWorkspace::Cargo { cargo, .. } => Some((id, cargo.workspace_root())),
+                ProjectWorkspace::Cargo { cargo, .. } => {
+                    Some((id, cargo.workspace_root(), config.clone()))
+                }
                 ProjectWorkspace::Json { project, .. } => {
                     // Enable flychecks for json projects if a custom flycheck command was supplied
-                    // in the workspace configuration.
+                    // in the workspace configuration, or if the crates specify their own check
+                    // commands.
                     match config {
-                        FlycheckConfig::CustomCommand { .. } => Some((id, project.path())),
-                        _ => None,
+                        FlycheckConfig::CustomCommand { .. } => {
+                            Some((id, project.path(), config.clone()))
+                        }
+                        _ => {
+                            let crates = crate_check_commands(project);
+                            if crates.is_empty() {
+                                None
+                            } else {
+                                Some((id, project.path(), FlycheckConfig::CrateCommands { crates }))
+                            }
+                        }
                     }
                 }
             })
-            .map(|(id, root)| {
+            .map(|(id, root, config)| {
                 let sender = sender.clone();
                 FlycheckHandle::spawn(
                     id,
                     Box::new(move |msg| sender.send(msg).unwrap()),
-                    config.clone(),
+                    config,
                     root.to_path_buf().into(),
                 )
             })
@@ -421,6 +474,42 @@ impl GlobalState {
     }
 }
 
+/// Applies the user's proc macro overrides, given as `(proc-macro crate, macro, behavior)`.
+fn apply_proc_macro_overrides(
+    crate_graph: &mut CrateGraph,
+    overrides: impl IntoIterator<Item = (String, String, ProcMacroBehavior)>,
+) {
+    for (krate_name, macro_name, behavior) in overrides {
+        let krate_name = krate_name.replace('-', "_");
+        let krates: Vec<_> = crate_graph
+            .iter()
+            .filter(|&it| crate_graph[it].display_name.as_deref() == Some(&*krate_name))
+            .collect();
+        for krate in krates {
+            crate_graph.set_proc_macro_behavior(krate, &macro_name, behavior);
+        }
+    }
+}
+
+fn crate_check_commands(project: &ProjectJson) -> Vec<CrateCheckCommand> {
+    project
+        .crates()
+        .filter_map(|(_, krate)| {
+            let check = krate.check()?;
+            Some(CrateCheckCommand {
+                label: krate.label().unwrap_or_default().to_string(),
+                include_dirs: krate
+                    .include_dirs()
+                    .iter()
+                    .map(|it| it.to_path_buf().into())
+                    .collect(),
+                command: check.program.clone(),
+                args: check.args.clone(),
+            })
+        })
+        .collect()
+}
+
 #[derive(Default)]
 pub(crate) struct ProjectFolders {
     pub(crate) load: Vec<vfs::loader::Entry>,
@@ -10,7 +10,7 @@ use crate::{
     ast::{
         self,
         edit::{AstNodeEdit, IndentLevel},
-        make, GenericParamsOwner,
+        make, GenericParamsOwner, ModuleItemOwner,
     },
     ted::{self, Position},
     AstNode, AstToken, Direction, SyntaxNode,
@@ -321,6 +321,30 @@ impl ast::AssocItemList {
     }
 }
 
+impl ast::ItemList {
+    pub fn add_item(&self, item: ast::Item) {
+        let (indent, position, whitespace) = match self.items().last() {
+            Some(last_item) => (
+                IndentLevel::from_node(last_item.syntax()),
+                Position::after(last_item.syntax()),
+                "\n\n",
+            ),
+            None => match self.l_curly_token() {
+                Some(l_curly) => {
+                    normalize_ws_between_braces(self.syntax());
+                    (IndentLevel::from_token(&l_curly) + 1, Position::after(&l_curly), "\n")
+                }
+                None => (IndentLevel::single(), Position::last_child_of(self.syntax()), "\n"),
+            },
+        };
+        let elements: Vec<SyntaxElement<_>> = vec![
+            make::tokens::whitespace(&format!("{}{}", whitespace, indent)).into(),
+            item.syntax().clone().into(),
+        ];
+        ted::insert_all(position, elements);
+    }
+}
+
 impl ast::Fn {
     pub fn get_or_create_body(&self) -> ast::BlockExpr {
         if self.body().is_none() {
@@ -523,6 +523,10 @@ pub fn visibility_pub_crate() -> ast::Visibility {
     ast_from_text("pub(crate) struct S")
 }
 
+pub fn visibility_pub_super() -> ast::Visibility {
+    ast_from_text("pub(super) struct S")
+}
+
 pub fn visibility_pub() -> ast::Visibility {
     ast_from_text("pub struct S")
 }
@@ -161,6 +161,97 @@ export function moveItem(ctx: Ctx, direction: ra.Direction): Cmd {
     };
 }
 
+export function moveItemToModule(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const textDocument = client.code2ProtocolConverter.asTextDocumentIdentifier(editor.document);
+        const position = client.code2ProtocolConverter.asPosition(editor.selection.active);
+
+        const destination = await vscode.window.showInputBox({
+            prompt: "Enter the path of the destination module, for example 'crate::foo::bar'",
+            value: "crate::",
+        });
+        if (!destination) return;
+
+        const choice = await vscode.window.showQuickPick(
+            ["Update references", "Update references and leave a re-export"],
+            { placeHolder: "Keep the item reachable under its old path?" },
+        );
+        if (!choice) return;
+        const reexport = choice !== "Update references";
+
+        try {
+            const edit = await client.sendRequest(ra.moveItemToModule, {
+                textDocument, position, destination, reexport,
+            });
+            await vscode.workspace.applyEdit(client.protocol2CodeConverter.asWorkspaceEdit(edit));
+        } catch (e) {
+            void vscode.window.showErrorMessage(`Failed to move item: ${e.toString()}`);
+        }
+    };
+}
+
+export function changeSignature(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const textDocument = client.code2ProtocolConverter.asTextDocumentIdentifier(editor.document);
+        const position = client.code2ProtocolConverter.asPosition(editor.selection.active);
+
+        const request = await vscode.window.showInputBox({
+            prompt: "Enter the new parameters: 'name' keeps a parameter, 'old as new' renames it, 'name: Type = default' adds one",
+            placeHolder: "b, a as x, c: bool = false",
+            validateInput: (input) => parseSignatureParameters(input) === null ? "Invalid parameter list" : null,
+        });
+        if (request === undefined) return;
+        const parameters = parseSignatureParameters(request);
+        if (!parameters) return;
+
+        try {
+            const edit = await client.sendRequest(ra.changeSignature, { textDocument, position, parameters });
+            await vscode.workspace.applyEdit(client.protocol2CodeConverter.asWorkspaceEdit(edit));
+        } catch (e) {
+            void vscode.window.showErrorMessage(`Failed to change signature: ${e.toString()}`);
+        }
+    };
+}
+
+function parseSignatureParameters(input: string): ra.SignatureParameter[] | null {
+    // Split on commas which are not nested inside a type or an expression.
+    const parts: string[] = [];
+    let depth = 0;
+    let start = 0;
+    for (let i = 0; i < input.length; i++) {
+        const c = input[i];
+        if ("([{<".includes(c)) depth++;
+        else if (")]}>".includes(c) && input[i - 1] !== '-' && input[i - 1] !== '=') depth--;
+        else if (c === ',' && depth === 0) {
+            parts.push(input.slice(start, i));
+            start = i + 1;
+        }
+    }
+    parts.push(input.slice(start));
+
+    const parameters: ra.SignatureParameter[] = [];
+    for (const part of parts.map((it) => it.trim())) {
+        if (part === "") continue;
+        const added = /^(\w+)\s*:(.+?)=(.+)$/s.exec(part);
+        if (added) {
+            parameters.push({ name: added[1], ty: added[2].trim(), defaultValue: added[3].trim() });
+            continue;
+        }
+        const existing = /^(\w+)(?:\s+as\s+(\w+))?$/.exec(part);
+        if (!existing) return null;
+        parameters.push({ oldName: existing[1], name: existing[2] ?? existing[1] });
+    }
+    return parameters;
+}
+
 export function onEnter(ctx: Ctx): Cmd {
     async function handleKeypress() {
         const editor = ctx.activeRustEditor;
@@ -149,3 +149,23 @@ export const enum Direction {
     Up = "Up",
     Down = "Down"
 }
+
+export const moveItemToModule = new lc.RequestType<MoveItemToModuleParams, lc.WorkspaceEdit, void>("experimental/moveItemToModule");
+
+export interface MoveItemToModuleParams extends lc.TextDocumentPositionParams {
+    destination: string;
+    reexport: boolean;
+}
+
+export const changeSignature = new lc.RequestType<ChangeSignatureParams, lc.WorkspaceEdit, void>("experimental/changeSignature");
+
+export interface ChangeSignatureParams extends lc.TextDocumentPositionParams {
+    parameters: SignatureParameter[];
+}
+
+export interface SignatureParameter {
+    oldName?: string;
+    name: string;
+    ty?: string;
+    defaultValue?: string;
+}
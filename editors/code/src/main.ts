@@ -118,6 +118,8 @@ async function tryActivate(context: vscode.ExtensionContext) {
     ctx.registerCommand('peekTests', commands.peekTests);
     ctx.registerCommand('moveItemUp', commands.moveItemUp);
     ctx.registerCommand('moveItemDown', commands.moveItemDown);
+    ctx.registerCommand('moveItemToModule', commands.moveItemToModule);
+    ctx.registerCommand('changeSignature', commands.changeSignature);
 
     defaultOnEnter.dispose();
     ctx.registerCommand('onEnter', commands.onEnter);